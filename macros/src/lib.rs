@@ -0,0 +1,173 @@
+//! Registration of the solvers of the aoc19 crate.
+//!
+//! A solver is marked with `#[aoc(day = 13, part = 1)]`, which adds a hidden
+//! wrapper returning the answer as text and a hidden `crate::solver::Solver`
+//! item next to it. `solvers![day13::part1, ..]` collects these items from an
+//! explicit list of solver functions into the table of all solvers. Every
+//! `#[aoc]` also asserts at compile time that its day and part made it into
+//! `crate::solver::SOLVERS`, a solver missing from the list fails the build.
+//!
+//! The expansions refer to `crate::solver`, the macros are only meant to be
+//! used inside of the aoc19 crate.
+
+use proc_macro::{TokenStream, TokenTree};
+
+// Prefix of the wrapper generated for a solver function.
+const WRAPPER_PREFIX: &str = "__aoc_solve_";
+// Prefix of the `Solver` item generated for a solver function.
+const SOLVER_PREFIX: &str = "__AOC_SOLVER_";
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({:?});", msg)
+        .parse()
+        .expect("valid compile_error invocation")
+}
+
+// Parse the attribute arguments `day = N, part = P`.
+fn parse_args(args: &str) -> Result<(u32, u32), String> {
+    let mut day = None;
+    let mut part = None;
+    for arg in args.split(',').filter(|arg| !arg.trim().is_empty()) {
+        let mut kv = arg.splitn(2, '=').map(str::trim);
+        let (key, value) = match (kv.next(), kv.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return Err(format!("expected `key = value`, found `{}`", arg.trim())),
+        };
+        let value = value
+            .parse::<u32>()
+            .map_err(|_| format!("`{}` expects a number, found `{}`", key, value))?;
+        match key {
+            "day" if (1..=25).contains(&value) => day = Some(value),
+            "part" if (1..=2).contains(&value) => part = Some(value),
+            "day" | "part" => return Err(format!("`{}` out of range: {}", key, value)),
+            _ => return Err(format!("unknown argument `{}`", key)),
+        }
+    }
+    match (day, part) {
+        (Some(day), Some(part)) => Ok((day, part)),
+        _ => Err("expected `#[aoc(day = N, part = P)]`".to_string()),
+    }
+}
+
+// Name of the function `item`.
+fn fn_name(item: TokenStream) -> Option<String> {
+    let mut tokens = item.into_iter();
+    while let Some(token) = tokens.next() {
+        if let TokenTree::Ident(ident) = token {
+            if ident.to_string() == "fn" {
+                return match tokens.next() {
+                    Some(TokenTree::Ident(name)) => Some(name.to_string()),
+                    _ => None,
+                };
+            }
+        }
+    }
+    None
+}
+
+/// Register a `fn(&str) -> crate::Result<T>` as solver of a day and part,
+/// the answer `T` has to implement `crate::solver::Answer`.
+#[proc_macro_attribute]
+pub fn aoc(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let (day, part) = match parse_args(&attr.to_string()) {
+        Ok(args) => args,
+        Err(msg) => return compile_error(&msg),
+    };
+    let name = match fn_name(item.clone()) {
+        Some(name) => name,
+        None => return compile_error("#[aoc] can only be applied to functions"),
+    };
+
+    let wrapper = format!(
+        "#[doc(hidden)]\n\
+         pub fn {wrapper}{name}(input: &str) -> crate::Result<String> {{\n\
+         Ok(crate::solver::Answer::answer(&{name}(input)?))\n\
+         }}\n\
+         #[doc(hidden)]\n\
+         pub const {solver}{upper}: crate::solver::Solver = crate::solver::Solver {{\n\
+         day: {day}, part: {part}, name: \"{name}\", solve: {wrapper}{name},\n\
+         }};\n\
+         const _: () = assert!(\n\
+         crate::solver::registered({day}, {part}),\n\
+         \"`{name}` of day {day} part {part} is missing from crate::solver::SOLVERS\",\n\
+         );",
+        wrapper = WRAPPER_PREFIX,
+        solver = SOLVER_PREFIX,
+        upper = name.to_uppercase(),
+        name = name,
+        day = day,
+        part = part,
+    );
+    let mut out = item;
+    out.extend(wrapper.parse::<TokenStream>().expect("valid wrapper"));
+    out
+}
+
+// Paths `module::function` of the solver list `input`.
+fn parse_paths(input: TokenStream) -> Result<Vec<(String, String)>, String> {
+    let mut paths = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut tokens = input.into_iter().peekable();
+    while tokens.peek().is_some() {
+        path.clear();
+        for token in tokens.by_ref() {
+            match token {
+                TokenTree::Punct(p) if p.as_char() == ',' => break,
+                TokenTree::Punct(p) if p.as_char() == ':' => {}
+                TokenTree::Ident(ident) => path.push(ident.to_string()),
+                token => return Err(format!("unexpected `{}` in solver list", token)),
+            }
+        }
+        match path.as_slice() {
+            [module, function] => paths.push((module.clone(), function.clone())),
+            _ => {
+                return Err(format!(
+                    "expected `module::function`, found `{}`",
+                    path.join("::")
+                ))
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Table of the solvers `module::function` registered with `#[aoc]`, as
+/// `&[crate::solver::Solver]` in the order of the list.
+#[proc_macro]
+pub fn solvers(input: TokenStream) -> TokenStream {
+    let paths = match parse_paths(input) {
+        Ok(paths) => paths,
+        Err(msg) => return compile_error(&msg),
+    };
+    let solvers: Vec<_> = paths
+        .iter()
+        .map(|(module, function)| {
+            format!(
+                "crate::solver::Solver {{ name: \"{}::{}\", ..crate::{}::{}{} }}",
+                module,
+                function,
+                module,
+                SOLVER_PREFIX,
+                function.to_uppercase()
+            )
+        })
+        .collect();
+    format!("&[{}]", solvers.join(", "))
+        .parse()
+        .expect("valid solver table")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args("day = 13, part = 1"), Ok((13, 1)));
+        assert_eq!(parse_args("part=2,day=1"), Ok((1, 2)));
+        assert!(parse_args("day = 13").is_err());
+        assert!(parse_args("day = 26, part = 1").is_err());
+        assert!(parse_args("day = x, part = 1").is_err());
+        assert!(parse_args("day = 1, part = 1, year = 2019").is_err());
+    }
+}
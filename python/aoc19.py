@@ -0,0 +1,146 @@
+"""Python bindings of the aoc19 library through its C ABI (src/ffi.rs).
+
+Build the shared library first with `cargo build --release`, or point the
+AOC19_LIB environment variable to a `libaoc19.so`:
+
+    import aoc19
+    vm = aoc19.Intcode(aoc19.read_program("input/day9"))
+    vm.run([1])
+    print(vm.take_output())
+
+The smoke test calling every export runs with
+`python3 -m unittest discover python` after `cargo build`.
+"""
+
+import ctypes
+import os
+import sys
+
+_ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+
+def _load():
+    name = {"darwin": "libaoc19.dylib", "win32": "aoc19.dll"}.get(sys.platform, "libaoc19.so")
+    candidates = [os.environ.get("AOC19_LIB")] + [
+        os.path.join(_ROOT, "target", profile, name) for profile in ("release", "debug")
+    ]
+    for path in candidates:
+        if path and os.path.exists(path):
+            return ctypes.CDLL(path)
+    raise OSError("{} not found, run `cargo build --release` or set AOC19_LIB".format(name))
+
+
+_lib = _load()
+
+_i64_p = ctypes.POINTER(ctypes.c_int64)
+_i32_p = ctypes.POINTER(ctypes.c_int32)
+_SIGNATURES = {
+    "aoc19_last_error": ([], ctypes.c_char_p),
+    "aoc19_intcode_new": ([_i64_p, ctypes.c_size_t], ctypes.c_void_p),
+    "aoc19_intcode_free": ([ctypes.c_void_p], None),
+    "aoc19_intcode_set_step_limit": ([ctypes.c_void_p, ctypes.c_uint64], ctypes.c_int32),
+    "aoc19_intcode_run": ([ctypes.c_void_p, _i64_p, ctypes.c_size_t], ctypes.c_int32),
+    "aoc19_intcode_output_len": ([ctypes.c_void_p], ctypes.c_ssize_t),
+    "aoc19_intcode_take_output": ([ctypes.c_void_p, _i64_p, ctypes.c_size_t], ctypes.c_int32),
+    "aoc19_intcode_peek": ([ctypes.c_void_p, ctypes.c_size_t], ctypes.c_int64),
+    "aoc19_intcode_poke": ([ctypes.c_void_p, ctypes.c_size_t, ctypes.c_int64], ctypes.c_int32),
+    "aoc19_day12_energy": ([ctypes.c_char_p, ctypes.c_size_t], ctypes.c_int64),
+    "aoc19_day14_ore_for_fuel": ([ctypes.c_char_p, ctypes.c_int64], ctypes.c_int64),
+    "aoc19_day14_max_fuel": ([ctypes.c_char_p, ctypes.c_int64], ctypes.c_int64),
+    "aoc19_day16_fft": ([_i32_p, ctypes.c_size_t, ctypes.c_size_t, _i32_p], ctypes.c_int32),
+    "aoc19_day16_message": (
+        [_i32_p, ctypes.c_size_t, ctypes.c_size_t, ctypes.c_size_t, _i32_p],
+        ctypes.c_int32,
+    ),
+}
+for _name, (_args, _res) in _SIGNATURES.items():
+    getattr(_lib, _name).argtypes = _args
+    getattr(_lib, _name).restype = _res
+
+
+class Aoc19Error(Exception):
+    pass
+
+
+def _check(result):
+    if result == -1:
+        raise Aoc19Error(_lib.aoc19_last_error().decode())
+    return result
+
+
+def _array(ctype, values):
+    values = list(values)
+    return (ctype * len(values))(*values), len(values)
+
+
+def read_program(path):
+    """The intcode program in the file at `path`."""
+    with open(path) as f:
+        return [int(v) for v in f.read().split(",")]
+
+
+class Intcode:
+    """Intcode VM, `run` returns True once the program halted, False if it
+    needs more input or exceeded the step limit."""
+
+    def __init__(self, prog):
+        array, length = _array(ctypes.c_int64, prog)
+        self._vm = _lib.aoc19_intcode_new(array, length)
+        if not self._vm:
+            raise Aoc19Error(_lib.aoc19_last_error().decode())
+
+    def __del__(self):
+        if getattr(self, "_vm", None):
+            _lib.aoc19_intcode_free(self._vm)
+            self._vm = None
+
+    def set_step_limit(self, limit):
+        _check(_lib.aoc19_intcode_set_step_limit(self._vm, limit))
+
+    def run(self, input=()):
+        array, length = _array(ctypes.c_int64, input)
+        return _check(_lib.aoc19_intcode_run(self._vm, array, length)) == 0
+
+    def take_output(self):
+        length = _check(_lib.aoc19_intcode_output_len(self._vm))
+        out = (ctypes.c_int64 * length)()
+        _check(_lib.aoc19_intcode_take_output(self._vm, out, length))
+        return list(out)
+
+    def peek(self, addr):
+        return _lib.aoc19_intcode_peek(self._vm, addr)
+
+    def poke(self, addr, value):
+        _check(_lib.aoc19_intcode_poke(self._vm, addr, value))
+
+
+def day12_energy(moons, steps):
+    """Total energy of the moons after `steps` steps."""
+    return _check(_lib.aoc19_day12_energy(moons.encode(), steps))
+
+
+def day14_ore_for_fuel(formulas, fuel=1):
+    return _check(_lib.aoc19_day14_ore_for_fuel(formulas.encode(), fuel))
+
+
+def day14_max_fuel(formulas, ore=1000000000000):
+    return _check(_lib.aoc19_day14_max_fuel(formulas.encode(), ore))
+
+
+def _digits(signal):
+    return [int(c) for c in signal.strip()]
+
+
+def day16_fft(signal, phases=100):
+    """The digits of `signal` (a string of digits) after `phases` phases."""
+    array, length = _array(ctypes.c_int32, _digits(signal))
+    out = (ctypes.c_int32 * length)()
+    _check(_lib.aoc19_day16_fft(array, length, phases, out))
+    return "".join(map(str, out))
+
+
+def day16_message(signal, repeat=10000, phases=100):
+    array, length = _array(ctypes.c_int32, _digits(signal))
+    out = (ctypes.c_int32 * 8)()
+    _check(_lib.aoc19_day16_message(array, length, repeat, phases, out))
+    return "".join(map(str, out))
@@ -0,0 +1,55 @@
+"""Smoke test of the bindings, calls every export of the library once.
+
+Runs against the library `aoc19` finds, build it first or set AOC19_LIB:
+
+    cargo build && AOC19_LIB=target/debug/libaoc19.so python3 -m unittest discover python
+"""
+
+import os
+import re
+import unittest
+
+import aoc19
+
+MOONS = "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n"
+FORMULAS = (
+    "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 D\n"
+    "7 A, 1 D => 1 E\n7 A, 1 E => 1 FUEL\n"
+)
+
+
+class TestBindings(unittest.TestCase):
+    def test_signatures(self):
+        # every export of src/ffi.rs is declared, with nothing left over
+        with open(os.path.join(aoc19._ROOT, "src", "ffi.rs")) as f:
+            exports = set(re.findall(r'extern "C" fn (aoc19_\w+)', f.read()))
+        self.assertEqual(exports, set(aoc19._SIGNATURES))
+
+    def test_intcode(self):
+        # echo the input until it is 0
+        vm = aoc19.Intcode([3, 9, 4, 9, 1005, 9, 0, 99, 0, 0])
+        self.assertFalse(vm.run([5]))
+        self.assertTrue(vm.run([0]))
+        self.assertEqual(vm.take_output(), [5, 0])
+        vm.poke(9, 42)
+        self.assertEqual(vm.peek(9), 42)
+        with self.assertRaises(aoc19.Aoc19Error):
+            vm.poke(2**63, 0)
+
+        loop = aoc19.Intcode([1105, 1, 0])
+        loop.set_step_limit(10)
+        self.assertFalse(loop.run())
+
+    def test_solvers(self):
+        self.assertEqual(aoc19.day12_energy(MOONS, 10), 179)
+        self.assertEqual(aoc19.day14_ore_for_fuel(FORMULAS), 31)
+        self.assertEqual(aoc19.day14_max_fuel(FORMULAS, 31), 1)
+        self.assertEqual(aoc19.day16_fft("12345678", 4), "01029498")
+        # the message starts at offset 3, 7 digits are left of one signal
+        self.assertEqual(aoc19.day16_message("0000003123", repeat=2, phases=1), "60396899")
+        with self.assertRaises(aoc19.Aoc19Error):
+            aoc19.day16_message("0000003123", repeat=1, phases=1)
+
+
+if __name__ == "__main__":
+    unittest.main()
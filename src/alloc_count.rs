@@ -0,0 +1,44 @@
+//! Global allocator counting the allocations per thread, so code running
+//! in parallel (e.g. tests) doesn't disturb the count of another thread.
+//!
+//! The count only moves if `CountingAlloc` is registered as global allocator
+//! of the binary:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: aoc19::alloc_count::CountingAlloc = aoc19::alloc_count::CountingAlloc;
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The system allocator, counting allocations.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // the counter is gone while a thread is torn down
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+/// Number of allocations of the current thread so far.
+pub fn allocations() -> usize {
+    ALLOCATIONS.with(|n| n.get())
+}
+
+/// Result of `f` and the number of allocations it made.
+pub fn count<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = allocations();
+    let result = f();
+    (result, allocations() - before)
+}
@@ -0,0 +1,308 @@
+//! Assembler for intcode programs, to write test programs for the machine
+//! without computing addresses by hand.
+//!
+//! One instruction per line, the mnemonics are the instructions of the
+//! machine: `add`, `mul`, `get`, `put`, `jpt`, `jpf`, `lt`, `eq`, `rbo` and
+//! `halt`. Operands are separated by commas, `[x]` is position mode,
+//! `[rb+x]` relative mode and a plain `x` immediate mode. Operands are sums
+//! of numbers, labels and constants:
+//!
+//! ```text
+//! .const FACTOR 2       # constants are numbers
+//! loop: get [x]         # labels are the address of what follows
+//!       mul [x], FACTOR, [x]
+//!       put [x]
+//!       jpt 1, loop
+//! x:    .data 0, x+1    # raw cells
+//! ```
+
+use crate::intcode::{IntcodeProgram, Value};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+pub enum E {
+    // line numbers are 1-based
+    InvalidLine(usize),
+    UnknownMnemonic { line: usize, mnemonic: String },
+    OperandCount { line: usize, expected: usize },
+    ImmediateDestination { line: usize },
+    UndefinedName { line: usize, name: String },
+    DuplicateName { line: usize, name: String },
+}
+
+// Mnemonic, opcode, number of operands and whether the last one is written.
+const MNEMONICS: &[(&str, Value, usize, bool)] = &[
+    ("add", 1, 3, true),
+    ("mul", 2, 3, true),
+    ("get", 3, 1, true),
+    ("put", 4, 1, false),
+    ("jpt", 5, 2, false),
+    ("jpf", 6, 2, false),
+    ("lt", 7, 3, true),
+    ("eq", 8, 3, true),
+    ("rbo", 9, 1, false),
+    ("halt", 99, 0, false),
+];
+
+// Addressing mode and the expression of an operand.
+struct Operand<'a> {
+    mode: Value,
+    expr: &'a str,
+}
+
+// A line which takes up memory, the names in it are resolved once all
+// labels are known.
+enum Item<'a> {
+    Insn(Value, Vec<Operand<'a>>),
+    Data(Vec<&'a str>),
+}
+
+/// Assemble `source` into a program, see the module documentation for the
+/// format.
+pub fn assemble(source: &str) -> crate::Result<IntcodeProgram> {
+    let mut names = HashMap::new();
+    let mut items = Vec::new();
+    let mut addr = 0;
+    for (nr, line) in source.lines().enumerate() {
+        let line_nr = nr + 1;
+        let mut line = line.split('#').next().unwrap_or("").trim();
+        let mut define = |name: &str, value: Value| {
+            if !is_name(name) {
+                return Err(crate::Error::boxed(E::InvalidLine(line_nr)));
+            }
+            match names.insert(name.to_string(), value) {
+                Some(_) => Err(crate::Error::boxed(E::DuplicateName {
+                    line: line_nr,
+                    name: name.to_string(),
+                })),
+                None => Ok(()),
+            }
+        };
+
+        while let Some((label, rest)) = line.split_once(':') {
+            define(label.trim(), addr as Value)?;
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (keyword, args) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(keyword, args)| (keyword, args.trim()));
+        let item = match keyword {
+            ".const" => {
+                let (name, value) = args
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| crate::Error::boxed(E::InvalidLine(line_nr)))?;
+                let value = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| crate::Error::boxed(E::InvalidLine(line_nr)))?;
+                define(name, value)?;
+                continue;
+            }
+            ".data" => Item::Data(split_operands(args, line_nr)?),
+            mnemonic => parse_insn(mnemonic, args, line_nr)?,
+        };
+        addr += match &item {
+            Item::Insn(_, operands) => 1 + operands.len(),
+            Item::Data(cells) => cells.len(),
+        };
+        items.push((line_nr, item));
+    }
+
+    let mut cells = Vec::with_capacity(addr);
+    for (line, item) in items {
+        match item {
+            Item::Insn(opcode, operands) => {
+                let modes = operands
+                    .iter()
+                    .rev()
+                    .fold(0, |modes, operand| modes * 10 + operand.mode);
+                cells.push(modes * 100 + opcode);
+                for operand in &operands {
+                    cells.push(eval(operand.expr, &names, line)?);
+                }
+            }
+            Item::Data(exprs) => {
+                for expr in exprs {
+                    cells.push(eval(expr, &names, line)?);
+                }
+            }
+        }
+    }
+    Ok(IntcodeProgram::new(cells)?)
+}
+
+// Names of labels and constants, `rb` is the relative base.
+fn is_name(name: &str) -> bool {
+    name != "rb"
+        && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// The comma separated operands in `args`, none of them is empty.
+fn split_operands(args: &str, line: usize) -> crate::Result<Vec<&str>> {
+    if args.is_empty() {
+        return Ok(Vec::new());
+    }
+    let operands: Vec<_> = args.split(',').map(str::trim).collect();
+    if operands.iter().any(|operand| operand.is_empty()) {
+        return Err(crate::Error::boxed(E::InvalidLine(line)));
+    }
+    Ok(operands)
+}
+
+fn parse_insn<'a>(mnemonic: &str, args: &'a str, line: usize) -> crate::Result<Item<'a>> {
+    let &(_, opcode, count, writes) = MNEMONICS
+        .iter()
+        .find(|(name, ..)| *name == mnemonic)
+        .ok_or_else(|| {
+            crate::Error::boxed(E::UnknownMnemonic {
+                line,
+                mnemonic: mnemonic.to_string(),
+            })
+        })?;
+
+    let operands = split_operands(args, line)?
+        .into_iter()
+        .map(|operand| parse_operand(operand, line))
+        .collect::<crate::Result<Vec<_>>>()?;
+    if operands.len() != count {
+        return Err(crate::Error::boxed(E::OperandCount {
+            line,
+            expected: count,
+        }));
+    }
+    if writes && operands[count - 1].mode == 1 {
+        return Err(crate::Error::boxed(E::ImmediateDestination { line }));
+    }
+    Ok(Item::Insn(opcode, operands))
+}
+
+fn parse_operand(operand: &str, line: usize) -> crate::Result<Operand<'_>> {
+    let Some(inner) = operand.strip_prefix('[') else {
+        return Ok(Operand {
+            mode: 1,
+            expr: operand,
+        });
+    };
+    let inner = inner
+        .strip_suffix(']')
+        .ok_or_else(|| crate::Error::boxed(E::InvalidLine(line)))?
+        .trim();
+    match inner.strip_prefix("rb") {
+        Some(offset) if offset.is_empty() || offset.starts_with(['+', '-', ' ']) => Ok(Operand {
+            mode: 2,
+            expr: offset,
+        }),
+        _ if inner.is_empty() => Err(crate::Error::boxed(E::InvalidLine(line))),
+        _ => Ok(Operand {
+            mode: 0,
+            expr: inner,
+        }),
+    }
+}
+
+// Value of the sum `expr`, a leading sign is allowed.
+fn eval(expr: &str, names: &HashMap<String, Value>, line: usize) -> crate::Result<Value> {
+    let expr = expr.trim();
+    let expr = expr.strip_prefix('+').unwrap_or(expr);
+    let expr = expr.replace('-', "+-");
+    let mut value: Value = 0;
+    for (idx, term) in expr.split('+').map(str::trim).enumerate() {
+        let (negate, term) = match term.strip_prefix('-') {
+            Some(term) => (true, term.trim()),
+            None => (false, term),
+        };
+        let term_value = match term.parse::<Value>() {
+            Ok(number) => number,
+            // `-x` starts with an empty term
+            Err(_) if idx == 0 && term.is_empty() && !negate => continue,
+            Err(_) if is_name(term) => *names.get(term).ok_or_else(|| {
+                crate::Error::boxed(E::UndefinedName {
+                    line,
+                    name: term.to_string(),
+                })
+            })?,
+            Err(_) => return Err(crate::Error::boxed(E::InvalidLine(line))),
+        };
+        let term_value = if negate {
+            term_value.checked_neg()
+        } else {
+            Some(term_value)
+        };
+        value = term_value
+            .and_then(|term_value| value.checked_add(term_value))
+            .ok_or_else(|| crate::Error::boxed(E::InvalidLine(line)))?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{IntcodeISS, StopReason};
+
+    #[test]
+    fn test_assemble() {
+        let prog = assemble(
+            "# double the input and loop
+             .const FACTOR 2
+             loop: get [x]
+                   mul [x], FACTOR, [x]
+                   put [x]
+                   jpt 1, loop
+             x:    .data 0",
+        )
+        .unwrap();
+        assert_eq!(
+            prog.cells(),
+            &[3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0, 0]
+        );
+        let mut iss = IntcodeISS::new(&prog);
+        assert_eq!(
+            iss.compute([1, 21].iter()).unwrap(),
+            (StopReason::NeedInput, vec![2, 42])
+        );
+
+        // relative mode, sums and labels in front of data
+        let prog = assemble(
+            "rbo stack
+             add [rb], [rb+1], [rb-1]
+             put [rb - 1]
+             halt
+             end: .data 0
+             stack: .data 20, 22, end-stack",
+        )
+        .unwrap();
+        assert_eq!(
+            prog.cells(),
+            &[109, 10, 22201, 0, 1, -1, 204, -1, 99, 0, 20, 22, -1]
+        );
+        let (reason, output) = IntcodeISS::new(&prog).compute([].iter()).unwrap();
+        assert_eq!((reason, output), (StopReason::ProgramHalt, vec![42]));
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(assemble("").is_err());
+        assert!(assemble("jmp 1").is_err());
+        assert!(assemble("add 1, 2").is_err());
+        assert!(assemble("add 1, 2, 3").is_err());
+        assert!(assemble("get x").is_err());
+        assert!(assemble("put [y]").is_err());
+        assert!(assemble("x: halt\nx: halt").is_err());
+        assert!(assemble(".const x 1\nx: halt").is_err());
+        assert!(assemble("rb: halt").is_err());
+        assert!(assemble("put [1").is_err());
+        assert!(assemble("put 1,,").is_err());
+        assert!(assemble("put 1 1").is_err());
+        assert!(assemble(".const x").is_err());
+        assert!(assemble(&format!("put {}, 1", Value::MAX)).is_err());
+        assert!(assemble(&format!("put {}+1", Value::MAX)).is_err());
+        assert_eq!(assemble("put -2").unwrap().cells(), &[104, -2]);
+    }
+}
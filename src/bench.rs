@@ -0,0 +1,101 @@
+//! Tiny benchmark helper: a closure is run a few times to warm up, then
+//! timed over a number of iterations and reported with the median time.
+//! Allocations are counted if `alloc_count::CountingAlloc` is the global
+//! allocator.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Timings of a benchmarked closure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    pub iterations: usize,
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    // allocations of a single run
+    pub allocations: usize,
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<32} median {:>12?}  min {:>12?}  max {:>12?}  allocs {:>7}  ({} iterations)",
+            self.name, self.median, self.min, self.max, self.allocations, self.iterations
+        )
+    }
+}
+
+/// Benchmark settings, see `run`.
+#[derive(Debug, Clone, Copy)]
+pub struct Bench {
+    pub warmup: usize,
+    pub iterations: usize,
+}
+
+impl Default for Bench {
+    fn default() -> Self {
+        Bench {
+            warmup: 3,
+            iterations: 21,
+        }
+    }
+}
+
+impl Bench {
+    /// Time `f` after `warmup` untimed runs and one run counting the
+    /// allocations. The result of `f` is passed through `black_box` so the
+    /// work isn't optimized away.
+    pub fn run<R>(&self, name: &str, mut f: impl FnMut() -> R) -> Measurement {
+        for _ in 0..self.warmup {
+            std::hint::black_box(f());
+        }
+        let (_, allocations) = crate::alloc_count::count(|| std::hint::black_box(f()));
+        let mut times: Vec<_> = (0..self.iterations.max(1))
+            .map(|_| {
+                let start = Instant::now();
+                std::hint::black_box(f());
+                start.elapsed()
+            })
+            .collect();
+        times.sort();
+        Measurement {
+            name: name.to_string(),
+            iterations: times.len(),
+            median: times[times.len() / 2],
+            min: times[0],
+            max: times[times.len() - 1],
+            allocations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let mut calls = 0;
+        let bench = Bench {
+            warmup: 2,
+            iterations: 5,
+        };
+        let m = bench.run("count", || calls += 1);
+        assert_eq!(calls, 8);
+        assert_eq!(m.allocations, 0);
+        assert_eq!(m.iterations, 5);
+        assert!(m.min <= m.median && m.median <= m.max);
+        assert!(m.to_string().starts_with("count "));
+
+        let m = Bench {
+            warmup: 0,
+            iterations: 0,
+        }
+        .run("once", || vec![1]);
+        assert_eq!(m.iterations, 1);
+        assert_eq!(m.allocations, 1);
+    }
+}
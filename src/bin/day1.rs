@@ -1,34 +1,16 @@
-fn main() {
-    let input = {
-        let fname = std::env::args()
-            .nth(1)
-            .expect("Please give input as first argument!");
-        std::fs::read_to_string(fname).unwrap()
-    };
+use aoc19::day1::{fuel, fuel_sum, parse, total_fuel};
 
-    // part one
-    let module_fuel: i32 = input
-        .lines()
-        .map(|mass| mass.parse::<i32>().unwrap())
-        .map(|mass| mass / 3 - 2)
-        .sum();
-    println!("Module fuel required: {}", module_fuel);
-
-    // part one
-    let total_fuel: i32 = input
-        .lines()
-        .map(|mass| mass.parse::<i32>().unwrap())
-        .map(|mut mass| {
-            let mut total = 0;
-            loop {
-                mass = mass / 3 - 2;
-                if mass < 0 {
-                    break;
-                }
-                total += mass;
-            }
-            total
-        })
-        .sum();
-    println!("Total fuel required: {}", total_fuel);
+// Usage: day1 <input> [--jobs N] [--quiet]
+fn main() -> aoc19::Result<()> {
+    let args = aoc19::cli::Args::from_env(&["--jobs"])?;
+    let answers = aoc19::cli::Answers::from_args(1, &args)?;
+    let masses = aoc19::input::read_with(args.required(0, "input")?, parse)?;
+    let jobs = args.jobs()?;
+    answers.part(1, "Module fuel required", fuel_sum(&masses, jobs, fuel));
+    answers.part(
+        2,
+        "Total fuel required",
+        fuel_sum(&masses, jobs, total_fuel),
+    );
+    Ok(())
 }
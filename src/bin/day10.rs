@@ -1,70 +1,121 @@
-use aoc19::Result;
-use std::collections::HashSet;
+use aoc19::day10::{best_location, create_asteroids, normalize, Asteroids, Vec2D};
+use aoc19::dense::Density;
+use aoc19::grid::Grid2D;
+use aoc19::svg::Svg;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
-struct Vec2D(i32, i32);
-type Asteroids = Vec<Vec2D>;
-
-impl std::ops::Sub for Vec2D {
-    type Output = Self;
-
-    fn sub(self, other: Self) -> Self {
-        Vec2D(self.0 - other.0, self.1 - other.1)
-    }
-}
-
-fn read_input() -> aoc19::Result<String> {
-    std::fs::read_to_string("input/day10").map_err(|e| e.into())
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Space,
+    Asteroid,
+    Station,
 }
 
-fn create_asteroids(input: &String) -> aoc19::Result<Asteroids> {
-    let mut belt = Vec::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, elem) in line.chars().enumerate() {
-            if elem == '#' {
-                belt.push(Vec2D(i32::try_from(x)?, i32::try_from(y)?));
-            }
-        }
+// Map of the asteroids with the monitoring station.
+fn asteroid_map(asteroids: &Asteroids) -> aoc19::Result<Grid2D<Cell>> {
+    let mut map = Grid2D::new(Cell::Space);
+    for asteroid in asteroids {
+        map.set(
+            usize::try_from(asteroid.0)?,
+            usize::try_from(asteroid.1)?,
+            Cell::Asteroid,
+        );
     }
-    Ok(belt)
-}
-
-fn gcd_euclid(a: i32, b: i32) -> i32 {
-    if b == 0 {
-        a
-    } else {
-        gcd_euclid(b, a % b)
+    if let Some((station, _)) = best_location(asteroids) {
+        map.set(
+            usize::try_from(station.0)?,
+            usize::try_from(station.1)?,
+            Cell::Station,
+        );
     }
+    Ok(map)
 }
 
-fn normalize(v: &Vec2D) -> Vec2D {
-    let gcd = gcd_euclid(v.0, v.1).abs();
-    Vec2D(v.0 / gcd, v.1 / gcd)
-}
+// Save the asteroid map as image with the monitoring station highlighted.
+fn save_map(path: &str, map: &Grid2D<Cell>) -> aoc19::Result<()> {
+    const SCALE: usize = 8;
 
-fn compute_visible(origin: &Vec2D, asteroids: &Asteroids) -> usize {
-    let normed_dist = asteroids
-        .iter()
-        .filter(|Vec2D(x, y)| !(origin.0 == *x && origin.1 == *y))
-        .map(|asteroid| normalize(&(*asteroid - *origin)))
-        .collect::<HashSet<Vec2D>>();
-    normed_dist.len()
+    aoc19::image::save(path, map, SCALE, |cell| match cell {
+        Cell::Space => [0x00, 0x00, 0x00],
+        Cell::Asteroid => [0xa0, 0xa0, 0xa0],
+        Cell::Station => [0xe0, 0x30, 0x30],
+    })
 }
 
-fn part_one() -> aoc19::Result<usize> {
-    let asteroids = create_asteroids(&read_input()?)?;
-
-    let max = asteroids
-        .iter()
-        .map(|asteroid| compute_visible(asteroid, &asteroids))
-        .fold(0, |val, max| std::cmp::max(val, max));
-
-    Ok(max)
+// Plot the asteroids with the lines of sight from the monitoring station to
+// every asteroid it detects.
+fn asteroid_svg(asteroids: &Asteroids) -> Svg {
+    const SCALE: f64 = 10.0;
+
+    let width = asteroids.iter().map(|a| a.0 + 1).max().unwrap_or(0);
+    let height = asteroids.iter().map(|a| a.1 + 1).max().unwrap_or(0);
+    let pos = |a: &Vec2D| {
+        (
+            (f64::from(a.0) + 0.5) * SCALE,
+            (f64::from(a.1) + 0.5) * SCALE,
+        )
+    };
+
+    let mut svg = Svg::new(f64::from(width) * SCALE, f64::from(height) * SCALE)
+        .with_background([0x00, 0x00, 0x00]);
+    let station = best_location(asteroids).map(|(station, _)| station);
+    if let Some(station) = station {
+        // the closest asteroid in every direction is the one detected
+        let mut detected: HashMap<Vec2D, Vec2D> = HashMap::new();
+        for asteroid in asteroids.iter().filter(|&&a| a != station) {
+            let dist = |a: &Vec2D| (a.0 - station.0).abs() + (a.1 - station.1).abs();
+            detected
+                .entry(normalize(&(*asteroid - station)))
+                .and_modify(|closest| {
+                    if dist(asteroid) < dist(closest) {
+                        *closest = *asteroid;
+                    }
+                })
+                .or_insert(*asteroid);
+        }
+        for asteroid in detected.values() {
+            svg.path(
+                &[pos(&station), pos(asteroid)],
+                [0x40, 0x40, 0x60],
+                SCALE / 20.0,
+            );
+        }
+    }
+    for asteroid in asteroids {
+        let (x, y) = pos(asteroid);
+        if Some(*asteroid) == station {
+            svg.circle(x, y, SCALE * 0.4, [0xe0, 0x30, 0x30]);
+        } else {
+            svg.circle(x, y, SCALE * 0.3, [0xa0, 0xa0, 0xa0]);
+        }
+    }
+    svg
 }
 
+// Usage: day10 [--image map.ppm|map.bmp|map.png] [--svg map.svg]
+//              [--map [--density braille|half-block|ascii]] [--quiet]
 fn main() -> aoc19::Result<()> {
-    println!("Part one: max num visible asteroids {}", part_one()?);
+    let args = aoc19::cli::Args::from_env(&["--image", "--svg", "--density"])?;
+    let answers = aoc19::cli::Answers::from_args(10, &args)?;
+    let input = aoc19::input::read_to_string("input/day10")?;
+    let asteroids = create_asteroids(&input)?;
+
+    answers.part(1, "max num visible asteroids", aoc19::day10::part1(&input)?);
+
+    if args.flag("--map") {
+        let density = args
+            .parse_value::<Density>("--density")?
+            .unwrap_or_else(Density::detect);
+        let map = asteroid_map(&asteroids)?;
+        answers.narrate(density.render(&map, |&cell| cell != Cell::Space).trim_end());
+    }
+    if let Some(path) = args.value("--image") {
+        save_map(path, &asteroid_map(&asteroids)?)?;
+    }
+    if let Some(path) = args.value("--svg") {
+        asteroid_svg(&asteroids).save(path)?;
+    }
 
     Ok(())
 }
@@ -74,111 +125,19 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_example1() {
-        // .7..7
-        // .....
-        // 67775
-        // ....7
-        // ...87
+    fn test_svg() {
         let input = ".#..#\n\
                      .....\n\
                      #####\n\
                      ....#\n\
-                     ...##"
-            .to_string();
-
-        let asteroids = create_asteroids(&input).unwrap();
-        assert_eq!(compute_visible(&Vec2D(1, 0), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(4, 0), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(0, 2), &asteroids), 6);
-        assert_eq!(compute_visible(&Vec2D(1, 2), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(2, 2), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(3, 2), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(4, 2), &asteroids), 5);
-        assert_eq!(compute_visible(&Vec2D(4, 3), &asteroids), 7);
-        assert_eq!(compute_visible(&Vec2D(3, 4), &asteroids), 8);
-        assert_eq!(compute_visible(&Vec2D(4, 4), &asteroids), 7);
-    }
-
-    #[test]
-    fn test_example2() {
-        let input = "......#.#.\n\
-                     #..#.#....\n\
-                     ..#######.\n\
-                     .#.#.###..\n\
-                     .#..#.....\n\
-                     ..#....#.#\n\
-                     #..#....#.\n\
-                     .##.#..###\n\
-                     ##...#..#.\n\
-                     .#....####"
-            .to_string();
-
-        let asteroids = create_asteroids(&input).unwrap();
-        assert_eq!(compute_visible(&Vec2D(5, 8), &asteroids), 33);
-    }
-
-    #[test]
-    fn test_example3() {
-        let input = "#.#...#.#.\n\
-                     .###....#.\n\
-                     .#....#...\n\
-                     ##.#.#.#.#\n\
-                     ....#.#.#.\n\
-                     .##..###.#\n\
-                     ..#...##..\n\
-                     ..##....##\n\
-                     ......#...\n\
-                     .####.###."
-            .to_string();
-
-        let asteroids = create_asteroids(&input).unwrap();
-        assert_eq!(compute_visible(&Vec2D(1, 2), &asteroids), 35);
-    }
-
-    #[test]
-    fn test_example4() {
-        let input = ".#..#..###\n\
-                     ####.###.#\n\
-                     ....###.#.\n\
-                     ..###.##.#\n\
-                     ##.##.#.#.\n\
-                     ....###..#\n\
-                     ..#.#..#.#\n\
-                     #..#.#.###\n\
-                     .##...##.#\n\
-                     .....#.#.."
-            .to_string();
-
-        let asteroids = create_asteroids(&input).unwrap();
-        assert_eq!(compute_visible(&Vec2D(6, 3), &asteroids), 41);
-    }
-
-    #[test]
-    fn test_example5() {
-        let input = ".#..##.###...#######\n\
-                     ##.############..##.\n\
-                     .#.######.########.#\n\
-                     .###.#######.####.#.\n\
-                     #####.##.#.##.###.##\n\
-                     ..#####..#.#########\n\
-                     ####################\n\
-                     #.####....###.#.#.##\n\
-                     ##.#################\n\
-                     #####.##.###..####..\n\
-                     ..######..##.#######\n\
-                     ####.##.####...##..#\n\
-                     .#####..#.######.###\n\
-                     ##...#.##########...\n\
-                     #.##########.#######\n\
-                     .####.#.###.###.#.##\n\
-                     ....##.##.###..#####\n\
-                     .#.#.###########.###\n\
-                     #.#.#.#####.####.###\n\
-                     ###.##.####.##.#..##"
-            .to_string();
-
-        let asteroids = create_asteroids(&input).unwrap();
-        assert_eq!(compute_visible(&Vec2D(11, 13), &asteroids), 210);
+                     ...##";
+        let asteroids = create_asteroids(input).unwrap();
+        // one line of sight per detected asteroid, the station at (3, 4)
+        let mut out = Vec::new();
+        asteroid_svg(&asteroids).write(&mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert_eq!(svg.matches("<path").count(), 8);
+        assert_eq!(svg.matches("<circle").count(), asteroids.len());
+        assert!(svg.contains("<circle cx=\"35\" cy=\"45\" r=\"4\" fill=\"#e03030\"/>"));
     }
 }
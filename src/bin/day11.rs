@@ -1,358 +1,192 @@
+use aoc19::day11::{paint, PaintingRobot};
+use aoc19::dense::Density;
+use aoc19::grid::Grid2D;
+use aoc19::heatmap::Heatmap;
+use aoc19::intcode::parse_program;
+use aoc19::search;
+use aoc19::term::{self, Key, RawTerminal, Renderer, Slot, Viewport};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
-type Addr = usize;
-type Value = i64;
-const PAGE_SIZE: Addr = 1024;
-
-#[derive(PartialEq, Debug)]
-enum StopReason {
-    NeedInput,
-    ProgramHalt,
-}
-
-struct IntcodeISS {
-    mem: Vec<Value>,
-    pc: Addr,
-    relative_base: Value,
-}
-
 #[derive(Debug)]
-enum Instruction {
-    Add(Addr, Value, Value),
-    Mul(Addr, Value, Value),
-    Get(Addr),
-    Put(Value),
-    Jpt(Value, Addr),
-    Jpf(Value, Addr),
-    Lt(Addr, Value, Value),
-    Eq(Addr, Value, Value),
-    Rbo(Value),
-    Halt,
+#[allow(dead_code)] // fields are reported through Debug
+enum E {
+    UnknownHeatmap(String),
 }
 
-impl IntcodeISS {
-    fn new(mem: &Vec<Value>) -> IntcodeISS {
-        IntcodeISS {
-            mem: mem.to_owned(),
-            pc: 0,
-            relative_base: 0,
-        }
-    }
-
-    fn resize_mem(&mut self, addr: Addr) {
-        let new_size = (addr + PAGE_SIZE) / PAGE_SIZE * PAGE_SIZE;
-        self.mem.resize(new_size as usize, 0);
-    }
-
-    fn peek(&mut self, addr: Addr) -> Value {
-        if let Some(cell) = self.mem.get(addr as usize) {
-            *cell
-        } else {
-            self.resize_mem(addr);
-            self.mem[addr as usize]
-        }
-    }
-
-    fn poke(&mut self, addr: Addr, val: Value) {
-        if let Some(cell) = self.mem.get_mut(addr as usize) {
-            *cell = val;
-        } else {
-            self.resize_mem(addr);
-            self.mem[addr as usize] = val;
-        }
-    }
-
-    fn addr_fetch(&mut self, am: Value, val: Value) -> Addr {
-        match am {
-            0 => val as Addr,
-            1 => val as Addr,
-            2 => (self.relative_base + val) as Addr,
-            _ => unimplemented!(),
-        }
-    }
-
-    fn fetch(&mut self, am: Value, val: Value) -> Value {
-        match am {
-            0 => self.peek(val as Addr),
-            1 => val,
-            2 => self.peek((self.relative_base + val) as Addr),
-            _ => unimplemented!(),
-        }
-    }
-
-    fn decode(&mut self, addr: Addr) -> Instruction {
-        let (md, m2, m1, opcode) = {
-            let word = self.peek(addr);
-            (
-                (word / 10000) % 10,
-                (word / 1000) % 10,
-                (word / 100) % 10,
-                word % 100,
-            )
-        };
-
-        let r1 = self.peek(self.pc + 1);
-        let r2 = self.peek(self.pc + 2);
-        let rd = self.peek(self.pc + 3);
-        match opcode {
-            1 => Instruction::Add(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            2 => Instruction::Mul(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            3 => Instruction::Get(self.addr_fetch(m1, r1)),
-            4 => Instruction::Put(self.fetch(m1, r1)),
-            5 => Instruction::Jpt(self.fetch(m1, r1), self.fetch(m2, r2) as Addr),
-            6 => Instruction::Jpf(self.fetch(m1, r1), self.fetch(m2, r2) as Addr),
-            7 => Instruction::Lt(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            8 => Instruction::Eq(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            9 => Instruction::Rbo(self.fetch(m1, r1)),
-            99 => Instruction::Halt,
-            op @ _ => {
-                dbg!(op);
-                unimplemented!();
-            }
-        }
-    }
-
-    fn compute(&mut self, mut input: std::slice::Iter<'_, Value>) -> (StopReason, Vec<Value>) {
-        enum IssOp {
-            Step(Addr),
-            Jump(Addr),
-            Halt,
-        }
-
-        let mut output = Vec::new();
-        let reason = loop {
-            let iss_op = match self.decode(self.pc) {
-                Instruction::Add(d, op1, op2) => {
-                    self.poke(d, op1 + op2);
-                    IssOp::Step(4)
-                }
-                Instruction::Mul(d, op1, op2) => {
-                    self.poke(d, op1 * op2);
-                    IssOp::Step(4)
-                }
-                Instruction::Get(d) => {
-                    if let Some(&i) = input.next() {
-                        self.poke(d, i);
-                        IssOp::Step(2)
-                    } else {
-                        break StopReason::NeedInput;
-                    }
-                }
-                Instruction::Put(op1) => {
-                    output.push(op1);
-                    IssOp::Step(2)
-                }
-                Instruction::Jpt(op1, d) => {
-                    if op1 != 0 {
-                        IssOp::Jump(d)
-                    } else {
-                        IssOp::Step(3)
-                    }
-                }
-                Instruction::Jpf(op1, d) => {
-                    if op1 == 0 {
-                        IssOp::Jump(d)
-                    } else {
-                        IssOp::Step(3)
-                    }
-                }
-                Instruction::Lt(d, op1, op2) => {
-                    self.poke(d, (op1 < op2) as Value);
-                    IssOp::Step(4)
-                }
-                Instruction::Eq(d, op1, op2) => {
-                    self.poke(d, (op1 == op2) as Value);
-                    IssOp::Step(4)
-                }
-                Instruction::Rbo(op1) => {
-                    self.relative_base += op1;
-                    IssOp::Step(2)
-                }
-                Instruction::Halt => IssOp::Halt,
-            };
-
-            match iss_op {
-                IssOp::Step(len) => self.pc += len,
-                IssOp::Jump(addr) => self.pc = addr,
-                IssOp::Halt => break StopReason::ProgramHalt,
-            }
-        };
-
-        (reason, output)
+// Draw the registration identifier, with one character per panel or packed
+// by `density`.
+fn draw_identifier(image: &Grid2D<i64>, density: Option<Density>) -> String {
+    match density {
+        Some(density) => density.render(image, |&pixel| pixel != 0),
+        None => image.render(|&pixel| if pixel == 0 { ' ' } else { '\u{2588}' }),
     }
 }
 
-fn read_program_from_file() -> aoc19::Result<Vec<Value>> {
-    std::fs::read_to_string("input/day11")
-        .and_then(|input| {
-            let prog = input
-                .split(',')
-                .map(|val| {
-                    val.trim_end_matches('\n')
-                        .parse::<Value>()
-                        .expect(&format!("Parse {} as number failed!", val))
-                })
-                .collect::<Vec<Value>>();
-            Ok(prog)
-        })
-        .map_err(|e| e.into())
+// The robot after painting the hull starting on a black panel.
+fn part_one_robot() -> aoc19::Result<PaintingRobot> {
+    let input = aoc19::input::read_to_string("input/day11")?;
+    paint(&parse_program(&input)?, 0)
 }
 
-enum Facing {
-    Up,
-    Right,
-    Down,
-    Left,
+// Place the values of the panels on a grid with the top left panel at
+// (0, 0), `origin` is the top left panel in robot coordinates.
+fn place<T: Copy>(
+    values: &HashMap<(i64, i64), T>,
+    origin: (i64, i64),
+) -> aoc19::Result<Grid2D<Option<T>>> {
+    let mut grid = Grid2D::new(None);
+    for (&(x, y), &v) in values {
+        grid.set(
+            usize::try_from(x - origin.0)?,
+            usize::try_from(y - origin.1)?,
+            Some(v),
+        );
+    }
+    Ok(grid)
 }
 
-struct PaintingRobot {
-    facing: Facing,
-    position: (i64, i64),
-    panels: HashMap<(i64, i64), i64>,
-    brain: IntcodeISS,
+// Top left panel visited by the robot.
+fn origin(robot: &PaintingRobot) -> (i64, i64) {
+    let min_x = robot.visits.keys().map(|p| p.0).min().unwrap_or(0);
+    let min_y = robot.visits.keys().map(|p| p.1).min().unwrap_or(0);
+    (min_x, min_y)
 }
 
-impl PaintingRobot {
-    fn new(prog: &Vec<Value>) -> PaintingRobot {
-        PaintingRobot {
-            facing: Facing::Up,
-            position: (0, 0),
-            panels: HashMap::new(),
-            brain: IntcodeISS::new(&prog),
-        }
-    }
-
-    fn get_panel_color(&mut self) -> i64 {
-        *self.panels.entry(self.position).or_insert(0)
-    }
-
-    fn set_panel_color(&mut self, col: i64) {
-        self.panels.insert(self.position, col);
-    }
-
-    fn update_facing(&mut self, turn: i64) {
-        self.facing = if turn == 0 {
-            // turn left
-            match self.facing {
-                Facing::Up => Facing::Left,
-                Facing::Right => Facing::Up,
-                Facing::Down => Facing::Right,
-                Facing::Left => Facing::Down,
-            }
-        } else if turn == 1 {
-            // turn right
-            match self.facing {
-                Facing::Up => Facing::Right,
-                Facing::Right => Facing::Down,
-                Facing::Down => Facing::Left,
-                Facing::Left => Facing::Up,
-            }
-        } else {
-            unimplemented!();
-        };
-    }
-
-    fn move_forward(&mut self) {
-        match self.facing {
-            Facing::Up => self.position.1 -= 1,
-            Facing::Right => self.position.0 += 1,
-            Facing::Down => self.position.1 += 1,
-            Facing::Left => self.position.0 -= 1,
-        };
-    }
+// Panels painted by the robot starting on a black panel, placed on a grid
+// with the top left panel at (0, 0).
+fn part_one_hull() -> aoc19::Result<Grid2D<Option<i64>>> {
+    let robot = part_one_robot()?;
+    place(&robot.panels, origin(&robot))
 }
 
-fn part_one() -> aoc19::Result<Value> {
-    let prog = read_program_from_file()?;
-
-    let mut robot = PaintingRobot::new(&prog);
-    loop {
-        let input = vec![robot.get_panel_color()];
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeatmapKind {
+    // how often the robot entered a panel
+    Visits,
+    // steps over painted panels from the start of the robot
+    Distance,
+}
 
-        let (stop_reason, output) = robot.brain.compute(input.iter());
-        assert_eq!(output.len(), 2);
-        robot.set_panel_color(output[0]);
-        robot.update_facing(output[1]);
-        robot.move_forward();
+impl std::str::FromStr for HeatmapKind {
+    type Err = aoc19::Error<E>;
 
-        if stop_reason == StopReason::ProgramHalt {
-            break;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "visits" => Ok(HeatmapKind::Visits),
+            "distance" => Ok(HeatmapKind::Distance),
+            _ => Err(aoc19::Error::new(E::UnknownHeatmap(s.to_string()))),
         }
     }
-    Ok(Value::try_from(robot.panels.len())?)
 }
 
-fn part_two() -> aoc19::Result<Value> {
-    let prog = read_program_from_file()?;
-
-    let mut robot = PaintingRobot::new(&prog);
-    robot.set_panel_color(1); // start on a white panel
-    loop {
-        let input = vec![robot.get_panel_color()];
-
-        let (stop_reason, output) = robot.brain.compute(input.iter());
-        assert_eq!(output.len(), 2);
-        robot.set_panel_color(output[0]);
-        robot.update_facing(output[1]);
-        robot.move_forward();
-
-        if stop_reason == StopReason::ProgramHalt {
-            break;
+// Heatmap values of the panels in part one.
+fn heatmap(kind: HeatmapKind) -> aoc19::Result<Grid2D<Option<u64>>> {
+    let robot = part_one_robot()?;
+    let origin = origin(&robot);
+    match kind {
+        HeatmapKind::Visits => place(&robot.visits, origin),
+        HeatmapKind::Distance => {
+            let hull = place(&robot.panels, origin)?;
+            let start = (usize::try_from(-origin.0)?, usize::try_from(-origin.1)?);
+            Ok(search::distances(&hull, start, |panel| panel.is_some()))
         }
     }
+}
 
-    // create image
-    let mut image: Vec<Vec<Value>> = Vec::new();
-    for (&(x, y), &c) in &robot.panels {
-        let x = usize::try_from(x)?;
-        let y = usize::try_from(y)?;
+// Draw the heatmap on the terminal and save it as image if `image` is given.
+fn show_heatmap(kind: HeatmapKind, image: Option<&str>) -> aoc19::Result<()> {
+    let values = heatmap(kind)?;
+    let max = values.iter().filter_map(|(_, v)| *v).max().unwrap_or(0);
 
-        if y >= image.len() {
-            image.resize_with(y + 1, || Vec::new());
-        }
+    let mut renderer = Renderer::new().with_color(true);
+    renderer.draw_heatmap(&values);
+    renderer.set_status(&format!("Heatmap {:?}, max {}", kind, max));
+    renderer.present()?;
 
-        let line = &mut image[y];
-        if x >= line.len() {
-            line.resize(x + 1, 0);
-        }
-        line[x] = c;
+    if let Some(path) = image {
+        let colors = Heatmap::new(&values);
+        aoc19::image::save(path, &values, 4, |v| match *v {
+            Some(v) => colors.color(v),
+            None => [0, 0, 0],
+        })?;
     }
+    Ok(())
+}
 
-    // draw image
-    for line in image.iter() {
-        for &pixel in line.iter() {
-            if pixel == 0 {
-                print!(" ");
-            } else {
-                print!("\u{2588}");
+// Explore the hull painted in part one, which is larger than the terminal:
+// arrow keys or h/j/k/l pan, +/- zoom, 0 resets the view and q quits.
+fn explore() -> aoc19::Result<()> {
+    let hull = part_one_hull()?;
+
+    let _raw = RawTerminal::enable()?;
+    let stdin = std::io::stdin();
+    let mut stdin = stdin.lock();
+
+    // top and bottom bar plus the row the cursor is parked in
+    let mut renderer = Renderer::new()
+        .with_alternate_screen()
+        .with_viewport(Viewport::fit_terminal(3));
+    renderer.draw_grid(&hull, |&panel| match panel {
+        None => ' ',
+        Some(0) => '\u{b7}',
+        Some(_) => '\u{2588}',
+    });
+    renderer.set_widget(
+        Slot::BottomLeft,
+        "arrows/hjkl: pan  +/-: zoom  0: reset  q: quit",
+    );
+    loop {
+        if let Some(view) = renderer.viewport() {
+            renderer.set_status(&format!(
+                "Hull {}x{}  view at {},{}  1:{}",
+                hull.width(),
+                hull.height(),
+                view.x,
+                view.y,
+                view.scale
+            ));
+        }
+        renderer.present()?;
+        match term::read_key(&mut stdin)? {
+            Key::Char(b'q') | Key::Char(0x03) => break,
+            key => {
+                renderer.pan_zoom(key);
             }
         }
-        println!("");
     }
-
-    Ok(Value::try_from(robot.panels.len())?)
+    Ok(())
 }
 
+// Usage: day11 [--dense [--density braille|half-block|ascii]] [--explore]
+//              [--heatmap visits|distance [--heatmap-image file.ppm|.bmp|.png]] [--quiet]
 fn main() -> aoc19::Result<()> {
-    println!("Part One: Number of panels painted {}", part_one()?);
-    println!("Part Two: Number of panels painted {}", part_two()?);
+    let args = aoc19::cli::Args::from_env(&["--density", "--heatmap", "--heatmap-image"])?;
+    let answers = aoc19::cli::Answers::from_args(11, &args)?;
+    let density = if args.flag("--dense") || args.value("--density").is_some() {
+        Some(
+            args.parse_value::<Density>("--density")?
+                .unwrap_or_else(Density::detect),
+        )
+    } else {
+        None
+    };
+
+    let input = aoc19::input::read_to_string("input/day11")?;
+    answers.part(1, "Number of panels painted", aoc19::day11::part1(&input)?);
+    let image = aoc19::day11::part2(&input)?;
+    answers.picture(
+        2,
+        "Registration identifier",
+        &draw_identifier(&image, density),
+    );
+
+    if let Some(kind) = args.parse_value::<HeatmapKind>("--heatmap")? {
+        show_heatmap(kind, args.value("--heatmap-image"))?;
+    }
+    if args.flag("--explore") {
+        explore()?;
+    }
     Ok(())
 }
 
@@ -360,181 +194,33 @@ fn main() -> aoc19::Result<()> {
 mod test {
     use super::*;
 
-    fn eval(p: &Vec<Value>, result_pos: Addr) -> Value {
-        let input = vec![];
-        let mut iss = IntcodeISS::new(p);
-        iss.compute(input.iter());
-        iss.peek(result_pos)
-    }
-
-    fn eval_with_io(p: &Vec<Value>, input: Vec<Value>) -> Vec<Value> {
-        let mut iss = IntcodeISS::new(p);
-        let (reason, output) = iss.compute(input.iter());
-        assert_eq!(reason, StopReason::ProgramHalt);
-        output
-    }
-
-    #[test]
-    fn test_example1() {
-        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
-        let prog = vec![1, 0, 0, 0, 99];
-        assert_eq!(eval(&prog, 0), 2);
-    }
-
-    #[test]
-    fn test_example2() {
-        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
-        let prog = vec![2, 3, 0, 3, 99];
-        assert_eq!(eval(&prog, 3), 6);
-    }
-
-    #[test]
-    fn test_example3() {
-        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
-        let prog = vec![2, 4, 4, 5, 99, 0];
-        assert_eq!(eval(&prog, 5), 9801);
-    }
-
-    #[test]
-    fn test_example4() {
-        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
-        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
-        assert_eq!(eval(&prog, 0), 30);
-    }
-
-    #[test]
-    fn test_addressing_mode() {
-        // 3 * [4] = 3 * 33 = 99 -> store at [4]
-        let prog = vec![1002, 4, 3, 4, 33];
-        assert_eq!(eval(&prog, 4), 99);
-
-        // 100 - 1 = 99 -> store at [4]
-        let prog = vec![1101, 100, -1, 4, 0];
-        assert_eq!(eval(&prog, 4), 99);
-    }
-
     #[test]
-    fn test_eq_with_load() {
-        // Using position mode, consider whether the input
-        // is equal to 8; output 1 (if it is) or 0 (if it is not).
-        let prog = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
-        let input = vec![8];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![-8];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-    }
-
-    #[test]
-    fn test_lt_with_load() {
-        // Using position mode, consider whether the input
-        // is less than 8; output 1 (if it is) or 0 (if it is not).
-        let prog = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
-        let input = vec![-42];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![3];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![8];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-    }
-
-    #[test]
-    fn test_eq_with_immediate() {
-        // Using immediate mode, consider whether the input
-        // is equal to 8; output 1 (if it is) or 0 (if it is not).
-        let prog = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
-        let input = vec![8];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![-8];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-    }
-
-    #[test]
-    fn test_lt_with_immediate() {
-        // Using immediate mode, consider whether the input
-        // is less than 8; output 1 (if it is) or 0 (if it is not).
-        let prog = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
-        let input = vec![-42];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![3];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![8];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-    }
-
-    #[test]
-    fn test_jump_with_load() {
-        // Take an input, then output 0 if the input was
-        // zero or 1 if the input was non-zero:
-        let prog = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
-        let input = vec![0];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![-7];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-    }
-
-    #[test]
-    fn test_jump_with_immediate() {
-        // Take an input, then output 0 if the input was
-        // zero or 1 if the input was non-zero:
-        let prog = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
-        let input = vec![0];
-        assert_eq!(eval_with_io(&prog, input), vec![0]);
-        let input = vec![-7];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![1]);
-    }
-
-    #[test]
-    fn test_integration() {
-        // The program uses an input instruction to ask for a single number.
-        // i < 8 -> output 999
-        // i = 8 -> output 1000
-        // i > 8 -> output 1001
-        let prog = vec![
-            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
-            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
-            20, 1105, 1, 46, 98, 99,
-        ];
-        let input = vec![-42];
-        assert_eq!(eval_with_io(&prog, input), vec![999]);
-        let input = vec![3];
-        assert_eq!(eval_with_io(&prog, input), vec![999]);
-        let input = vec![8];
-        assert_eq!(eval_with_io(&prog, input), vec![1000]);
-        let input = vec![42];
-        assert_eq!(eval_with_io(&prog, input), vec![1001]);
-    }
-
-    #[test]
-    fn test_boost_example1() {
-        let prog = vec![
-            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
-        ];
-        assert_eq!(eval_with_io(&prog, vec![]), prog);
-    }
-
-    #[test]
-    fn test_boost_example2() {
-        let prog = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
-        let output = eval_with_io(&prog, vec![]);
-        assert_eq!(output.len(), 1);
-        assert_eq!(output[0].to_string().chars().count(), 16);
+    fn test_hull() {
+        if aoc19::fixtures::personal_input(11).is_none() {
+            return;
+        }
+        let hull = part_one_hull().unwrap();
+        assert!(hull.get(0, 0).is_some());
     }
 
     #[test]
-    fn test_boost_example3() {
-        let prog = vec![104, 1125899906842624, 99];
-        assert_eq!(eval_with_io(&prog, vec![]), vec![1125899906842624]);
+    fn test_heatmap() {
+        if aoc19::fixtures::personal_input(11).is_none() {
+            return;
+        }
+        let visits = heatmap(HeatmapKind::Visits).unwrap();
+        let total: u64 = visits.iter().filter_map(|(_, v)| *v).sum();
+        let robot = part_one_robot().unwrap();
+        // every move plus the start panel
+        assert_eq!(total, robot.visits.values().sum::<u64>());
+        assert_eq!(visits.iter().filter(|(_, v)| v.is_some()).count(), 2064);
+
+        let distance = heatmap(HeatmapKind::Distance).unwrap();
+        assert_eq!(distance.iter().filter(|(_, d)| **d == Some(0)).count(), 1);
+        assert!(matches!(
+            "distance".parse::<HeatmapKind>(),
+            Ok(HeatmapKind::Distance)
+        ));
+        assert!("x".parse::<HeatmapKind>().is_err());
     }
 }
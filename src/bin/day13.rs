@@ -1,397 +1,690 @@
+use aoc19::cast::CastWriter;
+use aoc19::cli::Visualize;
+use aoc19::day13::{
+    update_screen, FollowBall, Game, PaddleStrategy, PredictBall, Screen, Summary, Tile,
+};
+use aoc19::gif::GifEncoder;
+use aoc19::grid::Grid2D;
+use aoc19::image::{FrameDir, Rgb};
+use aoc19::intcode::Value;
+use aoc19::palette::Palette;
+use aoc19::replay;
+use aoc19::term::{self, Key, RawTerminal, Renderer, Slot};
+use aoc19::viz::VizSink;
 use std::convert::TryFrom;
+use std::io::{Read, Write};
 
 #[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
 enum E {
-    WrongOutputLength,
-    InvalidTileId,
+    UnknownStrategy(String),
 }
 
-type Addr = usize;
-type Value = i64;
-const PAGE_SIZE: Addr = 1024;
-
-#[derive(PartialEq, Debug)]
-enum StopReason {
-    NeedInput,
-    ProgramHalt,
+fn tile_to_char(tile: Tile) -> char {
+    replay::TILE_CHARS[tile as usize]
 }
 
-struct IntcodeISS {
-    mem: Vec<Value>,
-    pc: Addr,
-    relative_base: Value,
+// Colors and characters of the tiles from the `day13` section of a palette,
+// indexed by tile id.
+#[derive(Clone, Copy)]
+struct TileColors {
+    rgb: [Rgb; 5],
+    chars: [char; 5],
 }
 
-#[derive(Debug)]
-enum Instruction {
-    Add(Addr, Value, Value),
-    Mul(Addr, Value, Value),
-    Get(Addr),
-    Put(Value),
-    Jpt(Value, Addr),
-    Jpf(Value, Addr),
-    Lt(Addr, Value, Value),
-    Eq(Addr, Value, Value),
-    Rbo(Value),
-    Halt,
-}
+impl TileColors {
+    const NAMES: [&'static str; 5] = ["empty", "wall", "block", "paddle", "ball"];
 
-impl IntcodeISS {
-    fn new(mem: &Vec<Value>) -> IntcodeISS {
-        IntcodeISS {
-            mem: mem.to_owned(),
-            pc: 0,
-            relative_base: 0,
+    fn new(palette: &Palette) -> aoc19::Result<TileColors> {
+        let mut colors = TileColors {
+            rgb: [[0; 3]; 5],
+            chars: [' '; 5],
+        };
+        for (idx, name) in TileColors::NAMES.iter().enumerate() {
+            colors.rgb[idx] = palette.color("day13", name)?;
+            colors.chars[idx] = palette.glyph("day13", name)?;
         }
+        Ok(colors)
     }
 
-    fn resize_mem(&mut self, addr: Addr) {
-        let new_size = (addr + PAGE_SIZE) / PAGE_SIZE * PAGE_SIZE;
-        self.mem.resize(new_size as usize, 0);
+    fn rgb(&self, tile: Tile) -> Rgb {
+        self.rgb[tile as usize]
     }
 
-    fn peek(&mut self, addr: Addr) -> Value {
-        if let Some(cell) = self.mem.get(addr as usize) {
-            *cell
-        } else {
-            self.resize_mem(addr);
-            self.mem[addr as usize]
-        }
+    fn glyph(&self, tile: Tile) -> char {
+        self.chars[tile as usize]
     }
+}
 
-    fn poke(&mut self, addr: Addr, val: Value) {
-        if let Some(cell) = self.mem.get_mut(addr as usize) {
-            *cell = val;
-        } else {
-            self.resize_mem(addr);
-            self.mem[addr as usize] = val;
-        }
-    }
+// Draw `screen` with the `status` line into the next frame of `sink`, in
+// the colors and characters of `colors` if given.
+fn draw_screen(sink: &mut dyn VizSink, screen: &Screen, status: &str, colors: Option<&TileColors>) {
+    sink.annotate(Slot::TopLeft, status);
+    sink.draw_grid(&screen.fb, |&tile| match colors {
+        Some(colors) => (colors.glyph(tile), Some(colors.rgb(tile))),
+        None => (tile_to_char(tile), None),
+    });
+}
 
-    fn addr_fetch(&mut self, am: Value, val: Value) -> Addr {
-        match am {
-            0 => val as Addr,
-            1 => val as Addr,
-            2 => (self.relative_base + val) as Addr,
-            _ => unimplemented!(),
-        }
+// Destination for the rendered frames of a game besides the terminal.
+trait FrameExport {
+    fn export(&mut self, screen: &Screen, score: Value) -> aoc19::Result<()>;
+
+    // Complete the export after the last frame.
+    fn finish(&mut self) -> aoc19::Result<()> {
+        Ok(())
     }
+}
 
-    fn fetch(&mut self, am: Value, val: Value) -> Value {
-        match am {
-            0 => self.peek(val as Addr),
-            1 => val,
-            2 => self.peek((self.relative_base + val) as Addr),
-            _ => unimplemented!(),
-        }
+fn finish_exports(exports: &mut [Box<dyn FrameExport>]) -> aoc19::Result<()> {
+    for export in exports.iter_mut() {
+        export.finish()?;
     }
+    Ok(())
+}
 
-    fn decode(&mut self, addr: Addr) -> Instruction {
-        let (md, m2, m1, opcode) = {
-            let word = self.peek(addr);
-            (
-                (word / 10000) % 10,
-                (word / 1000) % 10,
-                (word / 100) % 10,
-                word % 100,
-            )
-        };
+// Records the frames as asciinema cast, drawn by its own renderer. The
+// width leaves room for the score growing after the first frame.
+struct CastExport<W: Write> {
+    cast: CastWriter<W>,
+    renderer: Renderer,
+}
 
-        let r1 = self.peek(self.pc + 1);
-        let r2 = self.peek(self.pc + 2);
-        let rd = self.peek(self.pc + 3);
-        match opcode {
-            1 => Instruction::Add(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            2 => Instruction::Mul(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            3 => Instruction::Get(self.addr_fetch(m1, r1)),
-            4 => Instruction::Put(self.fetch(m1, r1)),
-            5 => Instruction::Jpt(self.fetch(m1, r1), self.fetch(m2, r2) as Addr),
-            6 => Instruction::Jpf(self.fetch(m1, r1), self.fetch(m2, r2) as Addr),
-            7 => Instruction::Lt(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            8 => Instruction::Eq(
-                self.addr_fetch(md, rd),
-                self.fetch(m1, r1),
-                self.fetch(m2, r2),
-            ),
-            9 => Instruction::Rbo(self.fetch(m1, r1)),
-            99 => Instruction::Halt,
-            op @ _ => {
-                dbg!(op);
-                unimplemented!();
-            }
+impl<W: Write> CastExport<W> {
+    fn new(out: W, fps: f64) -> CastExport<W> {
+        CastExport {
+            cast: CastWriter::new(out).with_fps(fps).with_min_size(20, 0),
+            renderer: Renderer::new(),
         }
     }
+}
 
-    fn compute(&mut self, mut input: std::slice::Iter<'_, Value>) -> (StopReason, Vec<Value>) {
-        enum IssOp {
-            Step(Addr),
-            Jump(Addr),
-            Halt,
-        }
-
-        let mut output = Vec::new();
-        let reason = loop {
-            let iss_op = match self.decode(self.pc) {
-                Instruction::Add(d, op1, op2) => {
-                    self.poke(d, op1 + op2);
-                    IssOp::Step(4)
-                }
-                Instruction::Mul(d, op1, op2) => {
-                    self.poke(d, op1 * op2);
-                    IssOp::Step(4)
-                }
-                Instruction::Get(d) => {
-                    if let Some(&i) = input.next() {
-                        self.poke(d, i);
-                        IssOp::Step(2)
-                    } else {
-                        break StopReason::NeedInput;
-                    }
-                }
-                Instruction::Put(op1) => {
-                    output.push(op1);
-                    IssOp::Step(2)
-                }
-                Instruction::Jpt(op1, d) => {
-                    if op1 != 0 {
-                        IssOp::Jump(d)
-                    } else {
-                        IssOp::Step(3)
-                    }
-                }
-                Instruction::Jpf(op1, d) => {
-                    if op1 == 0 {
-                        IssOp::Jump(d)
-                    } else {
-                        IssOp::Step(3)
-                    }
-                }
-                Instruction::Lt(d, op1, op2) => {
-                    self.poke(d, (op1 < op2) as Value);
-                    IssOp::Step(4)
-                }
-                Instruction::Eq(d, op1, op2) => {
-                    self.poke(d, (op1 == op2) as Value);
-                    IssOp::Step(4)
-                }
-                Instruction::Rbo(op1) => {
-                    self.relative_base += op1;
-                    IssOp::Step(2)
-                }
-                Instruction::Halt => IssOp::Halt,
-            };
-
-            match iss_op {
-                IssOp::Step(len) => self.pc += len,
-                IssOp::Jump(addr) => self.pc = addr,
-                IssOp::Halt => break StopReason::ProgramHalt,
-            }
-        };
-
-        (reason, output)
+impl<W: Write> FrameExport for CastExport<W> {
+    fn export(&mut self, screen: &Screen, score: Value) -> aoc19::Result<()> {
+        draw_screen(
+            &mut self.renderer,
+            screen,
+            &format!("Score: {}", score),
+            None,
+        );
+        self.cast.record(&mut self.renderer)?;
+        Ok(())
     }
 }
 
-fn read_program_from_file() -> aoc19::Result<Vec<Value>> {
-    std::fs::read_to_string("input/day13")
-        .and_then(|input| {
-            let prog = input
-                .split(',')
-                .map(|val| {
-                    val.trim_end_matches('\n')
-                        .parse::<Value>()
-                        .expect(&format!("Parse {} as number failed!", val))
-                })
-                .collect::<Vec<Value>>();
-            Ok(prog)
-        })
-        .map_err(|e| e.into())
+// Writes every frame as binary PPM image `frame_NNNNN.ppm` into a
+// directory, each tile drawn as a `scale` x `scale` square.
+struct PpmExport {
+    frames: FrameDir,
+    scale: usize,
+    colors: TileColors,
 }
 
-#[derive(Copy, Clone, PartialEq)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    Paddle,
-    Ball,
-}
+impl PpmExport {
+    const SCALE: usize = 8;
 
-impl TryFrom<Value> for Tile {
-    type Error = aoc19::Error<E>;
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        Ok(match value {
-            0 => Tile::Empty,
-            1 => Tile::Wall,
-            2 => Tile::Block,
-            3 => Tile::Paddle,
-            4 => Tile::Ball,
-            _ => return Err(aoc19::Error::new(E::InvalidTileId)),
+    fn new(dir: &str, colors: TileColors) -> aoc19::Result<PpmExport> {
+        Ok(PpmExport {
+            frames: FrameDir::create(dir)?,
+            scale: PpmExport::SCALE,
+            colors,
         })
     }
 }
 
-struct Screen {
-    fb: Vec<Vec<Tile>>,
-    xball: usize,
-    xpaddle: usize,
-}
-
-impl Screen {
-    fn new() -> Screen {
-        Screen {
-            fb: Vec::new(),
-            xball: 0,
-            xpaddle: 0,
-        }
+impl FrameExport for PpmExport {
+    fn export(&mut self, screen: &Screen, _score: Value) -> aoc19::Result<()> {
+        let colors = &self.colors;
+        self.frames
+            .save(&screen.fb, self.scale, |&tile| colors.rgb(tile))
     }
+}
 
-    fn insert_tile(&mut self, x: usize, y: usize, tile: Tile) {
-        if y >= self.fb.len() {
-            self.fb.resize_with(y + 1, || Vec::new());
-        }
-
-        match tile {
-            Tile::Ball => self.xball = x,
-            Tile::Paddle => self.xpaddle = x,
-            _ => {}
-        }
+// Writes the frames as animated GIF, each tile drawn as a `scale` x `scale`
+// square. The image size is taken from the first frame, later frames of a
+// screen which has grown since are cut to it. The GIF trailer is written by
+// `finish`.
+struct GifExport<W: Write> {
+    out: Option<W>,
+    gif: Option<GifEncoder<W>>,
+    size: (usize, usize),
+    delay: u16,
+    scale: usize,
+    colors: TileColors,
+}
 
-        let line = &mut self.fb[y];
-        if x >= line.len() {
-            line.resize(x + 1, Tile::Empty);
+impl<W: Write> GifExport<W> {
+    const SCALE: usize = 4;
+
+    fn new(out: W, fps: f64, colors: TileColors) -> GifExport<W> {
+        GifExport {
+            out: Some(out),
+            gif: None,
+            size: (0, 0),
+            // GIF delays are in hundredths of a second
+            delay: (100.0 / fps).round().max(1.0) as u16,
+            scale: GifExport::<W>::SCALE,
+            colors,
         }
-        line[x] = tile;
     }
+}
 
-    fn render(&self) {
-        let tile_to_char = |tile| match tile {
-            Tile::Empty => ' ',
-            Tile::Wall => '\u{2588}',
-            Tile::Block => '\u{2592}',
-            Tile::Paddle => '\u{2594}',
-            Tile::Ball => '\u{2022}',
-        };
-
-        for line in self.fb.iter() {
-            for tile in line.iter() {
-                print!("{}", tile_to_char(*tile));
+impl<W: Write> FrameExport for GifExport<W> {
+    fn export(&mut self, screen: &Screen, _score: Value) -> aoc19::Result<()> {
+        if let Some(out) = self.out.take() {
+            self.size = (screen.fb.width(), screen.fb.height());
+            // palette indices are the tile ids
+            self.gif = Some(GifEncoder::new(
+                out,
+                u16::try_from(screen.fb.width() * self.scale)?,
+                u16::try_from(screen.fb.height() * self.scale)?,
+                &self.colors.rgb,
+                self.delay,
+            )?);
+        }
+        if let Some(gif) = self.gif.as_mut() {
+            let (width, height) = self.size;
+            if (screen.fb.width(), screen.fb.height()) == self.size {
+                gif.frame_grid(&screen.fb, self.scale, |&tile| tile as u8)?;
+            } else {
+                let mut fb = Grid2D::with_size(width, height, Tile::Empty);
+                for ((x, y), &tile) in screen.fb.iter() {
+                    if x < width && y < height {
+                        fb.set(x, y, tile);
+                    }
+                }
+                gif.frame_grid(&fb, self.scale, |&tile| tile as u8)?;
             }
-            println!("");
         }
+        Ok(())
     }
 
-    fn count_tile(&self, tile: Tile) -> usize {
-        self.fb.iter().flatten().filter(|&t| *t == tile).count()
+    fn finish(&mut self) -> aoc19::Result<()> {
+        if let Some(gif) = self.gif.take() {
+            gif.finish()?;
+        }
+        Ok(())
     }
 }
 
-fn part_one() -> aoc19::Result<usize> {
-    let prog = read_program_from_file()?;
-
-    let mut iss = IntcodeISS::new(&prog);
-    let (stop_reason, output) = iss.compute(vec![].iter());
-    assert_eq!(stop_reason, StopReason::ProgramHalt);
-
-    if output.len() % 3 != 0 {
-        return Err(aoc19::Error::boxed(E::WrongOutputLength));
-    }
+// Play back a recorded game without executing the intcode program, drawn
+// into `sink` in `colors`. Every frame is also passed to the `exports`.
+fn replay(
+    replay: &str,
+    sink: &mut dyn VizSink,
+    colors: &TileColors,
+    exports: &mut [Box<dyn FrameExport>],
+) -> aoc19::Result<Value> {
+    let frames = replay::read(replay)?;
 
     let mut screen = Screen::new();
-    for chunk in output.chunks_exact(3) {
-        let (x, y, t) = (chunk[0], chunk[1], chunk[2]);
-        screen.insert_tile(usize::try_from(x)?, usize::try_from(y)?, Tile::try_from(t)?);
+    let mut score = 0;
+    let total = frames.len();
+    for (idx, frame) in frames.into_iter().enumerate() {
+        update_screen(&mut screen, &mut score, &frame.output)?;
+        let joystick = match frame.input {
+            Some(-1) => "\u{2190}",
+            Some(1) => "\u{2192}",
+            _ => " ",
+        };
+        draw_screen(
+            sink,
+            &screen,
+            &format!("Score: {} {}", score, joystick),
+            Some(colors),
+        );
+        sink.annotate(Slot::TopRight, &format!("Frame {}/{}", idx + 1, total));
+        sink.frame_done()?;
+        for export in exports.iter_mut() {
+            export.export(&screen, score)?;
+        }
     }
-    Ok(screen.count_tile(Tile::Block))
+    Ok(score)
 }
 
-fn part_two(visualize: bool) -> aoc19::Result<Value> {
-    let prog = read_program_from_file()?;
+// A fresh game of the puzzle input.
+fn new_game() -> aoc19::Result<Game> {
+    Game::new(&aoc19::input::read_with(
+        "input/day13",
+        aoc19::day13::parse,
+    )?)
+}
 
-    let mut iss = IntcodeISS::new(&prog);
-    iss.poke(0, 2); // play for free
+// Play the game with the given paddle `strategy`, starting from `resume`
+// if given. The game is drawn into `sink` in `colors`, every frame is also
+// passed to the `exports`.
+fn part_two(
+    strategy: &mut dyn PaddleStrategy,
+    resume: Option<Game>,
+    sink: &mut dyn VizSink,
+    colors: &TileColors,
+    mut record: Option<&mut dyn Write>,
+    exports: &mut [Box<dyn FrameExport>],
+) -> aoc19::Result<Summary> {
+    let mut game = match resume {
+        Some(game) => game,
+        None => new_game()?,
+    };
+
+    if let Some(out) = record.as_mut() {
+        replay::write_header(*out)?;
+    }
+
+    game.run(strategy, |game, input, output| {
+        if let Some(out) = record.as_mut() {
+            replay::write_frame(*out, input, output)?;
+        }
+        for export in exports.iter_mut() {
+            export.export(&game.screen, game.score)?;
+        }
+        draw_screen(
+            sink,
+            &game.screen,
+            &format!("Score: {}", game.score),
+            Some(colors),
+        );
+        sink.annotate(Slot::TopRight, &format!("Frame {}", game.frames));
+        sink.frame_done()
+    })
+}
 
-    let mut screen = Screen::new();
-    let mut score = 0;
-    let mut input = 0;
-    loop {
-        let (stop_reason, output) = iss.compute(vec![input].iter());
+enum GameKey {
+    Left,
+    Right,
+    Stay,
+    Save,
+    Quit,
+}
 
-        if output.len() % 3 != 0 {
-            return Err(aoc19::Error::boxed(E::WrongOutputLength));
-        }
-        for chunk in output.chunks_exact(3) {
-            let (x, y, t) = (chunk[0], chunk[1], chunk[2]);
-            if x == -1 && y == 0 {
-                score = t;
-            } else {
-                screen.insert_tile(usize::try_from(x)?, usize::try_from(y)?, Tile::try_from(t)?);
-            }
+// Block until one of the game keys is pressed: arrow left/right move the
+// joystick, space or arrow down keep it neutral, s saves, q or ctrl-c quit.
+fn read_game_key(stdin: &mut impl Read) -> std::io::Result<GameKey> {
+    loop {
+        match term::read_key(stdin)? {
+            Key::Char(b'q') | Key::Char(0x03) => return Ok(GameKey::Quit),
+            Key::Char(b' ') | Key::Down => return Ok(GameKey::Stay),
+            Key::Char(b's') => return Ok(GameKey::Save),
+            Key::Left => return Ok(GameKey::Left),
+            Key::Right => return Ok(GameKey::Right),
+            _ => {}
         }
+    }
+}
 
-        if visualize {
-            print!("\x1B[2J"); // clear screen
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            println!("Score: {}", score);
-            screen.render();
+// Play the game interactively in the terminal, the joystick is controlled
+// with the arrow keys. The game can be saved to `save_path` at any time and
+// continued later by passing the loaded state as `resume`.
+// The session can be recorded as asciinema cast with real timing.
+// Returns the score when the game is over or quit.
+fn play(
+    resume: Option<Game>,
+    save_path: &str,
+    mut record: Option<&mut dyn Write>,
+    mut cast: Option<CastWriter<Box<dyn Write>>>,
+    vis: &Visualize,
+) -> aoc19::Result<Value> {
+    let mut game = match resume {
+        Some(game) => game,
+        None => new_game()?,
+    };
+
+    if let Some(out) = record.as_mut() {
+        replay::write_header(*out)?;
+    }
+
+    let _raw = RawTerminal::enable()?;
+    let stdin = std::io::stdin();
+    let mut stdin = stdin.lock();
+
+    let colors = TileColors::new(&vis.stdout_palette()?)?;
+    let mut renderer = Renderer::new()
+        .with_alternate_screen()
+        .with_color(vis.color)
+        .with_overlay(vis.show_fps);
+    renderer.set_widget(
+        Slot::BottomLeft,
+        "\u{2190}/\u{2192}: move  space: stay  s: save  q: quit",
+    );
+    let mut input = None;
+    loop {
+        let output = game.advance(input)?;
+        if let Some(out) = record.as_mut() {
+            replay::write_frame(*out, input, output)?;
         }
 
-        if screen.xball < screen.xpaddle {
-            input = -1;
-        } else if screen.xball > screen.xpaddle {
-            input = 1;
+        let status = format!("Score: {}", game.score);
+        draw_screen(&mut renderer, &game.screen, &status, Some(&colors));
+        renderer.set_widget(
+            Slot::TopRight,
+            &format!("Blocks: {}", game.blocks_remaining()),
+        );
+        match cast.as_mut() {
+            Some(cast) => renderer.present_recorded(cast)?,
+            None => renderer.present()?,
         }
 
-        if stop_reason == StopReason::ProgramHalt {
+        if game.halted {
             break;
         }
+
+        let key = loop {
+            match read_game_key(&mut stdin)? {
+                GameKey::Save => {
+                    std::fs::write(save_path, game.save())?;
+                    renderer.set_widget(Slot::BottomRight, &format!("saved to {}", save_path));
+                    match cast.as_mut() {
+                        Some(cast) => renderer.present_recorded(cast)?,
+                        None => renderer.present()?,
+                    }
+                }
+                key => break key,
+            }
+        };
+        renderer.set_widget(Slot::BottomRight, "");
+        input = Some(match key {
+            GameKey::Left => -1,
+            GameKey::Right => 1,
+            GameKey::Quit => break,
+            _ => 0,
+        });
     }
 
-    Ok(score)
+    Ok(game.score)
 }
 
 fn main() -> aoc19::Result<()> {
-    println!("Part One: Number of blocks after exec {}", part_one()?);
-    println!("Part Two: Final score {}", part_two(false)?);
+    // usage: day13 [--play] [--visualize | --headless] [--fps n] [--frames-dir dir]
+    //              [--no-color] [--ascii] [--show-fps] [--theme name|file] [--serve addr]
+    //              [--record file] [--replay file [--speed x]]
+    //              [--strategy follow|predict] [--save file] [--resume file]
+    //              [--cast file] [--gif file] [--quiet]
+    let args = aoc19::cli::Args::from_env(
+        &[
+            aoc19::cli::VISUALIZE_OPTIONS,
+            &[
+                "--record",
+                "--cast",
+                "--gif",
+                "--replay",
+                "--speed",
+                "--strategy",
+                "--save",
+                "--resume",
+            ],
+        ]
+        .concat(),
+    )?;
+    let answers = aoc19::cli::Answers::from_args(13, &args)?;
+    let vis = Visualize::from_args(&args)?;
+    let colors = TileColors::new(&vis.sink_palette()?)?;
+    let mut strategy: Box<dyn PaddleStrategy> = match args.value("--strategy") {
+        Some("follow") => Box::new(FollowBall),
+        Some("predict") | None => Box::new(PredictBall::new()),
+        Some(other) => {
+            return Err(aoc19::Error::boxed(E::UnknownStrategy(other.to_string())));
+        }
+    };
+
+    let mut exports: Vec<Box<dyn FrameExport>> = Vec::new();
+    // playing records the cast itself, with the real timing
+    let cast_file = args.value("--cast").filter(|_| !args.flag("--play"));
+    if let Some(file) = cast_file {
+        let out = std::io::BufWriter::new(std::fs::File::create(file)?);
+        exports.push(Box::new(CastExport::new(out, vis.fps)));
+    }
+    if let Some(dir) = &vis.frames_dir {
+        exports.push(Box::new(PpmExport::new(dir, colors)?));
+    }
+    if let Some(file) = args.value("--gif") {
+        let out = std::io::BufWriter::new(std::fs::File::create(file)?);
+        exports.push(Box::new(GifExport::new(out, vis.fps, colors)));
+    }
+
+    if let Some(file) = args.value("--replay") {
+        let speed = args.parse_value::<f64>("--speed")?.unwrap_or(1.0);
+        // the replay is paced `speed` times as fast as the frame rate
+        let mut sink = Visualize {
+            fps: vis.fps * speed,
+            ..vis.clone()
+        }
+        .sink()?;
+        let replay_file = aoc19::input::read_to_string(file)?;
+        let score = replay(&replay_file, sink.as_mut(), &colors, &mut exports)?;
+        finish_exports(&mut exports)?;
+        answers.part(2, "Final score", score);
+        return Ok(());
+    }
+
+    let mut record = match args.value("--record") {
+        Some(file) => Some(std::io::BufWriter::new(std::fs::File::create(file)?)),
+        None => None,
+    };
+    let record = record.as_mut().map(|w| w as &mut dyn Write);
+
+    let resume = match args.value("--resume") {
+        Some(file) => Some(aoc19::input::read_with(file, Game::load)?),
+        None => None,
+    };
+
+    if args.flag("--play") {
+        let save_path = args.value("--save").unwrap_or("day13.save");
+        let cast = match args.value("--cast") {
+            Some(file) => {
+                let out: Box<dyn Write> =
+                    Box::new(std::io::BufWriter::new(std::fs::File::create(file)?));
+                Some(CastWriter::new(out))
+            }
+            None => None,
+        };
+        let score = play(resume, save_path, record, cast, &vis)?;
+        answers.part(2, "Final score", score);
+        return Ok(());
+    }
+
+    answers.part(
+        1,
+        "Number of blocks after exec",
+        aoc19::day13::part1(&aoc19::input::read_to_string("input/day13")?)?,
+    );
+    let summary = part_two(
+        strategy.as_mut(),
+        resume,
+        vis.sink()?.as_mut(),
+        &colors,
+        record,
+        &mut exports,
+    )?;
+    finish_exports(&mut exports)?;
+    answers.part(2, "Final score", summary.score);
+    answers.narrate(format!(
+        "{} frames, {} paddle moves, {} blocks remaining",
+        summary.frames, summary.paddle_moves, summary.blocks_remaining
+    ));
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use aoc19::image;
+    use aoc19::intcode::{Addr, IntcodeISS, IntcodeProgram, StopReason};
+    use aoc19::json::json_string;
+    use aoc19::viz::{CaptureSink, NullSink};
+    use std::convert::TryFrom;
 
-    #[test]
-    fn test_part_one() {
-        assert_eq!(part_one().unwrap(), 344);
+    fn default_colors() -> TileColors {
+        TileColors::new(&Palette::default()).unwrap()
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(false).unwrap(), 17336);
+        if aoc19::fixtures::personal_input(13).is_none() {
+            return;
+        }
+        let summary = part_two(
+            &mut PredictBall::new(),
+            None,
+            &mut NullSink,
+            &default_colors(),
+            None,
+            &mut [],
+        )
+        .unwrap();
+        assert_eq!(summary.score, 17336);
+        assert_eq!(summary.blocks_remaining, 0);
+        assert!(summary.paddle_moves > 0 && summary.paddle_moves < summary.frames);
+
+        let summary = part_two(
+            &mut FollowBall,
+            None,
+            &mut NullSink,
+            &default_colors(),
+            None,
+            &mut [],
+        )
+        .unwrap();
+        assert_eq!(summary.score, 17336);
+        assert_eq!(summary.blocks_remaining, 0);
     }
 
-    fn eval(p: &Vec<Value>, result_pos: Addr) -> Value {
-        let input = vec![];
-        let mut iss = IntcodeISS::new(p);
-        iss.compute(input.iter());
+    #[test]
+    fn test_replay_roundtrip() {
+        let mut out = Vec::new();
+        replay::write_header(&mut out).unwrap();
+        replay::write_frame(&mut out, None, &[0, 0, 1, 1, 0, 4]).unwrap();
+        replay::write_frame(&mut out, Some(-1), &[-1, 0, 42]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "day13-replay v1\n- 0,0,1 1,0,4\n-1 -1,0,42\n");
+
+        let frames = replay::read(&text).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].input, None);
+        assert_eq!(frames[0].output, vec![0, 0, 1, 1, 0, 4]);
+        assert_eq!(frames[1].input, Some(-1));
+        assert_eq!(frames[1].output, vec![-1, 0, 42]);
+
+        let mut capture = CaptureSink::new();
+        let score = replay(&text, &mut capture, &default_colors(), &mut []).unwrap();
+        assert_eq!(score, 42);
+        assert_eq!(
+            capture.frames(),
+            &[
+                "Score: 0   Frame 1/2\n\u{2588}\u{2022}\n",
+                "Score: 42 \u{2190} Frame 2/2\n\u{2588}\u{2022}\n",
+            ]
+        );
+
+        assert!(replay::read("- 0,0,1").is_err());
+        assert!(replay::read("day13-replay v1\n- 0,0").is_err());
+        assert!(replay::read("day13-replay v1\nx 0,0,1").is_err());
+    }
+
+    #[test]
+    fn test_update_screen() {
+        let mut screen = Screen::new();
+        let mut score = 0;
+        update_screen(&mut screen, &mut score, &[-1, 0, 42, 2, 1, 3]).unwrap();
+        assert_eq!(score, 42);
+        assert_eq!((screen.xpaddle, screen.ypaddle), (2, 1));
+
+        let mut update = |output: &[Value]| update_screen(&mut screen, &mut score, output);
+        // a partial triple waits for the rest of it
+        assert!(update(&[0, 0]).is_ok());
+        assert!(update(&[1]).is_ok());
+        assert!(update(&[0, 0, 5]).is_err());
+        // only (-1, 0) is a sentinel
+        assert!(update(&[-1, 1, 0]).is_err());
+        assert!(update(&[-2, 0, 0]).is_err());
+        assert!(update(&[0, -1, 0]).is_err());
+        // absurd positions don't allocate a huge screen
+        assert!(update(&[Screen::MAX_WIDTH as Value, 0, 1]).is_err());
+        assert!(update(&[0, 1 << 40, 1]).is_err());
+        assert_eq!((screen.fb.width(), screen.fb.height()), (3, 2));
+        assert_eq!(screen.fb.get(0, 0), Some(&Tile::Wall));
+    }
+
+    fn small_screen() -> Screen {
+        let mut screen = Screen::new();
+        screen.insert_tile(0, 0, Tile::Wall);
+        screen.insert_tile(1, 0, Tile::Block);
+        screen.insert_tile(1, 1, Tile::Ball);
+        screen
+    }
+
+    #[test]
+    fn test_cast_export() {
+        assert_eq!(json_string("a\"b\\\n\x1B"), "\"a\\\"b\\\\\\n\\u001b\"");
+
+        let mut out = Vec::new();
+        let mut cast = CastExport::new(&mut out, 2.0);
+        let mut screen = small_screen();
+        cast.export(&screen, 0).unwrap();
+        screen.insert_tile(1, 0, Tile::Empty);
+        cast.export(&screen, 5).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "{\"version\": 2, \"width\": 20, \"height\": 4}");
+        assert!(lines[1].starts_with("[0.000, \"o\", \"\\u001b[2J"));
+        // second frame only updates the status line and the removed block
+        assert_eq!(
+            lines[2],
+            "[0.500, \"o\", \"\\u001b[1;1HScore: 5\\u001b[K\\u001b[2;2H \\u001b[4;1H\"]"
+        );
+    }
+
+    #[test]
+    fn test_gif_export() {
+        let mut out = Vec::new();
+        {
+            let colors = TileColors::new(&Palette::default()).unwrap();
+            let mut gif = GifExport::new(&mut out, 2.0, colors);
+            let mut screen = small_screen();
+            gif.export(&screen, 0).unwrap();
+            screen.insert_tile(1, 0, Tile::Empty);
+            gif.export(&screen, 5).unwrap();
+            // a grown screen is cut to the size of the first frame
+            screen.fb.grow_to(3, 4);
+            gif.export(&screen, 5).unwrap();
+            gif.finish().unwrap();
+        }
+        assert_eq!(&out[0..6], b"GIF89a");
+        // 2 x 2 tiles at 4 x 4 pixels each
+        assert_eq!(&out[6..10], &[8, 0, 8, 0]);
+        // three frames, half a second each
+        let gce: &[u8] = &[0x21, 0xf9, 0x04, 0x00, 50, 0];
+        assert_eq!(out.windows(gce.len()).filter(|w| *w == gce).count(), 3);
+        assert_eq!(out.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut out = Vec::new();
+        let colors = TileColors::new(&Palette::default()).unwrap();
+        image::write_ppm(&mut out, &small_screen().fb, 2, |&tile| colors.rgb(tile)).unwrap();
+        let header = b"P6\n4 4\n255\n";
+        assert_eq!(&out[..header.len()], header);
+        let pixels = &out[header.len()..];
+        assert_eq!(pixels.len(), 4 * 4 * 3);
+        // first row: two wall pixels and two block pixels
+        assert_eq!(&pixels[0..3], &colors.rgb(Tile::Wall));
+        assert_eq!(&pixels[6..9], &colors.rgb(Tile::Block));
+        // last row: empty, then the ball
+        assert_eq!(&pixels[36..39], &colors.rgb(Tile::Empty));
+        assert_eq!(&pixels[42..45], &colors.rgb(Tile::Ball));
+    }
+
+    fn eval(p: &[Value], result_pos: Addr) -> Value {
+        let input = [];
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        iss.compute(input.iter()).unwrap();
         iss.peek(result_pos)
     }
 
-    fn eval_with_io(p: &Vec<Value>, input: Vec<Value>) -> Vec<Value> {
-        let mut iss = IntcodeISS::new(p);
-        let (reason, output) = iss.compute(input.iter());
+    fn eval_with_io(p: &[Value], input: Vec<Value>) -> Vec<Value> {
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        let (reason, output) = iss.compute(input.iter()).unwrap();
         assert_eq!(reason, StopReason::ProgramHalt);
         output
     }
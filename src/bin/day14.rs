@@ -1,161 +1,64 @@
-use std::collections::HashMap;
+use aoc19::day14::{Nanofactory, E, MAX_ORE};
 
-type ElemId = i64;
-type Elem = (ElemId, i64);
-type Reactions = HashMap<Elem, Vec<Elem>>;
-type ElemLookup = HashMap<String, ElemId>;
-
-#[derive(Debug)]
-enum Err {
-    InvalidInput,
-    FuelTokenNotFound,
-    OreTokenNotFound,
-    UnknownElement,
-}
-
-fn split_formula(formula: &str) -> aoc19::Result<(&str, &str)> {
-    let p: Vec<_> = formula.split("=>").collect();
-    if p.len() != 2 {
-        return Err(aoc19::Error::boxed(Err::InvalidInput));
-    }
-    Ok((p[0], p[1]))
+fn read_input() -> aoc19::Result<Nanofactory> {
+    aoc19::input::read_with("input/day14", Nanofactory::parse)
 }
 
-fn split_reactant(reactant: &str) -> aoc19::Result<(String, i64)> {
-    let p: Vec<_> = reactant.split_ascii_whitespace().collect();
-    if p.len() != 2 {
-        return Err(aoc19::Error::boxed(Err::InvalidInput));
-    }
-    Ok((p[1].to_string(), p[0].parse::<i64>()?))
-}
-
-fn gen_reactions(formulas: &String) -> aoc19::Result<(Reactions, ElemLookup)> {
-    let mut reactions = HashMap::new();
-
-    let mut idcnt = 0;
-    let mut ids = HashMap::new();
-
-    for formula in formulas.lines() {
-        let (in_formula, result) = split_formula(formula)?;
-
-        let mut reactants = Vec::new();
-        for reactant in in_formula.split(',') {
-            let reactant = reactant.trim();
-            let (r, q) = split_reactant(reactant)?;
-            let id = *ids.entry(r).or_insert_with(|| {
-                idcnt += 1;
-                idcnt
-            });
-            reactants.push((id, q));
+fn main() -> aoc19::Result<()> {
+    // usage: day14 [--dot reactions.dot] [--graphml reactions.graphml] [--report [--fuel N]]
+    //        day14 cost <quantity> <chemical> [--report]
+    //        day14 --format text|json
+    // --quiet prints only the answers, reports always go to stderr.
+    let args = aoc19::cli::Args::from_env(&["--dot", "--graphml", "--fuel"])?;
+    let answers = aoc19::cli::Answers::from_args(14, &args)?;
+    let factory = read_input()?;
+
+    if args.positional(0) == Some("cost") {
+        let missing = || aoc19::Error::boxed(aoc19::cli::E::MissingValue("cost".to_string()));
+        let quantity = args.positional(1).ok_or_else(missing)?;
+        let chemical = args.positional(2).ok_or_else(missing)?;
+        let quantity = quantity.parse::<i64>().map_err(|_| {
+            aoc19::Error::boxed(aoc19::cli::E::InvalidValue(
+                "cost".to_string(),
+                quantity.to_string(),
+            ))
+        })?;
+
+        let ore = factory.ore_for(chemical, quantity)?;
+        if args.flag("--report") {
+            let id = factory
+                .chemical(chemical)
+                .ok_or_else(|| aoc19::Error::boxed(E::UnknownChemical(chemical.to_string())))?;
+            factory.write_report(&mut std::io::stderr(), &factory.produce(id, quantity)?)?;
         }
-
-        let (r, q) = split_reactant(result)?;
-        let id = *ids.entry(r).or_insert_with(|| {
-            idcnt += 1;
-            idcnt
-        });
-        reactions.insert((id, q), reactants);
+        if answers.quiet() {
+            println!("{}", ore);
+        } else {
+            println!("produce {} {} requieres {} ORE", quantity, chemical, ore);
+        }
+        return Ok(());
     }
 
-    Ok((reactions, ids))
-}
-
-fn requiere_n_reactions(quantity_needed: i64, quatity_per_reaction: i64) -> i64 {
-    (quantity_needed + quatity_per_reaction - 1) / quatity_per_reaction
-}
-
-fn react(
-    product_id: ElemId,
-    mut needed_quantity: i64,
-    reactions: &Reactions,
-    ore_id: ElemId,
-    remaining: &mut HashMap<ElemId, i64>,
-) -> aoc19::Result<i64> {
-    let (&(_, product_quantity), reactants) = reactions
-        .iter()
-        .find(|(e, _)| e.0 == product_id)
-        .ok_or(aoc19::Error::boxed(Err::UnknownElement))?;
-
-    let remaining_quantity = remaining.entry(product_id).or_insert(0);
-    if *remaining_quantity >= needed_quantity {
-        *remaining_quantity -= needed_quantity;
-        return Ok(0);
-    } else {
-        needed_quantity -= *remaining_quantity;
-        *remaining_quantity = 0;
+    if let Some(dot) = args.value("--dot") {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(dot)?);
+        factory.write_dot(&mut out)?;
     }
-
-    let reaction_cnt = requiere_n_reactions(needed_quantity, product_quantity);
-    *remaining_quantity += reaction_cnt * product_quantity - needed_quantity;
-
-    let mut ore_cnt = 0;
-    for &(reactant_id, reactant_quantity) in reactants {
-        if reactant_id == ore_id {
-            ore_cnt += reaction_cnt * reactant_quantity;
-            continue;
-        }
-        ore_cnt += react(
-            reactant_id,
-            reaction_cnt * reactant_quantity,
-            reactions,
-            ore_id,
-            remaining,
-        )?;
+    if let Some(path) = args.value("--graphml") {
+        factory.graphml().save(path)?;
+    }
+    if args.flag("--report") {
+        let fuel = args.parse_value::<i64>("--fuel")?.unwrap_or(1);
+        let fuel_id = factory
+            .chemical("FUEL")
+            .ok_or_else(|| aoc19::Error::boxed(E::FuelTokenNotFound))?;
+        let production = factory.produce(fuel_id, fuel)?;
+        eprintln!("Producing {} FUEL:", fuel);
+        factory.write_report(&mut std::io::stderr(), &production)?;
     }
 
-    Ok(ore_cnt)
-}
-
-fn part_one() -> aoc19::Result<i64> {
-    let formulas = std::fs::read_to_string("input/day14")?;
-    let (reactions, lookup) = gen_reactions(&formulas)?;
-    let fuel_id = *lookup
-        .get("FUEL")
-        .ok_or(aoc19::Error::boxed(Err::FuelTokenNotFound))?;
-    let ore_id = *lookup
-        .get("ORE")
-        .ok_or(aoc19::Error::boxed(Err::OreTokenNotFound))?;
-
-    react(fuel_id, 1, &reactions, ore_id, &mut HashMap::new())
-}
-
-fn part_two() -> aoc19::Result<i64> {
-    let formulas = std::fs::read_to_string("input/day14")?;
-    let (reactions, lookup) = gen_reactions(&formulas)?;
-    let fuel_id = *lookup
-        .get("FUEL")
-        .ok_or(aoc19::Error::boxed(Err::FuelTokenNotFound))?;
-    let ore_id = *lookup
-        .get("ORE")
-        .ok_or(aoc19::Error::boxed(Err::OreTokenNotFound))?;
-
-    const MAX_ORE: i64 = 1_000_000_000_000;
-
-    let mut upper = MAX_ORE; // random choice
-    let mut lower = 0;
-    let fuel = loop {
-        let cand = (upper + lower) / 2;
-        let ore = react(fuel_id, cand, &reactions, ore_id, &mut HashMap::new())?;
-
-        if ore > MAX_ORE {
-            upper = cand;
-        } else {
-            lower = cand;
-        }
-
-        if upper - lower == 1 {
-            break lower;
-        }
-    };
-    Ok(fuel)
-}
-
-fn main() -> aoc19::Result<()> {
-    println!("Part One: produce 1 FUEL requieres {} ORE", part_one()?);
-    println!(
-        "Part Two: with 1 trillion ORE can produce {} FUEL",
-        part_two()?
-    );
+    let (ore, fuel) = (factory.ore_for_fuel(1)?, factory.max_fuel(MAX_ORE)?);
+    answers.part(1, "ORE required to produce 1 FUEL", ore);
+    answers.part(2, "FUEL produced with 1 trillion ORE", fuel);
     Ok(())
 }
 
@@ -163,18 +66,6 @@ fn main() -> aoc19::Result<()> {
 mod tests {
     use super::*;
 
-    fn react_one_fuel(formulas: String) -> aoc19::Result<i64> {
-        let (reactions, lookup) = gen_reactions(&formulas)?;
-        let fuel_id = *lookup
-            .get("FUEL")
-            .ok_or(aoc19::Error::boxed(Err::FuelTokenNotFound))?;
-        let ore_id = *lookup
-            .get("ORE")
-            .ok_or(aoc19::Error::boxed(Err::OreTokenNotFound))?;
-
-        react(fuel_id, 1, &reactions, ore_id, &mut HashMap::new())
-    }
-
     #[test]
     fn test_example1() -> aoc19::Result<()> {
         let input = r"10 ORE => 10 A
@@ -184,7 +75,7 @@ mod tests {
                      7 A, 1 D => 1 E
                      7 A, 1 E => 1 FUEL"
             .to_string();
-        assert_eq!(react_one_fuel(input)?, 31);
+        assert_eq!(Nanofactory::parse(&input)?.ore_for_fuel(1)?, 31);
         Ok(())
     }
 
@@ -198,7 +89,7 @@ mod tests {
                       4 C, 1 A => 1 CA
                       2 AB, 3 BC, 4 CA => 1 FUEL"
             .to_string();
-        assert_eq!(react_one_fuel(input)?, 165);
+        assert_eq!(Nanofactory::parse(&input)?.ore_for_fuel(1)?, 165);
         Ok(())
     }
 
@@ -214,7 +105,7 @@ mod tests {
                       165 ORE => 2 GPVTF
                       3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT"
             .to_string();
-        assert_eq!(react_one_fuel(input)?, 13312);
+        assert_eq!(Nanofactory::parse(&input)?.ore_for_fuel(1)?, 13312);
         Ok(())
     }
 
@@ -233,7 +124,7 @@ mod tests {
                       1 VJHF, 6 MNCFX => 4 RFSQX
                       176 ORE => 6 VJHF"
             .to_string();
-        assert_eq!(react_one_fuel(input)?, 180697);
+        assert_eq!(Nanofactory::parse(&input)?.ore_for_fuel(1)?, 180697);
         Ok(())
     }
 
@@ -257,7 +148,7 @@ mod tests {
                       7 XCVML => 6 RJRHP
                       5 BHXH, 4 VRPVC => 5 LTCX"
             .to_string();
-        assert_eq!(react_one_fuel(input)?, 2210736);
+        assert_eq!(Nanofactory::parse(&input)?.ore_for_fuel(1)?, 2210736);
         Ok(())
     }
 }
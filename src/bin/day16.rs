@@ -1,135 +1,73 @@
-use std::iter::Iterator;
+use aoc19::csv::CsvWriter;
+use aoc19::day16::{fft, real_signal_message};
 
-struct FFTPattern {
-    repeat: usize,
-    cnt: usize,
-    coefficient_id: usize,
+fn read_input() -> aoc19::Result<Vec<i32>> {
+    aoc19::input::read_with("input/day16", aoc19::input::parse_digits)
 }
 
-impl FFTPattern {
-    const COEFFICIENTS: [i32; 4] = [0, 1, 0, -1];
-
-    fn new(repeat: usize) -> FFTPattern {
-        assert!(repeat != 0);
-        FFTPattern {
-            repeat,
-            cnt: 0,
-            coefficient_id: 0,
-        }
-    }
+fn to_string(digits: &[i32], len: usize) -> String {
+    digits.iter().take(len).map(|n| n.to_string()).collect()
 }
 
-impl Iterator for FFTPattern {
-    type Item = i32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let coefficient = FFTPattern::COEFFICIENTS[self.coefficient_id];
-
-        if self.cnt == self.repeat - 1 {
-            self.coefficient_id = (self.coefficient_id + 1) & 0x03;
+// Write the signal after every phase (including the input as phase 0) as
+// csv, one column per digit.
+fn write_phases<W: std::io::Write>(
+    input: &[i32],
+    phases: usize,
+    jobs: usize,
+    out: W,
+) -> aoc19::Result<()> {
+    let digits = (0..input.len()).map(|i| format!("d{}", i));
+    let mut csv = CsvWriter::new(out, std::iter::once("phase".to_string()).chain(digits))?;
+    let mut signal = input.to_vec();
+    for phase in 0..=phases {
+        if phase > 0 {
+            signal = fft(&signal, 1, jobs);
         }
-        self.cnt = (self.cnt + 1) % self.repeat;
-
-        Some(coefficient)
-    }
-}
-
-fn compute_fft_phase(input: Vec<i32>) -> Vec<i32> {
-    let len = input.len();
-    let mut output = Vec::with_capacity(len);
-
-    for i in 0..len {
-        let res = input
-            .iter()
-            .zip(FFTPattern::new(i + 1).skip(1).take(len))
-            .fold(0, |res, (e, c)| res + e * c);
-        output.push(res.abs() % 10);
+        let digits = signal.iter().map(|d| d.to_string());
+        csv.row(std::iter::once(phase.to_string()).chain(digits))?;
     }
-
-    output
-}
-
-// compute simplified FFT if pattern can be reduced to triangular
-// matrix, see description in part_two()
-fn compute_fft_phase_triangular(input: Vec<i32>) -> Vec<i32> {
-    let len = input.len();
-    let mut output = Vec::with_capacity(len);
-    output.resize(len, 0);
-
-    output[len - 1] = input[len - 1];
-    for i in (0..len - 1).rev() {
-        output[i] = i32::abs(input[i] + output[i + 1]) % 10;
-    }
-
-    output
+    csv.finish()?;
+    Ok(())
 }
 
-fn read_input() -> aoc19::Result<Vec<i32>> {
-    let input = std::fs::read_to_string("input/day16")?;
-
-    let nums: Vec<_> = input
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as i32)
-        .collect();
-    Ok(nums)
+fn part_one(input: &[i32], phases: usize, len: usize, jobs: usize) -> String {
+    to_string(&fft(input, phases, jobs), len)
 }
 
-fn part_one() -> aoc19::Result<String> {
-    let mut input = read_input()?;
-
-    for _ in 0..100 {
-        input = compute_fft_phase(input);
-    }
-
-    Ok(input[0..8]
-        .iter()
-        .map(|n| n.to_string())
-        .collect::<String>())
-}
-
-fn part_two() -> aoc19::Result<String> {
-    let mut input = read_input()?;
-    input = input.repeat(10_000);
-
-    let offset = input[0..7]
-        .iter()
-        .map(|n| n.to_string())
-        .collect::<String>()
-        .parse::<usize>()?;
-
-    assert!(offset > input.len() / 2);
-    // if offset > input.len()/2 we get triangular matrix
-    // IN:    A  B  C  A  B  C
-    //     0  1  0 -1  0  1  0
-    //     0  0  1  1  0  0 -1
-    //     0  0  0  1  1  1  0
-    //     0  0  0  0  1  1  1 <- starting: offset > input.len()/2
-    //     0  0  0  0  0  1  1
-    //     0  0  0  0  0  0  1
-    //
-    // FFT can be simplified to
-    //   fft[len-1] = (IN[len-1]) % 10
-    //   fft[len-2] = (IN[len-2] + fft[len-1]) % 10
-    //   ...
-    //   fft[offset] = (IN[offset] + fft[offset+1]) % 10
-
-    input = input[offset..].to_vec();
-    for _ in 0..100 {
-        input = compute_fft_phase_triangular(input);
-    }
-
-    Ok(input[0..8]
-        .iter()
-        .map(|n| n.to_string())
-        .collect::<String>())
+fn part_two(
+    input: &[i32],
+    repeat: usize,
+    phases: usize,
+    len: usize,
+    jobs: usize,
+) -> aoc19::Result<String> {
+    Ok(to_string(
+        &real_signal_message(input, repeat, phases, jobs)?,
+        len,
+    ))
 }
 
 fn main() -> aoc19::Result<()> {
-    println!(
-        "Part One: first eigth numbers after 100x FFT '{}'",
-        part_one()?
-    );
-    println!("Part Two: '{}'", part_two()?);
+    // usage: day16 [--jobs N] [--phases N] [--repeat N] [--len N] [--format text|json]
+    //              [--csv phases.csv] [--quiet]
+    let args = aoc19::cli::Args::from_env(&["--jobs", "--phases", "--repeat", "--len", "--csv"])?;
+    let answers = aoc19::cli::Answers::from_args(16, &args)?;
+    let jobs = args.jobs()?;
+    let phases = args.parse_value::<usize>("--phases")?.unwrap_or(100);
+    let repeat = args.parse_value::<usize>("--repeat")?.unwrap_or(10_000);
+    let len = args.parse_value::<usize>("--len")?.unwrap_or(8);
+
+    let input = read_input()?;
+    if let Some(csv) = args.value("--csv") {
+        let out = std::io::BufWriter::new(std::fs::File::create(csv)?);
+        write_phases(&input, phases, jobs, out)?;
+    }
+    let first = part_one(&input, phases, len, jobs);
+    let message = part_two(&input, repeat, phases, len, jobs)?;
+    let description = format!("first {} numbers after {}x FFT", len, phases);
+    answers.part(1, &description, first);
+    answers.part(2, "message", message);
     Ok(())
 }
 
@@ -138,41 +76,34 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_fft_pattern() {
-        assert_eq!(
-            FFTPattern::new(1).take(8).collect::<Vec<_>>(),
-            vec![0, 1, 0, -1, 0, 1, 0, -1]
-        );
-        assert_eq!(
-            FFTPattern::new(2).take(8).collect::<Vec<_>>(),
-            vec![0, 0, 1, 1, 0, 0, -1, -1]
-        );
-        assert_eq!(
-            FFTPattern::new(3).take(12).collect::<Vec<_>>(),
-            vec![0, 0, 0, 1, 1, 1, 0, 0, 0, -1, -1, -1]
-        );
+    fn test_part_one() {
+        if aoc19::fixtures::personal_input(16).is_none() {
+            return;
+        }
+        let input = read_input().unwrap();
+        assert_eq!(part_one(&input, 100, 8, 1), "19239468");
+        assert_eq!(part_one(&input, 100, 8, 3), "19239468");
     }
 
     #[test]
-    fn test_fft() {
-        let input = vec![1, 1, 1, 1];
-        assert_eq!(compute_fft_phase(input), vec![0, 2, 2, 1]);
+    fn test_part_two() {
+        if aoc19::fixtures::personal_input(16).is_none() {
+            return;
+        }
+        let input = read_input().unwrap();
+        assert_eq!(part_two(&input, 10_000, 100, 8, 2).unwrap(), "96966221");
     }
 
     #[test]
-    fn test_example1() {
-        let input = vec![1, 2, 3, 4, 5, 6, 7, 8];
-
-        let res = compute_fft_phase(input);
-        assert_eq!(res, vec![4, 8, 2, 2, 6, 1, 5, 8]);
-
-        let res = compute_fft_phase(res);
-        assert_eq!(res, vec![3, 4, 0, 4, 0, 4, 3, 8]);
-
-        let res = compute_fft_phase(res);
-        assert_eq!(res, vec![0, 3, 4, 1, 5, 5, 1, 8]);
-
-        let res = compute_fft_phase(res);
-        assert_eq!(res, vec![0, 1, 0, 2, 9, 4, 9, 8]);
+    fn test_write_phases() {
+        let mut csv = Vec::new();
+        write_phases(&[1, 2, 3, 4, 5, 6, 7, 8], 2, 1, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "phase,d0,d1,d2,d3,d4,d5,d6,d7\n\
+             0,1,2,3,4,5,6,7,8\n\
+             1,4,8,2,2,6,1,5,8\n\
+             2,3,4,0,4,0,4,3,8\n"
+        );
     }
 }
@@ -1,247 +1,78 @@
+use aoc19::day3::{compute_wire, Pos2D};
+use aoc19::svg::Svg;
 use std::collections::HashSet;
-use std::iter::FromIterator;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
-type Pos2D = (i32, i32);
 
-#[derive(Copy,Clone)]
-enum Action {
-    U(i32),
-    D(i32),
-    R(i32),
-    L(i32),
-}
-
-fn unroll_action(c: &mut Vec<Pos2D>, a: Action, mut p: Pos2D) -> Pos2D {
-    let step: fn(x: Pos2D) -> Pos2D;
-    let num = match a {
-        Action::U(s) => {
-            step = |x| (x.0, x.1 + 1);
-            s
-        }
-        Action::D(s) => {
-            step = |x| (x.0, x.1 - 1);
-            s
-        }
-        Action::R(s) => {
-            step = |x| (x.0 + 1, x.1);
-            s
-        }
-        Action::L(s) => {
-            step = |x| (x.0 - 1, x.1);
-            s
-        }
+// Draw the wires from the central port (black dot) with their
+// intersections marked red. The y axis points up as in the puzzle.
+fn wires_svg(wires: &[Vec<Pos2D>]) -> Svg {
+    const COLORS: [aoc19::image::Rgb; 2] = [[0x20, 0x60, 0xc0], [0x20, 0xa0, 0x40]];
+
+    let points = || wires.iter().flatten().chain(std::iter::once(&(0, 0)));
+    let min_x = points().map(|p| p.0).min().unwrap_or(0);
+    let max_x = points().map(|p| p.0).max().unwrap_or(0);
+    let min_y = points().map(|p| p.1).min().unwrap_or(0);
+    let max_y = points().map(|p| p.1).max().unwrap_or(0);
+    let size = f64::from((max_x - min_x).max(max_y - min_y).max(1));
+    let margin = size / 50.0;
+    let pos = |p: &Pos2D| {
+        (
+            f64::from(p.0 - min_x) + margin,
+            f64::from(max_y - p.1) + margin,
+        )
     };
 
-    c.reserve(num as usize);
-    for _ in 0..num {
-        p = step(p);
-        c.push(p);
+    let mut svg = Svg::new(
+        f64::from(max_x - min_x) + 2.0 * margin,
+        f64::from(max_y - min_y) + 2.0 * margin,
+    )
+    .with_background([0xff, 0xff, 0xff]);
+    for (wire, &rgb) in wires.iter().zip(COLORS.iter().cycle()) {
+        // only the corners, the wire holds every single step
+        let steps: Vec<_> = std::iter::once(&(0, 0)).chain(wire).collect();
+        let dir = |a: &Pos2D, b: &Pos2D| (b.0 - a.0, b.1 - a.1);
+        let corners = steps
+            .windows(3)
+            .filter(|w| dir(w[0], w[1]) != dir(w[1], w[2]))
+            .map(|w| w[1]);
+        let path: Vec<_> = std::iter::once(steps[0])
+            .chain(corners)
+            .chain(steps.last().copied().filter(|_| steps.len() > 1))
+            .map(pos)
+            .collect();
+        svg.path(&path, rgb, size / 500.0);
     }
-    p
-}
-
-fn compute_wire(actions: &Vec<Action>) -> Vec<Pos2D> {
-    let mut pos: Pos2D = (0, 0);
-    let mut coords: Vec<Pos2D> = Vec::new();
-
-    for action in actions {
-        pos = unroll_action(&mut coords, *action, pos);
+    if let [wire1, wire2] = wires {
+        let wire2: HashSet<_> = wire2.iter().collect();
+        for p in wire1.iter().filter(|p| wire2.contains(p)) {
+            let (x, y) = pos(p);
+            svg.circle(x, y, size / 200.0, [0xe0, 0x30, 0x30]);
+        }
     }
-    coords
-}
-
-fn compute_manhattan_distance(wire1: &Vec<Pos2D>, wire2: &Vec<Pos2D>) -> i32 {
-    let make_set = |vec: &Vec<Pos2D>| -> HashSet<Pos2D> { HashSet::from_iter(vec.iter().cloned()) };
-    make_set(&wire1)
-        .intersection(&make_set(&wire2))
-        .fold(std::i32::MAX, |dist, coord| {
-            std::cmp::min(dist, coord.0.abs() + coord.1.abs())
-        })
-}
-
-fn compute_fewest_steps(wire1: &Vec<Pos2D>, wire2: &Vec<Pos2D>) -> i32 {
-    let make_set = |vec: &Vec<Pos2D>| -> HashSet<Pos2D> { HashSet::from_iter(vec.iter().cloned()) };
-    make_set(&wire1)
-        .intersection(&make_set(&wire2))
-        .fold(std::i32::MAX, |steps, inter| {
-            std::cmp::min(
-                steps,
-                wire1.iter().position(|x| x == inter).unwrap() as i32 + 1 +
-                wire2.iter().position(|x| x == inter).unwrap() as i32 + 1
-            )
-        })
+    let (x, y) = pos(&(0, 0));
+    svg.circle(x, y, size / 150.0, [0x00, 0x00, 0x00]);
+    svg
 }
 
+// Usage: d03 <file> [--svg wires.svg] [--quiet]
 fn main() -> Result<()> {
-    let wires = {
-        let fname = std::env::args().nth(1).unwrap_or_else(|| {
-            println!("usage: d03 <file>");
-            std::process::exit(1);
-        });
-
-        let actionize = |input: &str| -> Result<Action> {
-            let mut input = input.chars();
-            let a = input.next();
-            let steps = input.as_str().parse::<i32>()?;
-            let res = match a {
-                Some(a) if a == 'U' => Action::U(steps),
-                Some(a) if a == 'D' => Action::D(steps),
-                Some(a) if a == 'R' => Action::R(steps),
-                Some(a) if a == 'L' => Action::L(steps),
-                _ => unimplemented!(),
-            };
-            Ok(res)
-        };
-
-        let wire_descriptions = std::fs::read_to_string(fname)?;
-        let mut wires: Vec<Vec<Action>> = Vec::new();
-        for wire_description in wire_descriptions.lines() {
-            let wire = wire_description
-                .split(',')
-                .map(|action| actionize(action))
-                .collect::<Result<Vec<Action>>>()?;
-            wires.push(wire);
-        }
-
-        wires
-    };
-
-    if wires.len() != 2 {
-        println!(
-            "Error: Input contained {} wires, expected only two wires!",
-            wires.len()
-        );
-        std::process::exit(1);
-    }
-
-    println!(
-        "Part One: manhattan distance = {}",
-        compute_manhattan_distance(&compute_wire(&wires[0]), &compute_wire(&wires[1]))
+    let args = aoc19::cli::Args::from_env(&["--svg"])?;
+    let answers = aoc19::cli::Answers::from_args(3, &args)?;
+    let input = aoc19::input::read_to_string(args.required(0, "input")?)?;
+
+    answers.part(1, "manhattan distance =", aoc19::day3::part1(&input)?);
+    answers.part(
+        2,
+        "steps to the intersection with fewest steps =",
+        aoc19::day3::part2(&input)?,
     );
 
-    println!(
-        "Part Two: intersection with fewest steps = {} steps",
-        compute_fewest_steps(&compute_wire(&wires[0]), &compute_wire(&wires[1]))
-    );
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod test {
-    use super::Action::*;
-    use super::*;
-
-    #[test]
-    fn test_example1() {
-        let w1 = vec![R(8), U(5), L(5), D(3)];
-        let w2 = vec![U(7), R(6), D(4), L(4)];
-
-        assert_eq!(
-            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
-            6
-        );
-    }
-
-    #[test]
-    fn test_example2() {
-        let w1 = vec![R(75), D(30), R(83), U(83), L(12), D(49), R(71), U(7), L(72)];
-        let w2 = vec![U(62), R(66), U(55), R(34), D(71), R(55), D(58), R(83)];
-
-        assert_eq!(
-            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
-            159
-        );
-    }
-
-    #[test]
-    fn test_example3() {
-        let w1 = vec![
-            R(98),
-            U(47),
-            R(26),
-            D(63),
-            R(33),
-            U(87),
-            L(62),
-            D(20),
-            R(33),
-            U(53),
-            R(51),
-        ];
-        let w2 = vec![
-            U(98),
-            R(91),
-            D(20),
-            R(16),
-            D(67),
-            R(40),
-            U(7),
-            R(15),
-            U(6),
-            R(7),
-        ];
-
-        assert_eq!(
-            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
-            135
-        );
-    }
-
-    #[test]
-    fn test2_example1() {
-        let w1 = vec![R(8), U(5), L(5), D(3)];
-        let w2 = vec![U(7), R(6), D(4), L(4)];
-
-        assert_eq!(
-            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
-            30
-        );
+    if let Some(path) = args.value("--svg") {
+        let wires = aoc19::day3::parse(&input)?;
+        let coords: Vec<_> = wires.iter().map(|wire| compute_wire(wire)).collect();
+        wires_svg(&coords).save(path)?;
     }
 
-    #[test]
-    fn test2_example2() {
-        let w1 = vec![R(75), D(30), R(83), U(83), L(12), D(49), R(71), U(7), L(72)];
-        let w2 = vec![U(62), R(66), U(55), R(34), D(71), R(55), D(58), R(83)];
-
-        assert_eq!(
-            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
-            610
-        );
-    }
-
-    #[test]
-    fn test2_example3() {
-        let w1 = vec![
-            R(98),
-            U(47),
-            R(26),
-            D(63),
-            R(33),
-            U(87),
-            L(62),
-            D(20),
-            R(33),
-            U(53),
-            R(51),
-        ];
-        let w2 = vec![
-            U(98),
-            R(91),
-            D(20),
-            R(16),
-            D(67),
-            R(40),
-            U(7),
-            R(15),
-            U(6),
-            R(7),
-        ];
-
-        assert_eq!(
-            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
-            410
-        );
-    }
+    Ok(())
 }
@@ -1,139 +1,112 @@
-type Pixel = u32;
-type Layer = Vec<Pixel>;
-
-fn parse_space_image_format(image: &[Pixel], layer_w: usize, layer_h: usize) -> Vec<Layer> {
-    assert_eq!(image.len() % (layer_h * layer_w), 0);
-    image
-        .chunks_exact(layer_h * layer_w)
-        .map(|pixels| pixels.to_vec())
-        .collect::<Vec<Layer>>()
-}
-
-fn read_input() -> std::io::Result<Vec<Pixel>> {
-    let input = std::fs::read_to_string("input/day8")?;
-    let res = input
-        .trim()
-        .chars()
-        .map(|c| c.to_digit(10).unwrap())
-        .collect();
-    Ok(res)
+use aoc19::cli::Visualize;
+use aoc19::day8::{Pixel, HEIGHT, TRANSPARENT, WIDTH};
+use aoc19::grid::Grid2D;
+use aoc19::image::{FrameDir, Rgb};
+use aoc19::palette::Palette;
+use aoc19::term::Slot;
+
+// Colors and characters of the pixels from the `day8` section of a palette,
+// indexed by black, white and transparent.
+struct PixelColors {
+    rgb: [Rgb; 3],
+    chars: [char; 3],
 }
 
-fn part_one() -> std::io::Result<usize> {
-    let pixels = read_input()?;
-    let layers = parse_space_image_format(&pixels, 25, 6);
-
-    let cnt_n = |layer: &Layer, n| layer.iter().filter(|&&p| p == n).count();
+impl PixelColors {
+    const NAMES: [&'static str; 3] = ["black", "white", "transparent"];
+
+    fn new(palette: &Palette) -> aoc19::Result<PixelColors> {
+        let mut colors = PixelColors {
+            rgb: [[0; 3]; 3],
+            chars: [' '; 3],
+        };
+        for (idx, name) in PixelColors::NAMES.iter().enumerate() {
+            colors.rgb[idx] = palette.color("day8", name)?;
+            colors.chars[idx] = palette.glyph("day8", name)?;
+        }
+        Ok(colors)
+    }
 
-    let min_0_layer = {
-        let mut min_0 = std::usize::MAX;
-        let mut min_0_id = 0;
-        for (id, layer) in layers.iter().enumerate() {
-            let cnt_0 = cnt_n(layer, 0);
-            if cnt_0 < min_0 {
-                min_0 = cnt_0;
-                min_0_id = id;
-            }
+    fn index(pixel: Pixel) -> usize {
+        match pixel {
+            1 => 1,
+            TRANSPARENT => 2,
+            _ => 0,
         }
-        min_0_id
-    };
+    }
 
-    let num_1 = cnt_n(&layers[min_0_layer], 1);
-    let num_2 = cnt_n(&layers[min_0_layer], 2);
+    fn rgb(&self, pixel: Pixel) -> Rgb {
+        self.rgb[PixelColors::index(pixel)]
+    }
 
-    Ok(num_1 * num_2)
+    fn glyph(&self, pixel: Pixel) -> char {
+        self.chars[PixelColors::index(pixel)]
+    }
 }
 
-fn part_two() -> std::io::Result<()> {
-    const WIDTH: usize = 25;
-    const HEIGHT: usize = 6;
-
-    let pixels = read_input()?;
-    let layers = parse_space_image_format(&pixels, WIDTH, HEIGHT);
-
-    let mut image = Vec::new();
-    image.reserve(WIDTH * HEIGHT);
-    for p in 0..WIDTH * HEIGHT {
-        let visible_layer = layers
-            .iter()
-            .find(|layer| layer[p] != 2 /* transparent */)
-            .unwrap();
-        image.push(visible_layer[p]);
-    }
+// Show how the image is decoded: starting from a transparent image the
+// layers are stacked from the back to the front, one frame per layer.
+fn visualize(
+    input: &str,
+    vis: &Visualize,
+    colors: &PixelColors,
+    scale: usize,
+) -> aoc19::Result<()> {
+    let layers = aoc19::day8::parse(input)?;
+
+    let mut sink = vis.sink()?;
+    let mut frames = match &vis.frames_dir {
+        Some(dir) => Some(FrameDir::create(dir)?),
+        None => None,
+    };
 
-    for h in 0..HEIGHT {
-        for w in 0..WIDTH {
-            let p = image[h * WIDTH + w];
-            if p == 1 {
-                print!("\u{2588}");
-            } else {
-                print!(" ");
+    let mut image = Grid2D::new(TRANSPARENT);
+    image.set(WIDTH - 1, HEIGHT - 1, TRANSPARENT);
+    for (idx, layer) in layers.iter().enumerate().rev() {
+        for (p, &pixel) in layer.iter().enumerate() {
+            if pixel != TRANSPARENT {
+                image.set(p % WIDTH, p / WIDTH, pixel);
             }
         }
-        println!("");
-    }
-
-    Ok(())
-}
-
-fn main() -> std::io::Result<()> {
-    println!("Part One: result {}", part_one()?);
-    println!("Part Two:");
-    part_two()?;
 
+        if let Some(frames) = frames.as_mut() {
+            frames.save(&image, scale, |&pixel| colors.rgb(pixel))?;
+        }
+        sink.annotate(
+            Slot::TopLeft,
+            &format!("Layer {:3} of {}", idx + 1, layers.len()),
+        );
+        sink.draw_grid(&image, |&pixel| {
+            (colors.glyph(pixel), Some(colors.rgb(pixel)))
+        });
+        sink.frame_done()?;
+    }
     Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_parse() {
-        let image = vec![1, 2, 3, 4, 5, 6, 7, 8];
-
-        let layers = parse_space_image_format(&image, 2, 1);
-        assert_eq!(layers.len(), 4);
-        let expectation = vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]];
-        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
-
-        let layers = parse_space_image_format(&image, 2, 4);
-        assert_eq!(layers.len(), 1);
-        let expectation = vec![vec![1, 2, 3, 4, 5, 6, 7, 8]];
-        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
-
-        let layers = parse_space_image_format(&image, 4, 2);
-        assert_eq!(layers.len(), 1);
-        let expectation = vec![vec![1, 2, 3, 4, 5, 6, 7, 8]];
-        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+// Usage: day8 [--image message.ppm|message.bmp|message.png]
+//             [--visualize | --headless | --serve addr] [--fps n] [--frames-dir dir]
+//             [--no-color] [--ascii] [--show-fps] [--theme name|file] [--quiet]
+fn main() -> aoc19::Result<()> {
+    const SCALE: usize = 8;
+
+    let args = aoc19::cli::Args::from_env(&[aoc19::cli::VISUALIZE_OPTIONS, &["--image"]].concat())?;
+    let input = aoc19::input::read_to_string("input/day8")?;
+    let vis = Visualize::from_args(&args)?;
+    if vis.enabled || vis.frames_dir.is_some() {
+        let colors = PixelColors::new(&vis.sink_palette()?)?;
+        visualize(&input, &vis, &colors, SCALE)?;
     }
 
-    #[test]
-    fn test_example1() {
-        let input = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2];
-        let w = 3;
-        let h = 2;
+    let answers = aoc19::cli::Answers::from_args(8, &args)?;
+    answers.part(1, "result", aoc19::day8::part1(&input)?);
+    let image = aoc19::day8::part2(&input)?;
+    let colors = PixelColors::new(&vis.stdout_palette()?)?;
+    answers.picture(2, "message", &image.render(|&pixel| colors.glyph(pixel)));
 
-        let layers = parse_space_image_format(&input, w, h);
-        assert_eq!(layers.len(), 2);
-        let expectation = vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]];
-        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+    if let Some(path) = args.value("--image") {
+        aoc19::image::save(path, &image, SCALE, |&pixel| colors.rgb(pixel))?;
     }
 
-    #[test]
-    fn test_example2() {
-        let input = vec![0, 2, 2, 2, 1, 1, 2, 2, 2, 2, 1, 2, 0, 0, 0, 0];
-
-        let layers = parse_space_image_format(&input, 2, 2);
-
-        let mut image = Vec::new();
-        for p in 0..4 {
-            let color = layers
-                .iter()
-                .find(|layer| layer[p] != 2 /* transparent */)
-                .unwrap();
-            image.push(color[p]);
-        }
-        assert_eq!(image, vec![0, 1, 1, 0]);
-    }
+    Ok(())
 }
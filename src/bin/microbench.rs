@@ -0,0 +1,183 @@
+use aoc19::bench::Bench;
+use aoc19::cache::{Cache, Cacheable};
+use aoc19::day14::Nanofactory;
+use aoc19::day16::PrefixSums;
+use aoc19::grid::Grid2D;
+use aoc19::intcode::{Backend, IntcodeISS, IntcodeProgram, Value};
+use std::convert::TryFrom;
+
+// Count down from 10000 to 0 in a loop, exercising decode of add, compare
+// and jump instructions.
+const COUNTDOWN: [Value; 13] = [1101, 10000, 0, 100, 1001, 100, -1, 100, 1005, 100, 4, 99, 0];
+
+#[global_allocator]
+static ALLOC: aoc19::alloc_count::CountingAlloc = aoc19::alloc_count::CountingAlloc;
+
+// Chain of `n` orbits COM)O0, O0)O1, ..
+fn synthetic_orbits(n: usize) -> String {
+    let mut orbits = String::from("COM)O0\n");
+    for i in 1..n {
+        orbits.push_str(&format!("O{})O{}\n", i - 1, i));
+    }
+    orbits
+}
+
+// Chain of `n` reactions 1 ORE => 1 C0, 1 C0 => 1 C1, .. ending in FUEL.
+fn synthetic_reactions(n: usize) -> String {
+    let mut formulas = String::from("1 ORE => 1 C0\n");
+    for i in 1..n - 1 {
+        formulas.push_str(&format!("1 C{} => 1 C{}\n", i - 1, i));
+    }
+    formulas.push_str(&format!("1 C{} => 1 FUEL\n", n - 2));
+    formulas
+}
+
+// Map of `size` x `size` with every 16th position, picked pseudo-randomly,
+// holding an asteroid.
+fn synthetic_field(size: usize) -> String {
+    let mut state = 0x2545_f491_u32;
+    let mut field = String::with_capacity(size * (size + 1));
+    for _ in 0..size {
+        for _ in 0..size {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            field.push(if state.is_multiple_of(16) { '#' } else { '.' });
+        }
+        field.push('\n');
+    }
+    field
+}
+
+// Named closure to benchmark, returning some result of its work.
+type Benchmark<'a> = (&'static str, Box<dyn FnMut() -> i64 + 'a>);
+
+// Usage: microbench [filter..] [--warmup N] [--iterations N]
+//
+// Runs the benchmarks whose name contains any of the filters (all if none is
+// given). Build with --release for meaningful numbers. The allocations of a
+// single run are reported along with the times.
+fn main() -> aoc19::Result<()> {
+    let args = aoc19::cli::Args::from_env(&["--warmup", "--iterations"])?;
+    let defaults = Bench::default();
+    let bench = Bench {
+        warmup: args.parse_value("--warmup")?.unwrap_or(defaults.warmup),
+        iterations: args
+            .parse_value("--iterations")?
+            .unwrap_or(defaults.iterations),
+    };
+    let filters: Vec<_> = (0..).map_while(|idx| args.positional(idx)).collect();
+    let selected = |name: &str| filters.is_empty() || filters.iter().any(|f| name.contains(f));
+
+    let countdown = IntcodeProgram::try_from(&COUNTDOWN[..])?;
+    let boost = aoc19::intcode::parse_program(&aoc19::input::read_to_string("input/day9")?)?;
+    let amp_sw = aoc19::day7::parse(&aoc19::input::read_to_string("input/day7")?)?;
+    let signal = aoc19::input::parse_digits(&aoc19::input::read_to_string("input/day16")?)?;
+    let formulas = aoc19::input::read_to_string("input/day14")?;
+    let moons = aoc19::day12::System::parse(&aoc19::input::read_to_string("input/day12")?)?;
+    let field = aoc19::day10::create_asteroids(&synthetic_field(500))?;
+    let orbits = synthetic_orbits(20_000);
+    let reactions = synthetic_reactions(5_000);
+    let cache = Cache::new(std::env::temp_dir().join("aoc19-microbench-cache"));
+
+    let mut benches: Vec<Benchmark> = vec![
+        (
+            "intcode/countdown",
+            Box::new(|| {
+                let mut iss = IntcodeISS::new(&countdown);
+                match iss.compute([].iter()) {
+                    Ok(_) => iss.peek(100),
+                    Err(_) => -1,
+                }
+            }),
+        ),
+        (
+            "intcode/countdown-interpreted",
+            Box::new(|| {
+                let mut iss = IntcodeISS::new(&countdown).with_backend(Backend::Interpreter);
+                match iss.compute([].iter()) {
+                    Ok(_) => iss.peek(100),
+                    Err(_) => -1,
+                }
+            }),
+        ),
+        (
+            "intcode/boost-test",
+            Box::new(|| aoc19::day9::run_boost(&boost, 1).unwrap_or(-1)),
+        ),
+        (
+            "grid/set-get-128x128",
+            Box::new(|| {
+                let mut grid = Grid2D::new(0i64);
+                for y in 0..128 {
+                    for x in 0..128 {
+                        grid.set(x, y, (x ^ y) as i64);
+                    }
+                }
+                (0..128)
+                    .flat_map(|y| (0..128).map(move |x| (x, y)))
+                    .filter_map(|(x, y)| grid.get(x, y))
+                    .sum()
+            }),
+        ),
+        (
+            "permutations/gen-combinations-7",
+            Box::new(|| aoc19::day7::gen_combinations((0..7).collect()).len() as i64),
+        ),
+        (
+            "permutations/day7-max-signal",
+            Box::new(|| {
+                let eval = aoc19::day7::eval_amp_chain;
+                aoc19::day7::max_signal(&amp_sw, vec![0, 1, 2, 3, 4], eval, 1)
+                    .map_or(-1, Into::into)
+            }),
+        ),
+        (
+            "fft/phase-prefix-sums",
+            Box::new(|| aoc19::day16::fft_with(&signal, 1, 1, PrefixSums::Scalar)[0].into()),
+        ),
+        (
+            "fft/phase-prefix-sums-lanes",
+            Box::new(|| aoc19::day16::fft_with(&signal, 1, 1, PrefixSums::Lanes)[0].into()),
+        ),
+        (
+            "parse/day14-text",
+            Box::new(|| Nanofactory::parse(&formulas).map_or(-1, |f| f.to_cells().len() as i64)),
+        ),
+        (
+            "parse/day14-cached",
+            Box::new(|| {
+                cache
+                    .load_or_parse(&formulas, Nanofactory::parse)
+                    .map_or(-1, |f| f.to_cells().len() as i64)
+            }),
+        ),
+        (
+            "day12/energy-after-1000",
+            Box::new(|| aoc19::day12::energy_after(moons.clone(), 1000).into()),
+        ),
+        (
+            "day12/steps-to-repeat",
+            Box::new(|| aoc19::day12::steps_to_repeat(moons.clone()).map_or(-1, |s| s as i64)),
+        ),
+        (
+            "day10/best-location-500x500",
+            Box::new(|| aoc19::day10::best_location(&field).map_or(-1, |(_, n)| n as i64)),
+        ),
+        (
+            "parse/day6-synthetic-20000",
+            Box::new(|| aoc19::day6::parse(&orbits).map_or(-1, |_| 0)),
+        ),
+        (
+            "parse/day14-synthetic-5000",
+            Box::new(|| Nanofactory::parse(&reactions).map_or(-1, |_| 0)),
+        ),
+    ];
+
+    for (name, f) in benches.iter_mut() {
+        if selected(name) {
+            println!("{}", bench.run(name, f));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use aoc19::image::FrameDir;
+use aoc19::term::{self, Key, RawTerminal, Renderer, Slot};
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+enum E {
+    MissingRecording,
+    NoFrames(String),
+}
+
+// Load the frames of a recording as text: a directory of text frames
+// captured by a headless visualization (`frame_NNNNN.txt`) or a day13
+// replay file.
+fn load_frames(path: &str) -> aoc19::Result<Vec<String>> {
+    let frames = if std::path::Path::new(path).is_dir() {
+        let mut frames = Vec::new();
+        while let Ok(text) = std::fs::read_to_string(
+            std::path::Path::new(path).join(FrameDir::name(frames.len(), "txt")),
+        ) {
+            frames.push(text);
+        }
+        frames
+    } else {
+        aoc19::replay::text_frames(&aoc19::input::read_with(path, aoc19::replay::read)?)?
+    };
+    if frames.is_empty() {
+        return Err(aoc19::Error::boxed(E::NoFrames(path.to_string())));
+    }
+    Ok(frames)
+}
+
+// Position and state of the playback.
+#[derive(Debug, PartialEq)]
+struct Player {
+    frames: usize,
+    pos: usize,
+    playing: bool,
+    fps: f64,
+}
+
+impl Player {
+    const MIN_FPS: f64 = 0.5;
+    const MAX_FPS: f64 = 240.0;
+
+    fn new(frames: usize, fps: f64) -> Player {
+        Player {
+            frames,
+            pos: 0,
+            playing: true,
+            fps,
+        }
+    }
+
+    fn seek(&mut self, pos: isize) {
+        self.pos = pos.clamp(0, self.frames as isize - 1) as usize;
+    }
+
+    // Apply a key: space pauses/resumes, arrow left/right step one frame
+    // (pausing the playback), arrow up/down seek by a tenth of the recording,
+    // g/G go to the start/end and +/- change the speed. Returns false if the
+    // viewer is quit.
+    fn key(&mut self, key: Key) -> bool {
+        let pos = self.pos as isize;
+        let tenth = (self.frames as isize / 10).max(1);
+        match key {
+            Key::Char(b'q') | Key::Char(0x03) => return false,
+            Key::Char(b' ') => self.playing = !self.playing,
+            Key::Left | Key::Right => {
+                self.playing = false;
+                self.seek(if key == Key::Left { pos - 1 } else { pos + 1 });
+            }
+            Key::Down => self.seek(pos - tenth),
+            Key::Up => self.seek(pos + tenth),
+            Key::Char(b'g') => self.seek(0),
+            Key::Char(b'G') => self.seek(self.frames as isize),
+            Key::Char(b'+') => self.fps = (self.fps * 2.0).min(Player::MAX_FPS),
+            Key::Char(b'-') => self.fps = (self.fps / 2.0).max(Player::MIN_FPS),
+            _ => {}
+        }
+        true
+    }
+
+    // Advance to the next frame while playing, the playback pauses at the
+    // last frame.
+    fn tick(&mut self) {
+        if self.playing {
+            self.seek(self.pos as isize + 1);
+            self.playing = self.pos + 1 < self.frames;
+        }
+    }
+}
+
+fn draw(renderer: &mut Renderer, frame: &str, last: &str, player: &Player) {
+    // blank out what the previous frame drew beyond the current one
+    for (y, line) in last.lines().enumerate() {
+        for x in 0..line.chars().count() {
+            renderer.set(x, y, ' ');
+        }
+    }
+    for (y, line) in frame.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            renderer.set(x, y, c);
+        }
+    }
+    renderer.set_status(&format!(
+        "Frame {}/{} {}",
+        player.pos + 1,
+        player.frames,
+        if player.playing { "playing" } else { "paused" }
+    ));
+    renderer.set_widget(Slot::TopRight, &format!("{} fps", player.fps));
+}
+
+// Usage: replay <frames-dir|day13-replay> [--fps n]
+//
+// Keys: space pause, left/right step, up/down seek, g/G start/end, +/- speed,
+// q quit.
+fn main() -> aoc19::Result<()> {
+    let args = aoc19::cli::Args::from_env(&["--fps"])?;
+    let path = match args.positional(0) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: replay <frames-dir|day13-replay> [--fps n]");
+            return Err(aoc19::Error::boxed(E::MissingRecording));
+        }
+    };
+    let frames = load_frames(path)?;
+    let fps = args
+        .parse_value::<f64>("--fps")?
+        .unwrap_or(aoc19::cli::Visualize::DEFAULT_FPS)
+        .clamp(Player::MIN_FPS, Player::MAX_FPS);
+
+    let _raw = RawTerminal::enable()?;
+    // keys are read in the background so the playback continues meanwhile
+    let (keys, pressed) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut stdin = stdin.lock();
+        while let Ok(key) = term::read_key(&mut stdin) {
+            if keys.send(key).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut renderer = Renderer::new().with_alternate_screen();
+    renderer.set_widget(
+        Slot::BottomLeft,
+        "space: pause  \u{2190}/\u{2192}: step  \u{2191}/\u{2193}: seek  g/G: start/end  +/-: speed  q: quit",
+    );
+    let mut player = Player::new(frames.len(), fps);
+    let mut last = 0;
+    loop {
+        draw(&mut renderer, &frames[player.pos], &frames[last], &player);
+        renderer.present()?;
+        last = player.pos;
+
+        let key = if player.playing {
+            match pressed.recv_timeout(Duration::from_secs_f64(1.0 / player.fps)) {
+                Ok(key) => Some(key),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match pressed.recv() {
+                Ok(key) => Some(key),
+                Err(_) => break,
+            }
+        };
+        match key {
+            Some(key) => {
+                if !player.key(key) {
+                    break;
+                }
+            }
+            None => player.tick(),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_player() {
+        let mut player = Player::new(20, 10.0);
+        player.tick();
+        assert_eq!(player.pos, 1);
+        assert!(player.key(Key::Up));
+        assert_eq!(player.pos, 3);
+        assert!(player.key(Key::Left));
+        assert_eq!((player.pos, player.playing), (2, false));
+        player.tick();
+        assert_eq!(player.pos, 2);
+        assert!(player.key(Key::Char(b'G')));
+        assert!(player.key(Key::Right));
+        assert_eq!(player.pos, 19);
+        assert!(player.key(Key::Char(b'+')));
+        assert_eq!(player.fps, 20.0);
+
+        assert!(player.key(Key::Char(b'g')));
+        assert!(player.key(Key::Char(b' ')));
+        for _ in 0..30 {
+            player.tick();
+        }
+        // stops at the last frame
+        assert_eq!((player.pos, player.playing), (19, false));
+        assert!(!player.key(Key::Char(b'q')));
+    }
+
+    #[test]
+    fn test_load_frames() {
+        let dir = std::env::temp_dir().join(format!("aoc19-replay-{}", std::process::id()));
+        let mut frames = FrameDir::create(dir.to_str().unwrap()).unwrap();
+        frames.save_text("a\n").unwrap();
+        frames.save_text("b\n").unwrap();
+        assert_eq!(
+            load_frames(dir.to_str().unwrap()).unwrap(),
+            vec!["a\n", "b\n"]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let file = std::env::temp_dir().join(format!("aoc19-replay-{}.txt", std::process::id()));
+        std::fs::write(&file, "day13-replay v1\n- 0,0,1\n").unwrap();
+        assert_eq!(
+            load_frames(file.to_str().unwrap()).unwrap(),
+            vec!["Score: 0\n\u{2588}\n"]
+        );
+        std::fs::write(&file, "day13-replay v1\n").unwrap();
+        assert!(load_frames(file.to_str().unwrap()).is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
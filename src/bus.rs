@@ -0,0 +1,192 @@
+//! Network of intcode machines sending each other packets by address, like
+//! the computers of day 23.
+//!
+//! Each machine gets its address as first input. It sends a packet by
+//! outputting the destination address, X and Y, packets are queued at the
+//! destination until the machine asks for input. A machine asking for input
+//! while its queue is empty gets -1. Devices like the [`Nat`] can be
+//! attached at addresses which have no machine.
+
+use crate::intcode::{IntcodeError, IntcodeISS, IntcodeProgram, StopReason, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub from: Value,
+    pub to: Value,
+    pub x: Value,
+    pub y: Value,
+}
+
+/// Something at an address of the bus besides the machines.
+pub trait Device {
+    /// A packet sent to the device.
+    fn receive(&mut self, packet: &Packet);
+    /// The packets the device at `addr` sends when the network is idle, all
+    /// queues are empty and no machine sent anything.
+    fn idle(&mut self, addr: Value) -> Vec<Packet>;
+}
+
+/// Keeps the last packet sent to it and sends it to address 0 when the
+/// network is idle.
+#[derive(Debug, Default)]
+pub struct Nat {
+    last: Option<(Value, Value)>,
+}
+
+impl Device for Nat {
+    fn receive(&mut self, packet: &Packet) {
+        self.last = Some((packet.x, packet.y));
+    }
+
+    fn idle(&mut self, addr: Value) -> Vec<Packet> {
+        self.last
+            .map(|(x, y)| Packet {
+                from: addr,
+                to: 0,
+                x,
+                y,
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+pub struct Bus {
+    machines: Vec<IntcodeISS>,
+    queues: Vec<VecDeque<Value>>,
+    // output of each machine which isn't a whole packet yet
+    pending: Vec<Vec<Value>>,
+    devices: BTreeMap<Value, Box<dyn Device>>,
+}
+
+impl Bus {
+    /// `count` machines running `prog` at the addresses `0..count`.
+    pub fn new(prog: &IntcodeProgram, count: usize) -> Bus {
+        Bus {
+            machines: vec![IntcodeISS::new(prog); count],
+            queues: (0..count)
+                .map(|addr| VecDeque::from(vec![addr as Value]))
+                .collect(),
+            pending: vec![Vec::new(); count],
+            devices: BTreeMap::new(),
+        }
+    }
+
+    /// Attach `device` at `addr`, machines at the same address get the
+    /// packets instead.
+    pub fn attach(&mut self, addr: Value, device: Box<dyn Device>) {
+        self.devices.insert(addr, device);
+    }
+
+    pub fn machine(&self, addr: usize) -> &IntcodeISS {
+        &self.machines[addr]
+    }
+
+    // Queue `packet` at its destination, packets to nobody are dropped.
+    fn deliver(&mut self, packet: &Packet) {
+        let queue = usize::try_from(packet.to)
+            .ok()
+            .and_then(|to| self.queues.get_mut(to));
+        if let Some(queue) = queue {
+            queue.extend(&[packet.x, packet.y]);
+        } else if let Some(device) = self.devices.get_mut(&packet.to) {
+            device.receive(packet);
+        }
+    }
+
+    /// Run every machine until it needs more input, returns the packets
+    /// sent in this round. Packets are delivered right away, machines later
+    /// in the round already receive them. If the network is idle these are
+    /// the packets the devices send. Halted machines drop the packets sent
+    /// to them.
+    pub fn step(&mut self) -> Result<Vec<Packet>, IntcodeError> {
+        let idle = self.queues.iter().all(VecDeque::is_empty);
+        let mut sent = Vec::new();
+        for from in 0..self.machines.len() {
+            let queue = &mut self.queues[from];
+            if queue.is_empty() {
+                queue.push_back(-1);
+            }
+            let pending = &mut self.pending[from];
+            if self.machines[from].compute_io(queue, pending)? == StopReason::ProgramHalt {
+                queue.clear();
+            }
+
+            let whole = pending.len() / 3 * 3;
+            let packets: Vec<_> = pending
+                .drain(..whole)
+                .collect::<Vec<_>>()
+                .chunks(3)
+                .map(|p| Packet {
+                    from: from as Value,
+                    to: p[0],
+                    x: p[1],
+                    y: p[2],
+                })
+                .collect();
+            for packet in packets {
+                self.deliver(&packet);
+                sent.push(packet);
+            }
+        }
+
+        if idle && sent.is_empty() {
+            for (&addr, device) in self.devices.iter_mut() {
+                sent.extend(device.idle(addr));
+            }
+            for packet in &sent {
+                self.deliver(packet);
+            }
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bus() {
+        // machine 0 sends (10, 20) to machine 1, every machine sends the
+        // sum of the packets it receives to the NAT
+        let prog = crate::asm::assemble(
+            "       get [me]
+                    jpt [me], loop
+                    put 1
+                    put 10
+                    put 20
+             loop:  get [x]
+                    eq [x], -1, [empty]
+                    jpt [empty], loop
+                    get [y]
+                    add [x], [y], [sum]
+                    put 255
+                    put [me]
+                    put [sum]
+                    jpt 1, loop
+             me:    .data 0
+             x:     .data 0
+             y:     .data 0
+             sum:   .data 0
+             empty: .data 0",
+        )
+        .unwrap();
+        let packet = |from, to, x, y| Packet { from, to, x, y };
+        let first = vec![packet(0, 1, 10, 20), packet(1, 255, 1, 30)];
+
+        // nobody listens at 255
+        let mut bus = Bus::new(&prog, 2);
+        assert_eq!(bus.step().unwrap(), first);
+        assert_eq!(bus.step().unwrap(), vec![]);
+
+        let mut bus = Bus::new(&prog, 2);
+        bus.attach(255, Box::new(Nat::default()));
+        assert_eq!(bus.step().unwrap(), first);
+        assert_eq!(bus.step().unwrap(), vec![packet(255, 0, 1, 30)]);
+        assert_eq!(bus.step().unwrap(), vec![packet(0, 255, 0, 31)]);
+        assert_eq!(bus.step().unwrap(), vec![packet(255, 0, 0, 31)]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Optional cache of parsed inputs.
+//!
+//! Parsed representations (program cells, asteroid positions, reaction
+//! tables) are stored in the binary intcode format, see
+//! [`crate::intcode::BINARY_MAGIC`], in a cache directory. The files are
+//! keyed by the kind of representation and a hash of the input text, so a
+//! changed input is parsed again and stale entries are simply not used.
+
+use crate::intcode::{decode_binary, encode_binary, IntcodeProgram, Value};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+/// Environment variable naming the cache directory, the cache is off if it
+/// isn't set.
+pub const CACHE_VAR: &str = "AOC19_CACHE";
+
+/// A parsed representation which can be stored as cells.
+pub trait Cacheable: Sized {
+    /// Name of the representation, part of the cache file name.
+    const KIND: &'static str;
+
+    fn to_cells(&self) -> Vec<Value>;
+
+    /// Rebuild from the cells, `None` if they don't describe a valid value.
+    fn from_cells(cells: &[Value]) -> Option<Self>;
+}
+
+impl Cacheable for IntcodeProgram {
+    const KIND: &'static str = "program";
+
+    fn to_cells(&self) -> Vec<Value> {
+        self.cells().to_vec()
+    }
+
+    fn from_cells(cells: &[Value]) -> Option<Self> {
+        IntcodeProgram::try_from(cells).ok()
+    }
+}
+
+/// FNV-1a hash of `input`, stable across runs and builds.
+pub fn hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Directory of cached representations.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Cache {
+        Cache { dir: dir.into() }
+    }
+
+    /// The cache in the directory given by `AOC19_CACHE`, if set.
+    pub fn from_env() -> Option<Cache> {
+        std::env::var_os(CACHE_VAR).map(Cache::new)
+    }
+
+    fn path<T: Cacheable>(&self, input: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}-{:016x}.icb", T::KIND, hash(input)))
+    }
+
+    /// The cached representation of `input`, or `parse` it and store the
+    /// result. Unreadable or invalid cache files are replaced.
+    pub fn load_or_parse<T: Cacheable>(
+        &self,
+        input: &str,
+        parse: impl FnOnce(&str) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let path = self.path::<T>(input);
+        let cached = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| decode_binary(&bytes).ok())
+            .and_then(|cells| T::from_cells(&cells));
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let value = parse(input)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, encode_binary(&value.to_cells()))?;
+        Ok(value)
+    }
+}
+
+/// Parse `input` through the cache given by `AOC19_CACHE`, or directly if
+/// there is none.
+pub fn parse_cached<T: Cacheable>(
+    input: &str,
+    parse: impl FnOnce(&str) -> crate::Result<T>,
+) -> crate::Result<T> {
+    match Cache::from_env() {
+        Some(cache) => cache.load_or_parse(input, parse),
+        None => parse(input),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash("1,2,3"), hash("1,2,4"));
+    }
+
+    #[test]
+    fn test_load_or_parse() {
+        let dir = std::env::temp_dir().join(format!("aoc19-cache-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let input = "1,0,0,3,99";
+
+        let parsed = std::cell::Cell::new(0);
+        let parse = |input: &str| {
+            parsed.set(parsed.get() + 1);
+            crate::intcode::parse_program(input)
+        };
+        let prog = cache.load_or_parse(input, parse).unwrap();
+        assert_eq!(cache.load_or_parse(input, parse).unwrap(), prog);
+        assert_eq!(parsed.get(), 1);
+
+        // a broken cache file is replaced
+        std::fs::write(cache.path::<IntcodeProgram>(input), b"garbage").unwrap();
+        assert_eq!(cache.load_or_parse(input, parse).unwrap(), prog);
+        assert_eq!(parsed.get(), 2);
+        assert_eq!(cache.load_or_parse(input, parse).unwrap(), prog);
+        assert_eq!(parsed.get(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+//! Terminal recordings in the asciinema cast (v2) format.
+//!
+//! A cast is a JSON header followed by one `[time, "o", data]` event per
+//! frame, where `data` is the output written to the terminal. The frames are
+//! taken from a [`Renderer`], either while it is presented on the terminal
+//! (`Renderer::present_recorded`) or without drawing to the terminal at all
+//! ([`CastWriter::record`]).
+
+use crate::json::json_string;
+use crate::term::Renderer;
+use std::io::Write;
+use std::time::Instant;
+
+enum Timing {
+    // time since the first frame
+    Realtime(Option<Instant>),
+    // frames are `1 / fps` seconds apart
+    Fixed(f64),
+}
+
+/// Writes terminal frames as asciinema cast to `out`. The header needs the
+/// terminal size, so it is written with the first frame.
+pub struct CastWriter<W: Write> {
+    out: W,
+    timing: Timing,
+    min_size: (usize, usize),
+    frames: usize,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Cast with frames timestamped by when they are written.
+    pub fn new(out: W) -> CastWriter<W> {
+        CastWriter {
+            out,
+            timing: Timing::Realtime(None),
+            min_size: (0, 0),
+            frames: 0,
+        }
+    }
+
+    /// Timestamp frames `1 / fps` seconds apart, independent of how fast they
+    /// were actually computed.
+    pub fn with_fps(mut self, fps: f64) -> CastWriter<W> {
+        self.timing = Timing::Fixed(fps);
+        self
+    }
+
+    /// Minimal terminal size of the recording, for output growing after the
+    /// first frame (e.g. a status line).
+    pub fn with_min_size(mut self, width: usize, height: usize) -> CastWriter<W> {
+        self.min_size = (width, height);
+        self
+    }
+
+    /// Append the terminal output `data` of a frame, `size` is the size of
+    /// the terminal needed as `(columns, rows)`.
+    pub fn frame(&mut self, size: (usize, usize), data: &str) -> std::io::Result<()> {
+        if self.frames == 0 {
+            writeln!(
+                self.out,
+                "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+                size.0.max(self.min_size.0),
+                size.1.max(self.min_size.1)
+            )?;
+        }
+
+        let time = match &mut self.timing {
+            Timing::Realtime(start) => start
+                .get_or_insert_with(Instant::now)
+                .elapsed()
+                .as_secs_f64(),
+            Timing::Fixed(fps) => self.frames as f64 / *fps,
+        };
+        writeln!(self.out, "[{:.3}, \"o\", {}]", time, json_string(data))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Record the next frame of `renderer` without drawing it on the
+    /// terminal.
+    pub fn record(&mut self, renderer: &mut Renderer) -> std::io::Result<()> {
+        let data = renderer.diff();
+        self.frame(renderer.size(), &data)
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut out = Vec::new();
+        let mut cast = CastWriter::new(&mut out).with_fps(4.0).with_min_size(10, 0);
+        let mut renderer = Renderer::new();
+        renderer.set_status("a");
+        renderer.set(0, 0, '#');
+        cast.record(&mut renderer).unwrap();
+        renderer.set(0, 0, 'o');
+        cast.record(&mut renderer).unwrap();
+        assert_eq!(cast.frames(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "{\"version\": 2, \"width\": 10, \"height\": 3}",
+                "[0.000, \"o\", \"\\u001b[2J\\u001b[1;1Ha\\u001b[K\\u001b[2;1H#\\u001b[3;1H\"]",
+                "[0.250, \"o\", \"\\u001b[2;1Ho\\u001b[3;1H\"]",
+            ]
+        );
+    }
+}
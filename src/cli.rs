@@ -0,0 +1,466 @@
+use crate::image::FrameDir;
+use crate::palette::{Charset, Palette};
+use crate::term::{FrameClock, Renderer};
+use crate::viz::{CaptureSink, NullSink, TerminalSink, VizSink};
+use crate::web::{WebServer, WebSink};
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum E {
+    MissingValue(String),
+    InvalidValue(String, String),
+}
+
+/// Minimal command line parser shared by the binaries.
+///
+/// Options named in `with_value` consume the following argument (`--steps 10`
+/// or `--steps=10`), any other argument starting with `--` is a boolean flag
+/// and everything else is collected as positional argument. `--format` of
+/// the [`Answers`] always takes a value.
+#[derive(Debug, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    /// Parse the arguments of the running process (without the program name).
+    pub fn from_env(with_value: &[&str]) -> crate::Result<Args> {
+        Args::parse(std::env::args().skip(1), with_value)
+    }
+
+    pub fn parse<I>(args: I, with_value: &[&str]) -> crate::Result<Args>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+
+            let (name, value) = match arg.find('=') {
+                Some(idx) => (arg[..idx].to_string(), Some(arg[idx + 1..].to_string())),
+                None => (arg, None),
+            };
+
+            if name == "--format" || with_value.contains(&name.as_str()) {
+                let value = match value.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => return Err(crate::Error::boxed(E::MissingValue(name))),
+                };
+                parsed.values.insert(name, value);
+            } else {
+                parsed.flags.insert(name);
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn positional(&self, idx: usize) -> Option<&str> {
+        self.positional.get(idx).map(|s| s.as_str())
+    }
+
+    /// Positional argument `idx`, which is reported as missing `name` if it
+    /// wasn't given.
+    pub fn required(&self, idx: usize, name: &str) -> crate::Result<&str> {
+        self.positional(idx)
+            .ok_or_else(|| crate::Error::boxed(E::MissingValue(name.to_string())).into())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|s| s.as_str())
+    }
+
+    /// Parse the value of option `name`, `None` if the option was not given.
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> crate::Result<Option<T>> {
+        match self.value(name) {
+            Some(value) => value.parse::<T>().map(Some).map_err(|_| {
+                crate::Error::boxed(E::InvalidValue(name.to_string(), value.to_string())).into()
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of jobs given with `--jobs`, one per CPU by default. `--jobs`
+    /// has to be passed as option taking a value.
+    pub fn jobs(&self) -> crate::Result<usize> {
+        Ok(match self.parse_value::<usize>("--jobs")? {
+            Some(jobs) => jobs.max(1),
+            None => crate::par::jobs(),
+        })
+    }
+}
+
+/// Prints the answers of a day to stdout as `Part One: <description>
+/// <answer>`, or only the answer with `--quiet` so it can be piped into
+/// other tools. With `--format json` every answer is printed as JSON object
+/// instead, see [`crate::json::answer`]. Anything else a binary prints along
+/// the way is narration, which goes to stderr and is left out with `--quiet`.
+#[derive(Debug, Clone, Copy)]
+pub struct Answers {
+    day: u32,
+    quiet: bool,
+    format: Format,
+}
+
+impl Answers {
+    /// Answers of day `day`, the output format is given with `--format`,
+    /// text by default.
+    pub fn from_args(day: u32, args: &Args) -> crate::Result<Answers> {
+        Ok(Answers {
+            day,
+            quiet: args.flag("--quiet"),
+            format: args
+                .parse_value::<Format>("--format")?
+                .unwrap_or(Format::Text),
+        })
+    }
+
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    fn json(&self, part: usize, answer: &str) {
+        println!("{}", crate::json::answer(self.day, part, answer));
+    }
+
+    fn label(part: usize) -> &'static str {
+        match part {
+            1 => "Part One",
+            2 => "Part Two",
+            _ => "Part",
+        }
+    }
+
+    /// Print the answer of part `part` (1 or 2).
+    pub fn part(&self, part: usize, description: &str, answer: impl std::fmt::Display) {
+        if self.format == Format::Json {
+            self.json(part, &answer.to_string());
+        } else if self.quiet {
+            println!("{}", answer);
+        } else {
+            println!("{}: {} {}", Answers::label(part), description, answer);
+        }
+    }
+
+    /// Print an answer which has to be read off a picture, the picture is
+    /// printed below the description.
+    pub fn picture(&self, part: usize, description: &str, picture: &str) {
+        if self.format == Format::Json {
+            return self.json(part, picture);
+        }
+        if !self.quiet {
+            println!("{}: {}", Answers::label(part), description);
+        }
+        print!("{}", picture);
+    }
+
+    pub fn narrate(&self, text: impl std::fmt::Display) {
+        if !self.quiet {
+            eprintln!("{}", text);
+        }
+    }
+}
+
+/// Output format of the answers of a binary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    /// One JSON object per answer, see [`crate::json::answer`].
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = crate::Error<E>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(crate::Error::new(E::InvalidValue(
+                "--format".to_string(),
+                s.to_string(),
+            ))),
+        }
+    }
+}
+
+/// Options taking a value of the shared visualization flags, to be passed to
+/// `Args::parse` along with the options of a binary.
+pub const VISUALIZE_OPTIONS: &[&str] = &["--fps", "--frames-dir", "--theme", "--serve"];
+
+/// Visualization flags shared by the binaries drawing their progress:
+/// `--visualize` animates in the terminal at `--fps` frames per second,
+/// `--frames-dir` writes every frame as image into a directory and
+/// `--no-color` draws the terminal animation without colors. The colors are
+/// taken from the palette `--theme`, a built-in theme or theme file.
+///
+/// `--ascii` draws the tiles with plain ASCII characters. On an interactive
+/// terminal ASCII is also the default if the locale doesn't use UTF-8, files
+/// and pipes are always drawn in Unicode without `--ascii`.
+///
+/// `--headless` draws the animation as fast as possible into numbered text
+/// files in the frames directory (`frames` by default) instead of the
+/// terminal, next to the images.
+///
+/// `--show-fps` shows the frame rate and the time spent computing and
+/// drawing every frame below the terminal animation.
+///
+/// `--serve addr` streams the animation to a browser instead of the
+/// terminal, see [`crate::web`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visualize {
+    pub enabled: bool,
+    pub headless: bool,
+    pub fps: f64,
+    pub frames_dir: Option<String>,
+    pub color: bool,
+    pub theme: String,
+    /// Charset of anything not drawn on an interactive terminal.
+    pub charset: Charset,
+    pub serve: Option<String>,
+    pub show_fps: bool,
+}
+
+impl Default for Visualize {
+    fn default() -> Self {
+        Visualize {
+            enabled: false,
+            headless: false,
+            fps: Visualize::DEFAULT_FPS,
+            frames_dir: None,
+            color: true,
+            theme: Visualize::DEFAULT_THEME.to_string(),
+            charset: Charset::Unicode,
+            serve: None,
+            show_fps: false,
+        }
+    }
+}
+
+impl Visualize {
+    pub const DEFAULT_FPS: f64 = 10.0;
+    pub const DEFAULT_FRAMES_DIR: &'static str = "frames";
+    pub const DEFAULT_THEME: &'static str = "default";
+
+    pub fn from_args(args: &Args) -> crate::Result<Visualize> {
+        let fps = args
+            .parse_value::<f64>("--fps")?
+            .unwrap_or(Visualize::DEFAULT_FPS);
+        if fps.is_nan() || fps <= 0.0 {
+            return Err(crate::Error::boxed(E::InvalidValue(
+                "--fps".to_string(),
+                fps.to_string(),
+            )));
+        }
+        let headless = args.flag("--headless");
+        let serve = args.value("--serve").map(|addr| addr.to_string());
+        let frames_dir = match args.value("--frames-dir") {
+            Some(dir) => Some(dir.to_string()),
+            None if headless => Some(Visualize::DEFAULT_FRAMES_DIR.to_string()),
+            None => None,
+        };
+        Ok(Visualize {
+            enabled: args.flag("--visualize") || headless || serve.is_some(),
+            headless,
+            fps,
+            frames_dir,
+            color: !args.flag("--no-color"),
+            theme: args
+                .value("--theme")
+                .unwrap_or(Visualize::DEFAULT_THEME)
+                .to_string(),
+            charset: if args.flag("--ascii") {
+                Charset::Ascii
+            } else {
+                Charset::Unicode
+            },
+            serve,
+            show_fps: args.flag("--show-fps"),
+        })
+    }
+
+    /// Palette of the theme for files and pipes, drawing with `charset`.
+    pub fn palette(&self) -> crate::Result<Palette> {
+        Ok(Palette::theme(&self.theme)?.with_charset(self.charset))
+    }
+
+    /// Palette of the theme for output to stdout. If stdout is an
+    /// interactive terminal and `--ascii` wasn't given, the charset is taken
+    /// from the locale, see [`Charset::detect`].
+    pub fn stdout_palette(&self) -> crate::Result<Palette> {
+        let palette = self.palette()?;
+        if self.charset == Charset::Unicode && std::io::stdout().is_terminal() {
+            return Ok(palette.with_charset(Charset::detect()));
+        }
+        Ok(palette)
+    }
+
+    /// Palette of the animation drawn by [`Visualize::sink`], the stdout
+    /// palette if it's drawn on the terminal.
+    pub fn sink_palette(&self) -> crate::Result<Palette> {
+        if self.enabled && !self.headless && self.serve.is_none() {
+            self.stdout_palette()
+        } else {
+            self.palette()
+        }
+    }
+
+    /// Renderer drawing the animation on the terminal, or into the frames
+    /// directory if headless.
+    pub fn renderer(&self) -> crate::Result<Renderer> {
+        let renderer = Renderer::new()
+            .with_color(self.color)
+            .with_overlay(self.show_fps);
+        match &self.frames_dir {
+            Some(dir) if self.headless => Ok(renderer.headless(FrameDir::create(dir)?)),
+            _ => Ok(renderer),
+        }
+    }
+
+    /// Sink of the animation: drawn on the terminal, captured as text files
+    /// into the frames directory if headless, or dropped if not enabled.
+    /// When serving, the animation starts once a browser is connected.
+    pub fn sink(&self) -> crate::Result<Box<dyn VizSink>> {
+        if !self.enabled {
+            return Ok(Box::new(NullSink));
+        }
+        if let Some(addr) = &self.serve {
+            let server = WebServer::bind(addr.as_str())?;
+            eprintln!("Waiting for a browser on http://{}/", server.addr());
+            server.wait_for_client();
+            return Ok(Box::new(WebSink::new(server, self.clock())));
+        }
+        match &self.frames_dir {
+            Some(dir) if self.headless => Ok(Box::new(CaptureSink::to_dir(FrameDir::create(dir)?))),
+            _ => Ok(Box::new(TerminalSink::new(self.renderer()?, self.clock()))),
+        }
+    }
+
+    /// Clock pacing the animation, headless animations aren't paced.
+    pub fn clock(&self) -> Option<FrameClock> {
+        if self.enabled && !self.headless {
+            Some(FrameClock::new(self.fps))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str], with_value: &[&str]) -> crate::Result<Args> {
+        Args::parse(args.iter().map(|s| s.to_string()), with_value)
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = parse(
+            &["in", "--steps", "10", "--csv=out.csv", "--quiet"],
+            &["--steps", "--csv"],
+        )
+        .unwrap();
+        assert_eq!(args.positional(0), Some("in"));
+        assert_eq!(args.positional(1), None);
+        assert_eq!(args.parse_value::<usize>("--steps").unwrap(), Some(10));
+        assert_eq!(args.value("--csv"), Some("out.csv"));
+        assert!(args.flag("--quiet"));
+        assert!(!args.flag("--steps"));
+    }
+
+    #[test]
+    fn test_answers() {
+        let args = parse(&["--format", "json", "--quiet"], &[]).unwrap();
+        let answers = Answers::from_args(12, &args).unwrap();
+        assert_eq!(answers.format(), Format::Json);
+        assert!(answers.quiet());
+        let answers = Answers::from_args(12, &parse(&[], &[]).unwrap()).unwrap();
+        assert_eq!(answers.format(), Format::Text);
+        assert!(Answers::from_args(12, &parse(&["--format=xml"], &[]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(&["--steps"], &["--steps"]).is_err());
+
+        let args = parse(&["--steps", "ten"], &["--steps"]).unwrap();
+        assert!(args.parse_value::<usize>("--steps").is_err());
+        assert_eq!(args.parse_value::<usize>("--other").unwrap(), None);
+
+        let args = parse(&["in"], &[]).unwrap();
+        assert_eq!(args.required(0, "input").unwrap(), "in");
+        assert_eq!(
+            args.required(1, "output").unwrap_err().to_string(),
+            "Error: MissingValue(\"output\")"
+        );
+    }
+
+    #[test]
+    fn test_visualize() {
+        let with_value = [VISUALIZE_OPTIONS, &["--cast"]].concat();
+        let args = parse(
+            &[
+                "--visualize",
+                "--fps",
+                "30",
+                "--no-color",
+                "--show-fps",
+                "--ascii",
+            ],
+            &with_value,
+        )
+        .unwrap();
+        let vis = Visualize::from_args(&args).unwrap();
+        assert_eq!(
+            vis,
+            Visualize {
+                enabled: true,
+                headless: false,
+                fps: 30.0,
+                frames_dir: None,
+                color: false,
+                theme: "default".to_string(),
+                charset: Charset::Ascii,
+                serve: None,
+                show_fps: true,
+            }
+        );
+
+        let args = parse(&["--frames-dir", "out", "--cast", "x"], &with_value).unwrap();
+        let vis = Visualize::from_args(&args).unwrap();
+        assert_eq!(vis.frames_dir.as_deref(), Some("out"));
+        assert!(!vis.enabled && vis.color);
+
+        let args = parse(&["--headless"], &with_value).unwrap();
+        let vis = Visualize::from_args(&args).unwrap();
+        assert!(vis.enabled && vis.headless);
+        assert_eq!(vis.frames_dir.as_deref(), Some("frames"));
+        assert!(vis.clock().is_none());
+        // frame files don't depend on the locale
+        assert_eq!(vis.sink_palette().unwrap().charset(), Charset::Unicode);
+
+        let args = parse(&["--serve", "127.0.0.1:8019"], &with_value).unwrap();
+        let vis = Visualize::from_args(&args).unwrap();
+        assert!(vis.enabled && !vis.headless && !vis.show_fps);
+        assert_eq!(vis.serve.as_deref(), Some("127.0.0.1:8019"));
+
+        let args = parse(&["--fps", "0"], &with_value).unwrap();
+        assert!(Visualize::from_args(&args).is_err());
+    }
+}
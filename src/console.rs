@@ -0,0 +1,122 @@
+//! Runs an intcode machine on a console, for the ASCII programs which are
+//! meant to be played by a human.
+//!
+//! Every line typed is fed to the machine when it asks for input, including
+//! the line break. ASCII output is written as it is produced, other values
+//! are written as numbers on their own line.
+
+use crate::intcode::{Exit, InputSource, IntcodeISS, StopReason, Value};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+#[derive(Debug)]
+pub enum E {
+    // line typed containing non ASCII characters
+    NonAscii(String),
+}
+
+// Input of the machine, read line by line when it is needed. Errors end the
+// input and are reported once the machine stopped.
+struct LineInput<R> {
+    reader: R,
+    line: VecDeque<Value>,
+    error: Option<Box<dyn std::error::Error>>,
+}
+
+impl<R: BufRead> LineInput<R> {
+    fn read_line(&mut self) -> crate::Result<bool> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        if line.ends_with("\r\n") {
+            line.truncate(line.len() - 2);
+            line.push('\n');
+        }
+        if !line.is_ascii() {
+            return Err(crate::Error::boxed(E::NonAscii(line)));
+        }
+        self.line.extend(line.bytes().map(Value::from));
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> InputSource for LineInput<R> {
+    fn next_input(&mut self) -> Option<Value> {
+        if self.line.is_empty() && self.error.is_none() {
+            match self.read_line() {
+                Ok(_) => {}
+                Err(err) => self.error = Some(err),
+            }
+        }
+        self.line.pop_front()
+    }
+}
+
+fn write_value(out: &mut impl Write, value: Value) -> std::io::Result<()> {
+    match u8::try_from(value) {
+        Ok(byte) if byte.is_ascii() => out.write_all(&[byte]),
+        _ => writeln!(out, "{}", value),
+    }
+}
+
+/// Run `iss` with the lines of `input` as input and its output written to
+/// `output`, until it halts or the input ends.
+pub fn run<R: BufRead, W: Write>(
+    iss: &mut IntcodeISS,
+    input: R,
+    mut output: W,
+) -> crate::Result<StopReason> {
+    let mut input = LineInput {
+        reader: input,
+        line: VecDeque::new(),
+        error: None,
+    };
+    loop {
+        let exit = iss.run_until_output(&mut input)?;
+        if let Some(err) = input.error.take() {
+            return Err(err);
+        }
+        match exit {
+            Exit::Output(value) => {
+                write_value(&mut output, value)?;
+                output.flush()?;
+            }
+            Exit::Stop(reason) => return Ok(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        // echo the input until a `.` comes along, then output a number
+        let prog = crate::asm::assemble(
+            "loop: get [c]
+                   put [c]
+                   eq [c], 46, [stop]
+                   jpf [stop], loop
+                   put 1000
+                   halt
+             c:    .data 0
+             stop: .data 0",
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        let reason = run(&mut IntcodeISS::new(&prog), &b"hi\r\nyou.\n"[..], &mut out).unwrap();
+        assert_eq!(reason, StopReason::ProgramHalt);
+        assert_eq!(out, b"hi\nyou.1000\n");
+
+        let mut out = Vec::new();
+        let reason = run(&mut IntcodeISS::new(&prog), &b"hi"[..], &mut out).unwrap();
+        assert_eq!(reason, StopReason::NeedInput);
+        assert_eq!(out, b"hi");
+
+        let input = "\u{e4}\n".as_bytes();
+        assert!(run(&mut IntcodeISS::new(&prog), input, Vec::new()).is_err());
+    }
+}
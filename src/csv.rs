@@ -0,0 +1,87 @@
+//! CSV output of intermediate puzzle data, for plotting and analysis with
+//! other tools.
+//!
+//! Fields are separated by commas, fields containing a comma, a quote or a
+//! line break are quoted. Every row must have as many fields as the header.
+
+use std::fmt::Display;
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    // fields of the row, fields of the header
+    WrongNumberOfFields(usize, usize),
+}
+
+/// Writes rows of fields as CSV to `out`, starting with a header row.
+pub struct CsvWriter<W: Write> {
+    out: W,
+    columns: usize,
+}
+
+fn quote(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new<T: Display>(mut out: W, header: impl IntoIterator<Item = T>) -> crate::Result<Self> {
+        let header: Vec<_> = header.into_iter().map(|h| quote(&h.to_string())).collect();
+        writeln!(out, "{}", header.join(","))?;
+        Ok(CsvWriter {
+            out,
+            columns: header.len(),
+        })
+    }
+
+    /// Write one row, fields of different types can be passed as
+    /// `&[&a as &dyn Display, &b]`.
+    pub fn row<T: Display>(&mut self, fields: impl IntoIterator<Item = T>) -> crate::Result<()> {
+        let fields: Vec<_> = fields.into_iter().map(|f| quote(&f.to_string())).collect();
+        if fields.len() != self.columns {
+            return Err(crate::Error::boxed(E::WrongNumberOfFields(
+                fields.len(),
+                self.columns,
+            )));
+        }
+        writeln!(self.out, "{}", fields.join(","))?;
+        Ok(())
+    }
+
+    /// Flush the rows and return the output.
+    pub fn finish(mut self) -> crate::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl CsvWriter<std::io::BufWriter<std::fs::File>> {
+    /// Write to the file at `path`.
+    pub fn create<T: Display>(
+        path: &str,
+        header: impl IntoIterator<Item = T>,
+    ) -> crate::Result<Self> {
+        let out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        CsvWriter::new(out, header)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv() {
+        let mut csv = CsvWriter::new(Vec::new(), ["step", "name"]).unwrap();
+        csv.row([&1 as &dyn Display, &"a,b"]).unwrap();
+        csv.row(vec!["2", "say \"hi\""]).unwrap();
+        assert!(csv.row([3]).is_err());
+        assert_eq!(
+            String::from_utf8(csv.finish().unwrap()).unwrap(),
+            "step,name\n1,\"a,b\"\n2,\"say \"\"hi\"\"\"\n"
+        );
+    }
+}
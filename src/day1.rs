@@ -0,0 +1,63 @@
+//! The Tyranny of the Rocket Equation (day 1).
+//!
+//! The input lists the mass of every module, one per line.
+
+use crate::solver::aoc;
+
+pub fn parse(input: &str) -> crate::Result<Vec<i32>> {
+    crate::input::parse_list(input, '\n')
+}
+
+/// Fuel required to launch a module of `mass`.
+pub fn fuel(mass: i32) -> i32 {
+    mass / 3 - 2
+}
+
+/// Fuel required for a module of `mass`, including the fuel for the fuel.
+pub fn total_fuel(mut mass: i32) -> i32 {
+    let mut total = 0;
+    loop {
+        mass = fuel(mass);
+        if mass < 0 {
+            break;
+        }
+        total += mass;
+    }
+    total
+}
+
+/// Sum of `fuel` over the module `masses`, computed on `jobs` threads.
+pub fn fuel_sum(masses: &[i32], jobs: usize, fuel: fn(i32) -> i32) -> i32 {
+    crate::par::map_chunks(masses, jobs, |chunk| {
+        chunk.iter().map(|&mass| fuel(mass)).sum::<i32>()
+    })
+    .into_iter()
+    .sum()
+}
+
+/// Sum of the fuel required for the modules.
+#[aoc(day = 1, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i32> {
+    Ok(fuel_sum(&parse(input)?, crate::par::jobs(), fuel))
+}
+
+/// Sum of the fuel required for the modules, taking the mass of the fuel
+/// into account.
+#[aoc(day = 1, part = 2)]
+pub fn part2(input: &str) -> crate::Result<i32> {
+    Ok(fuel_sum(&parse(input)?, crate::par::jobs(), total_fuel))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuel() {
+        assert_eq!(fuel(12), 2);
+        assert_eq!(fuel(1969), 654);
+        assert_eq!(total_fuel(1969), 966);
+        assert_eq!(total_fuel(100756), 50346);
+        assert_eq!(part1("12\n14\n").unwrap(), 4);
+    }
+}
@@ -0,0 +1,439 @@
+//! Monitoring Station (day 10).
+//!
+//! The input is a map of the asteroid belt. Only part one is solved, the
+//! number of asteroids in sight of the best monitoring station.
+
+use crate::cache::Cacheable;
+use crate::intcode::Value;
+use crate::solver::aoc;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidCharacter { line: usize, column: usize, c: char },
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct Vec2D(pub i32, pub i32);
+pub type Asteroids = Vec<Vec2D>;
+
+impl std::ops::Sub for Vec2D {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vec2D(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+/// Parse the asteroid map, '#' marks an asteroid and '.' marks empty space.
+/// Any other character is rejected with its position (1-based line/column).
+pub fn create_asteroids(input: &str) -> crate::Result<Asteroids> {
+    let mut belt = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        for (x, elem) in line.trim_end().chars().enumerate() {
+            match elem {
+                '#' => belt.push(Vec2D(i32::try_from(x)?, i32::try_from(y)?)),
+                '.' => {}
+                c => {
+                    return Err(crate::Error::boxed(E::InvalidCharacter {
+                        line: y + 1,
+                        column: x + 1,
+                        c,
+                    }))
+                }
+            }
+        }
+    }
+    Ok(belt)
+}
+
+/// Cached as x, y pairs.
+impl Cacheable for Asteroids {
+    const KIND: &'static str = "asteroids";
+
+    fn to_cells(&self) -> Vec<Value> {
+        self.iter()
+            .flat_map(|a| [Value::from(a.0), Value::from(a.1)])
+            .collect()
+    }
+
+    fn from_cells(cells: &[Value]) -> Option<Self> {
+        if !cells.len().is_multiple_of(2) {
+            return None;
+        }
+        cells
+            .chunks_exact(2)
+            .map(|xy| {
+                Some(Vec2D(
+                    i32::try_from(xy[0]).ok()?,
+                    i32::try_from(xy[1]).ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Reduce a vector to its smallest integer direction with the same angle.
+/// The zero vector has no direction and is returned unchanged.
+pub fn normalize(v: &Vec2D) -> Vec2D {
+    let gcd = crate::math::gcd(u64::from(v.0.unsigned_abs()), u64::from(v.1.unsigned_abs()));
+    if gcd == 0 {
+        return *v;
+    }
+    // in 64 bits, the gcd of i32::MIN and 0 doesn't fit into an i32
+    let div = |c: i32| (i64::from(c) / gcd as i64) as i32;
+    Vec2D(div(v.0), div(v.1))
+}
+
+// Half of the plane a non-zero direction points into: 0 for angles in
+// [0, 180) degrees clockwise from up, 1 for [180, 360).
+fn half_plane(v: &Vec2D) -> u8 {
+    if v.0 > 0 || (v.0 == 0 && v.1 < 0) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Order non-zero directions by their angle clockwise from up, with y
+/// pointing down as on the map. Directions along the same ray are equal,
+/// no matter their length.
+pub fn cmp_angle(a: &Vec2D, b: &Vec2D) -> Ordering {
+    half_plane(a).cmp(&half_plane(b)).then_with(|| {
+        // within a half plane the sign of the cross product decides
+        let cross = i64::from(a.0) * i64::from(b.1) - i64::from(a.1) * i64::from(b.0);
+        0.cmp(&cross)
+    })
+}
+
+// Number of distinct rays from `origin` to the other asteroids. The
+// directions are sorted by angle in `dirs` (reused between calls), so
+// asteroids on the same ray end up next to each other.
+fn count_rays(origin: &Vec2D, asteroids: &[Vec2D], dirs: &mut Vec<Vec2D>) -> usize {
+    dirs.clear();
+    dirs.extend(
+        asteroids
+            .iter()
+            .filter(|&asteroid| asteroid != origin)
+            .map(|asteroid| *asteroid - *origin),
+    );
+    dirs.sort_unstable_by(cmp_angle);
+    dirs.dedup_by(|a, b| cmp_angle(a, b) == Ordering::Equal);
+    dirs.len()
+}
+
+/// Number of asteroids in sight of `origin`.
+pub fn compute_visible(origin: &Vec2D, asteroids: &Asteroids) -> usize {
+    count_rays(origin, asteroids, &mut Vec::with_capacity(asteroids.len()))
+}
+
+// Largest number of offsets for which a `DirectionIndex` is built.
+const MAX_INDEX_SIZE: usize = 1 << 22;
+
+// Direction of every offset between two positions inside a bounding box, as
+// id of the shortest offset with the same direction. Asteroids are bucketed
+// by direction from a candidate through a table lookup instead of
+// normalizing each pair.
+struct DirectionIndex {
+    // offsets range over -(width - 1)..width and -(height - 1)..height
+    width: i64,
+    height: i64,
+    ids: Vec<u32>,
+}
+
+impl DirectionIndex {
+    // Index for the bounding box of `asteroids`, `None` if it has more than
+    // MAX_INDEX_SIZE offsets.
+    fn new(asteroids: &[Vec2D]) -> Option<DirectionIndex> {
+        let extent = |coord: fn(&Vec2D) -> i32| {
+            let min = asteroids.iter().map(coord).min()?;
+            let max = asteroids.iter().map(coord).max()?;
+            Some(i64::from(max) - i64::from(min) + 1)
+        };
+        let (width, height) = (extent(|a| a.0)?, extent(|a| a.1)?);
+        let size = usize::try_from((2 * width - 1) * (2 * height - 1)).ok()?;
+        if size > MAX_INDEX_SIZE {
+            return None;
+        }
+
+        let mut index = DirectionIndex {
+            width,
+            height,
+            ids: Vec::with_capacity(size),
+        };
+        for dy in 1 - height..height {
+            for dx in 1 - width..width {
+                // offsets are bounded by the asteroid coordinates
+                let dir = normalize(&Vec2D(dx as i32, dy as i32));
+                let id = index.offset(&dir) as u32;
+                index.ids.push(id);
+            }
+        }
+        Some(index)
+    }
+
+    fn offset(&self, v: &Vec2D) -> usize {
+        let row = i64::from(v.1) + self.height - 1;
+        let col = i64::from(v.0) + self.width - 1;
+        (row * (2 * self.width - 1) + col) as usize
+    }
+
+    fn direction(&self, from: &Vec2D, to: &Vec2D) -> usize {
+        self.ids[self.offset(&(*to - *from))] as usize
+    }
+}
+
+/// Asteroid with the most other asteroids in sight, with their number.
+pub fn best_location(asteroids: &Asteroids) -> Option<(Vec2D, usize)> {
+    let index = match DirectionIndex::new(asteroids) {
+        Some(index) => index,
+        None => {
+            let mut dirs = Vec::with_capacity(asteroids.len());
+            return asteroids
+                .iter()
+                .map(|asteroid| (*asteroid, count_rays(asteroid, asteroids, &mut dirs)))
+                .max_by_key(|&(_, visible)| visible);
+        }
+    };
+
+    // buckets hit from the current candidate are marked with its number, so
+    // they don't have to be cleared between candidates
+    let mut seen = vec![0usize; index.ids.len()];
+    let mut best = None;
+    for (n, origin) in asteroids.iter().enumerate() {
+        let mark = n + 1;
+        let mut visible = 0;
+        for asteroid in asteroids.iter().filter(|&asteroid| asteroid != origin) {
+            let bucket = &mut seen[index.direction(origin, asteroid)];
+            if *bucket != mark {
+                *bucket = mark;
+                visible += 1;
+            }
+        }
+        // the last of equally good candidates, like max_by_key
+        if best.is_none_or(|(_, most)| visible >= most) {
+            best = Some((*origin, visible));
+        }
+    }
+    best
+}
+
+/// Number of asteroids in sight of the best monitoring station.
+#[aoc(day = 10, part = 1)]
+pub fn part1(input: &str) -> crate::Result<usize> {
+    let asteroids = crate::cache::parse_cached(input, create_asteroids)?;
+    Ok(best_location(&asteroids).map_or(0, |(_, visible)| visible))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        // axis aligned
+        assert_eq!(normalize(&Vec2D(0, 5)), Vec2D(0, 1));
+        assert_eq!(normalize(&Vec2D(0, -3)), Vec2D(0, -1));
+        assert_eq!(normalize(&Vec2D(7, 0)), Vec2D(1, 0));
+        assert_eq!(normalize(&Vec2D(-2, 0)), Vec2D(-1, 0));
+        // diagonal
+        assert_eq!(normalize(&Vec2D(-4, 6)), Vec2D(-2, 3));
+        assert_eq!(normalize(&Vec2D(3, -3)), Vec2D(1, -1));
+        // zero vector
+        assert_eq!(normalize(&Vec2D(0, 0)), Vec2D(0, 0));
+    }
+
+    #[test]
+    fn test_cmp_angle() {
+        // clockwise from up, y points down
+        let mut dirs = vec![
+            Vec2D(-1, -1),
+            Vec2D(0, 1),
+            Vec2D(1, 0),
+            Vec2D(-1, 0),
+            Vec2D(0, -1),
+            Vec2D(1, 1),
+            Vec2D(-1, 1),
+            Vec2D(1, -1),
+        ];
+        dirs.sort_by(cmp_angle);
+        assert_eq!(
+            dirs,
+            vec![
+                Vec2D(0, -1),
+                Vec2D(1, -1),
+                Vec2D(1, 0),
+                Vec2D(1, 1),
+                Vec2D(0, 1),
+                Vec2D(-1, 1),
+                Vec2D(-1, 0),
+                Vec2D(-1, -1),
+            ]
+        );
+        assert_eq!(cmp_angle(&Vec2D(2, -4), &Vec2D(1, -2)), Ordering::Equal);
+        assert_ne!(cmp_angle(&Vec2D(0, -1), &Vec2D(0, 1)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_count_rays() {
+        // every direction reduced by its gcd counts once
+        let asteroids: Asteroids = (0..40)
+            .flat_map(|y| (0..40).map(move |x| Vec2D(x, y)))
+            .filter(|Vec2D(x, y)| (x * 7 + y * 13) % 5 == 0)
+            .collect();
+        let mut dirs = Vec::new();
+        for origin in asteroids.iter().step_by(17) {
+            let normalized: std::collections::HashSet<_> = asteroids
+                .iter()
+                .filter(|&asteroid| asteroid != origin)
+                .map(|asteroid| normalize(&(*asteroid - *origin)))
+                .collect();
+            assert_eq!(count_rays(origin, &asteroids, &mut dirs), normalized.len());
+        }
+
+        // bucketing through the direction index gives the same counts
+        let (best, visible) = best_location(&asteroids).unwrap();
+        assert_eq!(compute_visible(&best, &asteroids), visible);
+        assert!(asteroids
+            .iter()
+            .all(|a| compute_visible(a, &asteroids) <= visible));
+        assert!(DirectionIndex::new(&asteroids).is_some());
+
+        // too large for an index
+        let far = vec![Vec2D(0, 0), Vec2D(1 << 20, 1 << 20)];
+        assert!(DirectionIndex::new(&far).is_none());
+        assert_eq!(best_location(&far), Some((Vec2D(1 << 20, 1 << 20), 1)));
+        assert_eq!(best_location(&Vec::new()), None);
+    }
+
+    #[test]
+    fn test_invalid_map() {
+        assert!(create_asteroids(".#\n#.\n").is_ok());
+        assert!(create_asteroids(".#\r\n#.\r\n").is_ok());
+
+        let err = create_asteroids(".#\n#?").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error: InvalidCharacter { line: 2, column: 2, c: '?' }"
+        );
+    }
+
+    #[test]
+    fn test_example1() {
+        // .7..7
+        // .....
+        // 67775
+        // ....7
+        // ...87
+        let input = ".#..#\n\
+                     .....\n\
+                     #####\n\
+                     ....#\n\
+                     ...##"
+            .to_string();
+
+        let asteroids = create_asteroids(&input).unwrap();
+        assert_eq!(compute_visible(&Vec2D(1, 0), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(4, 0), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(0, 2), &asteroids), 6);
+        assert_eq!(compute_visible(&Vec2D(1, 2), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(2, 2), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(3, 2), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(4, 2), &asteroids), 5);
+        assert_eq!(compute_visible(&Vec2D(4, 3), &asteroids), 7);
+        assert_eq!(compute_visible(&Vec2D(3, 4), &asteroids), 8);
+        assert_eq!(compute_visible(&Vec2D(4, 4), &asteroids), 7);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = "......#.#.\n\
+                     #..#.#....\n\
+                     ..#######.\n\
+                     .#.#.###..\n\
+                     .#..#.....\n\
+                     ..#....#.#\n\
+                     #..#....#.\n\
+                     .##.#..###\n\
+                     ##...#..#.\n\
+                     .#....####"
+            .to_string();
+
+        let asteroids = create_asteroids(&input).unwrap();
+        assert_eq!(compute_visible(&Vec2D(5, 8), &asteroids), 33);
+    }
+
+    #[test]
+    fn test_example3() {
+        let input = "#.#...#.#.\n\
+                     .###....#.\n\
+                     .#....#...\n\
+                     ##.#.#.#.#\n\
+                     ....#.#.#.\n\
+                     .##..###.#\n\
+                     ..#...##..\n\
+                     ..##....##\n\
+                     ......#...\n\
+                     .####.###."
+            .to_string();
+
+        let asteroids = create_asteroids(&input).unwrap();
+        assert_eq!(compute_visible(&Vec2D(1, 2), &asteroids), 35);
+    }
+
+    #[test]
+    fn test_example4() {
+        let input = ".#..#..###\n\
+                     ####.###.#\n\
+                     ....###.#.\n\
+                     ..###.##.#\n\
+                     ##.##.#.#.\n\
+                     ....###..#\n\
+                     ..#.#..#.#\n\
+                     #..#.#.###\n\
+                     .##...##.#\n\
+                     .....#.#.."
+            .to_string();
+
+        let asteroids = create_asteroids(&input).unwrap();
+        assert_eq!(compute_visible(&Vec2D(6, 3), &asteroids), 41);
+    }
+
+    #[test]
+    fn test_example5() {
+        let input = ".#..##.###...#######\n\
+                     ##.############..##.\n\
+                     .#.######.########.#\n\
+                     .###.#######.####.#.\n\
+                     #####.##.#.##.###.##\n\
+                     ..#####..#.#########\n\
+                     ####################\n\
+                     #.####....###.#.#.##\n\
+                     ##.#################\n\
+                     #####.##.###..####..\n\
+                     ..######..##.#######\n\
+                     ####.##.####...##..#\n\
+                     .#####..#.######.###\n\
+                     ##...#.##########...\n\
+                     #.##########.#######\n\
+                     .####.#.###.###.#.##\n\
+                     ....##.##.###..#####\n\
+                     .#.#.###########.###\n\
+                     #.#.#.#####.####.###\n\
+                     ###.##.####.##.#..##"
+            .to_string();
+
+        let asteroids = create_asteroids(&input).unwrap();
+        assert_eq!(compute_visible(&Vec2D(11, 13), &asteroids), 210);
+    }
+
+    #[test]
+    fn test_cache_cells() {
+        let asteroids = vec![Vec2D(1, 0), Vec2D(4, 3)];
+        assert_eq!(asteroids.to_cells(), vec![1, 0, 4, 3]);
+        assert_eq!(Asteroids::from_cells(&[1, 0, 4, 3]), Some(asteroids));
+        assert_eq!(Asteroids::from_cells(&[1, 0, 4]), None);
+        assert_eq!(Asteroids::from_cells(&[1 << 40, 0]), None);
+    }
+}
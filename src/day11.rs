@@ -0,0 +1,329 @@
+//! Space Police (day 11).
+//!
+//! The input is the intcode program of the hull painting robot.
+
+use crate::grid::Grid2D;
+use crate::intcode::{parse_program, IntcodeISS, IntcodeProgram, StopReason, Value};
+use crate::solver::aoc;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[derive(Debug)]
+pub enum E {
+    // output of one step, which has to be a color and a turn
+    UnexpectedOutput(Vec<Value>),
+    InvalidTurn(Value),
+}
+
+enum Facing {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+/// Robot painting the hull, controlled by an intcode program.
+pub struct PaintingRobot {
+    facing: Facing,
+    position: (i64, i64),
+    // color of every panel painted, 0 is black and 1 white
+    pub panels: HashMap<(i64, i64), i64>,
+    // how often the robot entered a panel
+    pub visits: HashMap<(i64, i64), u64>,
+    brain: IntcodeISS,
+}
+
+impl PaintingRobot {
+    pub fn new(prog: &IntcodeProgram) -> PaintingRobot {
+        PaintingRobot {
+            facing: Facing::Up,
+            position: (0, 0),
+            panels: HashMap::new(),
+            visits: vec![((0, 0), 1)].into_iter().collect(),
+            brain: IntcodeISS::new(prog),
+        }
+    }
+
+    /// Let the brain paint the hull until it halts.
+    pub fn run(&mut self) -> crate::Result<()> {
+        loop {
+            let input = [self.get_panel_color()];
+
+            let (stop_reason, output) = self.brain.compute(input.iter())?;
+            let (color, turn) = match output[..] {
+                [color, turn] => (color, turn),
+                _ => return Err(crate::Error::boxed(E::UnexpectedOutput(output))),
+            };
+            self.set_panel_color(color);
+            self.update_facing(turn)?;
+            self.move_forward();
+
+            if stop_reason == StopReason::ProgramHalt {
+                return Ok(());
+            }
+        }
+    }
+
+    fn get_panel_color(&mut self) -> i64 {
+        *self.panels.entry(self.position).or_insert(0)
+    }
+
+    pub fn set_panel_color(&mut self, col: i64) {
+        self.panels.insert(self.position, col);
+    }
+
+    fn update_facing(&mut self, turn: i64) -> crate::Result<()> {
+        self.facing = if turn == 0 {
+            // turn left
+            match self.facing {
+                Facing::Up => Facing::Left,
+                Facing::Right => Facing::Up,
+                Facing::Down => Facing::Right,
+                Facing::Left => Facing::Down,
+            }
+        } else if turn == 1 {
+            // turn right
+            match self.facing {
+                Facing::Up => Facing::Right,
+                Facing::Right => Facing::Down,
+                Facing::Down => Facing::Left,
+                Facing::Left => Facing::Up,
+            }
+        } else {
+            return Err(crate::Error::boxed(E::InvalidTurn(turn)));
+        };
+        Ok(())
+    }
+
+    fn move_forward(&mut self) {
+        match self.facing {
+            Facing::Up => self.position.1 -= 1,
+            Facing::Right => self.position.0 += 1,
+            Facing::Down => self.position.1 += 1,
+            Facing::Left => self.position.0 -= 1,
+        };
+        *self.visits.entry(self.position).or_insert(0) += 1;
+    }
+}
+
+/// The robot after painting the hull starting on a panel of `color`.
+pub fn paint(prog: &IntcodeProgram, color: i64) -> crate::Result<PaintingRobot> {
+    let mut robot = PaintingRobot::new(prog);
+    robot.set_panel_color(color);
+    robot.run()?;
+    Ok(robot)
+}
+
+/// Number of panels painted at least once, starting on a black panel.
+#[aoc(day = 11, part = 1)]
+pub fn part1(input: &str) -> crate::Result<usize> {
+    Ok(paint(&parse_program(input)?, 0)?.panels.len())
+}
+
+/// The registration identifier painted starting on a white panel, as image
+/// of the panel colors.
+#[aoc(day = 11, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Grid2D<i64>> {
+    let robot = paint(&parse_program(input)?, 1)?;
+
+    let mut image = Grid2D::new(0);
+    for (&(x, y), &c) in &robot.panels {
+        image.set(usize::try_from(x)?, usize::try_from(y)?, c);
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::Addr;
+    use std::convert::TryFrom;
+
+    fn eval(p: &[Value], result_pos: Addr) -> Value {
+        let input = [];
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        iss.compute(input.iter()).unwrap();
+        iss.peek(result_pos)
+    }
+
+    fn eval_with_io(p: &[Value], input: Vec<Value>) -> Vec<Value> {
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        let (reason, output) = iss.compute(input.iter()).unwrap();
+        assert_eq!(reason, StopReason::ProgramHalt);
+        output
+    }
+
+    #[test]
+    fn test_parts() {
+        let Some(input) = crate::fixtures::personal_input(11) else {
+            return;
+        };
+        assert_eq!(part1(&input).unwrap(), 2064);
+        let image = part2(&input).unwrap();
+        assert_eq!((image.width(), image.height()), (43, 6));
+    }
+
+    #[test]
+    fn test_example1() {
+        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
+        let prog = vec![1, 0, 0, 0, 99];
+        assert_eq!(eval(&prog, 0), 2);
+    }
+
+    #[test]
+    fn test_example2() {
+        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
+        let prog = vec![2, 3, 0, 3, 99];
+        assert_eq!(eval(&prog, 3), 6);
+    }
+
+    #[test]
+    fn test_example3() {
+        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
+        let prog = vec![2, 4, 4, 5, 99, 0];
+        assert_eq!(eval(&prog, 5), 9801);
+    }
+
+    #[test]
+    fn test_example4() {
+        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
+        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        assert_eq!(eval(&prog, 0), 30);
+    }
+
+    #[test]
+    fn test_addressing_mode() {
+        // 3 * [4] = 3 * 33 = 99 -> store at [4]
+        let prog = vec![1002, 4, 3, 4, 33];
+        assert_eq!(eval(&prog, 4), 99);
+
+        // 100 - 1 = 99 -> store at [4]
+        let prog = vec![1101, 100, -1, 4, 0];
+        assert_eq!(eval(&prog, 4), 99);
+    }
+
+    #[test]
+    fn test_eq_with_load() {
+        // Using position mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_load() {
+        // Using position mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_eq_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_jump_with_load() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_jump_with_immediate() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_integration() {
+        // The program uses an input instruction to ask for a single number.
+        // i < 8 -> output 999
+        // i = 8 -> output 1000
+        // i > 8 -> output 1001
+        let prog = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1000]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1001]);
+    }
+
+    #[test]
+    fn test_boost_example1() {
+        let prog = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(eval_with_io(&prog, vec![]), prog);
+    }
+
+    #[test]
+    fn test_boost_example2() {
+        let prog = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
+        let output = eval_with_io(&prog, vec![]);
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].to_string().chars().count(), 16);
+    }
+
+    #[test]
+    fn test_boost_example3() {
+        let prog = vec![104, 1125899906842624, 99];
+        assert_eq!(eval_with_io(&prog, vec![]), vec![1125899906842624]);
+    }
+}
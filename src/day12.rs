@@ -0,0 +1,417 @@
+//! N-body simulation of the moons of Jupiter (day 12).
+//!
+//! The simulation is generic over the number of spatial dimensions. Gravity
+//! along one axis only depends on the positions along that axis, which means
+//! a `System<D>` decomposes into `D` independent `System<1>`.
+
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidInputLine,
+    InvalidNumber,
+    WrongNumOfCoordinates,
+    PeriodTooLarge,
+}
+
+fn norm_l1<const D: usize>(v: &[i32; D]) -> i32 {
+    v.iter().map(|c| c.abs()).sum()
+}
+
+/// A body with position and velocity in `D` dimensions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Body<const D: usize> {
+    pub pos: [i32; D],
+    pub vel: [i32; D],
+}
+
+pub type Moon = Body<3>;
+
+impl<const D: usize> Body<D> {
+    /// Body resting at `pos`.
+    pub fn new(pos: [i32; D]) -> Self {
+        Body { pos, vel: [0; D] }
+    }
+
+    // Pull `self` and `other` towards each other.
+    fn attract(&mut self, other: &mut Body<D>) {
+        for d in 0..D {
+            let dv = compute_gravity_1d(self.pos[d], other.pos[d]);
+            self.vel[d] += dv;
+            other.vel[d] -= dv;
+        }
+    }
+
+    fn apply_velocity(&mut self) {
+        for d in 0..D {
+            self.pos[d] += self.vel[d];
+        }
+    }
+
+    pub fn energy(&self) -> i32 {
+        norm_l1(&self.pos) * norm_l1(&self.vel)
+    }
+}
+
+/// Velocity change along one axis of a moon at `p_moon1` pulled by a moon at
+/// `p_moon2`.
+pub fn compute_gravity_1d(p_moon1: i32, p_moon2: i32) -> i32 {
+    if p_moon2 > p_moon1 {
+        1
+    } else if p_moon2 < p_moon1 {
+        -1
+    } else {
+        0
+    }
+}
+
+// Parse a position like '<x=-1, y=0, z=2>', the axis names are ignored.
+fn line_to_body<const D: usize>(line: &str) -> crate::Result<Body<D>> {
+    let line = line.trim_matches(|c| c == '<' || c == '>');
+    let coords: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+
+    if coords.len() != D {
+        return Err(crate::Error::boxed(E::WrongNumOfCoordinates));
+    }
+
+    let mut pos = [0; D];
+    for (p, assignment) in pos.iter_mut().zip(coords) {
+        *p = assignment
+            .split('=')
+            .nth(1)
+            .ok_or(crate::Error::boxed(E::InvalidInputLine))
+            .and_then(|num| {
+                num.parse::<i32>()
+                    .or(Err(crate::Error::boxed(E::InvalidNumber)))
+            })?;
+    }
+    Ok(Body::new(pos))
+}
+
+/// A system of bodies in `D` dimensions, advanced one time step at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct System<const D: usize> {
+    bodies: Vec<Body<D>>,
+}
+
+impl<const D: usize> System<D> {
+    pub fn new(bodies: Vec<Body<D>>) -> Self {
+        System { bodies }
+    }
+
+    /// Parse one body per line in the form `<x=-1, y=0, z=2>` with exactly
+    /// `D` coordinates, empty lines are skipped.
+    pub fn parse(input: &str) -> crate::Result<Self> {
+        let mut bodies = Vec::new();
+        for line in input.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            bodies.push(line_to_body(line)?);
+        }
+        Ok(System { bodies })
+    }
+
+    pub fn moons(&self) -> impl Iterator<Item = &Body<D>> {
+        self.bodies.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// Project the system onto a single `axis`.
+    pub fn axis(&self, axis: usize) -> System<1> {
+        assert!(axis < D);
+        System {
+            bodies: self
+                .bodies
+                .iter()
+                .map(|b| Body {
+                    pos: [b.pos[axis]],
+                    vel: [b.vel[axis]],
+                })
+                .collect(),
+        }
+    }
+
+    /// Advance the simulation by one time step.
+    pub fn step(&mut self) {
+        // gravity is symmetric, every pair is visited once
+        for i in 1..self.bodies.len() {
+            let (head, tail) = self.bodies.split_at_mut(i);
+            let body = &mut tail[0];
+            for other in head {
+                body.attract(other);
+            }
+        }
+        for body in self.bodies.iter_mut() {
+            body.apply_velocity();
+        }
+    }
+
+    pub fn total_energy(&self) -> i32 {
+        self.bodies.iter().fold(0, |e, b| e + b.energy())
+    }
+}
+
+/// Iterator over successive states of a `System`, starting with the initial
+/// state.
+pub struct States<const D: usize> {
+    next: System<D>,
+}
+
+impl<const D: usize> Iterator for States<D> {
+    type Item = System<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.next.clone();
+        self.next.step();
+        Some(state)
+    }
+}
+
+impl<const D: usize> IntoIterator for System<D> {
+    type Item = System<D>;
+    type IntoIter = States<D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        States { next: self }
+    }
+}
+
+/// Total energy of the system after `steps` time steps.
+pub fn energy_after(mut system: System<3>, steps: usize) -> i32 {
+    for _ in 0..steps {
+        system.step();
+    }
+    system.total_energy()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Cycle {
+    // first step which is part of the cycle
+    start: u64,
+    // number of steps until a state repeats
+    len: u64,
+}
+
+impl Cycle {
+    // Combine the cycles of independent sub-systems: the whole system is
+    // periodic once every sub-system entered its cycle and repeats after all
+    // sub-system periods line up. `None` if the period overflows.
+    fn combine(self, other: Cycle) -> Option<Cycle> {
+        Some(Cycle {
+            start: std::cmp::max(self.start, other.start),
+            len: crate::math::checked_lcm(self.len, other.len)?,
+        })
+    }
+}
+
+// Find the cycle of the sequence x0, f(x0), f(f(x0)), ... using Brent's
+// algorithm, which only needs to keep two states around.
+fn find_cycle<S: Clone + PartialEq>(x0: S, f: impl Fn(&mut S)) -> Cycle {
+    // search successive powers of two for the cycle length
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = x0.clone();
+    let mut hare = x0.clone();
+    f(&mut hare);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        f(&mut hare);
+        len += 1;
+    }
+
+    // move hare `len` steps ahead and advance both until they meet at the
+    // start of the cycle
+    let mut tortoise = x0.clone();
+    let mut hare = x0;
+    for _ in 0..len {
+        f(&mut hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        f(&mut tortoise);
+        f(&mut hare);
+        start += 1;
+    }
+
+    Cycle { start, len }
+}
+
+/// Number of steps until the system repeats a previous state, the axes are
+/// searched in parallel. Fails if the number doesn't fit into a `u64`.
+pub fn steps_to_repeat(system: System<3>) -> crate::Result<u64> {
+    // The axes are independent of each other, search their cycles in parallel.
+    let axes: Vec<_> = (0..3).map(|axis| system.axis(axis)).collect();
+    let cycles_1d = crate::par::map_chunks(&axes, axes.len(), |axis| {
+        find_cycle(axis[0].clone(), System::step)
+    });
+
+    // Number of steps until a state matches any previous state.
+    let steps = cycles_1d[1..]
+        .iter()
+        .try_fold(cycles_1d[0], |last, curr| last.combine(*curr))
+        .and_then(|cycle| cycle.start.checked_add(cycle.len))
+        .ok_or_else(|| crate::Error::boxed(E::PeriodTooLarge))?;
+    Ok(steps)
+}
+
+/// Total energy after 1000 time steps.
+#[aoc(day = 12, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i32> {
+    Ok(energy_after(System::parse(input)?, 1000))
+}
+
+/// Number of steps until the moons repeat a previous state.
+#[aoc(day = 12, part = 2)]
+pub fn part2(input: &str) -> crate::Result<u64> {
+    steps_to_repeat(System::parse(input)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "<x=-1, y=0, z=2>\n\
+                           <x=2, y=-10, z=-7>\n\
+                           <x=4, y=-8, z=8>\n\
+                           <x=3, y=5, z=-1>\n";
+
+    #[test]
+    fn test_parse() {
+        let system = System::<3>::parse(EXAMPLE).unwrap();
+        assert_eq!(system.len(), 4);
+        assert_eq!(system.moons().next(), Some(&Moon::new([-1, 0, 2])));
+
+        assert!(System::<3>::parse("<x=1, y=2>").is_err());
+        assert!(System::<3>::parse("<x=1, y=2, z=a>").is_err());
+        assert!(System::<3>::parse("\n\n").unwrap().is_empty());
+        assert_eq!(System::<2>::parse("<x=1, y=2>").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut system = System::<3>::parse(EXAMPLE).unwrap();
+        system.step();
+        let first = system.moons().next().unwrap();
+        assert_eq!(first.pos, [2, -1, 1]);
+        assert_eq!(first.vel, [3, -1, -1]);
+    }
+
+    #[test]
+    fn test_states() {
+        let system = System::<3>::parse(EXAMPLE).unwrap();
+        let mut states = system.clone().into_iter();
+        assert_eq!(states.next(), Some(system));
+        assert_eq!(states.nth(9).unwrap().total_energy(), 179);
+    }
+
+    #[test]
+    fn test_lower_dimensions() {
+        // two bodies oscillating on a line
+        let system = System::new(vec![Body::new([0]), Body::new([2])]);
+        let pos: Vec<_> = system
+            .into_iter()
+            .take(7)
+            .map(|s| s.moons().map(|b| b.pos[0]).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(
+            pos,
+            vec![
+                vec![0, 2],
+                vec![1, 1],
+                vec![2, 0],
+                vec![2, 0],
+                vec![1, 1],
+                vec![0, 2],
+                vec![0, 2]
+            ]
+        );
+
+        // the per-axis projections evolve like the full system
+        let mut system = System::<2>::parse("<x=0, y=3>\n<x=4, y=-1>").unwrap();
+        let mut x = system.axis(0);
+        let mut y = system.axis(1);
+        for _ in 0..5 {
+            system.step();
+            x.step();
+            y.step();
+        }
+        assert_eq!(system.axis(0), x);
+        assert_eq!(system.axis(1), y);
+    }
+
+    #[test]
+    fn test_energy_after() {
+        let Some(input) = crate::fixtures::personal_input(12) else {
+            return;
+        };
+        assert_eq!(part1(&input).unwrap(), 9139)
+    }
+
+    #[test]
+    fn test_steps_to_repeat() {
+        let Some(input) = crate::fixtures::personal_input(12) else {
+            return;
+        };
+        assert_eq!(part2(&input).unwrap(), 420788524631496)
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = "<x=-1, y=0, z=2>\n\
+                     <x=2, y=-10, z=-7>\n\
+                     <x=4, y=-8, z=8>\n\
+                     <x=3, y=5, z=-1>\n";
+        let moons = System::parse(input).unwrap();
+        assert_eq!(moons.len(), 4);
+        assert_eq!(energy_after(moons.clone(), 10), 179);
+        assert_eq!(steps_to_repeat(moons).unwrap(), 2772);
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 2
+        let f = |x: &mut u32| *x = if *x == 4 { 2 } else { *x + 1 };
+        assert_eq!(find_cycle(0, f), Cycle { start: 2, len: 3 });
+        assert_eq!(find_cycle(3, f), Cycle { start: 0, len: 3 });
+
+        // fixed point
+        assert_eq!(find_cycle(7, |_| {}), Cycle { start: 0, len: 1 });
+
+        let c = Cycle { start: 2, len: 4 }.combine(Cycle { start: 5, len: 6 });
+        assert_eq!(c, Some(Cycle { start: 5, len: 12 }));
+
+        // periods of coprime sub-systems overflow
+        let c = Cycle {
+            start: 0,
+            len: 1 << 32,
+        }
+        .combine(Cycle {
+            start: 0,
+            len: (1 << 32) + 1,
+        });
+        assert_eq!(c, None);
+    }
+
+    #[test]
+    fn test_n_bodies() {
+        // two moons attract each other and oscillate around their center
+        let moons = System::parse("<x=0, y=0, z=0>\n<x=2, y=0, z=0>").unwrap();
+        assert_eq!(moons.len(), 2);
+        assert_eq!(steps_to_repeat(moons.clone()).unwrap(), 6);
+
+        let moons = System::parse("<x=1, y=2, z=3>").unwrap();
+        assert_eq!(energy_after(moons.clone(), 1000), 0);
+        assert_eq!(steps_to_repeat(moons).unwrap(), 1);
+
+        assert!(System::<3>::parse("<x=1, y=2>").is_err());
+    }
+}
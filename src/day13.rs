@@ -0,0 +1,642 @@
+//! Care package, an intcode arcade cabinet playing breakout (day 13).
+
+use crate::grid::Grid2D;
+use crate::intcode::{IntcodeBuilder, IntcodeISS, IntcodeProgram, Snapshot, StopReason, Value};
+use crate::json::{self, Json};
+use crate::solver::aoc;
+use std::convert::TryFrom;
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+pub enum E {
+    InvalidTileId,
+    InvalidPosition(Value, Value),
+    ScreenTooLarge(Value, Value),
+    InvalidSaveFile,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl TryFrom<Value> for Tile {
+    type Error = crate::Error<E>;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            _ => return Err(crate::Error::new(E::InvalidTileId)),
+        })
+    }
+}
+
+/// What a (x, y, tile) triple output by the game does.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    DrawTile { x: usize, y: usize, tile: Tile },
+    Score(Value),
+}
+
+/// Decodes the output of the game into events. The game may stop for input
+/// anywhere, the values of an incomplete triple are kept until the rest of it
+/// is output.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OutputDecoder {
+    partial: [Value; 2],
+    len: usize,
+}
+
+impl OutputDecoder {
+    pub fn new() -> OutputDecoder {
+        OutputDecoder::default()
+    }
+
+    /// Feed the next output value, returns the event once a triple is
+    /// complete. The special position (-1, 0) carries the score, any other
+    /// position must be on a screen of at most `Screen::MAX_WIDTH` x
+    /// `Screen::MAX_HEIGHT` tiles.
+    pub fn push(&mut self, value: Value) -> crate::Result<Option<Event>> {
+        if self.len < 2 {
+            self.partial[self.len] = value;
+            self.len += 1;
+            return Ok(None);
+        }
+        self.len = 0;
+        let [x, y] = self.partial;
+        if x == -1 && y == 0 {
+            return Ok(Some(Event::Score(value)));
+        }
+        let (x, y) = Screen::position(x, y)?;
+        let tile = Tile::try_from(value)?;
+        Ok(Some(Event::DrawTile { x, y, tile }))
+    }
+
+    /// Whether a triple was started but isn't complete yet.
+    pub fn is_pending(&self) -> bool {
+        self.len > 0
+    }
+}
+
+pub struct Screen {
+    pub fb: Grid2D<Tile>,
+    pub xball: usize,
+    pub yball: usize,
+    pub xpaddle: usize,
+    pub ypaddle: usize,
+    // output of the game not applied to the screen yet
+    decoder: OutputDecoder,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen::new()
+    }
+}
+
+impl Screen {
+    // Largest screen drawn by any game, positions outside of it are rejected
+    // instead of allocating a framebuffer for them.
+    pub const MAX_WIDTH: usize = 256;
+    pub const MAX_HEIGHT: usize = 256;
+
+    pub fn new() -> Screen {
+        Screen::with_size(0, 0)
+    }
+
+    /// Screen with a framebuffer of `width` x `height` empty tiles.
+    pub fn with_size(width: usize, height: usize) -> Screen {
+        Screen {
+            fb: Grid2D::with_size(width, height, Tile::Empty),
+            xball: 0,
+            yball: 0,
+            xpaddle: 0,
+            ypaddle: 0,
+            decoder: OutputDecoder::new(),
+        }
+    }
+
+    pub fn insert_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        match tile {
+            Tile::Ball => {
+                self.xball = x;
+                self.yball = y;
+            }
+            Tile::Paddle => {
+                self.xpaddle = x;
+                self.ypaddle = y;
+            }
+            _ => {}
+        }
+        self.fb.set(x, y, tile);
+    }
+
+    // Validate a position output by the game.
+    pub fn position(x: Value, y: Value) -> crate::Result<(usize, usize)> {
+        let (ux, uy) = match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(ux), Ok(uy)) => (ux, uy),
+            _ => return Err(crate::Error::boxed(E::InvalidPosition(x, y))),
+        };
+        if ux >= Screen::MAX_WIDTH || uy >= Screen::MAX_HEIGHT {
+            return Err(crate::Error::boxed(E::ScreenTooLarge(x, y)));
+        }
+        Ok((ux, uy))
+    }
+
+    pub fn count_tile(&self, tile: Tile) -> usize {
+        self.fb.iter().filter(|&(_, &t)| t == tile).count()
+    }
+}
+
+// Apply the output of the game to the screen, see `OutputDecoder`. A triple
+// which isn't complete is applied with the output of the next resume.
+pub fn update_screen(
+    screen: &mut Screen,
+    score: &mut Value,
+    output: &[Value],
+) -> crate::Result<()> {
+    // Size the framebuffer for the whole output first, the first frame draws
+    // the full screen and would grow it tile by tile otherwise.
+    let mut probe = screen.decoder;
+    let (mut width, mut height) = (0, 0);
+    for &value in output {
+        if let Some(Event::DrawTile { x, y, .. }) = probe.push(value)? {
+            width = width.max(x + 1);
+            height = height.max(y + 1);
+        }
+    }
+    screen.fb.grow_to(width, height);
+
+    for &value in output {
+        match screen.decoder.push(value)? {
+            Some(Event::DrawTile { x, y, tile }) => screen.insert_tile(x, y, tile),
+            Some(Event::Score(value)) => *score = value,
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// State of a running game: the machine plus what it has drawn so far and
+/// statistics about how the game was played.
+pub struct Game {
+    pub iss: IntcodeISS,
+    pub screen: Screen,
+    pub score: Value,
+    pub frames: usize,
+    pub paddle_moves: usize,
+    pub halted: bool,
+    // output of the last frame, reused for every frame
+    output: Vec<Value>,
+}
+
+/// Summary of a played game.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub score: Value,
+    pub blocks_remaining: usize,
+    pub frames: usize,
+    pub paddle_moves: usize,
+}
+
+// Save files are JSON objects with the score, the screen as rows of tile
+// ids and the machine snapshot:
+//
+//   {"format": "day13-save v2", "score": 0, "screen": ["1110"],
+//    "snapshot": {"pc": 0, "rb": 0, "mem": [1, 2]}}
+//
+// The text saves of v1 contain the header, the score, the screen, an empty
+// line and the snapshot in its text form, they can still be loaded.
+const SAVE_FORMAT: &str = "day13-save v2";
+const SAVE_HEADER_V1: &str = "day13-save v1";
+
+impl Game {
+    /// A fresh game of `prog`, played for free.
+    pub fn new(prog: &IntcodeProgram) -> crate::Result<Game> {
+        let iss = IntcodeBuilder::new(prog).poke(0, 2).build()?;
+        Ok(Game::with_state(iss, Screen::new(), 0))
+    }
+
+    pub fn with_state(iss: IntcodeISS, screen: Screen, score: Value) -> Game {
+        Game {
+            iss,
+            screen,
+            score,
+            frames: 0,
+            paddle_moves: 0,
+            halted: false,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn blocks_remaining(&self) -> usize {
+        self.screen.count_tile(Tile::Block)
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            score: self.score,
+            blocks_remaining: self.blocks_remaining(),
+            frames: self.frames,
+            paddle_moves: self.paddle_moves,
+        }
+    }
+
+    /// Run one frame: feed the joystick `input` (if any) and run the machine
+    /// until it asks for the next input or halts. Returns the raw output of
+    /// the frame after it has been applied to the screen.
+    pub fn advance(&mut self, input: Option<Value>) -> crate::Result<&[Value]> {
+        let stop_reason = self
+            .iss
+            .compute_into(input.as_slice().iter(), &mut self.output)?;
+        update_screen(&mut self.screen, &mut self.score, &self.output)?;
+        self.frames += 1;
+        if input.is_some_and(|i| i != 0) {
+            self.paddle_moves += 1;
+        }
+        self.halted = stop_reason == StopReason::ProgramHalt;
+        Ok(&self.output)
+    }
+
+    /// Play until the game is over with the paddle controlled by `strategy`.
+    /// After every frame `observer` is called with the game, the input and
+    /// the output of that frame.
+    pub fn run<F>(
+        &mut self,
+        strategy: &mut dyn PaddleStrategy,
+        mut observer: F,
+    ) -> crate::Result<Summary>
+    where
+        F: FnMut(&Game, Option<Value>, &[Value]) -> crate::Result<()>,
+    {
+        // a fresh game first has to draw the screen before the paddle can move
+        let mut input = if self.screen.fb.is_empty() {
+            None
+        } else {
+            Some(strategy.joystick(&self.screen, &self.iss))
+        };
+        while !self.halted {
+            self.advance(input)?;
+            observer(self, input, &self.output)?;
+            input = Some(strategy.joystick(&self.screen, &self.iss));
+        }
+        Ok(self.summary())
+    }
+
+    /// The game as JSON save.
+    pub fn save(&self) -> String {
+        let rows = self.screen.fb.render(|&t| (b'0' + t as u8) as char);
+        let save = Json::object(vec![
+            ("format", Json::from(SAVE_FORMAT)),
+            ("score", Json::from(self.score)),
+            ("screen", Json::from(rows.lines().collect::<Vec<_>>())),
+            ("snapshot", self.iss.snapshot().to_json()),
+        ]);
+        save.to_string()
+    }
+
+    /// Load a save of any format version.
+    pub fn load(save: &str) -> crate::Result<Game> {
+        if save.starts_with(SAVE_HEADER_V1) {
+            return Game::load_v1(save);
+        }
+        let invalid = || crate::Error::boxed(E::InvalidSaveFile);
+
+        let save = json::parse(save)?;
+        if save.get("format").and_then(Json::as_str) != Some(SAVE_FORMAT) {
+            return Err(invalid());
+        }
+        let score = save
+            .get("score")
+            .and_then(Json::as_i64)
+            .ok_or_else(invalid)?;
+        let rows = save
+            .get("screen")
+            .and_then(Json::as_array)
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|row| row.as_str().ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let snapshot = Snapshot::from_json(save.get("snapshot").ok_or_else(invalid)?)?;
+        Ok(Game::with_state(
+            IntcodeISS::from_snapshot(&snapshot),
+            Game::load_screen(&rows)?,
+            score,
+        ))
+    }
+
+    fn load_v1(save: &str) -> crate::Result<Game> {
+        let invalid = || crate::Error::boxed(E::InvalidSaveFile);
+
+        let mut lines = save.lines();
+        if lines.next() != Some(SAVE_HEADER_V1) {
+            return Err(invalid());
+        }
+        let score = lines
+            .next()
+            .and_then(|l| l.strip_prefix("score "))
+            .and_then(|s| s.parse::<Value>().ok())
+            .ok_or_else(invalid)?;
+
+        let rows: Vec<_> = lines.by_ref().take_while(|l| !l.is_empty()).collect();
+        let screen = Game::load_screen(&rows)?;
+        let snapshot = lines.collect::<Vec<_>>().join("\n").parse::<Snapshot>()?;
+        Ok(Game::with_state(
+            IntcodeISS::from_snapshot(&snapshot),
+            screen,
+            score,
+        ))
+    }
+
+    // The screen from rows of tile ids.
+    fn load_screen(rows: &[&str]) -> crate::Result<Screen> {
+        let mut screen = Screen::new();
+        for (y, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let id = c
+                    .to_digit(10)
+                    .ok_or_else(|| crate::Error::boxed(E::InvalidSaveFile))?;
+                let (x, y) = Screen::position(x as Value, y as Value)?;
+                screen.insert_tile(x, y, Tile::try_from(Value::from(id))?);
+            }
+        }
+        Ok(screen)
+    }
+}
+
+/// Controller deciding the joystick input for the next frame.
+pub trait PaddleStrategy {
+    fn joystick(&mut self, screen: &Screen, iss: &IntcodeISS) -> Value;
+}
+
+fn move_towards(xpaddle: usize, x: usize) -> Value {
+    match x.cmp(&xpaddle) {
+        std::cmp::Ordering::Less => -1,
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Greater => 1,
+    }
+}
+
+/// Greedily move the paddle below the current ball position.
+pub struct FollowBall;
+
+impl PaddleStrategy for FollowBall {
+    fn joystick(&mut self, screen: &Screen, _iss: &IntcodeISS) -> Value {
+        move_towards(screen.xpaddle, screen.xball)
+    }
+}
+
+/// Move the paddle to where the ball will arrive at the paddle row. The
+/// landing position is found by forking the game and running it with a
+/// resting joystick until the ball reaches the row above the paddle, which
+/// also accounts for bounces off blocks and walls on the way.
+#[derive(Default)]
+pub struct PredictBall {
+    target: Option<usize>,
+}
+
+impl PredictBall {
+    // Upper bound of frames to simulate for one prediction.
+    const MAX_FRAMES: usize = 10_000;
+
+    pub fn new() -> PredictBall {
+        PredictBall { target: None }
+    }
+
+    fn predict(screen: &Screen, iss: &IntcodeISS) -> usize {
+        let mut fork = iss.fork();
+        let mut xball = screen.xball;
+        let mut output = Vec::new();
+        let mut decoder = OutputDecoder::new();
+        for _ in 0..PredictBall::MAX_FRAMES {
+            // a failing fork leaves the prediction where the ball was seen last
+            let Ok(stop_reason) = fork.compute_into([0].iter(), &mut output) else {
+                break;
+            };
+            for &value in &output {
+                if let Ok(Some(Event::DrawTile {
+                    x,
+                    y,
+                    tile: Tile::Ball,
+                })) = decoder.push(value)
+                {
+                    xball = x;
+                    if y + 1 == screen.ypaddle {
+                        return xball;
+                    }
+                }
+            }
+            if stop_reason == StopReason::ProgramHalt {
+                break;
+            }
+        }
+        xball
+    }
+}
+
+impl PaddleStrategy for PredictBall {
+    fn joystick(&mut self, screen: &Screen, iss: &IntcodeISS) -> Value {
+        if screen.yball + 1 == screen.ypaddle {
+            // ball is about to bounce off the paddle, stay below it
+            self.target = None;
+            return move_towards(screen.xpaddle, screen.xball);
+        }
+        let target = *self
+            .target
+            .get_or_insert_with(|| PredictBall::predict(screen, iss));
+        move_towards(screen.xpaddle, target)
+    }
+}
+
+pub fn parse(input: &str) -> crate::Result<IntcodeProgram> {
+    crate::intcode::parse_program(input)
+}
+
+/// Number of block tiles on the screen when the game exits.
+#[aoc(day = 13, part = 1)]
+pub fn part1(input: &str) -> crate::Result<usize> {
+    let mut iss = IntcodeISS::new(&parse(input)?);
+
+    // draw the tiles one triple at a time as the game outputs them
+    let mut screen = Screen::new();
+    let mut decoder = OutputDecoder::new();
+    for value in iss.outputs([].iter()) {
+        if let Some(Event::DrawTile { x, y, tile }) = decoder.push(value?)? {
+            screen.insert_tile(x, y, tile);
+        }
+    }
+    Ok(screen.count_tile(Tile::Block))
+}
+
+/// Score after the last block is broken, the paddle follows the ball.
+#[aoc(day = 13, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Value> {
+    let mut game = Game::new(&parse(input)?)?;
+    Ok(game.run(&mut FollowBall, |_, _, _| Ok(()))?.score)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parts() -> crate::Result<()> {
+        let Some(input) = crate::fixtures::personal_input(13) else {
+            return Ok(());
+        };
+        assert_eq!(part1(&input)?, 344);
+        assert_eq!(part2(&input)?, 17336);
+        Ok(())
+    }
+
+    #[test]
+    fn test_game_run() -> crate::Result<()> {
+        let Some(input) = crate::fixtures::personal_input(13) else {
+            return Ok(());
+        };
+        let mut game = Game::new(&parse(&input)?)?;
+        let mut observed = 0;
+        let summary = game.run(&mut FollowBall, |game, input, _| {
+            assert_eq!(input.is_none(), observed == 0);
+            observed += 1;
+            assert_eq!(game.frames, observed);
+            Ok(())
+        })?;
+        assert_eq!(summary.frames, observed);
+        assert!(game.halted);
+        assert_eq!(game.summary(), summary);
+
+        // a finished game doesn't run any further
+        let again = game.run(&mut FollowBall, |_, _, _| Ok(()))?;
+        assert_eq!(again, summary);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_resume() -> crate::Result<()> {
+        let Some(input) = crate::fixtures::personal_input(13) else {
+            return Ok(());
+        };
+        // play a few frames, save and finish the game from the save
+        let mut game = Game::new(&parse(&input)?)?;
+        let mut strategy = PredictBall::new();
+        let mut joystick = None;
+        for _ in 0..100 {
+            game.advance(joystick)?;
+            joystick = Some(strategy.joystick(&game.screen, &game.iss));
+        }
+        assert!(game.score > 0);
+        assert_eq!(game.frames, 100);
+        assert!(game.blocks_remaining() < part1(&input)?);
+
+        let save = game.save();
+        let mut loaded = Game::load(&save)?;
+        assert_eq!(loaded.save(), save);
+        assert_eq!(loaded.score, game.score);
+        assert!(loaded.screen.fb == game.screen.fb);
+        assert_eq!(loaded.screen.xball, game.screen.xball);
+
+        let summary = loaded.run(&mut PredictBall::new(), |_, _, _| Ok(()))?;
+        assert_eq!(summary.score, 17336);
+        assert_eq!(summary.blocks_remaining, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_save() -> crate::Result<()> {
+        assert!(Game::load("day13-save v1\nscore x\n").is_err());
+        assert!(Game::load("day13-save v1\nscore 1\n09\n\npc=0 rb=0\n").is_err());
+        assert!(Game::load("{\"format\": \"day13-save v3\"}").is_err());
+
+        // saves of v1 are still loaded
+        let v1 = Game::load("day13-save v1\nscore 7\n12\n\npc=0 rb=0\n99")?;
+        assert_eq!(v1.score, 7);
+        assert!(v1.save().contains("\"screen\": [\"12\"]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_screen() -> crate::Result<()> {
+        // a full first frame of 40x20 tiles followed by the score
+        let mut output = Vec::new();
+        for y in 0..20 {
+            for x in 0..40 {
+                output.extend_from_slice(&[x, y, (x + y) % 3]);
+            }
+        }
+        output.extend_from_slice(&[-1, 0, 12]);
+
+        let mut screen = Screen::new();
+        let mut score = 0;
+        let (result, allocations) =
+            crate::alloc_count::count(|| update_screen(&mut screen, &mut score, &output));
+        result?;
+        assert_eq!(allocations, 1);
+        assert_eq!((screen.fb.width(), screen.fb.height()), (40, 20));
+        assert_eq!(screen.fb.get(2, 0), Some(&Tile::Block));
+        assert_eq!(score, 12);
+
+        // later frames only update tiles, partial triples are completed by
+        // the next output
+        let (result, allocations) =
+            crate::alloc_count::count(|| update_screen(&mut screen, &mut score, &[3, 4, 4, -1, 0]));
+        result?;
+        assert_eq!(allocations, 0);
+        assert_eq!((screen.xball, screen.yball), (3, 4));
+        assert_eq!(score, 12);
+        update_screen(&mut screen, &mut score, &[13])?;
+        assert_eq!(score, 13);
+
+        assert!(update_screen(&mut screen, &mut score, &[-2, 0, 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_decoder() -> crate::Result<()> {
+        let output = [1, 2, 3, -1, 0, 42, 0, 0, 1];
+        let expected = vec![
+            Event::DrawTile {
+                x: 1,
+                y: 2,
+                tile: Tile::Paddle,
+            },
+            Event::Score(42),
+            Event::DrawTile {
+                x: 0,
+                y: 0,
+                tile: Tile::Wall,
+            },
+        ];
+        // the game may stop for input after any value
+        for split in 0..=output.len() {
+            let mut decoder = OutputDecoder::new();
+            let mut events = Vec::new();
+            for part in [&output[..split], &output[split..]].iter() {
+                for &value in part.iter() {
+                    events.extend(decoder.push(value)?);
+                }
+            }
+            assert_eq!(events, expected);
+            assert!(!decoder.is_pending());
+        }
+
+        let mut decoder = OutputDecoder::new();
+        assert_eq!(decoder.push(3)?, None);
+        assert!(decoder.is_pending());
+        assert_eq!(decoder.push(-1)?, None);
+        assert!(decoder.push(0).is_err());
+        // the invalid triple is dropped
+        assert!(!decoder.is_pending());
+        assert!(decoder.push(-1)?.is_none());
+        assert!(decoder.push(0)?.is_none());
+        assert_eq!(decoder.push(7)?, Some(Event::Score(7)));
+        Ok(())
+    }
+}
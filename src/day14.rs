@@ -0,0 +1,651 @@
+//! Space stoichiometry: a nanofactory turning ORE into FUEL (day 14).
+//!
+//! The reactions form a DAG from FUEL down to ORE. Visiting the chemicals in
+//! topological order means the whole demand of a chemical is known before
+//! its reactions are run, so a production run is a single pass.
+
+use crate::cache::Cacheable;
+use crate::graphml::{AttrType, Graph};
+use crate::intcode::Value;
+use crate::solver::aoc;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::Write;
+
+#[derive(Debug, PartialEq)]
+pub enum Reason {
+    MissingArrow,
+    InvalidReactant,
+    InvalidQuantity,
+    DuplicateOutput,
+}
+
+#[derive(Debug)]
+pub enum E {
+    InvalidFormula {
+        line: usize,
+        token: String,
+        reason: Reason,
+    },
+    FuelTokenNotFound,
+    OreTokenNotFound,
+    UnknownElement,
+    UnknownChemical(String),
+    CyclicReactions,
+}
+
+pub type ElemId = usize;
+type Elem = (ElemId, i64);
+
+// Reaction producing `quantity` of a chemical from the `reactants`.
+struct Reaction {
+    quantity: i64,
+    reactants: Vec<Elem>,
+}
+
+/// Quantities per chemical after a production run. The requested product
+/// counts as consumed, ore is mined exactly as much as is consumed.
+pub struct Production {
+    produced: Vec<i64>,
+    consumed: Vec<i64>,
+}
+
+impl Production {
+    pub fn produced(&self, id: ElemId) -> i64 {
+        self.produced[id]
+    }
+
+    pub fn consumed(&self, id: ElemId) -> i64 {
+        self.consumed[id]
+    }
+
+    pub fn leftover(&self, id: ElemId) -> i64 {
+        self.produced[id] - self.consumed[id]
+    }
+}
+
+/// The reactions of a nanofactory, indexed by the id of the produced
+/// chemical.
+pub struct Nanofactory {
+    reactions: Vec<Option<Reaction>>,
+    // every product comes before its reactants
+    order: Vec<ElemId>,
+    names: Vec<String>,
+    ids: HashMap<String, ElemId>,
+    fuel: ElemId,
+    ore: ElemId,
+}
+
+/// Cached as the number of chemicals, their names as length and bytes,
+/// followed by the reaction of every chemical as quantity (0 if there is
+/// none), number of reactants and (id, quantity) per reactant.
+impl Cacheable for Nanofactory {
+    const KIND: &'static str = "reactions";
+
+    fn to_cells(&self) -> Vec<Value> {
+        let mut cells = vec![self.names.len() as Value];
+        for name in self.names.iter() {
+            cells.push(name.len() as Value);
+            cells.extend(name.bytes().map(Value::from));
+        }
+        for reaction in self.reactions.iter() {
+            match reaction {
+                None => cells.extend([0, 0]),
+                Some(reaction) => {
+                    cells.extend([reaction.quantity, reaction.reactants.len() as Value]);
+                    for &(id, quantity) in reaction.reactants.iter() {
+                        cells.extend([id as Value, quantity]);
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    fn from_cells(cells: &[Value]) -> Option<Self> {
+        let mut cells = cells.iter().copied();
+        let mut next_usize = || usize::try_from(cells.next()?).ok();
+
+        let len = next_usize()?;
+        let mut names = Vec::with_capacity(len);
+        for _ in 0..len {
+            let name_len = next_usize()?;
+            let bytes = (0..name_len)
+                .map(|_| u8::try_from(next_usize()?).ok())
+                .collect::<Option<Vec<_>>>()?;
+            names.push(String::from_utf8(bytes).ok()?);
+        }
+        let mut reactions = Vec::with_capacity(len);
+        for _ in 0..len {
+            let quantity = next_usize()? as i64;
+            let reactants = (0..next_usize()?)
+                .map(|_| Some((next_usize().filter(|&id| id < len)?, next_usize()? as i64)))
+                .collect::<Option<Vec<_>>>()?;
+            reactions.push(if quantity == 0 {
+                None
+            } else {
+                Some(Reaction {
+                    quantity,
+                    reactants,
+                })
+            });
+        }
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect();
+        Nanofactory::build(reactions, names, ids).ok()
+    }
+}
+
+fn split_formula(formula: &str) -> Result<(&str, &str), Reason> {
+    match formula.split_once("=>") {
+        Some((reactants, result)) if !result.contains("=>") => Ok((reactants, result)),
+        _ => Err(Reason::MissingArrow),
+    }
+}
+
+fn split_reactant(reactant: &str) -> Result<(&str, i64), Reason> {
+    let mut p = reactant.split_ascii_whitespace();
+    let (quantity, name) = match (p.next(), p.next(), p.next()) {
+        (Some(quantity), Some(name), None) => (quantity, name),
+        _ => return Err(Reason::InvalidReactant),
+    };
+    match quantity.parse::<i64>() {
+        Ok(q) if q > 0 => Ok((name, q)),
+        _ => Err(Reason::InvalidQuantity),
+    }
+}
+
+// Kahn's algorithm over the edges product -> reactant.
+fn topological_order(reactions: &[Option<Reaction>]) -> crate::Result<Vec<ElemId>> {
+    let mut consumers = vec![0; reactions.len()];
+    for reaction in reactions.iter().flatten() {
+        for &(id, _) in reaction.reactants.iter() {
+            consumers[id] += 1;
+        }
+    }
+
+    let mut ready: Vec<_> = (0..reactions.len())
+        .filter(|&id| consumers[id] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(reactions.len());
+    while let Some(id) = ready.pop() {
+        order.push(id);
+        for &(reactant, _) in reactions[id].iter().flat_map(|r| r.reactants.iter()) {
+            consumers[reactant] -= 1;
+            if consumers[reactant] == 0 {
+                ready.push(reactant);
+            }
+        }
+    }
+
+    if order.len() != reactions.len() {
+        return Err(crate::Error::boxed(E::CyclicReactions));
+    }
+    Ok(order)
+}
+
+fn requiere_n_reactions(quantity_needed: i64, quatity_per_reaction: i64) -> i64 {
+    (quantity_needed + quatity_per_reaction - 1) / quatity_per_reaction
+}
+
+impl Nanofactory {
+    /// Parse one reaction per line, e.g. `7 A, 1 E => 1 FUEL`. The reactions
+    /// must produce FUEL from ORE.
+    pub fn parse(formulas: &str) -> crate::Result<Nanofactory> {
+        // names are interned as slices of the formulas, only the distinct
+        // names are copied once parsing is done
+        let mut reactions = Vec::new();
+        let mut interned: HashMap<&str, ElemId> = HashMap::new();
+        let mut id_of = |name, reactions: &mut Vec<Option<Reaction>>| {
+            let next = interned.len();
+            let id = *interned.entry(name).or_insert(next);
+            if id == reactions.len() {
+                reactions.push(None);
+            }
+            id
+        };
+
+        for (nr, formula) in formulas.lines().enumerate() {
+            let invalid = |token: &str, reason| {
+                crate::Error::new(E::InvalidFormula {
+                    line: nr + 1,
+                    token: token.trim().to_string(),
+                    reason,
+                })
+            };
+            let (in_formula, result) =
+                split_formula(formula).map_err(|reason| invalid(formula, reason))?;
+
+            let mut reactants = Vec::new();
+            for reactant in in_formula.split(',') {
+                let (r, q) =
+                    split_reactant(reactant).map_err(|reason| invalid(reactant, reason))?;
+                reactants.push((id_of(r, &mut reactions), q));
+            }
+
+            let (r, quantity) = split_reactant(result).map_err(|reason| invalid(result, reason))?;
+            let id = id_of(r, &mut reactions);
+            if reactions[id].is_some() {
+                return Err(invalid(result, Reason::DuplicateOutput).into());
+            }
+            reactions[id] = Some(Reaction {
+                quantity,
+                reactants,
+            });
+        }
+
+        let mut names = vec![String::new(); interned.len()];
+        for (&name, &id) in interned.iter() {
+            names[id] = name.to_string();
+        }
+        let ids = interned
+            .into_iter()
+            .map(|(name, id)| (name.to_string(), id))
+            .collect();
+        Nanofactory::build(reactions, names, ids)
+    }
+
+    // Order the reactions and look up FUEL and ORE.
+    fn build(
+        reactions: Vec<Option<Reaction>>,
+        names: Vec<String>,
+        ids: HashMap<String, ElemId>,
+    ) -> crate::Result<Nanofactory> {
+        let order = topological_order(&reactions)?;
+        let fuel = *ids
+            .get("FUEL")
+            .ok_or(crate::Error::boxed(E::FuelTokenNotFound))?;
+        let ore = *ids
+            .get("ORE")
+            .ok_or(crate::Error::boxed(E::OreTokenNotFound))?;
+        Ok(Nanofactory {
+            reactions,
+            order,
+            names,
+            ids,
+            fuel,
+            ore,
+        })
+    }
+
+    /// Id of the chemical called `name`.
+    pub fn chemical(&self, name: &str) -> Option<ElemId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: ElemId) -> &str {
+        &self.names[id]
+    }
+
+    /// Run the reactions needed to produce `quantity` of `product`.
+    pub fn produce(&self, product: ElemId, quantity: i64) -> crate::Result<Production> {
+        let mut produced = vec![0; self.reactions.len()];
+        let mut demand = vec![0; self.reactions.len()];
+        demand[product] = quantity;
+
+        for &id in self.order.iter() {
+            if id == self.ore || demand[id] == 0 {
+                continue;
+            }
+            let reaction = self.reactions[id]
+                .as_ref()
+                .ok_or(crate::Error::boxed(E::UnknownElement))?;
+
+            let reaction_cnt = requiere_n_reactions(demand[id], reaction.quantity);
+            produced[id] = reaction_cnt * reaction.quantity;
+            for &(reactant_id, reactant_quantity) in reaction.reactants.iter() {
+                demand[reactant_id] += reaction_cnt * reactant_quantity;
+            }
+        }
+
+        produced[self.ore] = demand[self.ore];
+
+        Ok(Production {
+            produced,
+            consumed: demand,
+        })
+    }
+
+    /// Amount of ore needed to produce `quantity` of the chemical `name`.
+    pub fn ore_for(&self, name: &str, quantity: i64) -> crate::Result<i64> {
+        let id = self
+            .chemical(name)
+            .ok_or_else(|| crate::Error::boxed(E::UnknownChemical(name.to_string())))?;
+        Ok(self.produce(id, quantity)?.consumed(self.ore))
+    }
+
+    /// Amount of ore needed to produce `fuel` FUEL.
+    pub fn ore_for_fuel(&self, fuel: i64) -> crate::Result<i64> {
+        Ok(self.produce(self.fuel, fuel)?.consumed(self.ore))
+    }
+
+    /// Largest amount of fuel which can be produced from `ore_budget` ore.
+    pub fn max_fuel(&self, ore_budget: i64) -> crate::Result<i64> {
+        // Producing fuel in bulk never needs more ore per fuel than producing
+        // a single one, which gives the lower bound for the search. Leftovers
+        // can at best be reused for every other fuel, so twice the lower
+        // bound is usually out of budget, otherwise the upper bound is
+        // doubled until it is.
+        let lower = ore_budget / self.ore_for_fuel(1)?;
+        if lower == 0 {
+            return Ok(0);
+        }
+
+        let fits = |fuel| Ok(self.ore_for_fuel(fuel)? <= ore_budget);
+        let mut upper = 2 * lower;
+        while fits(upper)? {
+            upper *= 2;
+        }
+        crate::search::last_true(lower, upper, fits)
+    }
+
+    /// Print the produced, consumed and leftover quantities of every
+    /// chemical involved in `production`, in topological order.
+    pub fn write_report(&self, out: &mut dyn Write, production: &Production) -> crate::Result<()> {
+        writeln!(
+            out,
+            "{:<8} {:>14} {:>14} {:>10}",
+            "chemical", "produced", "consumed", "leftover"
+        )?;
+        for &id in self.order.iter() {
+            if production.consumed(id) == 0 {
+                continue;
+            }
+            writeln!(
+                out,
+                "{:<8} {:>14} {:>14} {:>10}",
+                self.name(id),
+                production.produced(id),
+                production.consumed(id),
+                production.leftover(id)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the reactions as graphviz graph: every chemical is a node
+    /// labeled with the quantity one reaction produces, edges point from a
+    /// product to its reactants and are labeled with the quantity consumed
+    /// per reaction.
+    pub fn write_dot(&self, out: &mut dyn Write) -> crate::Result<()> {
+        writeln!(out, "digraph reactions {{")?;
+        for (id, reaction) in self.reactions.iter().enumerate() {
+            let name = self.name(id);
+            match reaction {
+                Some(r) => writeln!(out, "    {} [label=\"{}\\n{}\"];", name, name, r.quantity)?,
+                None => writeln!(out, "    {} [shape=box];", name)?,
+            }
+        }
+        for (id, reaction) in self.reactions.iter().enumerate() {
+            for &(reactant, quantity) in reaction.iter().flat_map(|r| r.reactants.iter()) {
+                writeln!(
+                    out,
+                    "    {} -> {} [label=\"{}\"];",
+                    self.name(id),
+                    self.name(reactant),
+                    quantity
+                )?;
+            }
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    /// The reactions as GraphML graph with the same structure as
+    /// [`write_dot`](Nanofactory::write_dot): nodes carry the quantity one
+    /// reaction produces (none for ORE), edges the quantity consumed.
+    pub fn graphml(&self) -> Graph {
+        let mut graph = Graph::new("reactions")
+            .with_node_attr("label", AttrType::String)
+            .with_node_attr("produced", AttrType::Int)
+            .with_edge_attr("quantity", AttrType::Int);
+        for (id, reaction) in self.reactions.iter().enumerate() {
+            let name = self.name(id);
+            match reaction {
+                Some(r) => graph.node(name, &[("label", &name), ("produced", &r.quantity)]),
+                None => graph.node(name, &[("label", &name)]),
+            };
+        }
+        for (id, reaction) in self.reactions.iter().enumerate() {
+            for &(reactant, quantity) in reaction.iter().flat_map(|r| r.reactants.iter()) {
+                graph.edge(
+                    self.name(id),
+                    self.name(reactant),
+                    &[("quantity", &quantity)],
+                );
+            }
+        }
+        graph
+    }
+}
+
+/// ORE available in part two.
+pub const MAX_ORE: i64 = 1_000_000_000_000;
+
+/// ORE required to produce 1 FUEL.
+#[aoc(day = 14, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i64> {
+    crate::cache::parse_cached(input, Nanofactory::parse)?.ore_for_fuel(1)
+}
+
+/// FUEL which can be produced with 1 trillion ORE.
+#[aoc(day = 14, part = 2)]
+pub fn part2(input: &str) -> crate::Result<i64> {
+    crate::cache::parse_cached(input, Nanofactory::parse)?.max_fuel(MAX_ORE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "157 ORE => 5 NZVS
+                           165 ORE => 6 DCFZ
+                           44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
+                           12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ
+                           179 ORE => 7 PSHF
+                           177 ORE => 5 HKGWZ
+                           7 DCFZ, 7 PSHF => 2 XJWVT
+                           165 ORE => 2 GPVTF
+                           3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
+
+    #[test]
+    fn test_topological_order() -> crate::Result<()> {
+        let input = "7 A, 1 E => 1 FUEL\n10 ORE => 10 A\n7 A, 1 ORE => 1 E";
+        let factory = Nanofactory::parse(input)?;
+        let pos = |name: &str| {
+            let id = factory.chemical(name).unwrap();
+            factory.order.iter().position(|&e| e == id).unwrap()
+        };
+        assert!(pos("FUEL") < pos("E"));
+        assert!(pos("E") < pos("A"));
+        assert!(pos("A") < pos("ORE"));
+
+        let cyclic = "1 B => 1 A\n1 A, 1 ORE => 1 B\n1 A => 1 FUEL";
+        assert!(Nanofactory::parse(cyclic).is_err());
+        assert!(Nanofactory::parse("1 ORE => 1 A").is_err());
+        assert!(Nanofactory::parse("1 A => 1 FUEL").is_err());
+
+        // B is used but never produced
+        let factory = Nanofactory::parse("1 B, 1 ORE => 1 FUEL")?;
+        assert!(factory.ore_for_fuel(1).is_err());
+        Ok(())
+    }
+
+    fn parse_error(formulas: &str) -> (usize, String, Reason) {
+        let err = match Nanofactory::parse(formulas) {
+            Ok(_) => panic!("parsing {:?} succeeded", formulas),
+            Err(err) => err,
+        };
+        match err.downcast::<crate::Error<E>>() {
+            Ok(err) => match err.err {
+                E::InvalidFormula {
+                    line,
+                    token,
+                    reason,
+                } => (line, token, reason),
+                err => panic!("unexpected error {:?}", err),
+            },
+            Err(err) => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn test_invalid_formulas() {
+        assert_eq!(
+            parse_error("10 ORE => 10 A\n7 A, 1 ORE = 1 FUEL"),
+            (2, "7 A, 1 ORE = 1 FUEL".to_string(), Reason::MissingArrow)
+        );
+        assert_eq!(
+            parse_error("10 ORE => 10 A => 1 B"),
+            (1, "10 ORE => 10 A => 1 B".to_string(), Reason::MissingArrow)
+        );
+        assert_eq!(
+            parse_error("10 ORE => 10 A\n7 A, x ORE => 1 FUEL"),
+            (2, "x ORE".to_string(), Reason::InvalidQuantity)
+        );
+        assert_eq!(
+            parse_error("0 ORE => 10 A"),
+            (1, "0 ORE".to_string(), Reason::InvalidQuantity)
+        );
+        assert_eq!(
+            parse_error("10 ORE => 10A"),
+            (1, "10A".to_string(), Reason::InvalidReactant)
+        );
+        assert_eq!(
+            parse_error("10 ORE, => 10 A"),
+            (1, "".to_string(), Reason::InvalidReactant)
+        );
+        assert_eq!(
+            parse_error("10 ORE => 10 A\n1 FUEL => 1 B\n1 ORE => 2 A"),
+            (3, "2 A".to_string(), Reason::DuplicateOutput)
+        );
+    }
+
+    #[test]
+    fn test_ore_for() -> crate::Result<()> {
+        let factory = Nanofactory::parse(EXAMPLE)?;
+        assert_eq!(factory.ore_for("FUEL", 1)?, factory.ore_for_fuel(1)?);
+        // 7 DCFZ, 7 PSHF => 2 XJWVT
+        // 7 DCFZ (2 reactions of 165 ORE), 7 PSHF (1 reaction of 179 ORE)
+        assert_eq!(factory.ore_for("XJWVT", 2)?, 2 * 165 + 179);
+        // 35 DCFZ (6 reactions), 35 PSHF (5 reactions)
+        assert_eq!(factory.ore_for("XJWVT", 10)?, 6 * 165 + 5 * 179);
+        assert_eq!(factory.ore_for("ORE", 42)?, 42);
+        assert!(factory.ore_for("XYZ", 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_fuel() -> crate::Result<()> {
+        let factory = Nanofactory::parse(EXAMPLE)?;
+        assert_eq!(factory.max_fuel(1_000_000_000_000)?, 82892753);
+        assert_eq!(factory.max_fuel(13312)?, 1);
+        assert_eq!(factory.max_fuel(13311)?, 0);
+
+        // no leftovers to reuse, the bounds are exact
+        let factory = Nanofactory::parse("3 ORE => 1 FUEL")?;
+        assert_eq!(factory.max_fuel(10)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_production() -> crate::Result<()> {
+        let input = "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 FUEL";
+        let factory = Nanofactory::parse(input)?;
+        let id = |name: &str| factory.chemical(name).unwrap();
+        let production = factory.produce(id("FUEL"), 1)?;
+        assert_eq!(production.consumed(id("ORE")), 21);
+        assert_eq!(production.produced(id("A")), 20);
+        assert_eq!(production.consumed(id("A")), 14);
+        assert_eq!(production.leftover(id("A")), 6);
+        assert_eq!(production.leftover(id("FUEL")), 0);
+        assert_eq!(production.leftover(id("ORE")), 0);
+
+        let mut out = Vec::new();
+        factory.write_report(&mut out, &production)?;
+        let report = String::from_utf8(out)?;
+        assert_eq!(report.lines().count(), 6);
+        assert!(report.contains("\nA                    20             14          6\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_dot() -> crate::Result<()> {
+        let factory = Nanofactory::parse("10 ORE => 10 A\n7 A, 1 ORE => 1 FUEL")?;
+        let mut out = Vec::new();
+        factory.write_dot(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"digraph reactions {
+    ORE [shape=box];
+    A [label="A\n10"];
+    FUEL [label="FUEL\n1"];
+    A -> ORE [label="10"];
+    FUEL -> A [label="7"];
+    FUEL -> ORE [label="1"];
+}
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_graphml() -> crate::Result<()> {
+        let factory = Nanofactory::parse("10 ORE => 10 A\n7 A, 1 ORE => 1 FUEL")?;
+        let mut out = Vec::new();
+        factory.graphml().write(&mut out)?;
+        let graphml = String::from_utf8(out)?;
+        assert!(graphml.contains(
+            "    <node id=\"A\">\n      <data key=\"node_label\">A</data>\n      <data key=\"node_produced\">10</data>\n    </node>\n"
+        ));
+        assert!(graphml.contains(
+            "    <edge source=\"FUEL\" target=\"A\">\n      <data key=\"edge_quantity\">7</data>\n"
+        ));
+        assert_eq!(graphml.matches("<edge ").count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parts() -> crate::Result<()> {
+        let Some(input) = crate::fixtures::personal_input(14) else {
+            return Ok(());
+        };
+        assert_eq!(part1(&input)?, 273638);
+        assert_eq!(part2(&input)?, 4200533);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_cells() -> crate::Result<()> {
+        let factory = Nanofactory::parse(EXAMPLE)?;
+        let cached = Nanofactory::from_cells(&factory.to_cells()).unwrap();
+        assert_eq!(cached.ore_for_fuel(1)?, factory.ore_for_fuel(1)?);
+        assert_eq!(cached.max_fuel(MAX_ORE)?, factory.max_fuel(MAX_ORE)?);
+        assert_eq!(cached.chemical("XJWVT"), factory.chemical("XJWVT"));
+
+        let cells = factory.to_cells();
+        assert!(Nanofactory::from_cells(&cells[..cells.len() - 1]).is_none());
+        assert!(Nanofactory::from_cells(&[]).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_allocations() {
+        // 3 ORE => 1 C0, 1 C0 => 1 C1, .. 1 Cn-2 => 1 FUEL
+        let n = 1000;
+        let input: String = (0..n)
+            .map(|i| match i {
+                0 => "3 ORE => 1 C0\n".to_string(),
+                i if i == n - 1 => format!("1 C{} => 1 FUEL\n", i - 1),
+                i => format!("1 C{} => 1 C{}\n", i - 1, i),
+            })
+            .collect();
+        let (factory, allocations) =
+            crate::alloc_count::count(|| Nanofactory::parse(&input).unwrap());
+        assert_eq!(factory.ore_for_fuel(1).unwrap(), 3);
+        assert!(allocations < 4 * n, "{} allocations", allocations);
+    }
+}
@@ -0,0 +1,562 @@
+//! Flawed Frequency Transmission (day 16).
+//!
+//! Every output digit of a phase is the sum of alternating +/- blocks of the
+//! input digits, which is computed with prefix sums of the input. Output
+//! digits only depend on input digits at and after their own position, so
+//! the digits from some offset on can be computed without the ones before.
+//! The prefix sums are scanned one digit at a time or, the default with the
+//! `simd` feature, eight digits at a time with lane-wise adds.
+
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    SignalTooShort,
+    InvalidOffset(usize),
+}
+
+// The naive phase computation multiplying with the pattern is only kept as
+// test oracle for the faster implementations.
+// The first eight digits of `digits` as string.
+fn message(digits: &[i32]) -> String {
+    digits.iter().take(8).map(|n| n.to_string()).collect()
+}
+
+/// First eight digits after 100 phases.
+#[aoc(day = 16, part = 1)]
+pub fn part1(input: &str) -> crate::Result<String> {
+    let signal = crate::input::parse_digits(input)?;
+    Ok(message(&fft(&signal, 100, crate::par::jobs())))
+}
+
+/// Eight digit message of the real signal after 100 phases.
+#[aoc(day = 16, part = 2)]
+pub fn part2(input: &str) -> crate::Result<String> {
+    let signal = crate::input::parse_digits(input)?;
+    Ok(message(&real_signal_message(
+        &signal,
+        10_000,
+        100,
+        crate::par::jobs(),
+    )?))
+}
+
+#[cfg(test)]
+struct FFTPattern {
+    repeat: usize,
+    cnt: usize,
+    coefficient_id: usize,
+}
+
+#[cfg(test)]
+impl FFTPattern {
+    const COEFFICIENTS: [i32; 4] = [0, 1, 0, -1];
+
+    fn new(repeat: usize) -> FFTPattern {
+        assert!(repeat != 0);
+        FFTPattern {
+            repeat,
+            cnt: 0,
+            coefficient_id: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Iterator for FFTPattern {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let coefficient = FFTPattern::COEFFICIENTS[self.coefficient_id];
+
+        if self.cnt == self.repeat - 1 {
+            self.coefficient_id = (self.coefficient_id + 1) & 0x03;
+        }
+        self.cnt = (self.cnt + 1) % self.repeat;
+
+        Some(coefficient)
+    }
+}
+
+#[cfg(test)]
+fn compute_fft_phase(input: Vec<i32>) -> Vec<i32> {
+    let len = input.len();
+    let mut output = Vec::with_capacity(len);
+
+    for i in 0..len {
+        let res = input
+            .iter()
+            .zip(FFTPattern::new(i + 1).skip(1).take(len))
+            .fold(0, |res, (e, c)| res + e * c);
+        output.push(res.abs() % 10);
+    }
+
+    output
+}
+
+// Output digit `offset + k` of a phase given the prefix sums of the input
+// digits from `offset` on: instead of multiplying with the pattern,
+// alternating +/- blocks of offset + k + 1 input digits are summed, every
+// block sum is a lookup in the prefix sums. Digits before the output digit
+// are multiplied by 0, so the input digits before `offset` aren't needed.
+fn fft_digit(prefix: &[i32], offset: usize, k: usize) -> i32 {
+    let len = prefix.len() - 1;
+    let block_sum = |start: usize, end: usize| prefix[end.min(len)] - prefix[start.min(len)];
+
+    let block = offset + k + 1;
+    let mut res = 0;
+    // pattern is 0 up to the output digit, then +1, 0, -1, 0 blocks
+    let mut start = k;
+    while start < len {
+        res += block_sum(start, start + block);
+        res -= block_sum(start + 2 * block, start + 3 * block);
+        start += 4 * block;
+    }
+    res.abs() % 10
+}
+
+// Split the output digits 0..len (after `offset`) into `jobs` contiguous
+// ranges of about the same work. Digit k loops over
+// (len - k) / (4 * (offset + k + 1)) block pairs, so the first digits are by
+// far the most expensive ones.
+fn balanced_ranges(len: usize, offset: usize, jobs: usize) -> Vec<std::ops::Range<usize>> {
+    let cost = |k: usize| (len - k) / (4 * (offset + k + 1)) + 1;
+    let total: usize = (0..len).map(cost).sum();
+    let per_job = total / jobs.max(1) + 1;
+
+    let mut ranges = Vec::with_capacity(jobs);
+    let (mut start, mut acc) = (0, 0);
+    for i in 0..len {
+        acc += cost(i);
+        if acc >= per_job {
+            ranges.push(start..i + 1);
+            start = i + 1;
+            acc = 0;
+        }
+    }
+    if start < len {
+        ranges.push(start..len);
+    }
+    ranges
+}
+
+/// How the prefix sums of the input of a phase are computed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefixSums {
+    /// One digit after the other.
+    Scalar,
+    /// Eight digits at a time, which lets the compiler use vector adds.
+    Lanes,
+}
+
+impl Default for PrefixSums {
+    fn default() -> Self {
+        if cfg!(feature = "simd") {
+            PrefixSums::Lanes
+        } else {
+            PrefixSums::Scalar
+        }
+    }
+}
+
+impl PrefixSums {
+    // prefix[k] = input[0] + .. + input[k-1]
+    fn compute(self, input: &[i32], prefix: &mut [i32]) {
+        let done = match self {
+            PrefixSums::Scalar => 0,
+            PrefixSums::Lanes => prefix_sums_lanes(input, prefix),
+        };
+        for k in done..input.len() {
+            prefix[k + 1] = prefix[k] + input[k];
+        }
+    }
+}
+
+// Prefix sums of the whole chunks of eight input digits, every chunk is
+// scanned in three shifted lane-wise adds and offset by the sum of the
+// chunks before. Returns the number of digits done.
+fn prefix_sums_lanes(input: &[i32], prefix: &mut [i32]) -> usize {
+    const LANES: usize = 8;
+    prefix[0] = 0;
+    let mut carry = 0;
+    let chunks = input.chunks_exact(LANES);
+    let done = input.len() - chunks.remainder().len();
+    for (chunk, out) in chunks.zip(prefix[1..].chunks_exact_mut(LANES)) {
+        let mut lanes = [0; LANES];
+        lanes.copy_from_slice(chunk);
+        let mut shift = 1;
+        while shift < LANES {
+            let prev = lanes;
+            for i in shift..LANES {
+                lanes[i] += prev[i - shift];
+            }
+            shift *= 2;
+        }
+        for (o, lane) in out.iter_mut().zip(lanes.iter()) {
+            *o = carry + lane;
+        }
+        carry += lanes[LANES - 1];
+    }
+    done
+}
+
+// Output digits `range` of a phase from the prefix sums.
+fn compute_digits(
+    prefix: &[i32],
+    offset: usize,
+    output: &mut [i32],
+    range: std::ops::Range<usize>,
+) {
+    for (o, k) in output.iter_mut().zip(range) {
+        *o = fft_digit(prefix, offset, k);
+    }
+}
+
+// Same as compute_fft_phase, but based on prefix sums of the input with
+// the output digits computed on `jobs` threads. Only the `len` digits from
+// `offset` on are computed. The prefix sums and the work split are kept to
+// be reused by the following phases.
+struct PrefixFft {
+    prefix: Vec<i32>,
+    sums: PrefixSums,
+    offset: usize,
+    ranges: Vec<std::ops::Range<usize>>,
+}
+
+impl PrefixFft {
+    fn new(len: usize, offset: usize, jobs: usize, sums: PrefixSums) -> PrefixFft {
+        PrefixFft {
+            prefix: vec![0; len + 1],
+            sums,
+            offset,
+            ranges: balanced_ranges(len, offset, jobs),
+        }
+    }
+
+    fn phase(&mut self, input: &[i32], output: &mut [i32]) {
+        self.sums.compute(input, &mut self.prefix);
+
+        let (prefix, offset) = (&self.prefix, self.offset);
+        if let [range] = self.ranges.as_slice() {
+            // single job, skip the copy of the digits
+            compute_digits(prefix, offset, output, range.clone());
+            return;
+        }
+        let digits = crate::par::map_chunks(&self.ranges, self.ranges.len(), |ranges| {
+            let range = ranges[0].clone();
+            let mut digits = vec![0; range.len()];
+            compute_digits(prefix, offset, &mut digits, range);
+            digits
+        });
+        output.copy_from_slice(&digits.concat());
+    }
+}
+
+// compute simplified FFT if pattern can be reduced to triangular
+// matrix, see description in real_signal_message()
+fn compute_fft_phase_triangular(input: &[i32], output: &mut [i32]) {
+    let len = input.len();
+
+    output[len - 1] = input[len - 1];
+    for i in (0..len - 1).rev() {
+        output[i] = i32::abs(input[i] + output[i + 1]) % 10;
+    }
+}
+
+// Digits from `offset` on of `signal` after `phases` phases. Uses the
+// triangular simplification if the offset is in the second half of the
+// signal, see description in real_signal_message().
+fn fft_from(
+    signal: &[i32],
+    offset: usize,
+    phases: usize,
+    jobs: usize,
+    sums: PrefixSums,
+) -> Vec<i32> {
+    let input = signal[offset..].to_vec();
+    if offset > signal.len() / 2 {
+        run_phases(input, phases, compute_fft_phase_triangular)
+    } else {
+        let mut fft = PrefixFft::new(input.len(), offset, jobs, sums);
+        run_phases(input, phases, |input, output| fft.phase(input, output))
+    }
+}
+
+// Run `phases` phases starting with `input`, the phases alternate between
+// the input and a second buffer of the same size instead of allocating a
+// new output for every phase.
+fn run_phases(
+    mut input: Vec<i32>,
+    phases: usize,
+    mut phase: impl FnMut(&[i32], &mut [i32]),
+) -> Vec<i32> {
+    let mut output = vec![0; input.len()];
+    for _ in 0..phases {
+        phase(&input, &mut output);
+        std::mem::swap(&mut input, &mut output);
+    }
+    input
+}
+
+/// The signal `input` after `phases` phases, with the digits computed on
+/// `jobs` threads.
+pub fn fft(input: &[i32], phases: usize, jobs: usize) -> Vec<i32> {
+    fft_with(input, phases, jobs, PrefixSums::default())
+}
+
+/// Same as `fft`, with the prefix sums computed as `sums`.
+pub fn fft_with(input: &[i32], phases: usize, jobs: usize, sums: PrefixSums) -> Vec<i32> {
+    fft_from(input, 0, phases, jobs, sums)
+}
+
+/// The digits of the real signal, which is `input` repeated `repeat` times,
+/// after `phases` phases, starting from the message offset given by the
+/// first seven digits of `input`.
+pub fn real_signal_message(
+    input: &[i32],
+    repeat: usize,
+    phases: usize,
+    jobs: usize,
+) -> crate::Result<Vec<i32>> {
+    if input.len() < 7 {
+        return Err(crate::Error::boxed(E::SignalTooShort));
+    }
+    let offset = input[0..7]
+        .iter()
+        .fold(0, |offset, &n| offset * 10 + n as usize);
+
+    let signal = input.repeat(repeat);
+    if offset >= signal.len() {
+        return Err(crate::Error::boxed(E::InvalidOffset(offset)));
+    }
+
+    // if offset > signal.len()/2 we get triangular matrix
+    // IN:    A  B  C  A  B  C
+    //     0  1  0 -1  0  1  0
+    //     0  0  1  1  0  0 -1
+    //     0  0  0  1  1  1  0
+    //     0  0  0  0  1  1  1 <- starting: offset > signal.len()/2
+    //     0  0  0  0  0  1  1
+    //     0  0  0  0  0  0  1
+    //
+    // FFT can be simplified to
+    //   fft[len-1] = (IN[len-1]) % 10
+    //   fft[len-2] = (IN[len-2] + fft[len-1]) % 10
+    //   ...
+    //   fft[offset] = (IN[offset] + fft[offset+1]) % 10
+    //
+    // For offsets in the first half the digits from the offset on are
+    // computed with the prefix sum phase, which is much slower.
+    Ok(fft_from(
+        &signal,
+        offset,
+        phases,
+        jobs,
+        PrefixSums::default(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fft_pattern() {
+        assert_eq!(
+            FFTPattern::new(1).take(8).collect::<Vec<_>>(),
+            vec![0, 1, 0, -1, 0, 1, 0, -1]
+        );
+        assert_eq!(
+            FFTPattern::new(2).take(8).collect::<Vec<_>>(),
+            vec![0, 0, 1, 1, 0, 0, -1, -1]
+        );
+        assert_eq!(
+            FFTPattern::new(3).take(12).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1, 1, 1, 0, 0, 0, -1, -1, -1]
+        );
+    }
+
+    #[test]
+    fn test_fft() {
+        let input = vec![1, 1, 1, 1];
+        assert_eq!(compute_fft_phase(input), vec![0, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_fft_prefix() {
+        // the naive phase serves as oracle
+        let mut input: Vec<i32> = (0..257).map(|i| (i * 7 + i / 3) % 10).collect();
+        for jobs in 1..5 {
+            let expected = compute_fft_phase(input.clone());
+            let mut fft = PrefixFft::new(input.len(), 0, jobs, PrefixSums::default());
+            input = run_phases(input, 1, |input, output| fft.phase(input, output));
+            assert_eq!(input, expected);
+        }
+
+        let fft = |input: Vec<i32>, jobs| {
+            let mut fft = PrefixFft::new(input.len(), 0, jobs, PrefixSums::default());
+            run_phases(input, 1, |input, output| fft.phase(input, output))
+        };
+        assert_eq!(fft(vec![1, 1, 1, 1], 1), vec![0, 2, 2, 1]);
+        assert_eq!(fft(vec![1, 1, 1, 1], 8), vec![0, 2, 2, 1]);
+        assert_eq!(fft(vec![9], 2), vec![9]);
+    }
+
+    #[test]
+    fn test_prefix_sums() {
+        // the scalar sums serve as oracle, with and without a partial chunk
+        for len in [0, 1, 7, 8, 9, 64, 1031] {
+            let input: Vec<i32> = (0..len).map(|i| (i * 7 + i / 3) % 10).collect();
+            let mut scalar = vec![0; len as usize + 1];
+            PrefixSums::Scalar.compute(&input, &mut scalar);
+            let mut lanes = vec![-1; len as usize + 1];
+            PrefixSums::Lanes.compute(&input, &mut lanes);
+            assert_eq!(lanes, scalar);
+        }
+
+        let input: Vec<i32> = (0..650).map(|i| (i * 3 + i / 7) % 10).collect();
+        assert_eq!(
+            fft_with(&input, 10, 3, PrefixSums::Lanes),
+            fft_with(&input, 10, 3, PrefixSums::Scalar)
+        );
+    }
+
+    #[test]
+    fn test_phase_allocations() {
+        let input: Vec<i32> = (0..1000).map(|i| i % 10).collect();
+        let count = |phases| {
+            let input = input.clone();
+            let before = crate::alloc_count::allocations();
+            run_phases(input, phases, compute_fft_phase_triangular);
+            crate::alloc_count::allocations() - before
+        };
+        // only the second buffer is allocated, independent of the phases
+        assert_eq!(count(1), 1);
+        assert_eq!(count(100), 1);
+
+        let mut fft = PrefixFft::new(input.len(), 0, 1, PrefixSums::default());
+        let before = crate::alloc_count::allocations();
+        run_phases(input, 100, |input, output| fft.phase(input, output));
+        assert_eq!(crate::alloc_count::allocations() - before, 1);
+    }
+
+    #[test]
+    fn test_balanced_ranges() {
+        for (len, jobs) in &[(650, 4), (6500, 3), (5, 8), (1, 1)] {
+            let ranges = balanced_ranges(*len, 0, *jobs);
+            assert!(ranges.len() <= *jobs);
+            assert_eq!(ranges.first().unwrap().start, 0);
+            assert_eq!(ranges.last().unwrap().end, *len);
+            for pair in ranges.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+            }
+        }
+        // the expensive first digits are spread over several jobs
+        assert!(balanced_ranges(6500, 0, 4)[0].len() < 6500 / 16);
+    }
+
+    #[test]
+    fn test_real_signal_message() {
+        let input = |s: &str| -> Vec<i32> { s.bytes().map(|b| (b - b'0') as i32).collect() };
+        let message =
+            |s: &str| real_signal_message(&input(s), 10_000, 100, 1).unwrap()[..8].to_vec();
+        assert_eq!(
+            message("03036732577212944063491565474664"),
+            input("84462026")
+        );
+        assert_eq!(
+            message("02935109699940807407585447034323"),
+            input("78725270")
+        );
+        assert_eq!(
+            message("03081770884921959731165446850517"),
+            input("53553731")
+        );
+
+        assert!(real_signal_message(&input("123456"), 10, 1, 1).is_err());
+        assert!(real_signal_message(&input("9999999"), 10, 1, 1).is_err());
+        assert_eq!(
+            real_signal_message(&input("0000006"), 1, 1, 1).unwrap(),
+            vec![6]
+        );
+    }
+
+    #[test]
+    fn test_fft_from_offset() {
+        let signal: Vec<i32> = (0..300).map(|i| (i * 3 + i / 7) % 10).collect();
+        let mut expected = signal.clone();
+        for _ in 0..3 {
+            expected = compute_fft_phase(expected);
+        }
+
+        // offsets in the first half use the general prefix sum phase
+        for &offset in &[0, 1, 42, 149, 150] {
+            assert_eq!(
+                fft_from(&signal, offset, 3, 2, PrefixSums::default()),
+                expected[offset..]
+            );
+        }
+        // offsets in the second half use the triangular phase
+        for &offset in &[151, 200, 299] {
+            assert_eq!(
+                fft_from(&signal, offset, 3, 2, PrefixSums::default()),
+                expected[offset..]
+            );
+        }
+
+        // both agree where the triangular simplification holds
+        let input = signal[200..].to_vec();
+        let mut fft = PrefixFft::new(input.len(), 200, 3, PrefixSums::default());
+        assert_eq!(
+            run_phases(input.clone(), 3, |input, output| fft.phase(input, output)),
+            run_phases(input, 3, compute_fft_phase_triangular)
+        );
+    }
+
+    #[test]
+    fn test_fft_examples() {
+        let input = |s: &str| -> Vec<i32> { s.bytes().map(|b| (b - b'0') as i32).collect() };
+        let first8 = |s: &str| fft(&input(s), 100, 2)[..8].to_vec();
+        assert_eq!(
+            first8("80871224585914546619083218645595"),
+            input("24176176")
+        );
+        assert_eq!(
+            first8("19617804207202209144916044189917"),
+            input("73745418")
+        );
+        assert_eq!(
+            first8("69317163492948606335995924319873"),
+            input("52432133")
+        );
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+        let res = compute_fft_phase(input);
+        assert_eq!(res, vec![4, 8, 2, 2, 6, 1, 5, 8]);
+
+        let res = compute_fft_phase(res);
+        assert_eq!(res, vec![3, 4, 0, 4, 0, 4, 3, 8]);
+
+        let res = compute_fft_phase(res);
+        assert_eq!(res, vec![0, 3, 4, 1, 5, 5, 1, 8]);
+
+        let res = compute_fft_phase(res);
+        assert_eq!(res, vec![0, 1, 0, 2, 9, 4, 9, 8]);
+    }
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(
+            part1("80871224585914546619083218645595").unwrap(),
+            "24176176"
+        );
+        assert_eq!(
+            part2("03036732577212944063491565474664").unwrap(),
+            "84462026"
+        );
+    }
+}
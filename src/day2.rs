@@ -0,0 +1,221 @@
+//! 1202 Program Alarm (day 2).
+//!
+//! Day 2 only needs the add and multiply instructions, it keeps its own
+//! small machine instead of the shared one in `intcode`.
+
+use crate::intcode::{IntcodeError, IntcodeProgram};
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    // number of cells of the program
+    ProgramTooLarge(usize),
+}
+
+pub type MemCell = u32;
+const MEM_SIZE: usize = 1024;
+
+struct IntcodeISS {
+    mem: [MemCell; MEM_SIZE],
+    pc: u32,
+}
+
+impl IntcodeISS {
+    fn new() -> IntcodeISS {
+        IntcodeISS {
+            mem: [0; MEM_SIZE],
+            pc: 0,
+        }
+    }
+
+    // The program must fit into memory and MemCell, see parse.
+    fn load_program(&mut self, prog: &IntcodeProgram) {
+        assert!(prog.len() <= MEM_SIZE);
+        let cells = prog.cells_as().expect("program cells out of range");
+        self.mem[..cells.len()].copy_from_slice(&cells);
+    }
+
+    fn peek(&self, i: u32) -> MemCell {
+        self.mem[i as usize]
+    }
+
+    fn poke(&mut self, i: u32, val: MemCell) {
+        self.mem[i as usize] = val;
+    }
+
+    // Address `val` used by the instruction at pc, it must be in memory.
+    fn addr(&self, val: MemCell) -> Result<u32, IntcodeError> {
+        if (val as usize) < MEM_SIZE {
+            Ok(val)
+        } else {
+            let (pc, addr) = (self.pc as usize, val.into());
+            Err(crate::Error::new(crate::intcode::E::InvalidAddress {
+                pc,
+                addr,
+            }))
+        }
+    }
+
+    // Address in parameter `nr` of the instruction at pc.
+    fn param(&self, nr: u32) -> Result<u32, IntcodeError> {
+        self.addr(self.peek(self.addr(self.pc + nr)?))
+    }
+
+    fn compute(&mut self) -> Result<(), IntcodeError> {
+        enum IssOp {
+            Step(u32),
+            Halt,
+        }
+
+        loop {
+            let pc = self.pc as usize;
+            let overflow = || crate::Error::new(crate::intcode::E::ArithmeticOverflow { pc });
+            let iss_op = match self.peek(self.addr(self.pc)?) {
+                1 => {
+                    let op1 = self.peek(self.param(1)?);
+                    let op2 = self.peek(self.param(2)?);
+                    self.poke(self.param(3)?, op1.checked_add(op2).ok_or_else(overflow)?);
+                    IssOp::Step(4)
+                }
+                2 => {
+                    let op1 = self.peek(self.param(1)?);
+                    let op2 = self.peek(self.param(2)?);
+                    self.poke(self.param(3)?, op1.checked_mul(op2).ok_or_else(overflow)?);
+                    IssOp::Step(4)
+                }
+                99 => IssOp::Halt,
+                word => {
+                    let (opcode, word) = ((word % 100).into(), word.into());
+                    let err = crate::intcode::E::InvalidOpcode { pc, opcode, word };
+                    return Err(crate::Error::new(err));
+                }
+            };
+
+            match iss_op {
+                IssOp::Step(len) => self.pc += len,
+                IssOp::Halt => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Parse the gravity assist program, it must fit into the memory of the
+/// day 2 machine.
+pub fn parse(input: &str) -> crate::Result<IntcodeProgram> {
+    let prog = crate::intcode::parse_program(input)?;
+    if prog.len() > MEM_SIZE {
+        return Err(crate::Error::boxed(E::ProgramTooLarge(prog.len())));
+    }
+    prog.cells_as::<MemCell>()?;
+    Ok(prog)
+}
+
+/// Run `prog` with `noun` and `verb` in place, returns the value at
+/// position 0.
+pub fn eval(prog: &IntcodeProgram, noun: MemCell, verb: MemCell) -> Result<MemCell, IntcodeError> {
+    let mut iss = IntcodeISS::new();
+    iss.load_program(prog);
+    iss.poke(1, noun);
+    iss.poke(2, verb);
+    iss.compute()?;
+    Ok(iss.peek(0))
+}
+
+/// The value at position 0 after running the program with noun=12 verb=2.
+#[aoc(day = 2, part = 1)]
+pub fn part1(input: &str) -> crate::Result<MemCell> {
+    // ... before running the program, replace position 1 with the value 12 and replace position 2
+    // with the value 2.
+    Ok(eval(&parse(input)?, 12, 2)?)
+}
+
+/// Noun and verb producing `expected`, the nouns are searched on `jobs`
+/// threads. The smallest matching noun wins, nouns and verbs the program
+/// fails with don't match.
+pub fn find_noun_verb(
+    prog: &IntcodeProgram,
+    expected: MemCell,
+    jobs: usize,
+) -> Option<(MemCell, MemCell)> {
+    // just simply brute force expected_result
+    let nouns: Vec<MemCell> = (0..=99).collect();
+    crate::par::map_chunks(&nouns, jobs, |nouns| {
+        nouns
+            .iter()
+            .flat_map(|&noun| (0..=99).map(move |verb| (noun, verb)))
+            .find(|&(noun, verb)| eval(prog, noun, verb).ok() == Some(expected))
+    })
+    .into_iter()
+    .flatten()
+    .next()
+}
+
+/// `100 * noun + verb` of the input producing 19690720, `None` if there is
+/// none.
+#[aoc(day = 2, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Option<MemCell>> {
+    let expected_result = 19690720;
+    Ok(
+        find_noun_verb(&parse(input)?, expected_result, crate::par::jobs())
+            .map(|(noun, verb)| 100 * noun + verb),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn run(p: &[crate::intcode::Value], result_pos: u32) -> MemCell {
+        let mut iss = IntcodeISS::new();
+        iss.load_program(&IntcodeProgram::try_from(p).unwrap());
+        iss.compute().unwrap();
+        iss.peek(result_pos)
+    }
+
+    #[test]
+    fn test_example1() {
+        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
+        let prog = vec![1, 0, 0, 0, 99];
+        assert_eq!(run(&prog, 0), 2);
+    }
+
+    #[test]
+    fn test_example2() {
+        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
+        let prog = vec![2, 3, 0, 3, 99];
+        assert_eq!(run(&prog, 3), 6);
+    }
+
+    #[test]
+    fn test_example3() {
+        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
+        let prog = vec![2, 4, 4, 5, 99, 0];
+        assert_eq!(run(&prog, 5), 9801);
+    }
+
+    #[test]
+    fn test_example4() {
+        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
+        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        assert_eq!(run(&prog, 0), 30);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("1,0,0,0,99").is_ok());
+        assert!(parse("1,-1,0,0,99").is_err());
+        assert!(parse(&vec!["0"; MEM_SIZE + 1].join(",")).is_err());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let prog = |cells: &[crate::intcode::Value]| IntcodeProgram::try_from(cells).unwrap();
+        assert_eq!(eval(&prog(&[1, 0, 0, 0, 99]), 0, 0).unwrap(), 2);
+        // unknown opcode, address beyond memory, overflowing add
+        assert!(eval(&prog(&[1, 0, 0, 0, 98]), 0, 0).is_err());
+        assert!(eval(&prog(&[1, 0, 0, 0, 99]), 0, MEM_SIZE as MemCell).is_err());
+        let max = MemCell::MAX.into();
+        assert!(eval(&prog(&[1, 0, 0, 0, 99, max]), 5, 5).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Crossed Wires (day 3).
+//!
+//! The input describes two wires, one per line, as comma separated moves
+//! like `R8,U5,L5,D3` starting at the central port.
+
+use crate::solver::aoc;
+use std::collections::HashSet;
+use std::iter::FromIterator;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidAction(String),
+    // number of wires in the input
+    WrongNumOfWires(usize),
+}
+
+pub type Pos2D = (i32, i32);
+
+#[derive(Copy, Clone)]
+pub enum Action {
+    U(i32),
+    D(i32),
+    R(i32),
+    L(i32),
+}
+
+fn unroll_action(c: &mut Vec<Pos2D>, a: Action, mut p: Pos2D) -> Pos2D {
+    let step: fn(x: Pos2D) -> Pos2D;
+    let num = match a {
+        Action::U(s) => {
+            step = |x| (x.0, x.1 + 1);
+            s
+        }
+        Action::D(s) => {
+            step = |x| (x.0, x.1 - 1);
+            s
+        }
+        Action::R(s) => {
+            step = |x| (x.0 + 1, x.1);
+            s
+        }
+        Action::L(s) => {
+            step = |x| (x.0 - 1, x.1);
+            s
+        }
+    };
+
+    c.reserve(num as usize);
+    for _ in 0..num {
+        p = step(p);
+        c.push(p);
+    }
+    p
+}
+
+pub fn compute_wire(actions: &[Action]) -> Vec<Pos2D> {
+    let mut pos: Pos2D = (0, 0);
+    let mut coords: Vec<Pos2D> = Vec::new();
+
+    for action in actions {
+        pos = unroll_action(&mut coords, *action, pos);
+    }
+    coords
+}
+
+pub fn compute_manhattan_distance(wire1: &[Pos2D], wire2: &[Pos2D]) -> i32 {
+    let make_set = |vec: &[Pos2D]| -> HashSet<Pos2D> { HashSet::from_iter(vec.iter().cloned()) };
+    make_set(wire1)
+        .intersection(&make_set(wire2))
+        .fold(i32::MAX, |dist, coord| {
+            std::cmp::min(dist, coord.0.abs() + coord.1.abs())
+        })
+}
+
+pub fn compute_fewest_steps(wire1: &[Pos2D], wire2: &[Pos2D]) -> i32 {
+    let make_set = |vec: &[Pos2D]| -> HashSet<Pos2D> { HashSet::from_iter(vec.iter().cloned()) };
+    make_set(wire1)
+        .intersection(&make_set(wire2))
+        .fold(i32::MAX, |steps, inter| {
+            std::cmp::min(
+                steps,
+                wire1.iter().position(|x| x == inter).unwrap() as i32
+                    + 1
+                    + wire2.iter().position(|x| x == inter).unwrap() as i32
+                    + 1,
+            )
+        })
+}
+
+fn actionize(input: &str) -> crate::Result<Action> {
+    let invalid = || crate::Error::boxed(E::InvalidAction(input.to_string()));
+    let mut chars = input.chars();
+    let a = chars.next();
+    let steps = chars.as_str().parse::<i32>().map_err(|_| invalid())?;
+    match a {
+        Some('U') => Ok(Action::U(steps)),
+        Some('D') => Ok(Action::D(steps)),
+        Some('R') => Ok(Action::R(steps)),
+        Some('L') => Ok(Action::L(steps)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse the moves of the wires, one wire per line.
+pub fn parse(input: &str) -> crate::Result<Vec<Vec<Action>>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().split(',').map(actionize).collect())
+        .collect()
+}
+
+// The coordinates of both wires.
+fn parse_two_wires(input: &str) -> crate::Result<(Vec<Pos2D>, Vec<Pos2D>)> {
+    match &parse(input)?[..] {
+        [wire1, wire2] => Ok((compute_wire(wire1), compute_wire(wire2))),
+        wires => Err(crate::Error::boxed(E::WrongNumOfWires(wires.len()))),
+    }
+}
+
+/// Manhattan distance from the central port to the closest intersection.
+#[aoc(day = 3, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let (wire1, wire2) = parse_two_wires(input)?;
+    Ok(compute_manhattan_distance(&wire1, &wire2))
+}
+
+/// Fewest combined steps the wires take to reach an intersection.
+#[aoc(day = 3, part = 2)]
+pub fn part2(input: &str) -> crate::Result<i32> {
+    let (wire1, wire2) = parse_two_wires(input)?;
+    Ok(compute_fewest_steps(&wire1, &wire2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::Action::*;
+    use super::*;
+
+    #[test]
+    fn test_example1() {
+        let w1 = vec![R(8), U(5), L(5), D(3)];
+        let w2 = vec![U(7), R(6), D(4), L(4)];
+
+        assert_eq!(
+            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
+            6
+        );
+    }
+
+    #[test]
+    fn test_example2() {
+        let w1 = vec![R(75), D(30), R(83), U(83), L(12), D(49), R(71), U(7), L(72)];
+        let w2 = vec![U(62), R(66), U(55), R(34), D(71), R(55), D(58), R(83)];
+
+        assert_eq!(
+            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
+            159
+        );
+    }
+
+    #[test]
+    fn test_example3() {
+        let w1 = vec![
+            R(98),
+            U(47),
+            R(26),
+            D(63),
+            R(33),
+            U(87),
+            L(62),
+            D(20),
+            R(33),
+            U(53),
+            R(51),
+        ];
+        let w2 = vec![
+            U(98),
+            R(91),
+            D(20),
+            R(16),
+            D(67),
+            R(40),
+            U(7),
+            R(15),
+            U(6),
+            R(7),
+        ];
+
+        assert_eq!(
+            compute_manhattan_distance(&compute_wire(&w1), &compute_wire(&w2)),
+            135
+        );
+    }
+
+    #[test]
+    fn test2_example1() {
+        let w1 = vec![R(8), U(5), L(5), D(3)];
+        let w2 = vec![U(7), R(6), D(4), L(4)];
+
+        assert_eq!(
+            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
+            30
+        );
+    }
+
+    #[test]
+    fn test2_example2() {
+        let w1 = vec![R(75), D(30), R(83), U(83), L(12), D(49), R(71), U(7), L(72)];
+        let w2 = vec![U(62), R(66), U(55), R(34), D(71), R(55), D(58), R(83)];
+
+        assert_eq!(
+            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
+            610
+        );
+    }
+
+    #[test]
+    fn test2_example3() {
+        let w1 = vec![
+            R(98),
+            U(47),
+            R(26),
+            D(63),
+            R(33),
+            U(87),
+            L(62),
+            D(20),
+            R(33),
+            U(53),
+            R(51),
+        ];
+        let w2 = vec![
+            U(98),
+            R(91),
+            D(20),
+            R(16),
+            D(67),
+            R(40),
+            U(7),
+            R(15),
+            U(6),
+            R(7),
+        ];
+
+        assert_eq!(
+            compute_fewest_steps(&compute_wire(&w1), &compute_wire(&w2)),
+            410
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(part1("R8,U5,L5,D3\nU7,R6,D4,L4\n").unwrap(), 6);
+        assert_eq!(part2("R8,U5,L5,D3\nU7,R6,D4,L4\n").unwrap(), 30);
+        assert!(part1("R8,U5,L5,D3\n").is_err());
+        assert!(part1("R8,X5\nU7\n").is_err());
+    }
+}
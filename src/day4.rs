@@ -0,0 +1,134 @@
+//! Secure Container (day 4).
+//!
+//! The input is the range of passwords as `<lower>-<upper>`.
+
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidRange(String),
+}
+
+pub fn parse(input: &str) -> crate::Result<std::ops::RangeInclusive<i32>> {
+    let invalid = || crate::Error::boxed(E::InvalidRange(input.trim().to_string()));
+    let bounds = crate::input::parse_list::<i32>(input, '-').map_err(|_| invalid())?;
+    match bounds[..] {
+        [lower, upper] => Ok(lower..=upper),
+        _ => Err(invalid()),
+    }
+}
+
+/// Digits never decrease and at least two adjacent digits are the same.
+pub fn is_pw_valid(pw: i32) -> bool {
+    let mut prev = None;
+    let mut pair_seen = false;
+
+    for digit in pw.to_string().chars().rev() {
+        if let Some(prev_digit) = prev {
+            // constraint 1: digits never decrease
+            if digit > prev_digit {
+                return false;
+            }
+            // constraint 2: at least one group with at least two same digits
+            if digit == prev_digit {
+                pair_seen = true;
+            }
+        }
+        prev = Some(digit)
+    }
+    pair_seen
+}
+
+/// Digits never decrease and there is a group of exactly two same digits.
+pub fn is_pw_valid2(pw: i32) -> bool {
+    let mut prev = None;
+    let mut pair_seen = false;
+    let mut cnt = 1;
+
+    for digit in pw.to_string().chars().rev() {
+        if let Some(prev_digit) = prev {
+            // constraint 1: digits never decrease
+            if digit > prev_digit {
+                return false;
+            }
+            if digit == prev_digit {
+                cnt += 1;
+            } else {
+                // constraint 2: at least one group with exact two same digits
+                if cnt == 2 {
+                    pair_seen = true;
+                }
+                cnt = 1;
+            }
+        }
+        prev = Some(digit)
+    }
+    pair_seen || cnt == 2
+}
+
+/// Number of passwords in `range` accepted by `valid`, checked on `jobs`
+/// threads.
+pub fn count_valid(
+    range: std::ops::RangeInclusive<i32>,
+    jobs: usize,
+    valid: fn(i32) -> bool,
+) -> usize {
+    let passwords: Vec<_> = range.collect();
+    crate::par::map_chunks(&passwords, jobs, |chunk| {
+        chunk.iter().filter(|&&pw| valid(pw)).count()
+    })
+    .into_iter()
+    .sum()
+}
+
+/// Number of valid passwords in the range.
+#[aoc(day = 4, part = 1)]
+pub fn part1(input: &str) -> crate::Result<usize> {
+    Ok(count_valid(parse(input)?, crate::par::jobs(), is_pw_valid))
+}
+
+/// Number of valid passwords in the range with the stricter rules.
+#[aoc(day = 4, part = 2)]
+pub fn part2(input: &str) -> crate::Result<usize> {
+    Ok(count_valid(parse(input)?, crate::par::jobs(), is_pw_valid2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples1() {
+        let validate = |pw, valid| assert_eq!(is_pw_valid(pw), valid);
+
+        validate(111111, true);
+        validate(223450, false);
+        validate(123789, false);
+    }
+
+    #[test]
+    fn test_examples2() {
+        let validate = |pw, valid| assert_eq!(is_pw_valid2(pw), valid);
+
+        validate(112233, true);
+        validate(123444, false);
+        validate(111122, true);
+        validate(133345, false);
+        validate(133445, true);
+        validate(112345, true);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("236491-713787\n").unwrap(), 236491..=713787);
+        assert!(parse("236491").is_err());
+    }
+
+    #[test]
+    fn test_count_valid() {
+        for jobs in 1..5 {
+            assert_eq!(count_valid(111110..=111122, jobs, is_pw_valid), 10);
+            assert_eq!(count_valid(111110..=111122, jobs, is_pw_valid2), 1);
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! Sunny with a Chance of Asteroids (day 5).
+//!
+//! Day 5 keeps its own machine without relative addressing, the shared one
+//! in `intcode` was added later on.
+
+use crate::intcode::IntcodeProgram;
+use crate::solver::aoc;
+use std::convert::TryFrom;
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+pub enum E {
+    NoDiagnosticCode,
+    // pc of the instruction and the address it refers to
+    InvalidAddress { pc: Addr, addr: Value },
+    InvalidOpcode { pc: Addr, opcode: Value },
+    InvalidMode { pc: Addr, mode: Value },
+    InputConsumed { pc: Addr },
+}
+
+type Addr = u32;
+pub type Value = i32;
+
+struct IntcodeISS {
+    mem: Vec<Value>,
+    pc: Addr,
+}
+
+enum Instruction {
+    Add(Addr, Value, Value),
+    Mul(Addr, Value, Value),
+    Get(Addr),
+    Put(Value),
+    // the target is only checked when jumping
+    Jpt(Value, Value),
+    Jpf(Value, Value),
+    Lt(Addr, Value, Value),
+    Eq(Addr, Value, Value),
+    Halt,
+}
+
+impl IntcodeISS {
+    // Fails if a cell of `prog` doesn't fit into the narrower Value.
+    fn new(prog: &IntcodeProgram) -> crate::Result<IntcodeISS> {
+        Ok(IntcodeISS {
+            mem: prog.cells_as()?,
+            pc: 0,
+        })
+    }
+
+    fn invalid_address(&self, addr: Value) -> Box<dyn std::error::Error> {
+        crate::Error::boxed(E::InvalidAddress { pc: self.pc, addr })
+    }
+
+    // Address `val` of the instruction at pc, which must be inside memory.
+    fn addr(&self, val: Value) -> crate::Result<Addr> {
+        match usize::try_from(val) {
+            Ok(addr) if addr < self.mem.len() => Ok(val as Addr),
+            _ => Err(self.invalid_address(val)),
+        }
+    }
+
+    fn peek(&self, i: Addr) -> crate::Result<Value> {
+        self.mem
+            .get(i as usize)
+            .copied()
+            .ok_or_else(|| self.invalid_address(i as Value))
+    }
+
+    fn poke(&mut self, i: Addr, val: Value) -> crate::Result<()> {
+        let invalid = self.invalid_address(i as Value);
+        *self.mem.get_mut(i as usize).ok_or(invalid)? = val;
+        Ok(())
+    }
+
+    fn decode(&self, addr: Addr) -> crate::Result<Instruction> {
+        let (md, m2, m1, opcode) = {
+            let word = self.peek(addr)?;
+            (
+                (word / 10000) % 10,
+                (word / 1000) % 10,
+                (word / 100) % 10,
+                word % 100,
+            )
+        };
+        // Parameters that an instruction writes to will never be in immediate mode.
+        if md != 0 {
+            return Err(crate::Error::boxed(E::InvalidMode {
+                pc: self.pc,
+                mode: md,
+            }));
+        }
+
+        let r1 = || self.peek(self.pc + 1);
+        let r2 = || self.peek(self.pc + 2);
+        let rd = || self.peek(self.pc + 3).and_then(|val| self.addr(val));
+        let fetch = |addressing_mode, val: crate::Result<Value>| match addressing_mode {
+            0 => self.peek(self.addr(val?)?),
+            1 => val,
+            mode => Err(crate::Error::boxed(E::InvalidMode { pc: self.pc, mode }).into()),
+        };
+
+        Ok(match opcode {
+            1 => Instruction::Add(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            2 => Instruction::Mul(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            3 => Instruction::Get(self.addr(r1()?)?),
+            4 => Instruction::Put(fetch(m1, r1())?),
+            5 => Instruction::Jpt(fetch(m1, r1())?, fetch(m2, r2())?),
+            6 => Instruction::Jpf(fetch(m1, r1())?, fetch(m2, r2())?),
+            7 => Instruction::Lt(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            8 => Instruction::Eq(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            99 => Instruction::Halt,
+            opcode => {
+                return Err(crate::Error::boxed(E::InvalidOpcode {
+                    pc: self.pc,
+                    opcode,
+                }))
+            }
+        })
+    }
+
+    fn compute(&mut self, mut input: std::slice::Iter<'_, Value>) -> crate::Result<Vec<Value>> {
+        enum IssOp {
+            Step(Addr),
+            Jump(Addr),
+            Halt,
+        }
+
+        let mut output = Vec::new();
+        loop {
+            let iss_op = match self.decode(self.pc)? {
+                Instruction::Add(d, op1, op2) => {
+                    self.poke(d, op1 + op2)?;
+                    IssOp::Step(4)
+                }
+                Instruction::Mul(d, op1, op2) => {
+                    self.poke(d, op1 * op2)?;
+                    IssOp::Step(4)
+                }
+                Instruction::Get(d) => {
+                    let i = *input
+                        .next()
+                        .ok_or_else(|| crate::Error::boxed(E::InputConsumed { pc: self.pc }))?;
+                    self.poke(d, i)?;
+                    IssOp::Step(2)
+                }
+                Instruction::Put(op1) => {
+                    output.push(op1);
+                    IssOp::Step(2)
+                }
+                Instruction::Jpt(op1, d) => {
+                    if op1 != 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Jpf(op1, d) => {
+                    if op1 == 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Lt(d, op1, op2) => {
+                    self.poke(d, (op1 < op2) as Value)?;
+                    IssOp::Step(4)
+                }
+                Instruction::Eq(d, op1, op2) => {
+                    self.poke(d, (op1 == op2) as Value)?;
+                    IssOp::Step(4)
+                }
+                Instruction::Halt => IssOp::Halt,
+            };
+
+            match iss_op {
+                IssOp::Step(len) => self.pc += len,
+                IssOp::Jump(addr) => self.pc = addr,
+                IssOp::Halt => break,
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn parse(input: &str) -> crate::Result<IntcodeProgram> {
+    crate::intcode::parse_program(input)
+}
+
+/// Run the diagnostic program for the system `id`, returns the test results
+/// and the diagnostic code, which is the last output.
+pub fn run_diagnostic(prog: &IntcodeProgram, id: Value) -> crate::Result<(Vec<Value>, Value)> {
+    let input = [id];
+    let mut iss = IntcodeISS::new(prog)?;
+    let mut output = iss.compute(input.iter())?;
+    let code = output
+        .pop()
+        .ok_or_else(|| crate::Error::boxed(E::NoDiagnosticCode))?;
+    Ok((output, code))
+}
+
+/// Diagnostic code of the air conditioner.
+#[aoc(day = 5, part = 1)]
+pub fn part1(input: &str) -> crate::Result<Value> {
+    // 1 = ID for air conditioner
+    Ok(run_diagnostic(&parse(input)?, 1)?.1)
+}
+
+/// Diagnostic code of the ship's thermal radiator controller.
+#[aoc(day = 5, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Value> {
+    // 5 = ID for ship's thermal radiator controller
+    Ok(run_diagnostic(&parse(input)?, 5)?.1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_iss(p: &[crate::intcode::Value]) -> IntcodeISS {
+        IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap()).unwrap()
+    }
+
+    fn eval(p: &[crate::intcode::Value], result_pos: Addr) -> Value {
+        let input = [];
+        let mut iss = new_iss(p);
+        iss.compute(input.iter()).unwrap();
+        iss.peek(result_pos).unwrap()
+    }
+
+    fn eval_with_io(p: &[crate::intcode::Value], input: Vec<Value>) -> Vec<Value> {
+        let mut iss = new_iss(p);
+        iss.compute(input.iter()).unwrap()
+    }
+
+    fn eval_err(p: &[crate::intcode::Value], input: Vec<Value>) -> String {
+        let mut iss = new_iss(p);
+        iss.compute(input.iter()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_example1() {
+        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
+        let prog = vec![1, 0, 0, 0, 99];
+        assert_eq!(eval(&prog, 0), 2);
+    }
+
+    #[test]
+    fn test_example2() {
+        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
+        let prog = vec![2, 3, 0, 3, 99];
+        assert_eq!(eval(&prog, 3), 6);
+    }
+
+    #[test]
+    fn test_example3() {
+        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
+        let prog = vec![2, 4, 4, 5, 99, 0];
+        assert_eq!(eval(&prog, 5), 9801);
+    }
+
+    #[test]
+    fn test_example4() {
+        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
+        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        assert_eq!(eval(&prog, 0), 30);
+    }
+
+    #[test]
+    fn test_addressing_mode() {
+        // 3 * [4] = 3 * 33 = 99 -> store at [4]
+        let prog = vec![1002, 4, 3, 4, 33];
+        assert_eq!(eval(&prog, 4), 99);
+
+        // 100 - 1 = 99 -> store at [4]
+        let prog = vec![1101, 100, -1, 4, 0];
+        assert_eq!(eval(&prog, 4), 99);
+    }
+
+    #[test]
+    fn test_eq_with_load() {
+        // Using position mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_load() {
+        // Using position mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_eq_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_jump_with_load() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_jump_with_immediate() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_parts() -> crate::Result<()> {
+        let Some(input) = crate::fixtures::personal_input(5) else {
+            return Ok(());
+        };
+        assert_eq!(part1(&input)?, 2845163);
+        assert_eq!(part2(&input)?, 9436229);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_programs() {
+        assert_eq!(
+            eval_err(&[1, 0, 0, 7, 99], vec![]),
+            "Error: InvalidAddress { pc: 0, addr: 7 }"
+        );
+        assert_eq!(
+            eval_err(&[1101, 1, 1, 0, 4, -1, 99], vec![]),
+            "Error: InvalidAddress { pc: 4, addr: -1 }"
+        );
+        assert_eq!(
+            eval_err(&[1101, 1, 1, 5, 42, 0], vec![]),
+            "Error: InvalidOpcode { pc: 4, opcode: 42 }"
+        );
+        assert_eq!(
+            eval_err(&[10001, 0, 0, 0, 99], vec![]),
+            "Error: InvalidMode { pc: 0, mode: 1 }"
+        );
+        assert_eq!(
+            eval_err(&[204, 0, 99], vec![]),
+            "Error: InvalidMode { pc: 0, mode: 2 }"
+        );
+        assert_eq!(
+            eval_err(&[3, 0, 3, 0, 99], vec![1]),
+            "Error: InputConsumed { pc: 2 }"
+        );
+        // jump targets are only checked when taken
+        assert_eq!(eval_with_io(&[1105, 0, -1, 104, 7, 99], vec![]), vec![7]);
+        assert_eq!(
+            eval_err(&[1105, 1, -1, 99], vec![]),
+            "Error: InvalidAddress { pc: 0, addr: -1 }"
+        );
+        // running off the end of memory
+        assert_eq!(
+            eval_err(&[1101, 1, 1, 0], vec![]),
+            "Error: InvalidAddress { pc: 4, addr: 4 }"
+        );
+    }
+
+    #[test]
+    fn test_integration() {
+        // The program uses an input instruction to ask for a single number.
+        // i < 8 -> output 999
+        // i = 8 -> output 1000
+        // i > 8 -> output 1001
+        let prog = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1000]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1001]);
+    }
+}
@@ -0,0 +1,523 @@
+//! Universal Orbit Map (day 6).
+//!
+//! The input lists one orbit per line as `A)B`, object B orbits A.
+
+// NOTE from the description:
+//   Except for the universal Center of Mass (COM), every object in
+//   space is in orbit around exactly one other object
+//
+//   -> directed, acyclic graph
+//   -> nodes are 1:N (fanin:fanout)
+
+use crate::graphml::{AttrType, Graph};
+use crate::solver::aoc;
+use crate::svg::Svg;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    // line number
+    InvalidOrbit(usize),
+    UnknownObject(String),
+}
+
+pub type NodeId = usize;
+
+struct Node {
+    depth: i32,
+    child_ids: Vec<NodeId>,
+    parent_id: Option<NodeId>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            depth: 0,
+            child_ids: Vec::new(),
+            parent_id: None,
+        }
+    }
+}
+
+/// The objects and who orbits whom, objects are referred to by their id.
+/// The names of the objects are borrowed from the parsed input.
+pub struct UniversalOrbitMap<'a> {
+    com_id: Option<NodeId>,
+    name_to_id: HashMap<&'a str, NodeId>,
+    names: Vec<&'a str>,
+    nodes: Vec<Node>,
+}
+
+impl<'a> UniversalOrbitMap<'a> {
+    fn new() -> UniversalOrbitMap<'a> {
+        UniversalOrbitMap {
+            com_id: None,
+            name_to_id: HashMap::new(),
+            names: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// A)B -> A: center, B: orbit
+    pub fn add_orbit(&mut self, center: &'a str, orbit: &'a str) {
+        let (c_id, o_id) = (self.insert_or_get_id(center), self.insert_or_get_id(orbit));
+        self.nodes[c_id].child_ids.push(o_id);
+        self.nodes[o_id].parent_id = Some(c_id);
+    }
+
+    fn insert_or_get_id(&mut self, node: &'a str) -> NodeId {
+        if let Some(&id) = self.name_to_id.get(node) {
+            id
+        } else {
+            // new node
+            let id = self.nodes.len();
+            if node == "COM" {
+                self.com_id = Some(id);
+            }
+            self.name_to_id.insert(node, id);
+            self.names.push(node);
+            self.nodes.push(Node::new());
+            id
+        }
+    }
+
+    /// Recursively annotate every node in the graph with its depth
+    /// relative to 'COM' (origin).
+    /// A nodes depth encodes its distance to 'COM'.
+    pub fn annotate_depth(&mut self) -> crate::Result<()> {
+        let id = self
+            .com_id
+            .ok_or_else(|| crate::Error::boxed(E::UnknownObject("COM".to_string())))?;
+        self.annotate_depth_subgraph(id, -1);
+        Ok(())
+    }
+
+    fn annotate_depth_subgraph(&mut self, root: NodeId, parent_depth: i32) {
+        let node = &mut self.nodes[root];
+        node.depth = parent_depth + 1;
+
+        let children = node.child_ids.to_owned(); // need to copy due to poor design
+        for child in children {
+            self.annotate_depth_subgraph(child, parent_depth + 1);
+        }
+    }
+
+    /// The checksum is calculated by summing up every direct +
+    /// indirect orbit from 'COM'.
+    /// The checksum can be computed by summing up the distances of each node:
+    /// `sum(distance(COM, node) for node in nodes)`
+    pub fn get_checksum(&self) -> i32 {
+        self.nodes.iter().map(|n| n.depth).sum()
+    }
+
+    /// Return the parent chain for a given node.
+    ///
+    /// ```text
+    ///         G - H   J - K
+    ///        /       /
+    /// COM - B - C - D - E
+    ///                \
+    ///                 I
+    /// get_parent_chain('C') -> [B, COM]
+    /// get_parent_chain('J') -> [D, C, B, COM]
+    /// ```
+    pub fn get_parent_chain(&self, node_name: &str) -> Option<Vec<NodeId>> {
+        let mut node = *self.name_to_id.get(node_name)?;
+
+        let mut parent_chain = Vec::new();
+        while let Some(id) = self.nodes[node].parent_id {
+            parent_chain.push(id);
+            node = id;
+        }
+        Some(parent_chain)
+    }
+
+    /// Return the objects on the way from `from` to `to` (both included),
+    /// going up to their first common parent and down again.
+    pub fn get_transfer_path(&self, from: &str, to: &str) -> Option<Vec<NodeId>> {
+        let (from_id, to_id) = (*self.name_to_id.get(from)?, *self.name_to_id.get(to)?);
+        let mut up = vec![from_id];
+        up.extend(self.get_parent_chain(from)?);
+        let mut down = vec![to_id];
+        down.extend(self.get_parent_chain(to)?);
+
+        let common = up.iter().position(|id| down.contains(id))?;
+        let common_down = down.iter().position(|&id| id == up[common])?;
+        up.truncate(common + 1);
+        up.extend(down[..common_down].iter().rev());
+        Some(up)
+    }
+
+    /// Draw the orbits around `root` as indented tree, children sorted by
+    /// name. Objects more than `max_depth` levels below the root are left
+    /// out, their number is shown next to the last object drawn. Objects in
+    /// `highlight` are marked with a '*'.
+    ///
+    /// ```text
+    /// COM
+    /// |-- B
+    /// |   `-- C (+2)
+    /// `-- G *
+    /// ```
+    pub fn write_tree(
+        &self,
+        out: &mut dyn Write,
+        root: &str,
+        max_depth: Option<usize>,
+        highlight: &HashSet<NodeId>,
+    ) -> std::io::Result<()> {
+        let root = match self.name_to_id.get(root) {
+            Some(&id) => id,
+            None => return Ok(()),
+        };
+        self.write_subtree(out, root, "", "", 0, max_depth, highlight)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_subtree(
+        &self,
+        out: &mut dyn Write,
+        id: NodeId,
+        branch: &str,
+        indent: &str,
+        depth: usize,
+        max_depth: Option<usize>,
+        highlight: &HashSet<NodeId>,
+    ) -> std::io::Result<()> {
+        write!(out, "{}{}", branch, self.names[id])?;
+        let collapsed = max_depth.is_some_and(|max| depth >= max);
+        if collapsed && !self.nodes[id].child_ids.is_empty() {
+            write!(out, " (+{})", self.count_orbits(id))?;
+        }
+        if highlight.contains(&id) {
+            write!(out, " *")?;
+        }
+        writeln!(out)?;
+        if collapsed {
+            return Ok(());
+        }
+
+        let mut children = self.nodes[id].child_ids.clone();
+        children.sort_by(|&a, &b| self.names[a].cmp(self.names[b]));
+        for (idx, &child) in children.iter().enumerate() {
+            let last = idx + 1 == children.len();
+            let (branch, next) = if last {
+                ("`-- ", "    ")
+            } else {
+                ("|-- ", "|   ")
+            };
+            self.write_subtree(
+                out,
+                child,
+                &format!("{}{}", indent, branch),
+                &format!("{}{}", indent, next),
+                depth + 1,
+                max_depth,
+                highlight,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Number of objects directly or indirectly orbiting `id`.
+    pub fn count_orbits(&self, id: NodeId) -> usize {
+        self.nodes[id]
+            .child_ids
+            .iter()
+            .map(|&child| 1 + self.count_orbits(child))
+            .sum()
+    }
+
+    /// All objects with their depth below COM (annotated before), the number
+    /// of objects orbiting them and whether they are highlighted, edges point
+    /// from the center to the orbiting object.
+    pub fn graphml(&self, highlight: &HashSet<NodeId>) -> Graph {
+        let mut graph = Graph::new("orbits")
+            .with_node_attr("depth", AttrType::Int)
+            .with_node_attr("orbits", AttrType::Int)
+            .with_node_attr("highlight", AttrType::Boolean);
+        for (id, node) in self.nodes.iter().enumerate() {
+            graph.node(
+                self.names[id],
+                &[
+                    ("depth", &node.depth),
+                    ("orbits", &self.count_orbits(id)),
+                    ("highlight", &highlight.contains(&id)),
+                ],
+            );
+        }
+        for (id, node) in self.nodes.iter().enumerate() {
+            for &child in &node.child_ids {
+                graph.edge(self.names[id], self.names[child], &[]);
+            }
+        }
+        graph
+    }
+
+    // Lay out the orbits around `root` from left to right as `(column,
+    // row)` per object: the column is the depth below the root, leaves take
+    // consecutive rows and every other object is centered on its children.
+    fn layout(&self, root: NodeId) -> HashMap<NodeId, (usize, f64)> {
+        fn place(
+            uom: &UniversalOrbitMap,
+            id: NodeId,
+            depth: usize,
+            next_row: &mut f64,
+            layout: &mut HashMap<NodeId, (usize, f64)>,
+        ) -> f64 {
+            let children = &uom.nodes[id].child_ids;
+            let row = if children.is_empty() {
+                *next_row += 1.0;
+                *next_row - 1.0
+            } else {
+                let rows: Vec<_> = children
+                    .iter()
+                    .map(|&child| place(uom, child, depth + 1, next_row, layout))
+                    .collect();
+                (rows[0] + rows[rows.len() - 1]) / 2.0
+            };
+            layout.insert(id, (depth, row));
+            row
+        }
+
+        let mut layout = HashMap::new();
+        place(self, root, 0, &mut 0.0, &mut layout);
+        layout
+    }
+
+    /// Draw the orbits around `root` as graph, objects in `highlight` and the
+    /// orbits between them in red.
+    pub fn tree_svg(&self, root: &str, highlight: &HashSet<NodeId>) -> Option<Svg> {
+        const COLUMN: f64 = 60.0;
+        const ROW: f64 = 14.0;
+        const EDGE: [u8; 3] = [0x80, 0x80, 0x80];
+        const NODE: [u8; 3] = [0x20, 0x60, 0xc0];
+        const HIGHLIGHT: [u8; 3] = [0xe0, 0x30, 0x30];
+
+        let layout = self.layout(*self.name_to_id.get(root)?);
+        let columns = layout.values().map(|&(col, _)| col).max().unwrap_or(0);
+        let rows = layout.values().map(|&(_, row)| row).fold(0.0, f64::max);
+        let pos = |id: &NodeId| {
+            let (col, row) = layout[id];
+            ((col as f64 + 0.5) * COLUMN, (row + 1.0) * ROW)
+        };
+
+        let mut svg = Svg::new((columns + 1) as f64 * COLUMN, (rows + 2.0) * ROW)
+            .with_background([0xff, 0xff, 0xff]);
+        for id in layout.keys() {
+            for child in &self.nodes[*id].child_ids {
+                let (x0, y0) = pos(id);
+                let (x1, y1) = pos(child);
+                let on_path = highlight.contains(id) && highlight.contains(child);
+                svg.path(
+                    &[(x0, y0), (x0 + COLUMN / 2.0, y1), (x1, y1)],
+                    if on_path { HIGHLIGHT } else { EDGE },
+                    1.0,
+                );
+            }
+        }
+        for id in layout.keys() {
+            let (x, y) = pos(id);
+            let rgb = if highlight.contains(id) {
+                HIGHLIGHT
+            } else {
+                NODE
+            };
+            svg.circle(x, y, 3.0, rgb)
+                .text(x + 4.0, y - 2.0, 8.0, self.names[*id], rgb);
+        }
+        Some(svg)
+    }
+}
+
+/// Parse one orbit per line, `A)B` means B orbits A.
+pub fn parse(input: &str) -> crate::Result<UniversalOrbitMap<'_>> {
+    let mut uom = UniversalOrbitMap::new();
+    for (nr, line) in input.lines().enumerate() {
+        match line.trim().split_once(')') {
+            Some((center, orbit)) if !orbit.contains(')') => uom.add_orbit(center, orbit),
+            _ => return Err(crate::Error::boxed(E::InvalidOrbit(nr + 1))),
+        }
+    }
+    Ok(uom)
+}
+
+/// Total number of direct and indirect orbits.
+#[aoc(day = 6, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let mut uom = parse(input)?;
+    uom.annotate_depth()?;
+    Ok(uom.get_checksum())
+}
+
+/// Minimum number of orbit transfers to get from YOU to the object SAN is
+/// orbiting.
+#[aoc(day = 6, part = 2)]
+pub fn part2(input: &str) -> crate::Result<i32> {
+    let uom = parse(input)?;
+    let chain = |name: &str| {
+        uom.get_parent_chain(name)
+            .ok_or_else(|| crate::Error::boxed(E::UnknownObject(name.to_string())))
+    };
+    let chain_you = chain("YOU")?;
+    let chain_san = chain("SAN")?;
+
+    // find first common parent of 'YOU' and 'SAN' to compute minimal number of orbit transfers
+    let mut min_orbit_transfers = 0;
+    for (orbit_transfer_you, id_you) in chain_you.iter().enumerate() {
+        if let Some(orbit_transfer_san) = chain_san.iter().position(|id_san| id_you == id_san) {
+            min_orbit_transfers = orbit_transfer_you + orbit_transfer_san;
+            break;
+        }
+    }
+    Ok(min_orbit_transfers as i32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let Some(input) = crate::fixtures::personal_input(6) else {
+            return;
+        };
+        let checksum = part1(&input).unwrap();
+        assert_eq!(checksum, 314247);
+    }
+
+    #[test]
+    fn test_part2() {
+        let Some(input) = crate::fixtures::personal_input(6) else {
+            return;
+        };
+        let transfers = part2(&input).unwrap();
+        assert_eq!(transfers, 514);
+    }
+
+    #[test]
+    fn test_example() {
+        let orbits = vec![
+            ("COM", "B"),
+            ("B", "C"),
+            ("C", "D"),
+            ("D", "E"),
+            ("E", "F"),
+            ("B", "G"),
+            ("G", "H"),
+            ("D", "I"),
+            ("E", "J"),
+            ("J", "K"),
+            ("K", "L"),
+        ];
+
+        let mut uom = UniversalOrbitMap::new();
+        for (center, orbit) in orbits {
+            uom.add_orbit(center, orbit);
+        }
+        uom.annotate_depth().unwrap();
+
+        assert_eq!(uom.get_checksum(), 42);
+    }
+
+    #[test]
+    fn test_parent_chain() {
+        //              E
+        //            /
+        // COM - A - B - C
+        //        \
+        //         D
+        let orbits = vec![("COM", "A"), ("A", "B"), ("B", "C"), ("A", "D"), ("B", "E")];
+
+        let mut uom = UniversalOrbitMap::new();
+        for (center, orbit) in orbits {
+            uom.add_orbit(center, orbit);
+        }
+
+        let chain_b = uom.get_parent_chain("B");
+        assert_eq!(chain_b.unwrap(), vec![1, 0]);
+
+        let chain_c = uom.get_parent_chain("C");
+        assert_eq!(chain_c.unwrap(), vec![2, 1, 0]);
+
+        let chain_d = uom.get_parent_chain("D");
+        assert_eq!(chain_d.unwrap(), vec![1, 0]);
+
+        let chain_e = uom.get_parent_chain("E");
+        assert_eq!(chain_e.unwrap(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_tree() {
+        //              E
+        //            /
+        // COM - A - B - C
+        //        \
+        //         D
+        let orbits = vec![("COM", "A"), ("A", "B"), ("B", "C"), ("A", "D"), ("B", "E")];
+
+        let mut uom = UniversalOrbitMap::new();
+        for (center, orbit) in orbits {
+            uom.add_orbit(center, orbit);
+        }
+
+        let path = uom.get_transfer_path("C", "D").unwrap();
+        let names: Vec<_> = path.iter().map(|&id| uom.names[id]).collect();
+        assert_eq!(names, vec!["C", "B", "A", "D"]);
+
+        let tree = |root, max_depth, highlight: &HashSet<NodeId>| {
+            let mut out = Vec::new();
+            uom.write_tree(&mut out, root, max_depth, highlight)
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            tree("COM", None, &path.into_iter().collect()),
+            "COM\n`-- A *\n    |-- B *\n    |   |-- C *\n    |   `-- E\n    `-- D *\n"
+        );
+        assert_eq!(
+            tree("A", Some(1), &HashSet::new()),
+            "A\n|-- B (+2)\n`-- D\n"
+        );
+        assert_eq!(tree("X", None, &HashSet::new()), "");
+
+        let layout = uom.layout(0);
+        let pos = |name: &str| layout[&uom.name_to_id[name]];
+        assert_eq!(pos("COM"), (0, 1.25));
+        assert_eq!(pos("B"), (2, 0.5));
+        assert_eq!(pos("C"), (3, 0.0));
+        assert_eq!(pos("E"), (3, 1.0));
+        assert_eq!(pos("D"), (2, 2.0));
+
+        uom.annotate_depth().unwrap();
+        let mut out = Vec::new();
+        uom.graphml(&[uom.name_to_id["E"]].iter().copied().collect())
+            .write(&mut out)
+            .unwrap();
+        let graphml = String::from_utf8(out).unwrap();
+        assert!(graphml.contains(
+            "    <node id=\"B\">\n      <data key=\"node_depth\">2</data>\n      <data key=\"node_orbits\">2</data>\n      <data key=\"node_highlight\">false</data>\n"
+        ));
+        assert!(graphml.contains("<data key=\"node_highlight\">true</data>"));
+        assert!(graphml.contains("    <edge source=\"B\" target=\"E\">\n"));
+        assert_eq!(graphml.matches("<edge ").count(), 5);
+    }
+
+    #[test]
+    fn test_parse_allocations() {
+        // COM)O0, O0)O1, .. a chain of `n` orbits
+        let n = 1000;
+        let input: String = (0..n)
+            .map(|i| match i {
+                0 => "COM)O0\n".to_string(),
+                i => format!("O{})O{}\n", i - 1, i),
+            })
+            .collect();
+        let (uom, allocations) = crate::alloc_count::count(|| parse(&input).unwrap());
+        assert_eq!(uom.names.len(), n + 1);
+        // names are borrowed from the input, only the children of every
+        // object and the growing tables allocate
+        assert!(allocations < n + 64, "{} allocations", allocations);
+    }
+}
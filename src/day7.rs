@@ -0,0 +1,578 @@
+//! Amplification Circuit (day 7).
+//!
+//! Five amplifiers run the same intcode program, each one gets its phase
+//! setting followed by the output of the previous amplifier.
+
+use crate::intcode::{IntcodeError, IntcodeProgram, E};
+use crate::solver::aoc;
+use std::convert::TryFrom;
+use std::sync::mpsc;
+
+type Addr = u32;
+pub type Value = i32;
+
+#[derive(PartialEq, Debug)]
+enum StopReason {
+    NeedInput,
+    ProgramHalt,
+}
+
+struct IntcodeISS {
+    mem: Vec<Value>,
+    pc: Addr,
+}
+
+enum Instruction {
+    Add(Addr, Value, Value),
+    Mul(Addr, Value, Value),
+    Get(Addr),
+    Put(Value),
+    // the target is only checked when jumping
+    Jpt(Value, Value),
+    Jpf(Value, Value),
+    Lt(Addr, Value, Value),
+    Eq(Addr, Value, Value),
+    Halt,
+}
+
+impl IntcodeISS {
+    // The cells of `prog` must fit into the narrower Value, see parse.
+    fn new(prog: &IntcodeProgram) -> IntcodeISS {
+        IntcodeISS {
+            mem: prog.cells_as().expect("program cells out of range"),
+            pc: 0,
+        }
+    }
+
+    fn peek(&self, i: Addr) -> Value {
+        self.mem[i as usize]
+    }
+
+    fn poke(&mut self, i: Addr, val: Value) {
+        self.mem[i as usize] = val;
+    }
+
+    // Address `val` used by the instruction at pc, it must be in memory.
+    fn addr(&self, val: Value) -> Result<Addr, IntcodeError> {
+        match Addr::try_from(val) {
+            Ok(addr) if (addr as usize) < self.mem.len() => Ok(addr),
+            _ => {
+                let (pc, addr) = (self.pc as usize, val.into());
+                Err(crate::Error::new(E::InvalidAddress { pc, addr }))
+            }
+        }
+    }
+
+    fn decode(&self, addr: Addr) -> Result<Instruction, IntcodeError> {
+        let word = self.peek(addr);
+        let (md, m2, m1, opcode) = (
+            (word / 10000) % 10,
+            (word / 1000) % 10,
+            (word / 100) % 10,
+            word % 100,
+        );
+        let pc = self.pc as usize;
+        let invalid_mode = || {
+            crate::Error::new(E::InvalidMode {
+                pc,
+                word: word.into(),
+            })
+        };
+        // Parameters that an instruction writes to will never be in immediate mode.
+        if md != 0 {
+            return Err(invalid_mode());
+        }
+
+        let param = |nr| {
+            self.addr((self.pc + nr) as Value)
+                .map(|addr| self.peek(addr))
+        };
+        let r1 = || param(1);
+        let r2 = || param(2);
+        let rd = || param(3).and_then(|val| self.addr(val));
+        let fetch = |addressing_mode, val: Result<Value, IntcodeError>| match addressing_mode {
+            0 => Ok(self.peek(self.addr(val?)?)),
+            1 => val,
+            _ => Err(invalid_mode()),
+        };
+
+        Ok(match opcode {
+            1 => Instruction::Add(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            2 => Instruction::Mul(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            3 => Instruction::Get(self.addr(r1()?)?),
+            4 => Instruction::Put(fetch(m1, r1())?),
+            5 => Instruction::Jpt(fetch(m1, r1())?, fetch(m2, r2())?),
+            6 => Instruction::Jpf(fetch(m1, r1())?, fetch(m2, r2())?),
+            7 => Instruction::Lt(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            8 => Instruction::Eq(rd()?, fetch(m1, r1())?, fetch(m2, r2())?),
+            99 => Instruction::Halt,
+            opcode => {
+                let (opcode, word) = (opcode.into(), word.into());
+                return Err(crate::Error::new(E::InvalidOpcode { pc, opcode, word }));
+            }
+        })
+    }
+
+    fn compute(
+        &mut self,
+        mut input: std::slice::Iter<'_, Value>,
+    ) -> Result<(StopReason, Vec<Value>), IntcodeError> {
+        enum IssOp {
+            Step(Addr),
+            Jump(Addr),
+            Halt,
+        }
+
+        let mut output = Vec::new();
+        let reason = loop {
+            let iss_op = match self.decode(self.pc)? {
+                Instruction::Add(d, op1, op2) => {
+                    self.poke(d, op1 + op2);
+                    IssOp::Step(4)
+                }
+                Instruction::Mul(d, op1, op2) => {
+                    self.poke(d, op1 * op2);
+                    IssOp::Step(4)
+                }
+                Instruction::Get(d) => {
+                    if let Some(&i) = input.next() {
+                        self.poke(d, i);
+                        IssOp::Step(2)
+                    } else {
+                        break StopReason::NeedInput;
+                    }
+                }
+                Instruction::Put(op1) => {
+                    output.push(op1);
+                    IssOp::Step(2)
+                }
+                Instruction::Jpt(op1, d) => {
+                    if op1 != 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Jpf(op1, d) => {
+                    if op1 == 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Lt(d, op1, op2) => {
+                    self.poke(d, (op1 < op2) as Value);
+                    IssOp::Step(4)
+                }
+                Instruction::Eq(d, op1, op2) => {
+                    self.poke(d, (op1 == op2) as Value);
+                    IssOp::Step(4)
+                }
+                Instruction::Halt => IssOp::Halt,
+            };
+
+            match iss_op {
+                IssOp::Step(len) => self.pc += len,
+                IssOp::Jump(addr) => self.pc = addr,
+                IssOp::Halt => break StopReason::ProgramHalt,
+            }
+        };
+
+        Ok((reason, output))
+    }
+}
+
+/// Parse the amplifier controller software, its cells must fit into a
+/// [`Value`].
+pub fn parse(input: &str) -> crate::Result<IntcodeProgram> {
+    let prog = crate::intcode::parse_program(input)?;
+    prog.cells_as::<Value>()?;
+    Ok(prog)
+}
+
+/// Evaluation of the amplifiers with a phase setting, see
+/// [`eval_amp_chain`] and [`eval_amp_chain_loopback`].
+pub type AmpChain = fn(&IntcodeProgram, [i32; 5]) -> Result<i32, IntcodeError>;
+
+/// Highest signal of `eval` over all permutations of `phases`, the
+/// permutations are evaluated on `jobs` threads. Fails with the first
+/// phase setting the program fails with.
+pub fn max_signal(
+    prog: &IntcodeProgram,
+    phases: Vec<i32>,
+    eval: AmpChain,
+    jobs: usize,
+) -> Result<i32, IntcodeError> {
+    let settings: Vec<_> = gen_combinations(phases)
+        .iter()
+        .map(|c| {
+            let mut phase_setting = [0i32; 5];
+            phase_setting.copy_from_slice(c);
+            phase_setting
+        })
+        .collect();
+    crate::par::map_chunks(&settings, jobs, |settings| {
+        settings.iter().try_fold(0, |signal, &setting| {
+            Ok(std::cmp::max(signal, eval(prog, setting)?))
+        })
+    })
+    .into_iter()
+    .try_fold(0, |signal, chunk| Ok(std::cmp::max(signal, chunk?)))
+}
+
+/// Output signal of the amplifiers running `amp_sw` in series.
+pub fn eval_amp_chain(
+    amp_sw: &IntcodeProgram,
+    phase_setting: [i32; 5],
+) -> Result<i32, IntcodeError> {
+    let mut input = [0, 0];
+    for phase in phase_setting.iter() {
+        input[0] = *phase; // prepare phase setting
+        let (_, output) = IntcodeISS::new(amp_sw).compute(input.iter())?;
+        input[1] = output[0];
+    }
+    Ok(input[1])
+}
+
+/// Output signal of the amplifiers running `amp_sw` in a feedback loop.
+///
+/// The amplifiers run on the shared machine on one thread each, channels
+/// connect every amplifier to the next one and the last one back to the
+/// first one. The signal is what is left for the first one after all of
+/// them halted.
+pub fn eval_amp_chain_loopback(
+    amp_sw: &IntcodeProgram,
+    phase_setting: [i32; 5],
+) -> Result<i32, IntcodeError> {
+    let (senders, mut receivers): (Vec<_>, Vec<_>) = (0..5).map(|_| mpsc::channel()).unzip();
+    for (sender, &phase) in senders.iter().zip(phase_setting.iter()) {
+        sender.send(phase.into()).expect("amplifier is connected");
+    }
+    senders[0].send(0).expect("amplifier is connected");
+
+    let amps: Vec<_> = receivers
+        .drain(..)
+        .enumerate()
+        .map(|(idx, input)| {
+            let output = senders[(idx + 1) % 5].clone();
+            crate::intcode::IntcodeISS::new(amp_sw).spawn(input, output)
+        })
+        .collect();
+    // the amplifiers only wait for each other from now on
+    drop(senders);
+
+    // the first amplifier's input is complete once all of them halted
+    let mut inputs = Vec::new();
+    let mut failed = None;
+    for amp in amps {
+        let (reason, _, input) = amp.join().expect("amplifier panicked");
+        failed = failed.or(reason.err());
+        inputs.push(input);
+    }
+    if let Some(err) = failed {
+        return Err(err);
+    }
+    let signal = inputs[0].try_iter().last().expect("no signal left");
+    Ok(signal as i32)
+}
+
+/// All permutations of `input`.
+pub fn gen_combinations(mut input: Vec<i32>) -> Vec<Vec<i32>> {
+    let mut res_vec: Vec<Vec<i32>> = Vec::new();
+    let input_len = input.len();
+    if input_len == 1 {
+        res_vec.push(input);
+        return res_vec;
+    }
+    for i in (0..input_len).rev() {
+        input.swap(i, input_len - 1);
+        let now = input.pop().expect("Must have value!");
+        let combinations = gen_combinations(input.to_owned());
+
+        for mut comb in combinations {
+            comb.push(now);
+            res_vec.push(comb);
+        }
+        input.push(now);
+    }
+    res_vec
+}
+
+/// Highest signal that can be sent to the thrusters.
+#[aoc(day = 7, part = 1)]
+pub fn part1(input: &str) -> crate::Result<i32> {
+    Ok(max_signal(
+        &parse(input)?,
+        vec![0, 1, 2, 3, 4],
+        eval_amp_chain,
+        crate::par::jobs(),
+    )?)
+}
+
+/// Highest signal that can be sent to the thrusters with the feedback loop.
+#[aoc(day = 7, part = 2)]
+pub fn part2(input: &str) -> crate::Result<i32> {
+    Ok(max_signal(
+        &parse(input)?,
+        vec![5, 6, 7, 8, 9],
+        eval_amp_chain_loopback,
+        crate::par::jobs(),
+    )?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let Some(input) = crate::fixtures::personal_input(7) else {
+            return;
+        };
+        assert_eq!(part1(&input).unwrap(), 359142);
+    }
+
+    #[test]
+    fn test_part_two() {
+        let Some(input) = crate::fixtures::personal_input(7) else {
+            return;
+        };
+        assert_eq!(part2(&input).unwrap(), 4374895);
+    }
+
+    #[test]
+    fn test_combinator() {
+        let input = vec![0, 1];
+        assert_eq!(gen_combinations(input), vec![vec![0, 1], vec![1, 0]]);
+    }
+
+    fn new_iss(p: &[crate::intcode::Value]) -> IntcodeISS {
+        IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap())
+    }
+
+    fn eval(p: &[crate::intcode::Value], result_pos: Addr) -> Value {
+        let input = [];
+        let mut iss = new_iss(p);
+        iss.compute(input.iter()).unwrap();
+        iss.peek(result_pos)
+    }
+
+    fn eval_with_io(p: &[crate::intcode::Value], input: Vec<Value>) -> Vec<Value> {
+        let mut iss = new_iss(p);
+        let (reason, output) = iss.compute(input.iter()).unwrap();
+        assert_eq!(reason, StopReason::ProgramHalt);
+        output
+    }
+
+    #[test]
+    fn test_example1() {
+        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
+        let prog = vec![1, 0, 0, 0, 99];
+        assert_eq!(eval(&prog, 0), 2);
+    }
+
+    #[test]
+    fn test_example2() {
+        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
+        let prog = vec![2, 3, 0, 3, 99];
+        assert_eq!(eval(&prog, 3), 6);
+    }
+
+    #[test]
+    fn test_example3() {
+        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
+        let prog = vec![2, 4, 4, 5, 99, 0];
+        assert_eq!(eval(&prog, 5), 9801);
+    }
+
+    #[test]
+    fn test_example4() {
+        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
+        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        assert_eq!(eval(&prog, 0), 30);
+    }
+
+    #[test]
+    fn test_addressing_mode() {
+        // 3 * [4] = 3 * 33 = 99 -> store at [4]
+        let prog = vec![1002, 4, 3, 4, 33];
+        assert_eq!(eval(&prog, 4), 99);
+
+        // 100 - 1 = 99 -> store at [4]
+        let prog = vec![1101, 100, -1, 4, 0];
+        assert_eq!(eval(&prog, 4), 99);
+    }
+
+    #[test]
+    fn test_eq_with_load() {
+        // Using position mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_load() {
+        // Using position mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_eq_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_jump_with_load() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_jump_with_immediate() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_integration() {
+        // The program uses an input instruction to ask for a single number.
+        // i < 8 -> output 999
+        // i = 8 -> output 1000
+        // i > 8 -> output 1001
+        let prog = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1000]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1001]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let run = |cells: &[crate::intcode::Value]| new_iss(cells).compute([].iter());
+        assert!(run(&[104, 1, 42]).is_err());
+        assert!(run(&[11101, 1, 1, 0, 99]).is_err());
+        assert!(run(&[4, -1, 99]).is_err());
+        assert!(run(&[1105, 1, 10, 99]).is_err());
+        assert!(run(&[1105, 0, 10, 99]).is_ok());
+
+        let prog = IntcodeProgram::new(vec![3, 0, 104, 0, 42]).unwrap();
+        assert!(eval_amp_chain(&prog, [0, 1, 2, 3, 4]).is_err());
+        assert!(eval_amp_chain_loopback(&prog, [5, 6, 7, 8, 9]).is_err());
+        assert!(max_signal(&prog, vec![0, 1, 2, 3, 4], eval_amp_chain, 2).is_err());
+    }
+
+    #[test]
+    fn test_example_amp1() {
+        let prog = IntcodeProgram::new(vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ])
+        .unwrap();
+        let phase = [4, 3, 2, 1, 0];
+        assert_eq!(eval_amp_chain(&prog, phase).unwrap(), 43210);
+    }
+
+    #[test]
+    fn test_example_amp2() {
+        let prog = IntcodeProgram::new(vec![
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ])
+        .unwrap();
+        let phase = [0, 1, 2, 3, 4];
+        assert_eq!(eval_amp_chain(&prog, phase).unwrap(), 54321);
+    }
+
+    #[test]
+    fn test_example_amp3() {
+        let prog = IntcodeProgram::new(vec![
+            3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
+            33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+        ])
+        .unwrap();
+        let phase = [1, 0, 4, 3, 2];
+        assert_eq!(eval_amp_chain(&prog, phase).unwrap(), 65210);
+    }
+
+    #[test]
+    fn test_example_amp1_loopback() {
+        let prog = IntcodeProgram::new(vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ])
+        .unwrap();
+        let phase = [9, 8, 7, 6, 5];
+        assert_eq!(eval_amp_chain_loopback(&prog, phase).unwrap(), 139629729);
+    }
+
+    #[test]
+    fn test_example_amp2_loopback() {
+        let prog = IntcodeProgram::new(vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ])
+        .unwrap();
+        let phase = [9, 7, 8, 5, 6];
+        assert_eq!(eval_amp_chain_loopback(&prog, phase).unwrap(), 18216);
+    }
+}
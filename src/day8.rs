@@ -0,0 +1,154 @@
+//! Space Image Format (day 8).
+//!
+//! The input is a 25x6 image as digits, layer after layer.
+
+use crate::grid::Grid2D;
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    // number of pixels
+    IncompleteLayer(usize),
+}
+
+pub type Pixel = u32;
+pub type Layer = Vec<Pixel>;
+
+pub const WIDTH: usize = 25;
+pub const HEIGHT: usize = 6;
+pub const TRANSPARENT: Pixel = 2;
+
+pub fn parse_space_image_format(image: &[Pixel], layer_w: usize, layer_h: usize) -> Vec<Layer> {
+    assert_eq!(image.len() % (layer_h * layer_w), 0);
+    image
+        .chunks_exact(layer_h * layer_w)
+        .map(|pixels| pixels.to_vec())
+        .collect::<Vec<Layer>>()
+}
+
+/// The layers of the image.
+pub fn parse(input: &str) -> crate::Result<Vec<Layer>> {
+    let pixels = crate::input::parse_digits::<Pixel>(input)?;
+    if pixels.len() % (WIDTH * HEIGHT) != 0 {
+        return Err(crate::Error::boxed(E::IncompleteLayer(pixels.len())));
+    }
+    Ok(parse_space_image_format(&pixels, WIDTH, HEIGHT))
+}
+
+/// Number of 1 digits multiplied by the number of 2 digits on the layer
+/// with the fewest 0 digits.
+#[aoc(day = 8, part = 1)]
+pub fn part1(input: &str) -> crate::Result<usize> {
+    let layers = parse(input)?;
+
+    let cnt_n = |layer: &Layer, n| layer.iter().filter(|&&p| p == n).count();
+
+    let min_0_layer = {
+        let mut min_0 = usize::MAX;
+        let mut min_0_id = 0;
+        for (id, layer) in layers.iter().enumerate() {
+            let cnt_0 = cnt_n(layer, 0);
+            if cnt_0 < min_0 {
+                min_0 = cnt_0;
+                min_0_id = id;
+            }
+        }
+        min_0_id
+    };
+
+    let num_1 = cnt_n(&layers[min_0_layer], 1);
+    let num_2 = cnt_n(&layers[min_0_layer], 2);
+
+    Ok(num_1 * num_2)
+}
+
+/// Decode the image by stacking the layers, the first non-transparent pixel
+/// is visible.
+#[aoc(day = 8, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Grid2D<Pixel>> {
+    let layers = parse(input)?;
+
+    let mut image = Grid2D::new(0);
+    for p in 0..WIDTH * HEIGHT {
+        let pixel = layers
+            .iter()
+            .map(|layer| layer[p])
+            .find(|&pixel| pixel != TRANSPARENT)
+            .unwrap_or(TRANSPARENT);
+        image.set(p % WIDTH, p / WIDTH, pixel);
+    }
+
+    Ok(image)
+}
+
+/// Draw a pixel as character.
+pub fn pixel_to_char(pixel: Pixel) -> char {
+    match pixel {
+        1 => '\u{2588}',
+        TRANSPARENT => '\u{b7}',
+        _ => ' ',
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let image = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+        let layers = parse_space_image_format(&image, 2, 1);
+        assert_eq!(layers.len(), 4);
+        let expectation = vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]];
+        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+
+        let layers = parse_space_image_format(&image, 2, 4);
+        assert_eq!(layers.len(), 1);
+        let expectation = vec![vec![1, 2, 3, 4, 5, 6, 7, 8]];
+        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+
+        let layers = parse_space_image_format(&image, 4, 2);
+        assert_eq!(layers.len(), 1);
+        let expectation = vec![vec![1, 2, 3, 4, 5, 6, 7, 8]];
+        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2];
+        let w = 3;
+        let h = 2;
+
+        let layers = parse_space_image_format(&input, w, h);
+        assert_eq!(layers.len(), 2);
+        let expectation = vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]];
+        assert_eq!(layers.cmp(&expectation), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = vec![0, 2, 2, 2, 1, 1, 2, 2, 2, 2, 1, 2, 0, 0, 0, 0];
+
+        let layers = parse_space_image_format(&input, 2, 2);
+
+        let mut image = Vec::new();
+        for p in 0..4 {
+            let color = layers
+                .iter()
+                .find(|layer| layer[p] != 2 /* transparent */)
+                .unwrap();
+            image.push(color[p]);
+        }
+        assert_eq!(image, vec![0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_part1() {
+        let Some(input) = crate::fixtures::personal_input(8) else {
+            return;
+        };
+        assert_eq!(part1(&input).unwrap(), 1920);
+        assert!(parse("0123").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Sensor Boost (day 9).
+//!
+//! The BOOST program tests the intcode machine and reports malfunctioning
+//! opcodes, a working machine only outputs the answer.
+
+use crate::intcode::{parse_program, IntcodeISS, IntcodeProgram, Value};
+use crate::solver::aoc;
+
+#[derive(Debug)]
+pub enum E {
+    // output of the program, which reports malfunctioning opcodes
+    UnexpectedOutput(Vec<Value>),
+}
+
+/// Run the BOOST program in `mode`, returns its single output.
+pub fn run_boost(prog: &IntcodeProgram, mode: Value) -> crate::Result<Value> {
+    let input = [mode];
+
+    // BOOST tests the machine, so overflows are reported instead of wrapping
+    let mut iss = IntcodeISS::new(prog);
+    let (_, output) = iss.try_compute(input.iter())?;
+    match output[..] {
+        [keycode] => Ok(keycode),
+        _ => Err(crate::Error::boxed(E::UnexpectedOutput(output))),
+    }
+}
+
+/// BOOST keycode, the output in test mode.
+#[aoc(day = 9, part = 1)]
+pub fn part1(input: &str) -> crate::Result<Value> {
+    run_boost(&parse_program(input)?, 1)
+}
+
+/// Coordinates of the distress signal, the output in sensor boost mode.
+#[aoc(day = 9, part = 2)]
+pub fn part2(input: &str) -> crate::Result<Value> {
+    run_boost(&parse_program(input)?, 2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{Addr, StopReason};
+    use std::convert::TryFrom;
+
+    fn eval(p: &[Value], result_pos: Addr) -> Value {
+        let input = [];
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        iss.compute(input.iter()).unwrap();
+        iss.peek(result_pos)
+    }
+
+    fn eval_with_io(p: &[Value], input: Vec<Value>) -> Vec<Value> {
+        let mut iss = IntcodeISS::new(&IntcodeProgram::try_from(p).unwrap());
+        let (reason, output) = iss.compute(input.iter()).unwrap();
+        assert_eq!(reason, StopReason::ProgramHalt);
+        output
+    }
+
+    #[test]
+    fn test_example1() {
+        // 1,0,0,0,99 becomes 2,0,0,0,99 (1 + 1 = 2)
+        let prog = vec![1, 0, 0, 0, 99];
+        assert_eq!(eval(&prog, 0), 2);
+    }
+
+    #[test]
+    fn test_example2() {
+        // 2,3,0,3,99 becomes 2,3,0,6,99 (3 * 2 = 6).
+        let prog = vec![2, 3, 0, 3, 99];
+        assert_eq!(eval(&prog, 3), 6);
+    }
+
+    #[test]
+    fn test_example3() {
+        // 2,4,4,5,99,0 becomes 2,4,4,5,99,9801 (99 * 99 = 9801).
+        let prog = vec![2, 4, 4, 5, 99, 0];
+        assert_eq!(eval(&prog, 5), 9801);
+    }
+
+    #[test]
+    fn test_example4() {
+        // 1,1,1,4,99,5,6,0,99 becomes 30,1,1,4,2,5,6,0,99.
+        let prog = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        assert_eq!(eval(&prog, 0), 30);
+    }
+
+    #[test]
+    fn test_addressing_mode() {
+        // 3 * [4] = 3 * 33 = 99 -> store at [4]
+        let prog = vec![1002, 4, 3, 4, 33];
+        assert_eq!(eval(&prog, 4), 99);
+
+        // 100 - 1 = 99 -> store at [4]
+        let prog = vec![1101, 100, -1, 4, 0];
+        assert_eq!(eval(&prog, 4), 99);
+    }
+
+    #[test]
+    fn test_eq_with_load() {
+        // Using position mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_load() {
+        // Using position mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_eq_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is equal to 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_lt_with_immediate() {
+        // Using immediate mode, consider whether the input
+        // is less than 8; output 1 (if it is) or 0 (if it is not).
+        let prog = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+    }
+
+    #[test]
+    fn test_jump_with_load() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_jump_with_immediate() {
+        // Take an input, then output 0 if the input was
+        // zero or 1 if the input was non-zero:
+        let prog = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+        let input = vec![0];
+        assert_eq!(eval_with_io(&prog, input), vec![0]);
+        let input = vec![-7];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1]);
+    }
+
+    #[test]
+    fn test_integration() {
+        // The program uses an input instruction to ask for a single number.
+        // i < 8 -> output 999
+        // i = 8 -> output 1000
+        // i > 8 -> output 1001
+        let prog = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        let input = vec![-42];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![3];
+        assert_eq!(eval_with_io(&prog, input), vec![999]);
+        let input = vec![8];
+        assert_eq!(eval_with_io(&prog, input), vec![1000]);
+        let input = vec![42];
+        assert_eq!(eval_with_io(&prog, input), vec![1001]);
+    }
+
+    #[test]
+    fn test_boost_example1() {
+        let prog = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(eval_with_io(&prog, vec![]), prog);
+    }
+
+    #[test]
+    fn test_boost_example2() {
+        let prog = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
+        let output = eval_with_io(&prog, vec![]);
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].to_string().chars().count(), 16);
+    }
+
+    #[test]
+    fn test_boost_example3() {
+        let prog = vec![104, 1125899906842624, 99];
+        assert_eq!(eval_with_io(&prog, vec![]), vec![1125899906842624]);
+    }
+}
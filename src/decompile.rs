@@ -0,0 +1,381 @@
+//! Decompiler lifting intcode programs into pseudocode, to read what the
+//! puzzle programs do.
+//!
+//! Code is found by following the control flow from address 0, jumps to
+//! computed targets aren't followed. Cells which aren't code are listed as
+//! data. Operands are written like in the [`crate::asm`] format, `[x]` is
+//! the cell at `x` and `[rb+x]` relative to the relative base. Backward
+//! jumps which are the only way into the code they jump over become loops:
+//!
+//! ```text
+//!      0  [100] = 10000
+//!         do {
+//!      4      [100] = [100] - 1
+//!      8  } while ([100] != 0)
+//!     11  halt
+//!     12  data 0
+//! ```
+
+use crate::intcode::{Addr, IntcodeProgram, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+// Data cells per line.
+const DATA_PER_LINE: usize = 8;
+
+// Operand as written in the program.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Operand {
+    Pos(Value),
+    Imm(Value),
+    Rel(Value),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Operand::Pos(addr) => write!(f, "[{}]", addr),
+            Operand::Imm(val) => write!(f, "{}", val),
+            Operand::Rel(0) => write!(f, "[rb]"),
+            Operand::Rel(offset) if offset < 0 => write!(f, "[rb{}]", offset),
+            Operand::Rel(offset) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+
+impl Operand {
+    pub(crate) fn target(&self) -> Option<Addr> {
+        match *self {
+            Operand::Imm(val) => Addr::try_from(val).ok(),
+            _ => None,
+        }
+    }
+}
+
+// An instruction found in the program, the operands in the order of the
+// program.
+pub(crate) struct Insn {
+    pub(crate) opcode: Value,
+    pub(crate) operands: Vec<Operand>,
+}
+
+impl Insn {
+    pub(crate) fn size(&self) -> Addr {
+        1 + self.operands.len()
+    }
+
+    // Where a jump goes if it is taken and whether it can fall through,
+    // `None` for jumps never taken.
+    pub(crate) fn jump(&self) -> Option<(Operand, bool)> {
+        let (cond, target) = match self.operands[..] {
+            [cond, target] if self.opcode == 5 || self.opcode == 6 => (cond, target),
+            _ => return None,
+        };
+        match cond {
+            Operand::Imm(val) if (val != 0) == (self.opcode == 5) => Some((target, false)),
+            Operand::Imm(_) => None,
+            _ => Some((target, true)),
+        }
+    }
+}
+
+fn decode(cells: &[Value], addr: Addr) -> Option<Insn> {
+    let word = *cells.get(addr)?;
+    let opcode = word % 100;
+    let count = match opcode {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        99 => 0,
+        _ => return None,
+    };
+    let mut modes = word / 100;
+    let mut operands = Vec::with_capacity(count);
+    for idx in 1..=count {
+        let val = *cells.get(addr + idx)?;
+        operands.push(match modes % 10 {
+            0 => Operand::Pos(val),
+            1 => Operand::Imm(val),
+            2 => Operand::Rel(val),
+            _ => return None,
+        });
+        modes /= 10;
+    }
+    Some(Insn { opcode, operands })
+}
+
+// Value written by an add or mul of immediates.
+pub(crate) fn constant(insn: &Insn) -> Option<Value> {
+    match (insn.opcode, &insn.operands[..]) {
+        (1, [Operand::Imm(a), Operand::Imm(b), _]) => a.checked_add(*b),
+        (2, [Operand::Imm(a), Operand::Imm(b), _]) => a.checked_mul(*b),
+        _ => None,
+    }
+}
+
+// Whether the jump at `addr` is a call, the instruction right before it
+// stores the address after it to return to.
+fn is_call(code: &BTreeMap<Addr, Insn>, addr: Addr) -> bool {
+    let Some(insn) = code.get(&addr) else {
+        return false;
+    };
+    let ret = (addr + insn.size()) as Value;
+    matches!(insn.jump(), Some((_, false)))
+        && code
+            .range(..addr)
+            .next_back()
+            .is_some_and(|(&prev, prev_insn)| {
+                prev + prev_insn.size() == addr && constant(prev_insn) == Some(ret)
+            })
+}
+
+// The instructions reachable from address 0 by their address, calls
+// return behind the jump.
+pub(crate) fn find_code(cells: &[Value]) -> BTreeMap<Addr, Insn> {
+    let mut code = BTreeMap::new();
+    let mut todo = vec![0];
+    while !todo.is_empty() {
+        while let Some(addr) = todo.pop() {
+            if code.contains_key(&addr) {
+                continue;
+            }
+            let Some(insn) = decode(cells, addr) else {
+                continue;
+            };
+            match insn.jump() {
+                Some((target, falls_through)) => {
+                    todo.extend(target.target());
+                    if falls_through {
+                        todo.push(addr + insn.size());
+                    }
+                }
+                None if insn.opcode == 99 => {}
+                None => todo.push(addr + insn.size()),
+            }
+            code.insert(addr, insn);
+        }
+        todo = code
+            .iter()
+            .filter(|&(&addr, _)| is_call(&code, addr))
+            .map(|(addr, insn)| addr + insn.size())
+            .filter(|ret| !code.contains_key(ret))
+            .collect();
+    }
+    code
+}
+
+// The loops as (head, address of the backward jump). The code of a loop is
+// only entered at its head, loops nest.
+fn find_loops(code: &BTreeMap<Addr, Insn>) -> BTreeMap<Addr, Addr> {
+    let jumps: Vec<(Addr, Addr)> = code
+        .iter()
+        .filter_map(|(&addr, insn)| Some((addr, insn.jump()?.0.target()?)))
+        .collect();
+    let mut candidates: Vec<_> = jumps
+        .iter()
+        .filter(|&&(addr, target)| target <= addr && code.contains_key(&target))
+        .map(|&(addr, target)| (target, addr))
+        .collect();
+    // outer loops first
+    candidates.sort_by_key(|&(head, end)| (head, std::cmp::Reverse(end)));
+
+    let mut loops: BTreeMap<Addr, Addr> = BTreeMap::new();
+    for (head, end) in candidates {
+        let entered = jumps
+            .iter()
+            .any(|&(addr, target)| (addr < head || addr > end) && target > head && target <= end);
+        let crossing = loops
+            .iter()
+            .any(|(&h, &e)| h == head || (h < head && head <= e && e < end));
+        if !entered && !crossing {
+            loops.insert(head, end);
+        }
+    }
+    loops
+}
+
+fn sum(a: Operand, op: &str, b: Operand) -> String {
+    match b {
+        Operand::Imm(val) if val < 0 && op == "+" => format!("{} - {}", a, -(val as i128)),
+        _ => format!("{} {} {}", a, op, b),
+    }
+}
+
+fn condition(insn: &Insn) -> String {
+    let cond = insn.operands[0];
+    match (cond, insn.opcode) {
+        (Operand::Imm(_), _) => "true".to_string(),
+        (_, 5) => format!("{} != 0", cond),
+        _ => format!("{} == 0", cond),
+    }
+}
+
+// Label of a jump target, the operand of computed ones.
+fn label(target: Operand) -> String {
+    match target.target() {
+        Some(addr) => format!("L{}", addr),
+        None => target.to_string(),
+    }
+}
+
+// Pseudocode of an instruction which doesn't close a loop.
+fn statement(insn: &Insn, call: bool) -> String {
+    use Operand::Imm;
+    match (insn.opcode, &insn.operands[..]) {
+        (1, [Imm(0), b, d]) | (1, [b, Imm(0), d]) => format!("{} = {}", d, b),
+        (1, [a, b, d]) => format!("{} = {}", d, sum(*a, "+", *b)),
+        (2, [Imm(1), b, d]) | (2, [b, Imm(1), d]) => format!("{} = {}", d, b),
+        (2, [Imm(-1), b, d]) | (2, [b, Imm(-1), d]) => format!("{} = -{}", d, b),
+        (2, [a, b, d]) => format!("{} = {}", d, sum(*a, "*", *b)),
+        (3, [d]) => format!("{} = input()", d),
+        (4, [a]) => format!("output({})", a),
+        (5, [_, target]) | (6, [_, target]) if call => format!("call {}", label(*target)),
+        (5, _) | (6, _) => match insn.jump() {
+            Some((target, false)) => format!("goto {}", label(target)),
+            Some((target, true)) => {
+                format!("if ({}) goto {}", condition(insn), label(target))
+            }
+            None => "nop".to_string(),
+        },
+        (7, [a, b, d]) => format!("{} = {}", d, sum(*a, "<", *b)),
+        (8, [a, b, d]) => format!("{} = {}", d, sum(*a, "==", *b)),
+        (9, [Imm(val)]) if *val < 0 => format!("rb -= {}", -(*val as i128)),
+        (9, [a]) => format!("rb += {}", a),
+        _ => "halt".to_string(),
+    }
+}
+
+/// Pseudocode of `prog`, one statement per line after the address of its
+/// instruction.
+pub fn decompile(prog: &IntcodeProgram) -> String {
+    let cells = prog.cells();
+    let code = find_code(cells);
+    let loops = find_loops(&code);
+    let loop_ends: BTreeSet<Addr> = loops.values().copied().collect();
+    // the jumps which aren't loops need labels
+    let labels: BTreeSet<Addr> = code
+        .iter()
+        .filter(|&(addr, _)| !loop_ends.contains(addr))
+        .filter_map(|(_, insn)| insn.jump()?.0.target())
+        .filter(|target| code.contains_key(target))
+        .collect();
+
+    let mut out = String::new();
+    // ends of the loops entered
+    let mut open: Vec<Addr> = Vec::new();
+    let mut line = |addr: Option<Addr>, depth: usize, text: &str| {
+        let addr = addr.map_or(String::new(), |addr| addr.to_string());
+        out.push_str(&format!("{:>6}  {}{}\n", addr, "    ".repeat(depth), text));
+    };
+    let mut addr = 0;
+    while addr < cells.len() {
+        let Some(insn) = code.get(&addr) else {
+            let end = (addr..cells.len())
+                .find(|a| code.contains_key(a))
+                .unwrap_or(cells.len());
+            for chunk in cells[addr..end].chunks(DATA_PER_LINE) {
+                let values: Vec<_> = chunk.iter().map(Value::to_string).collect();
+                line(
+                    Some(addr),
+                    open.len(),
+                    &format!("data {}", values.join(", ")),
+                );
+                addr += chunk.len();
+            }
+            continue;
+        };
+
+        if labels.contains(&addr) {
+            line(None, open.len(), &format!("L{}:", addr));
+        }
+        if let Some(&end) = loops.get(&addr) {
+            line(None, open.len(), "do {");
+            open.push(end);
+        }
+        // loops with the same end are closed by the same jump
+        let depth = open.len();
+        while open.last() == Some(&addr) {
+            open.pop();
+        }
+        if open.len() < depth {
+            line(
+                Some(addr),
+                open.len(),
+                &format!("}} while ({})", condition(insn)),
+            );
+        } else {
+            line(Some(addr), depth, &statement(insn, is_call(&code, addr)));
+        }
+        addr += insn.size();
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decompile() {
+        let countdown = IntcodeProgram::new(vec![
+            1101, 10000, 0, 100, 1001, 100, -1, 100, 1005, 100, 4, 99, 0,
+        ])
+        .unwrap();
+        assert_eq!(
+            decompile(&countdown),
+            "     0  [100] = 10000\n\
+             \x20       do {\n\
+             \x20    4      [100] = [100] - 1\n\
+             \x20    8  } while ([100] != 0)\n\
+             \x20   11  halt\n\
+             \x20   12  data 0\n"
+        );
+
+        // forward jumps get labels, computed targets aren't followed
+        let prog = crate::asm::assemble(
+            "      get [x]
+                   jpf [x], skip
+                   put [x]
+             skip: rbo -3
+                   jpt 1, [rb+2]
+             x:    .data 7",
+        )
+        .unwrap();
+        let text = decompile(&prog);
+        let lines: Vec<_> = text.lines().map(str::trim).collect();
+        assert_eq!(
+            lines,
+            [
+                "0  [12] = input()",
+                "2  if ([12] == 0) goto L7",
+                "5  output([12])",
+                "L7:",
+                "7  rb -= 3",
+                "9  goto [rb+2]",
+                "12  data 7",
+            ]
+        );
+
+        // the code after a call is found through the return address
+        let prog = crate::asm::assemble(
+            "      add ret, 0, [rb]
+                   jpf 0, sub
+             ret:  halt
+             sub:  jpt 1, [rb]",
+        )
+        .unwrap();
+        let text = decompile(&prog);
+        let lines: Vec<_> = text.lines().map(str::trim).collect();
+        assert_eq!(
+            lines,
+            [
+                "0  [rb] = 7",
+                "4  call L8",
+                "7  halt",
+                "L8:",
+                "8  goto [rb]"
+            ]
+        );
+    }
+}
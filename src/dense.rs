@@ -0,0 +1,150 @@
+//! Dense rendering of on/off grids, packing several cells into one
+//! character so large maps fit on the terminal.
+//!
+//! Braille characters hold 2x4 cells and half blocks 1x2 cells. Without a
+//! UTF-8 terminal 1x2 cells are packed into ASCII punctuation instead.
+
+use crate::grid::Grid2D;
+
+#[derive(Debug)]
+pub enum E {
+    UnknownDensity(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Density {
+    Braille,
+    HalfBlock,
+    Ascii,
+}
+
+// Bit of the Braille dot for the cell at (x, y) of the 2x4 block.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BASE: u32 = 0x2800;
+
+impl std::str::FromStr for Density {
+    type Err = crate::Error<E>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "braille" => Ok(Density::Braille),
+            "half-block" => Ok(Density::HalfBlock),
+            "ascii" => Ok(Density::Ascii),
+            _ => Err(crate::Error::new(E::UnknownDensity(s.to_string()))),
+        }
+    }
+}
+
+impl Density {
+    /// Braille if the locale of the terminal uses UTF-8, else ASCII.
+    pub fn detect() -> Density {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        if locale.contains("utf-8") || locale.contains("utf8") {
+            Density::Braille
+        } else {
+            Density::Ascii
+        }
+    }
+
+    /// Cells packed into one character as `(columns, rows)`.
+    pub fn block(self) -> (usize, usize) {
+        match self {
+            Density::Braille => (2, 4),
+            Density::HalfBlock | Density::Ascii => (1, 2),
+        }
+    }
+
+    fn to_char(self, dots: &[[bool; 2]; 4]) -> char {
+        match self {
+            Density::Braille => {
+                let mut bits = 0;
+                for (y, row) in dots.iter().enumerate() {
+                    for (x, &on) in row.iter().enumerate() {
+                        if on {
+                            bits |= BRAILLE_DOTS[y][x];
+                        }
+                    }
+                }
+                std::char::from_u32(BRAILLE_BASE + bits).unwrap_or(' ')
+            }
+            Density::HalfBlock => match (dots[0][0], dots[1][0]) {
+                (true, true) => '\u{2588}',
+                (true, false) => '\u{2580}',
+                (false, true) => '\u{2584}',
+                (false, false) => ' ',
+            },
+            Density::Ascii => match (dots[0][0], dots[1][0]) {
+                (true, true) => ':',
+                (true, false) => '\'',
+                (false, true) => '.',
+                (false, false) => ' ',
+            },
+        }
+    }
+
+    /// Pack the cells of `grid` for which `on` holds into characters.
+    pub fn pack<T: Clone>(self, grid: &Grid2D<T>, on: impl Fn(&T) -> bool) -> Grid2D<char> {
+        let (bw, bh) = self.block();
+        let mut packed = Grid2D::new(' ');
+        for y in (0..grid.height()).step_by(bh) {
+            for x in (0..grid.width()).step_by(bw) {
+                let mut dots = [[false; 2]; 4];
+                for (dy, row) in dots.iter_mut().enumerate().take(bh) {
+                    for (dx, dot) in row.iter_mut().enumerate().take(bw) {
+                        *dot = grid.get(x + dx, y + dy).is_some_and(&on);
+                    }
+                }
+                packed.set(x / bw, y / bh, self.to_char(&dots));
+            }
+        }
+        packed
+    }
+
+    /// Draw `grid` as text, see [`Density::pack`].
+    pub fn render<T: Clone>(self, grid: &Grid2D<T>, on: impl Fn(&T) -> bool) -> String {
+        self.pack(grid, on).render(|&c| c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Grid2D<bool> {
+        let mut grid = Grid2D::new(false);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                grid.set(x, y, c == '#');
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_pack() {
+        let g = grid(&["#..#.", ".#...", "....#"]);
+        assert_eq!(
+            Density::HalfBlock.render(&g, |&on| on),
+            "\u{2580}\u{2584} \u{2580} \n    \u{2580}\n"
+        );
+        assert_eq!(Density::Ascii.render(&g, |&on| on), "'. ' \n    '\n");
+        // dots 1 and 5 (first block), dot 4 (second), dot 3 (third)
+        assert_eq!(
+            Density::Braille.render(&g, |&on| on),
+            "\u{2811}\u{2808}\u{2804}\n"
+        );
+        assert_eq!(Density::Braille.render(&Grid2D::new(false), |&on| on), "");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("braille".parse::<Density>().unwrap(), Density::Braille);
+        assert_eq!("half-block".parse::<Density>().unwrap(), Density::HalfBlock);
+        assert!("dots".parse::<Density>().is_err());
+    }
+}
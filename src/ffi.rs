@@ -0,0 +1,366 @@
+//! C ABI of the library, to use the intcode VM and the solvers from other
+//! languages, e.g. from Python through `ctypes` (see `python/aoc19.py`).
+//!
+//! The library is built as `target/<profile>/libaoc19.so` along with the
+//! Rust library. All symbols are prefixed with `aoc19_`.
+//!
+//! Functions returning a status return 0 on success and -1 on failure,
+//! functions returning a number return -1 on failure. Panics don't unwind
+//! into the caller, they fail the call like errors. The reason of the last
+//! failure on the calling thread is available from [`aoc19_last_error`].
+//!
+//! # Safety
+//!
+//! Pointers passed to the functions must be valid for the given lengths,
+//! strings must be nul terminated and VM handles must come from
+//! [`aoc19_intcode_new`] and not be used after [`aoc19_intcode_free`].
+#![allow(clippy::missing_safety_doc)] // see the module documentation
+
+use crate::day12::System;
+use crate::day14::Nanofactory;
+use crate::intcode::{IntcodeISS, IntcodeProgram, StopReason, Value};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+#[derive(Debug)]
+pub enum E {
+    NullPointer,
+    InvalidUtf8,
+    BufferTooSmall(usize),
+    // number of digits there are of a message of 8
+    MessageTooShort(usize),
+    // the call panicked, with the panic message
+    Panic(String),
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+// Keep the reason of a failure for `aoc19_last_error`.
+fn set_error(err: &dyn std::fmt::Debug) {
+    let text = format!("{:?}", err).replace('\0', "");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(text).unwrap_or_default());
+}
+
+// Run `call` and map its result to the C return value, `fail` on errors.
+// Panics must not unwind into the caller, they fail the call as well.
+fn status<T>(fail: T, call: impl FnOnce() -> crate::Result<T>) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(call)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_error(&e);
+            fail
+        }
+        Err(panic) => {
+            let message = match panic.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+            };
+            set_error(&E::Panic(message));
+            fail
+        }
+    }
+}
+
+// The VM behind the handle `vm`.
+unsafe fn vm_arg<'a>(vm: *mut Vm) -> crate::Result<&'a mut Vm> {
+    vm.as_mut()
+        .ok_or_else(|| crate::Error::boxed(E::NullPointer).into())
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> crate::Result<&'a str> {
+    if s.is_null() {
+        return Err(crate::Error::boxed(E::NullPointer));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| crate::Error::boxed(E::InvalidUtf8).into())
+}
+
+unsafe fn slice_arg<'a, T>(ptr: *const T, len: usize) -> crate::Result<&'a [T]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(crate::Error::boxed(E::NullPointer)),
+        (false, _) => Ok(std::slice::from_raw_parts(ptr, len)),
+    }
+}
+
+unsafe fn copy_out<T: Copy>(values: &[T], out: *mut T, cap: usize) -> crate::Result<()> {
+    if values.len() > cap {
+        return Err(crate::Error::boxed(E::BufferTooSmall(values.len())));
+    }
+    if !values.is_empty() {
+        if out.is_null() {
+            return Err(crate::Error::boxed(E::NullPointer));
+        }
+        std::ptr::copy_nonoverlapping(values.as_ptr(), out, values.len());
+    }
+    Ok(())
+}
+
+/// Description of the last failure on the calling thread, empty if there
+/// was none. The text is valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn aoc19_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// An intcode VM and the output of its last run.
+pub struct Vm {
+    iss: IntcodeISS,
+    output: Vec<Value>,
+}
+
+/// Create a VM running the program `prog` of `len` values, NULL on failure.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_new(prog: *const Value, len: usize) -> *mut Vm {
+    status(std::ptr::null_mut(), || {
+        let prog = IntcodeProgram::try_from(slice_arg(prog, len)?)?;
+        Ok(Box::into_raw(Box::new(Vm {
+            iss: IntcodeISS::new(&prog),
+            output: Vec::new(),
+        })))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_free(vm: *mut Vm) {
+    status((), || {
+        if !vm.is_null() {
+            drop(Box::from_raw(vm));
+        }
+        Ok(())
+    })
+}
+
+/// Limit every following run of the VM to `limit` steps, see
+/// [`aoc19_intcode_run`].
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_set_step_limit(vm: *mut Vm, limit: u64) -> i32 {
+    status(-1, || {
+        let vm = vm_arg(vm)?;
+        vm.iss = vm.iss.clone().with_step_limit(limit);
+        Ok(0)
+    })
+}
+
+/// Run the VM with `len` input values until it halts (returns 0) or needs
+/// more input (returns 1). A VM with a step limit (see
+/// [`aoc19_intcode_set_step_limit`]) returns 2 when it is exceeded. The output is kept until it is taken with
+/// [`aoc19_intcode_take_output`].
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_run(vm: *mut Vm, input: *const Value, len: usize) -> i32 {
+    status(-1, || {
+        let vm = vm_arg(vm)?;
+        let (reason, output) = vm.iss.compute(slice_arg(input, len)?.iter())?;
+        vm.output.extend(output);
+        Ok(match reason {
+            StopReason::ProgramHalt => 0,
+            StopReason::NeedInput => 1,
+            StopReason::LimitExceeded => 2,
+        })
+    })
+}
+
+/// Number of output values not taken yet, -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_output_len(vm: *const Vm) -> isize {
+    status(-1, || Ok(vm_arg(vm as *mut Vm)?.output.len() as isize))
+}
+
+/// Move the output values into `out`, which must hold at least
+/// [`aoc19_intcode_output_len`] values.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_take_output(
+    vm: *mut Vm,
+    out: *mut Value,
+    cap: usize,
+) -> i32 {
+    status(-1, || {
+        let vm = vm_arg(vm)?;
+        copy_out(&vm.output, out, cap)?;
+        vm.output.clear();
+        Ok(0)
+    })
+}
+
+/// Read the memory at `addr`, a NULL VM reads as 0.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_peek(vm: *mut Vm, addr: usize) -> Value {
+    status(0, || Ok(vm.as_mut().map_or(0, |vm| vm.iss.peek(addr))))
+}
+
+/// Write `val` to the memory at `addr`, which must be within the address
+/// limit of the VM.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_intcode_poke(vm: *mut Vm, addr: usize, val: Value) -> i32 {
+    status(-1, || {
+        vm_arg(vm)?.iss.poke(addr, val)?;
+        Ok(0)
+    })
+}
+
+/// Day 12: total energy of the moons in `input` after `steps` steps.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_day12_energy(input: *const c_char, steps: usize) -> i64 {
+    status(-1, || {
+        let system = System::<3>::parse(str_arg(input)?)?;
+        Ok(i64::from(crate::day12::energy_after(system, steps)))
+    })
+}
+
+/// Day 14: ORE needed to produce `fuel` FUEL with the reactions `formulas`.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_day14_ore_for_fuel(formulas: *const c_char, fuel: i64) -> i64 {
+    status(-1, || {
+        Nanofactory::parse(str_arg(formulas)?)?.ore_for_fuel(fuel)
+    })
+}
+
+/// Day 14: most FUEL which can be produced from `ore` ORE.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_day14_max_fuel(formulas: *const c_char, ore: i64) -> i64 {
+    status(-1, || Nanofactory::parse(str_arg(formulas)?)?.max_fuel(ore))
+}
+
+/// Day 16: the `len` digits of `signal` after `phases` phases, written to
+/// `out` which must hold `len` digits.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_day16_fft(
+    signal: *const i32,
+    len: usize,
+    phases: usize,
+    out: *mut i32,
+) -> i32 {
+    status(-1, || {
+        let output = crate::day16::fft(slice_arg(signal, len)?, phases, 1);
+        copy_out(&output, out, len)?;
+        Ok(0)
+    })
+}
+
+/// Day 16: the eight digit message of the real signal, `signal` repeated
+/// `repeat` times, after `phases` phases written to `out`. Fails if there
+/// are fewer than eight digits after the message offset.
+#[no_mangle]
+pub unsafe extern "C" fn aoc19_day16_message(
+    signal: *const i32,
+    len: usize,
+    repeat: usize,
+    phases: usize,
+    out: *mut i32,
+) -> i32 {
+    status(-1, || {
+        let digits = crate::day16::real_signal_message(slice_arg(signal, len)?, repeat, phases, 1)?;
+        if digits.len() < 8 {
+            return Err(crate::Error::boxed(E::MessageTooShort(digits.len())));
+        }
+        copy_out(&digits[..8], out, 8)?;
+        Ok(0)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(aoc19_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_intcode() {
+        // echo the input until it is 0
+        let prog = [3, 9, 4, 9, 1005, 9, 0, 99, 0, 0];
+        unsafe {
+            let vm = aoc19_intcode_new(prog.as_ptr(), prog.len());
+            assert!(!vm.is_null());
+            assert_eq!(aoc19_intcode_run(vm, [5].as_ptr(), 1), 1);
+            assert_eq!(aoc19_intcode_run(vm, [0].as_ptr(), 1), 0);
+            assert_eq!(aoc19_intcode_output_len(vm), 2);
+
+            let mut out = [0; 1];
+            assert_eq!(aoc19_intcode_take_output(vm, out.as_mut_ptr(), 1), -1);
+            assert_eq!(last_error(), "Error { err: BufferTooSmall(2) }");
+            let mut out = [0; 2];
+            assert_eq!(aoc19_intcode_take_output(vm, out.as_mut_ptr(), 2), 0);
+            assert_eq!(out, [5, 0]);
+            assert_eq!(aoc19_intcode_output_len(vm), 0);
+
+            assert_eq!(aoc19_intcode_poke(vm, 9, 42), 0);
+            assert_eq!(aoc19_intcode_peek(vm, 9), 42);
+            assert_eq!(aoc19_intcode_poke(vm, usize::MAX, 0), -1);
+            assert!(last_error().contains("AddressOutOfBounds"));
+            assert_eq!(aoc19_intcode_peek(vm, usize::MAX), 0);
+            aoc19_intcode_free(vm);
+
+            // loops forever
+            let prog = [1105, 1, 0];
+            let vm = aoc19_intcode_new(prog.as_ptr(), prog.len());
+            assert_eq!(aoc19_intcode_set_step_limit(vm, 10), 0);
+            assert_eq!(aoc19_intcode_run(vm, std::ptr::null(), 0), 2);
+            aoc19_intcode_free(vm);
+            assert_eq!(aoc19_intcode_set_step_limit(std::ptr::null_mut(), 10), -1);
+
+            assert_eq!(aoc19_intcode_run(std::ptr::null_mut(), [].as_ptr(), 0), -1);
+            assert!(aoc19_intcode_new(std::ptr::null(), 3).is_null());
+            assert_eq!(aoc19_intcode_poke(std::ptr::null_mut(), 0, 0), -1);
+        }
+    }
+
+    #[test]
+    fn test_panic() {
+        let result = status(-1, || -> crate::Result<i32> { panic!("boom") });
+        assert_eq!(result, -1);
+        assert_eq!(last_error(), "Panic(\"boom\")");
+    }
+
+    #[test]
+    fn test_solvers() {
+        let moons = CString::new(
+            "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n",
+        )
+        .unwrap();
+        let formulas = CString::new(
+            "10 ORE => 10 A\n\
+             1 ORE => 1 B\n\
+             7 A, 1 B => 1 C\n\
+             7 A, 1 C => 1 D\n\
+             7 A, 1 D => 1 E\n\
+             7 A, 1 E => 1 FUEL\n",
+        )
+        .unwrap();
+        let signal = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut out = [0; 8];
+        unsafe {
+            assert_eq!(aoc19_day12_energy(moons.as_ptr(), 10), 179);
+            assert_eq!(aoc19_day12_energy(std::ptr::null(), 10), -1);
+            assert_eq!(last_error(), "Error { err: NullPointer }");
+            assert_eq!(aoc19_day14_ore_for_fuel(formulas.as_ptr(), 1), 31);
+            assert_eq!(aoc19_day14_max_fuel(formulas.as_ptr(), 31), 1);
+            assert_eq!(aoc19_day16_fft(signal.as_ptr(), 8, 4, out.as_mut_ptr()), 0);
+            assert_eq!(out, [0, 1, 0, 2, 9, 4, 9, 8]);
+            assert_eq!(
+                aoc19_day16_message(signal.as_ptr(), 8, 1, 1, out.as_mut_ptr()),
+                -1
+            );
+            // the message starts at offset 3, with 7 digits left of one
+            // signal and 17 of two
+            let signal = [0, 0, 0, 0, 0, 0, 3, 1, 2, 3];
+            assert_eq!(
+                aoc19_day16_message(signal.as_ptr(), 10, 1, 1, out.as_mut_ptr()),
+                -1
+            );
+            assert_eq!(last_error(), "Error { err: MessageTooShort(7) }");
+            assert_eq!(
+                aoc19_day16_message(signal.as_ptr(), 10, 2, 1, out.as_mut_ptr()),
+                0
+            );
+        }
+    }
+}
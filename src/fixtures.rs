@@ -0,0 +1,154 @@
+//! Examples from the puzzle texts with their answers.
+//!
+//! The personal puzzle inputs (`input/dayN`) aren't part of every checkout,
+//! the examples are public and always available to test the solvers against.
+//! Days whose solvers can't run the examples as given (e.g. day 2 patches
+//! fixed addresses, day 8 expects the full size image) have no fixtures.
+
+/// Example input of one part of a day and the expected answer.
+pub struct Fixture {
+    pub day: u32,
+    pub part: u32,
+    pub input: &'static str,
+    pub answer: &'static str,
+}
+
+const fn fixture(day: u32, part: u32, input: &'static str, answer: &'static str) -> Fixture {
+    Fixture {
+        day,
+        part,
+        input,
+        answer,
+    }
+}
+
+const DAY1: &str = "12\n14\n1969\n100756\n";
+
+const DAY3: &str = "R8,U5,L5,D3\nU7,R6,D4,L4\n";
+const DAY3_LARGE: &str = "R75,D30,R83,U83,L12,D49,R71,U7,L72\n\
+                          U62,R66,U55,R34,D71,R55,D58,R83\n";
+
+// Outputs 999 for inputs below 8, the system ids of both parts are.
+const DAY5: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+                    1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+                    999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99\n";
+
+const DAY6: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\n";
+const DAY6_TRANSFER: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\n\
+                             K)YOU\nI)SAN\n";
+
+const DAY7: &str = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0\n";
+const DAY7_FEEDBACK: &str = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,\
+                             1001,28,-1,28,1005,28,6,99,0,0,5\n";
+
+// Outputs the large number in the middle, no matter the mode.
+const DAY9: &str = "104,1125899906842624,99\n";
+
+const DAY10: &str = ".#..#\n.....\n#####\n....#\n...##\n";
+const DAY10_LARGE: &str = "......#.#.\n#..#.#....\n..#######.\n.#.#.###..\n.#..#.....\n\
+                           ..#....#.#\n#..#....#.\n.##.#..###\n##...#..#.\n.#....####\n";
+
+const DAY12: &str = "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n";
+const DAY12_LARGE: &str =
+    "<x=-8, y=-10, z=0>\n<x=5, y=5, z=10>\n<x=2, y=-7, z=3>\n<x=9, y=-8, z=-3>\n";
+
+const DAY14: &str = "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 D\n\
+                     7 A, 1 D => 1 E\n7 A, 1 E => 1 FUEL\n";
+const DAY14_LARGE: &str = "157 ORE => 5 NZVS\n\
+                           165 ORE => 6 DCFZ\n\
+                           44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL\n\
+                           12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ\n\
+                           179 ORE => 7 PSHF\n\
+                           177 ORE => 5 HKGWZ\n\
+                           7 DCFZ, 7 PSHF => 2 XJWVT\n\
+                           165 ORE => 2 GPVTF\n\
+                           3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT\n";
+
+/// All fixtures, sorted by day and part.
+pub const FIXTURES: &[Fixture] = &[
+    fixture(1, 1, DAY1, "34241"),
+    fixture(1, 2, DAY1, "51316"),
+    fixture(3, 1, DAY3, "6"),
+    fixture(3, 1, DAY3_LARGE, "159"),
+    fixture(3, 2, DAY3, "30"),
+    fixture(3, 2, DAY3_LARGE, "610"),
+    // the example passwords as ranges of a single password
+    fixture(4, 1, "111111-111111", "1"),
+    fixture(4, 1, "223450-223450", "0"),
+    fixture(4, 1, "123789-123789", "0"),
+    fixture(4, 2, "112233-112233", "1"),
+    fixture(4, 2, "123444-123444", "0"),
+    fixture(4, 2, "111122-111122", "1"),
+    fixture(5, 1, DAY5, "999"),
+    fixture(5, 2, DAY5, "999"),
+    fixture(6, 1, DAY6, "42"),
+    fixture(6, 2, DAY6_TRANSFER, "4"),
+    fixture(7, 1, DAY7, "43210"),
+    fixture(7, 2, DAY7_FEEDBACK, "139629729"),
+    fixture(9, 1, DAY9, "1125899906842624"),
+    fixture(9, 2, DAY9, "1125899906842624"),
+    fixture(10, 1, DAY10, "8"),
+    fixture(10, 1, DAY10_LARGE, "33"),
+    fixture(12, 2, DAY12, "2772"),
+    fixture(12, 2, DAY12_LARGE, "4686774924"),
+    fixture(14, 1, DAY14, "31"),
+    fixture(14, 1, DAY14_LARGE, "13312"),
+    fixture(14, 2, DAY14_LARGE, "82892753"),
+    fixture(16, 1, "80871224585914546619083218645595\n", "24176176"),
+    fixture(16, 1, "19617804207202209144916044189917\n", "73745418"),
+    fixture(16, 2, "03036732577212944063491565474664\n", "84462026"),
+    fixture(16, 2, "02935109699940807407585447034323\n", "78725270"),
+];
+
+/// Fixtures of `day`.
+pub fn day(day: u32) -> impl Iterator<Item = &'static Fixture> {
+    FIXTURES.iter().filter(move |fixture| fixture.day == day)
+}
+
+/// The personal puzzle input of `day`, `None` if it isn't there. Tests on
+/// the personal inputs run whenever the input is checked out and skip
+/// themselves with it only where it is missing:
+///
+/// ```text
+/// let Some(input) = crate::fixtures::personal_input(6) else { return };
+/// ```
+pub fn personal_input(day: u32) -> Option<String> {
+    let path = crate::solver::input_path(day);
+    if !std::path::Path::new(&path).is_file() {
+        return None;
+    }
+    crate::input::read_to_string(&path).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        assert!(FIXTURES
+            .windows(2)
+            .all(|w| (w[0].day, w[0].part) <= (w[1].day, w[1].part)));
+
+        for fixture in FIXTURES {
+            let solver = crate::solver::day(fixture.day)
+                .find(|solver| solver.part == fixture.part)
+                .unwrap_or_else(|| {
+                    panic!("no solver for day {} part {}", fixture.day, fixture.part)
+                });
+            let answer = (solver.solve)(fixture.input)
+                .unwrap_or_else(|err| panic!("{} on {:?}: {}", solver.name, fixture.input, err));
+            assert_eq!(
+                answer, fixture.answer,
+                "{} on {:?}",
+                solver.name, fixture.input
+            );
+        }
+    }
+
+    #[test]
+    fn test_personal_input() {
+        assert_eq!(day(14).count(), 3);
+        assert!(personal_input(25).is_none());
+    }
+}
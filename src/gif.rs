@@ -0,0 +1,314 @@
+//! Minimal animated GIF (GIF89a) encoder.
+//!
+//! Every frame covers the whole image and is given as palette indices, one
+//! per pixel in row-major order. Frames are LZW compressed but otherwise
+//! stored as is, there is no frame differencing or transparency.
+
+use crate::grid::Grid2D;
+use crate::image::Rgb;
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidPalette(usize),
+    InvalidFrameSize { expected: usize, got: usize },
+}
+
+const MAX_CODE: u16 = 4095;
+
+/// Animated GIF written frame by frame to `out`, looping forever.
+pub struct GifEncoder<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    min_code_size: u8,
+    delay: u16,
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Write the GIF header for `width` x `height` frames with colors from
+    /// `palette` (at most 256 entries), shown `delay` hundredths of a second
+    /// each.
+    pub fn new(
+        mut out: W,
+        width: u16,
+        height: u16,
+        palette: &[Rgb],
+        delay: u16,
+    ) -> crate::Result<GifEncoder<W>> {
+        if palette.is_empty() || palette.len() > 256 {
+            return Err(crate::Error::boxed(E::InvalidPalette(palette.len())));
+        }
+        // the color table holds 2^bits entries, at least 4 as the LZW code
+        // size can't be below 2
+        let bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(2) as u8;
+
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // global color table, 8 bit color resolution
+        out.write_all(&[0xf0 | (bits - 1), 0, 0])?;
+        for idx in 0..1 << bits {
+            out.write_all(palette.get(idx).unwrap_or(&[0, 0, 0]))?;
+        }
+        // NETSCAPE2.0 application extension, loop forever
+        out.write_all(&[0x21, 0xff, 0x0b])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifEncoder {
+            out,
+            width,
+            height,
+            min_code_size: bits,
+            delay,
+        })
+    }
+
+    /// Append a frame of palette indices.
+    pub fn frame(&mut self, indices: &[u8]) -> crate::Result<()> {
+        let expected = usize::from(self.width) * usize::from(self.height);
+        if indices.len() != expected {
+            return Err(crate::Error::boxed(E::InvalidFrameSize {
+                expected,
+                got: indices.len(),
+            }));
+        }
+
+        // graphic control extension with the frame delay
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.out.write_all(&self.delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+        // image descriptor covering the whole image, no local color table
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, self.min_code_size])?;
+
+        let data = lzw_encode(indices, self.min_code_size);
+        for block in data.chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])?;
+        Ok(())
+    }
+
+    /// Append `grid` as frame, every cell drawn as `scale` x `scale` square
+    /// with the palette index given by `index`.
+    pub fn frame_grid<T: Clone>(
+        &mut self,
+        grid: &Grid2D<T>,
+        scale: usize,
+        index: impl Fn(&T) -> u8,
+    ) -> crate::Result<()> {
+        let mut indices = Vec::with_capacity(grid.width() * grid.height() * scale * scale);
+        for line in grid.rows() {
+            let start = indices.len();
+            for cell in line {
+                let idx = index(cell);
+                indices.extend(std::iter::repeat_n(idx, scale));
+            }
+            for _ in 1..scale {
+                indices.extend_from_within(start..start + line.len() * scale);
+            }
+        }
+        self.frame(&indices)
+    }
+
+    /// Write the GIF trailer and return the underlying writer.
+    pub fn finish(mut self) -> crate::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// Packs variable width codes LSB first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.acc |= u32::from(code) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+// LZW compress palette indices as required by GIF: codes start one bit wider
+// than `min_code_size` and grow up to 12 bits, then the table is reset by a
+// clear code.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut bits = BitWriter {
+        bytes: Vec::new(),
+        acc: 0,
+        bits: 0,
+    };
+    let mut table = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size + 1;
+
+    bits.write(clear, width);
+    let mut indices = indices.iter();
+    let mut prefix = match indices.next() {
+        Some(&idx) => u16::from(idx),
+        None => {
+            bits.write(end, width);
+            return bits.finish();
+        }
+    };
+    for &idx in indices {
+        if let Some(&code) = table.get(&(prefix, idx)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.write(prefix, width);
+        if next > MAX_CODE {
+            bits.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_code_size + 1;
+        } else {
+            table.insert((prefix, idx), next);
+            // the decoder adds its table entries one code later, widen
+            // once it can see code `next`
+            if next == 1 << width && width < 12 {
+                width += 1;
+            }
+            next += 1;
+        }
+        prefix = u16::from(idx);
+    }
+    bits.write(prefix, width);
+    bits.write(end, width);
+    bits.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Straightforward GIF LZW decoder to check the encoder against.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> {
+            let mut table: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            table
+        };
+
+        let mut table = reset();
+        let mut width = min_code_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut output = Vec::new();
+        let (mut acc, mut nbits, mut pos) = (0u32, 0u8, 0);
+        loop {
+            while nbits < width {
+                acc |= u32::from(data[pos]) << nbits;
+                pos += 1;
+                nbits += 8;
+            }
+            let code = (acc & ((1 << width) - 1)) as usize;
+            acc >>= width;
+            nbits -= width;
+
+            if code == clear {
+                table = reset();
+                width = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (&prev, table.get(code)) {
+                (_, Some(entry)) => entry.clone(),
+                (Some(prev), None) => {
+                    let mut entry = prev.clone();
+                    entry.push(prev[0]);
+                    entry
+                }
+                (None, None) => panic!("invalid code {}", code),
+            };
+            if let Some(mut prev) = prev.take() {
+                if table.len() <= MAX_CODE as usize {
+                    prev.push(entry[0]);
+                    table.push(prev);
+                    if table.len() == 1 << width && width < 12 {
+                        width += 1;
+                    }
+                }
+            }
+            output.extend_from_slice(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_roundtrip() {
+        let inputs = vec![
+            vec![],
+            vec![1],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0],
+            // long enough to fill the code table several times
+            (0..100_000u64).map(|i| ((i * i) % 251 % 7) as u8).collect(),
+            (0..50_000u64).map(|i| (i * 7919 % 256) as u8).collect(),
+        ];
+        for input in inputs {
+            let max = input.iter().copied().max().unwrap_or(0);
+            let min_code_size = (8 - max.leading_zeros() as u8).max(2);
+            let data = lzw_encode(&input, min_code_size);
+            assert_eq!(lzw_decode(&data, min_code_size), input);
+        }
+    }
+
+    #[test]
+    fn test_gif_structure() {
+        let mut grid = Grid2D::new(0u8);
+        grid.set(1, 1, 2);
+        let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0]];
+        let mut gif = GifEncoder::new(Vec::new(), 4, 4, &palette, 10).unwrap();
+        gif.frame_grid(&grid, 2, |&v| v).unwrap();
+        assert!(gif.frame(&[0; 15]).is_err());
+        let out = gif.finish().unwrap();
+
+        assert_eq!(&out[0..6], b"GIF89a");
+        assert_eq!(&out[6..10], &[4, 0, 4, 0]);
+        // 4 entry global color table, padded with black
+        assert_eq!(out[10], 0xf1);
+        assert_eq!(&out[13..25], &[0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 0]);
+        assert_eq!(&out[25..28], &[0x21, 0xff, 0x0b]);
+        // graphic control extension with the delay, then the image
+        let frame = &out[44..];
+        assert_eq!(&frame[0..8], &[0x21, 0xf9, 0x04, 0x00, 10, 0, 0, 0]);
+        assert_eq!(frame[8], 0x2c);
+        assert_eq!(frame[18], 2);
+
+        let len = usize::from(frame[19]);
+        let pixels = lzw_decode(&frame[20..20 + len], 2);
+        assert_eq!(pixels, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 0, 0, 2, 2]);
+        assert_eq!(&frame[20 + len..], &[0x00, 0x3b]);
+    }
+}
@@ -0,0 +1,227 @@
+//! Golden traces of the intcode machine.
+//!
+//! The corpus are the intcode examples of [`crate::fixtures`] and the puzzle
+//! inputs of the intcode days which are present. Each program is run once
+//! with the input of its part, the trace records how the run stopped, a
+//! fingerprint of the executed instructions and of the output. Traces
+//! recorded with a known good machine are kept in [`PATH`], rewrites of the
+//! machine are checked against them:
+//!
+//! ```text
+//! intcode-golden v1
+//! day9.1.example0 halt steps=2 trace=8d2eea74eee292cc outputs=1:c11fb292fa782b0b
+//! ```
+//!
+//! Recorded traces whose program isn't in the corpus (e.g. a missing puzzle
+//! input) are not checked.
+
+use crate::intcode::{Addr, IntcodeISS, IntcodeProgram, StopReason, TraceHash, Value};
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidGolden(usize),
+}
+
+pub const HEADER: &str = "intcode-golden v1";
+
+/// Recorded traces, relative to the repository root.
+pub const PATH: &str = "golden/intcode";
+
+// Day, part, patches and input of a run.
+type Run = (u32, u32, &'static [(Addr, Value)], &'static [Value]);
+
+// Runs of the intcode days, the interactive days only run until they wait
+// for more input.
+const RUNS: &[Run] = &[
+    (2, 1, &[(1, 12), (2, 2)], &[]),
+    (5, 1, &[], &[1]),
+    (5, 2, &[], &[5]),
+    (7, 1, &[], &[0, 0]),
+    (7, 2, &[], &[5, 0]),
+    (9, 1, &[], &[1]),
+    (9, 2, &[], &[2]),
+    (11, 1, &[], &[0]),
+    (11, 2, &[], &[1]),
+    (13, 1, &[], &[]),
+];
+
+/// A program of the corpus and the input it is run with.
+pub struct Case {
+    pub name: String,
+    pub program: IntcodeProgram,
+    pub input: Vec<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Trace {
+    pub name: String,
+    pub stop: StopReason,
+    pub trace: TraceHash,
+    pub outputs: usize,
+    pub output_hash: u64,
+}
+
+/// The examples and the puzzle inputs which are present.
+pub fn corpus() -> crate::Result<Vec<Case>> {
+    let mut cases = Vec::new();
+    for &(day, part, patches, input) in RUNS {
+        let examples = crate::fixtures::day(day).filter(|fixture| fixture.part == part);
+        for (nr, fixture) in examples.enumerate() {
+            cases.push(Case {
+                name: format!("day{}.{}.example{}", day, part, nr),
+                program: crate::intcode::parse_program(fixture.input)?.patched(patches)?,
+                input: input.to_vec(),
+            });
+        }
+
+        let path = crate::solver::input_path(day);
+        if std::path::Path::new(&path).is_file() {
+            let program = crate::input::read_with(&path, crate::intcode::parse_program)?;
+            cases.push(Case {
+                name: format!("day{}.{}.input", day, part),
+                program: program.patched(patches)?,
+                input: input.to_vec(),
+            });
+        }
+    }
+    Ok(cases)
+}
+
+/// Run `case` on the current machine.
+pub fn record(case: &Case) -> crate::Result<Trace> {
+    let mut trace = TraceHash::new();
+    let (stop, output) =
+        IntcodeISS::new(&case.program).compute_traced(case.input.iter(), &mut trace)?;
+    let output_text: Vec<_> = output.iter().map(|v| v.to_string()).collect();
+    Ok(Trace {
+        name: case.name.clone(),
+        stop,
+        trace,
+        outputs: output.len(),
+        output_hash: crate::cache::hash(&output_text.join(",")),
+    })
+}
+
+pub fn write(out: &mut dyn Write, traces: &[Trace]) -> crate::Result<()> {
+    writeln!(out, "{}", HEADER)?;
+    for t in traces {
+        let stop = match t.stop {
+            StopReason::ProgramHalt => "halt",
+            StopReason::NeedInput => "input",
+            StopReason::LimitExceeded => "limit",
+        };
+        writeln!(
+            out,
+            "{} {} steps={} trace={:016x} outputs={}:{:016x}",
+            t.name, stop, t.trace.steps, t.trace.hash, t.outputs, t.output_hash
+        )?;
+    }
+    Ok(())
+}
+
+/// Parse recorded traces, line numbers in errors are 1-based.
+pub fn read(golden: &str) -> crate::Result<Vec<Trace>> {
+    let mut lines = golden.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header == HEADER => {}
+        _ => return Err(crate::Error::boxed(E::InvalidGolden(1))),
+    }
+
+    let mut traces = Vec::new();
+    for (nr, line) in lines {
+        let trace =
+            parse_trace(line).ok_or_else(|| crate::Error::boxed(E::InvalidGolden(nr + 1)))?;
+        traces.push(trace);
+    }
+    Ok(traces)
+}
+
+// One line of a golden file, see write.
+fn parse_trace(line: &str) -> Option<Trace> {
+    let fields: Vec<_> = line.split_ascii_whitespace().collect();
+    let (name, stop, steps, trace, outputs) = match fields[..] {
+        [name, stop, steps, trace, outputs] => (name, stop, steps, trace, outputs),
+        _ => return None,
+    };
+    let stop = match stop {
+        "halt" => StopReason::ProgramHalt,
+        "input" => StopReason::NeedInput,
+        "limit" => StopReason::LimitExceeded,
+        _ => return None,
+    };
+    let (outputs, output_hash) = field(outputs, "outputs")?.split_once(':')?;
+    Some(Trace {
+        name: name.to_string(),
+        stop,
+        trace: TraceHash {
+            steps: field(steps, "steps")?.parse().ok()?,
+            hash: u64::from_str_radix(field(trace, "trace")?, 16).ok()?,
+        },
+        outputs: outputs.parse().ok()?,
+        output_hash: u64::from_str_radix(output_hash, 16).ok()?,
+    })
+}
+
+// Value of the `key=value` field `text`.
+fn field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.strip_prefix(key)?.strip_prefix('=')
+}
+
+/// Differences of the `current` traces to the `golden` ones, one line per
+/// trace which changed or wasn't recorded.
+pub fn drift(golden: &[Trace], current: &[Trace]) -> Vec<String> {
+    current
+        .iter()
+        .filter_map(|t| match golden.iter().find(|g| g.name == t.name) {
+            None => Some(format!("{}: not recorded", t.name)),
+            Some(g) if g != t => Some(format!("{}: recorded {:?}, now {:?}", t.name, g, t)),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let case = Case {
+            name: "echo".to_string(),
+            program: "3,7,4,7,1105,1,0,0".parse().unwrap(),
+            input: vec![5],
+        };
+        let trace = record(&case).unwrap();
+        assert_eq!(trace.stop, StopReason::NeedInput);
+        assert_eq!((trace.trace.steps, trace.outputs), (4, 1));
+
+        let mut out = Vec::new();
+        write(&mut out, &[trace]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let traces = read(&text).unwrap();
+        assert_eq!(traces, vec![record(&case).unwrap()]);
+        assert!(drift(&traces, &[record(&case).unwrap()]).is_empty());
+
+        let changed = Case {
+            input: vec![6],
+            ..case
+        };
+        assert_eq!(drift(&traces, &[record(&changed).unwrap()]).len(), 1);
+        assert!(read("intcode-golden v1\necho halt steps=1").is_err());
+        assert!(read("").is_err());
+    }
+
+    #[test]
+    fn test_golden() {
+        let golden = read(&crate::input::read_to_string(PATH).unwrap()).unwrap();
+        let current: Vec<_> = corpus()
+            .unwrap()
+            .iter()
+            .map(|case| record(case).unwrap())
+            .collect();
+        assert!(current.iter().any(|t| t.name.contains("example")));
+        let drift = drift(&golden, &current);
+        assert!(drift.is_empty(), "{}", drift.join("\n"));
+    }
+}
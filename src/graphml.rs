@@ -0,0 +1,221 @@
+//! Minimal GraphML writer, to load graph-shaped puzzle data into tools like
+//! Gephi or yEd.
+//!
+//! The attributes of nodes and edges have to be declared with their type
+//! before they are used, every node and edge may set any of them:
+//!
+//! ```text
+//! let mut graph = Graph::new("orbits").with_node_attr("depth", AttrType::Int);
+//! graph.node("COM", &[("depth", &0)]).node("A", &[("depth", &1)]);
+//! graph.edge("COM", "A", &[]);
+//! ```
+
+use crate::svg::escape;
+use std::fmt::{self, Display};
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    UnknownAttribute(String),
+}
+
+/// Type of an attribute, as named by GraphML.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttrType {
+    Boolean,
+    Int,
+    Double,
+    String,
+}
+
+impl Display for AttrType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AttrType::Boolean => "boolean",
+            AttrType::Int => "int",
+            AttrType::Double => "double",
+            AttrType::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+type Attrs = Vec<(String, String)>;
+
+/// Directed graph written as GraphML document.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    name: String,
+    node_attrs: Vec<(String, AttrType)>,
+    edge_attrs: Vec<(String, AttrType)>,
+    nodes: Vec<(String, Attrs)>,
+    edges: Vec<(String, String, Attrs)>,
+}
+
+fn attrs(values: &[(&str, &dyn Display)]) -> Attrs {
+    values
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+impl Graph {
+    pub fn new(name: &str) -> Graph {
+        Graph {
+            name: name.to_string(),
+            node_attrs: Vec::new(),
+            edge_attrs: Vec::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Declare the node attribute `name`.
+    pub fn with_node_attr(mut self, name: &str, ty: AttrType) -> Graph {
+        self.node_attrs.push((name.to_string(), ty));
+        self
+    }
+
+    /// Declare the edge attribute `name`.
+    pub fn with_edge_attr(mut self, name: &str, ty: AttrType) -> Graph {
+        self.edge_attrs.push((name.to_string(), ty));
+        self
+    }
+
+    pub fn node(&mut self, id: &str, values: &[(&str, &dyn Display)]) -> &mut Graph {
+        self.nodes.push((id.to_string(), attrs(values)));
+        self
+    }
+
+    /// Edge from the node `source` to the node `target`.
+    pub fn edge(
+        &mut self,
+        source: &str,
+        target: &str,
+        values: &[(&str, &dyn Display)],
+    ) -> &mut Graph {
+        self.edges
+            .push((source.to_string(), target.to_string(), attrs(values)));
+        self
+    }
+
+    // Write the data elements of `values`, which must be declared in
+    // `declared` as key `<prefix><name>`.
+    fn write_data(
+        out: &mut dyn Write,
+        prefix: &str,
+        declared: &[(String, AttrType)],
+        values: &Attrs,
+    ) -> crate::Result<()> {
+        for (name, value) in values {
+            if !declared.iter().any(|(n, _)| n == name) {
+                return Err(crate::Error::boxed(E::UnknownAttribute(name.clone())));
+            }
+            writeln!(
+                out,
+                "      <data key=\"{}{}\">{}</data>",
+                prefix,
+                escape(name),
+                escape(value)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the GraphML document, fails if an attribute which wasn't
+    /// declared is set.
+    pub fn write(&self, out: &mut dyn Write) -> crate::Result<()> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+        )?;
+        let keys = [("node", &self.node_attrs), ("edge", &self.edge_attrs)];
+        for (domain, declared) in keys.iter() {
+            for (name, ty) in declared.iter() {
+                writeln!(
+                    out,
+                    "  <key id=\"{}_{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                    domain,
+                    escape(name),
+                    domain,
+                    escape(name),
+                    ty
+                )?;
+            }
+        }
+
+        writeln!(
+            out,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            escape(&self.name)
+        )?;
+        for (id, values) in &self.nodes {
+            writeln!(out, "    <node id=\"{}\">", escape(id))?;
+            Graph::write_data(out, "node_", &self.node_attrs, values)?;
+            writeln!(out, "    </node>")?;
+        }
+        for (source, target, values) in &self.edges {
+            writeln!(
+                out,
+                "    <edge source=\"{}\" target=\"{}\">",
+                escape(source),
+                escape(target)
+            )?;
+            Graph::write_data(out, "edge_", &self.edge_attrs, values)?;
+            writeln!(out, "    </edge>")?;
+        }
+        writeln!(out, "  </graph>")?;
+        writeln!(out, "</graphml>")?;
+        Ok(())
+    }
+
+    /// Write the document to the file at `path`.
+    pub fn save(&self, path: &str) -> crate::Result<()> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut graph = Graph::new("g")
+            .with_node_attr("depth", AttrType::Int)
+            .with_edge_attr("label", AttrType::String);
+        graph
+            .node("A", &[("depth", &0)])
+            .node("B&C", &[])
+            .edge("A", "B&C", &[("label", &"<1>")]);
+
+        let mut out = Vec::new();
+        graph.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="node_depth" for="node" attr.name="depth" attr.type="int"/>
+  <key id="edge_label" for="edge" attr.name="label" attr.type="string"/>
+  <graph id="g" edgedefault="directed">
+    <node id="A">
+      <data key="node_depth">0</data>
+    </node>
+    <node id="B&amp;C">
+    </node>
+    <edge source="A" target="B&amp;C">
+      <data key="edge_label">&lt;1&gt;</data>
+    </edge>
+  </graph>
+</graphml>
+"#
+        );
+
+        graph.node("C", &[("color", &"red")]);
+        assert!(graph.write(&mut Vec::new()).is_err());
+    }
+}
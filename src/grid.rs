@@ -0,0 +1,175 @@
+/// Dense 2D grid which grows on demand when cells outside of it are set,
+/// new cells are initialized with the grid's fill value. Used as
+/// framebuffer for the puzzles drawing images (day 11, day 13).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid2D<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    fill: T,
+}
+
+impl<T: Clone> Grid2D<T> {
+    /// Create an empty grid, cells are initialized with `fill` when the grid
+    /// grows.
+    pub fn new(fill: T) -> Grid2D<T> {
+        Grid2D {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            fill,
+        }
+    }
+
+    /// Create a `width` x `height` grid filled with `fill`, for when the size
+    /// is known up front.
+    pub fn with_size(width: usize, height: usize, fill: T) -> Grid2D<T> {
+        let mut grid = Grid2D::new(fill);
+        grid.grow_to(width, height);
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Set the cell at `(x, y)`, growing the grid if needed.
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        if x >= self.width || y >= self.height {
+            self.grow_to(x + 1, y + 1);
+        }
+        self.cells[y * self.width + x] = value;
+    }
+
+    /// Grow the grid to at least `width` x `height` cells.
+    pub fn grow_to(&mut self, width: usize, height: usize) {
+        let (width, height) = (width.max(self.width), height.max(self.height));
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        // The storage is reused: new rows are appended (amortized by the
+        // capacity of the Vec) and rows only move if the grid gets wider.
+        self.cells.resize(width * height, self.fill.clone());
+        if width != self.width {
+            // move rows back to front, a row never moves onto a row which
+            // hasn't been moved yet
+            for y in (0..self.height).rev() {
+                for x in (0..self.width).rev() {
+                    self.cells.swap(y * self.width + x, y * width + x);
+                }
+            }
+            for y in 0..self.height {
+                for cell in &mut self.cells[y * width + self.width..(y + 1) * width] {
+                    *cell = self.fill.clone();
+                }
+            }
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Reset all cells to the fill value, keeping the size and storage of
+    /// the grid.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = self.fill.clone();
+        }
+    }
+
+    /// Iterate over the rows of the grid from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics for a zero chunk size, an empty grid has no rows
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Iterate over all cells as `((x, y), value)` in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| ((i % width, i / width), v))
+    }
+
+    /// Draw the grid as text, one line per row, with `to_char` mapping every
+    /// cell to a character.
+    pub fn render(&self, to_char: impl Fn(&T) -> char) -> String {
+        let mut text = String::with_capacity(self.height * (self.width + 1));
+        for row in self.rows() {
+            text.extend(row.iter().map(&to_char));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grow() {
+        let mut grid = Grid2D::new('.');
+        assert!(grid.is_empty());
+        assert_eq!(grid.render(|&c| c), "");
+
+        grid.set(1, 0, 'a');
+        grid.set(0, 2, 'b');
+        assert_eq!((grid.width(), grid.height()), (2, 3));
+        assert_eq!(grid.get(1, 0), Some(&'a'));
+        assert_eq!(grid.get(1, 2), Some(&'.'));
+        assert_eq!(grid.get(2, 0), None);
+
+        grid.set(3, 1, 'c');
+        assert_eq!(grid.render(|&c| c), ".a..\n...c\nb...\n");
+        assert_eq!(
+            grid.iter().filter(|(_, &c)| c != '.').collect::<Vec<_>>(),
+            vec![((1, 0), &'a'), ((3, 1), &'c'), ((0, 2), &'b')]
+        );
+
+        grid.clear();
+        assert_eq!(grid.render(|&c| c), "....\n....\n....\n");
+    }
+
+    #[test]
+    fn test_grow_in_place() {
+        let mut grid = Grid2D::with_size(2, 2, 0);
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        grid.set(0, 0, 1);
+        grid.set(1, 0, 2);
+        grid.set(0, 1, 3);
+        grid.set(1, 1, 4);
+
+        // wider and higher at once
+        grid.grow_to(4, 3);
+        assert_eq!(grid.render(|&v| (b'0' + v) as char), "1200\n3400\n0000\n");
+        // never shrinks
+        grid.grow_to(1, 1);
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+
+        // adding rows reuses the storage
+        let mut grid = Grid2D::with_size(8, 0, 0u8);
+        let (_, allocations) = crate::alloc_count::count(|| {
+            for y in 0..64 {
+                grid.set(7, y, 1);
+            }
+        });
+        assert!(allocations < 8, "{} allocations", allocations);
+    }
+}
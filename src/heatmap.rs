@@ -0,0 +1,83 @@
+//! Heatmaps of values on a grid, like visit counts or search distances.
+//!
+//! Values are mapped onto a gradient from blue (lowest) over green and
+//! yellow to red (highest), or onto shade characters for output without
+//! colors.
+
+use crate::grid::Grid2D;
+use crate::image::Rgb;
+
+const GRADIENT: [Rgb; 4] = [
+    [0x30, 0x40, 0xc0],
+    [0x30, 0xb0, 0x50],
+    [0xe0, 0xd0, 0x30],
+    [0xe0, 0x30, 0x30],
+];
+const SHADES: [char; 4] = ['\u{2591}', '\u{2592}', '\u{2593}', '\u{2588}'];
+
+/// Range of the values of a heatmap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Heatmap {
+    min: u64,
+    max: u64,
+}
+
+impl Heatmap {
+    /// Heatmap over the range of the values in `grid`.
+    pub fn new(grid: &Grid2D<Option<u64>>) -> Heatmap {
+        let values = || grid.iter().filter_map(|(_, v)| *v);
+        Heatmap {
+            min: values().min().unwrap_or(0),
+            max: values().max().unwrap_or(0),
+        }
+    }
+
+    /// Position of `value` in the range, from 0.0 (lowest) to 1.0 (highest).
+    pub fn level(&self, value: u64) -> f64 {
+        if self.max == self.min {
+            return 1.0;
+        }
+        (value.clamp(self.min, self.max) - self.min) as f64 / (self.max - self.min) as f64
+    }
+
+    pub fn color(&self, value: u64) -> Rgb {
+        let pos = self.level(value) * (GRADIENT.len() - 1) as f64;
+        let idx = (pos as usize).min(GRADIENT.len() - 2);
+        let t = pos - idx as f64;
+        let (from, to) = (GRADIENT[idx], GRADIENT[idx + 1]);
+        let mix = |c: usize| {
+            (f64::from(from[c]) + t * (f64::from(to[c]) - f64::from(from[c]))).round() as u8
+        };
+        [mix(0), mix(1), mix(2)]
+    }
+
+    pub fn shade(&self, value: u64) -> char {
+        let idx = (self.level(value) * SHADES.len() as f64) as usize;
+        SHADES[idx.min(SHADES.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_heatmap() {
+        let mut grid = Grid2D::new(None);
+        grid.set(0, 0, Some(2));
+        grid.set(2, 0, Some(8));
+        let heatmap = Heatmap::new(&grid);
+        assert_eq!(heatmap.level(5), 0.5);
+        assert_eq!(heatmap.level(1), 0.0);
+        assert_eq!(heatmap.color(2), GRADIENT[0]);
+        assert_eq!(heatmap.color(8), GRADIENT[3]);
+        assert_eq!(heatmap.color(4), GRADIENT[1]);
+        assert_eq!(heatmap.color(5), [0x88, 0xc0, 0x40]);
+        assert_eq!(heatmap.shade(2), '\u{2591}');
+        assert_eq!(heatmap.shade(8), '\u{2588}');
+
+        // a single value is the hottest
+        let heatmap = Heatmap::new(&Grid2D::new(Some(3)));
+        assert_eq!(heatmap.shade(3), '\u{2588}');
+    }
+}
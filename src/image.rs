@@ -0,0 +1,213 @@
+//! Dependency-free image writers for grids, every cell is drawn as a
+//! `scale` x `scale` square with the color given by a mapping closure.
+//! PNG images are written by the `png` module.
+
+use crate::grid::Grid2D;
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    UnsupportedFormat(String),
+}
+
+pub type Rgb = [u8; 3];
+
+// Pixel rows of the scaled image from top to bottom, as packed RGB.
+pub(crate) fn scaled_rows<'a, T: Clone>(
+    grid: &'a Grid2D<T>,
+    scale: usize,
+    color: impl Fn(&T) -> Rgb + 'a,
+) -> impl Iterator<Item = Vec<u8>> + 'a {
+    grid.rows().flat_map(move |line| {
+        let mut row = Vec::with_capacity(line.len() * scale * 3);
+        for cell in line {
+            let rgb = color(cell);
+            for _ in 0..scale {
+                row.extend_from_slice(&rgb);
+            }
+        }
+        std::iter::repeat_n(row, scale)
+    })
+}
+
+/// Write `grid` as binary PPM (P6) image.
+pub fn write_ppm<T: Clone>(
+    out: &mut dyn Write,
+    grid: &Grid2D<T>,
+    scale: usize,
+    color: impl Fn(&T) -> Rgb,
+) -> std::io::Result<()> {
+    let (width, height) = (grid.width() * scale, grid.height() * scale);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for row in scaled_rows(grid, scale, color) {
+        out.write_all(&row)?;
+    }
+    Ok(())
+}
+
+/// Write `grid` as uncompressed 24 bit BMP image.
+pub fn write_bmp<T: Clone>(
+    out: &mut dyn Write,
+    grid: &Grid2D<T>,
+    scale: usize,
+    color: impl Fn(&T) -> Rgb,
+) -> std::io::Result<()> {
+    const HEADER_SIZE: usize = 14 + 40;
+    let (width, height) = (grid.width() * scale, grid.height() * scale);
+    // rows are padded to a multiple of 4 bytes
+    let stride = (width * 3).div_ceil(4) * 4;
+    let image_size = stride * height;
+
+    // file header
+    out.write_all(b"BM")?;
+    out.write_all(&((HEADER_SIZE + image_size) as u32).to_le_bytes())?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&(HEADER_SIZE as u32).to_le_bytes())?;
+    // info header
+    out.write_all(&40u32.to_le_bytes())?;
+    out.write_all(&(width as i32).to_le_bytes())?;
+    out.write_all(&(height as i32).to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // planes
+    out.write_all(&24u16.to_le_bytes())?; // bits per pixel
+    out.write_all(&0u32.to_le_bytes())?; // no compression
+    out.write_all(&(image_size as u32).to_le_bytes())?;
+    out.write_all(&2835i32.to_le_bytes())?; // 72 dpi
+    out.write_all(&2835i32.to_le_bytes())?;
+    out.write_all(&[0; 8])?; // palette
+
+    // rows are stored bottom-up with pixels as BGR
+    let rows: Vec<_> = scaled_rows(grid, scale, color).collect();
+    for row in rows.iter().rev() {
+        let mut bgr = Vec::with_capacity(stride);
+        for rgb in row.chunks_exact(3) {
+            bgr.extend_from_slice(&[rgb[2], rgb[1], rgb[0]]);
+        }
+        bgr.resize(stride, 0);
+        out.write_all(&bgr)?;
+    }
+    Ok(())
+}
+
+/// Save `grid` to `path`, the format is chosen by the extension of the path
+/// (`.ppm`, `.bmp` or `.png`).
+pub fn save<T: Clone>(
+    path: &str,
+    grid: &Grid2D<T>,
+    scale: usize,
+    color: impl Fn(&T) -> Rgb,
+) -> crate::Result<()> {
+    let write = if path.ends_with(".ppm") {
+        write_ppm
+    } else if path.ends_with(".bmp") {
+        write_bmp
+    } else if path.ends_with(".png") {
+        crate::png::write_png
+    } else {
+        return Err(crate::Error::boxed(E::UnsupportedFormat(path.to_string())));
+    };
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write(&mut out, grid, scale, color)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the frames of an animation as numbered PPM images
+/// `frame_NNNNN.ppm` or text files `frame_NNNNN.txt` into a directory.
+pub struct FrameDir {
+    dir: std::path::PathBuf,
+    frames: usize,
+}
+
+impl FrameDir {
+    /// Create the directory `dir` if it doesn't exist yet.
+    pub fn create(dir: &str) -> crate::Result<FrameDir> {
+        std::fs::create_dir_all(dir)?;
+        Ok(FrameDir {
+            dir: std::path::PathBuf::from(dir),
+            frames: 0,
+        })
+    }
+
+    /// File name of the frame number `frame`, e.g. `frame_00042.txt`.
+    pub fn name(frame: usize, extension: &str) -> String {
+        format!("frame_{:05}.{}", frame, extension)
+    }
+
+    /// Write `grid` as next frame, see [`write_ppm`].
+    pub fn save<T: Clone>(
+        &mut self,
+        grid: &Grid2D<T>,
+        scale: usize,
+        color: impl Fn(&T) -> Rgb,
+    ) -> crate::Result<()> {
+        let path = self.dir.join(FrameDir::name(self.frames, "ppm"));
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_ppm(&mut out, grid, scale, color)?;
+        out.flush()?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Write `text` as next frame.
+    pub fn save_text(&mut self, text: &str) -> std::io::Result<()> {
+        let path = self.dir.join(FrameDir::name(self.frames, "txt"));
+        std::fs::write(path, text)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid() -> Grid2D<u8> {
+        let mut grid = Grid2D::new(0);
+        grid.set(1, 0, 1);
+        grid.set(0, 1, 2);
+        grid
+    }
+
+    fn color(v: &u8) -> Rgb {
+        [*v, 10 + *v, 20 + *v]
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut out = Vec::new();
+        write_ppm(&mut out, &grid(), 1, color).unwrap();
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 10, 20, 1, 11, 21, 2, 12, 22, 0, 10, 20]);
+        assert_eq!(out, expected);
+
+        let mut out = Vec::new();
+        write_ppm(&mut out, &grid(), 3, color).unwrap();
+        assert_eq!(out.len(), b"P6\n6 6\n255\n".len() + 6 * 6 * 3);
+    }
+
+    #[test]
+    fn test_write_bmp() {
+        let mut out = Vec::new();
+        write_bmp(&mut out, &grid(), 1, color).unwrap();
+        // two rows of 2 pixels, padded from 6 to 8 bytes
+        assert_eq!(out.len(), 54 + 2 * 8);
+        assert_eq!(&out[0..2], b"BM");
+        assert_eq!(&out[2..6], &70u32.to_le_bytes());
+        assert_eq!(&out[18..22], &2i32.to_le_bytes());
+        assert_eq!(&out[22..26], &2i32.to_le_bytes());
+        assert_eq!(&out[28..30], &24u16.to_le_bytes());
+        // bottom row first, as BGR
+        assert_eq!(&out[54..62], &[22, 12, 2, 20, 10, 0, 0, 0]);
+        assert_eq!(&out[62..70], &[20, 10, 0, 21, 11, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_save_unsupported() {
+        assert!(save("image.gif", &grid(), 1, color).is_err());
+    }
+}
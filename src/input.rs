@@ -0,0 +1,273 @@
+use std::io::{Read, Write};
+
+#[derive(Debug)]
+pub enum E {
+    InvalidDigit { line: usize, column: usize, c: char },
+    // url, error reported by curl
+    FetchFailed(String, String),
+    // 0-based index of the value in the list, value
+    InvalidValue(usize, String),
+    // path, error reported when reading it
+    ReadFailed(String, String),
+    // path, error reported when parsing its content
+    InvalidInput(String, String),
+    // the session cookie holds control characters
+    InvalidSession,
+}
+
+/// Path used for reading the puzzle input from stdin.
+pub const STDIN: &str = "-";
+
+/// Environment variable holding the session cookie for adventofcode.com.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+/// Read the whole puzzle input from `path`, or from stdin if `path` is `"-"`.
+/// A `http://` or `https://` url is fetched, see `fetch`.
+/// Errors name the path which couldn't be read.
+pub fn read_to_string(path: &str) -> crate::Result<String> {
+    let failed =
+        |e: std::io::Error| crate::Error::boxed(E::ReadFailed(path.to_string(), e.to_string()));
+    if path == STDIN {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(failed)?;
+        Ok(input)
+    } else if is_url(path) {
+        fetch(path, std::env::var(SESSION_VAR).ok().as_deref())
+    } else {
+        Ok(std::fs::read_to_string(path).map_err(failed)?)
+    }
+}
+
+/// Read the input from `path` (see `read_to_string`) and `parse` it, parse
+/// errors are reported along with the path.
+pub fn read_with<T>(path: &str, parse: impl FnOnce(&str) -> crate::Result<T>) -> crate::Result<T> {
+    parse(&read_to_string(path)?)
+        .map_err(|e| crate::Error::boxed(E::InvalidInput(path.to_string(), e.to_string())).into())
+}
+
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+// Host part of `url`, without user info and port.
+fn host(url: &str) -> &str {
+    let rest = url.split("://").nth(1).unwrap_or("");
+    let authority = rest.split(&['/', '?', '#'][..]).next().unwrap_or("");
+    let authority = authority.rsplit('@').next().unwrap_or("");
+    authority.split(':').next().unwrap_or("")
+}
+
+// Whether the session cookie may be sent to `url`: adventofcode.com over
+// https only.
+fn sends_session(url: &str) -> bool {
+    let host = host(url);
+    url.starts_with("https://")
+        && (host == "adventofcode.com" || host.ends_with(".adventofcode.com"))
+}
+
+// Arguments for curl to fetch `url` and the config curl reads from stdin.
+// The session cookie goes through the config to stay out of the process
+// list, requests with the cookie don't follow redirects so it can't be
+// carried to another host. Other requests only follow redirects to https.
+// Sessions with control characters are rejected, a line break would end
+// the cookie and start another option of the config.
+fn curl_args(url: &str, session: Option<&str>) -> crate::Result<(Vec<String>, Option<String>)> {
+    let mut args = vec!["--silent", "--show-error", "--fail"];
+    let config = match session {
+        Some(session) if sends_session(url) => {
+            let session = session.trim();
+            if session.chars().any(char::is_control) {
+                return Err(crate::Error::boxed(E::InvalidSession));
+            }
+            args.extend(&["--config", "-"]);
+            let cookie = format!("session={}", session)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            Some(format!("cookie = \"{}\"\n", cookie))
+        }
+        _ => {
+            args.extend(&["--location", "--proto-redir", "=https"]);
+            None
+        }
+    };
+    let mut args: Vec<_> = args.into_iter().map(String::from).collect();
+    args.push(url.to_string());
+    Ok((args, config))
+}
+
+/// Fetch `url` with curl (std has no TLS), applying the `session` cookie
+/// for adventofcode.com over https.
+pub fn fetch(url: &str, session: Option<&str>) -> crate::Result<String> {
+    let failed =
+        |e: std::io::Error| crate::Error::boxed(E::FetchFailed(url.to_string(), e.to_string()));
+    let (args, config) = curl_args(url, session)?;
+    let mut curl = std::process::Command::new("curl")
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(failed)?;
+    // closing stdin ends the config
+    let mut stdin = curl.stdin.take();
+    if let (Some(stdin), Some(config)) = (stdin.as_mut(), config) {
+        stdin.write_all(config.as_bytes()).map_err(failed)?;
+    }
+    drop(stdin);
+    let output = curl.wait_with_output().map_err(failed)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(crate::Error::boxed(E::FetchFailed(url.to_string(), stderr)));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Parse a string of decimal digits like `"80871224"`. Surrounding
+/// whitespace (e.g. a trailing `\n` or `\r\n`) is ignored, any other
+/// character is reported with its 1-based line and column.
+pub fn parse_digits<T: From<u8>>(input: &str) -> crate::Result<Vec<T>> {
+    // the digits can only span one line, find where it starts
+    let leading = &input[..input.len() - input.trim_start().len()];
+    let line = 1 + leading.matches('\n').count();
+    let column = 1 + leading.len() - leading.rfind('\n').map_or(0, |nl| nl + 1);
+
+    input
+        .trim()
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c.to_digit(10) {
+            Some(d) => Ok(T::from(d as u8)),
+            None => Err(crate::Error::new(E::InvalidDigit {
+                line,
+                column: column + i,
+                c,
+            })
+            .into()),
+        })
+        .collect()
+}
+
+/// Parse the `sep` separated values of `input`, whitespace around the
+/// values is ignored. Empty input yields no values.
+pub fn parse_list<T: std::str::FromStr>(input: &str, sep: char) -> crate::Result<Vec<T>> {
+    if input.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    input
+        .trim()
+        .split(sep)
+        .enumerate()
+        .map(|(idx, value)| {
+            value.trim().parse::<T>().map_err(|_| {
+                crate::Error::boxed(E::InvalidValue(idx, value.trim().to_string())).into()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn invalid_digit(input: &str) -> (usize, usize, char) {
+        let err = parse_digits::<i32>(input).err().unwrap();
+        match err.downcast::<crate::Error<E>>().unwrap().err {
+            E::InvalidDigit { line, column, c } => (line, column, c),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_digits() {
+        assert_eq!(parse_digits::<i32>("12345").unwrap(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(parse_digits::<u8>("907\n").unwrap(), vec![9, 0, 7]);
+        assert_eq!(parse_digits::<u32>("907\r\n").unwrap(), vec![9, 0, 7]);
+        assert_eq!(parse_digits::<i32>("  42 \t\n\n").unwrap(), vec![4, 2]);
+        assert!(parse_digits::<i32>("\n").unwrap().is_empty());
+
+        assert_eq!(invalid_digit("12a4"), (1, 3, 'a'));
+        assert_eq!(invalid_digit("12 34"), (1, 3, ' '));
+        assert_eq!(invalid_digit("12\r34"), (1, 3, '\r'));
+        assert_eq!(invalid_digit("\n  1-"), (2, 4, '-'));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list::<i64>("1,-2, 3\n", ',').unwrap(), vec![1, -2, 3]);
+        assert_eq!(parse_list::<u32>("12\n14\n", '\n').unwrap(), vec![12, 14]);
+        assert!(parse_list::<i64>("\n", ',').unwrap().is_empty());
+        assert!(parse_list::<i64>("1,,2", ',').is_err());
+    }
+
+    #[test]
+    fn test_read_with() {
+        let path = std::env::temp_dir().join(format!("aoc19-read-with-{}", std::process::id()));
+        std::fs::write(&path, "1,x\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let err = read_with(path, |input| parse_list::<i64>(input, ','))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Error: InvalidInput("));
+        assert!(err.contains(path));
+        assert!(err.contains("InvalidValue(1, \\\"x\\\")"));
+        assert_eq!(read_with(path, |input| Ok(input.len())).unwrap(), 4);
+        std::fs::remove_file(path).unwrap();
+
+        let err = read_to_string("input/missing").unwrap_err().to_string();
+        assert!(err.starts_with("Error: ReadFailed(\"input/missing\""));
+    }
+
+    #[test]
+    fn test_url() {
+        assert!(is_url("https://adventofcode.com/2019/day/1/input"));
+        assert!(!is_url("input/day1"));
+        assert_eq!(
+            host("https://user@adventofcode.com:443/2019"),
+            "adventofcode.com"
+        );
+        assert_eq!(host("https://evil.com#@adventofcode.com/"), "evil.com");
+        assert_eq!(host("https://evil.com?@adventofcode.com/"), "evil.com");
+        assert_eq!(host("https://adventofcode.com?x=1"), "adventofcode.com");
+
+        let url = "https://adventofcode.com/2019/day/1/input";
+        let (args, config) = curl_args(url, Some("abc\n")).unwrap();
+        assert_eq!(
+            args,
+            vec!["--silent", "--show-error", "--fail", "--config", "-", url]
+        );
+        assert_eq!(config.as_deref(), Some("cookie = \"session=abc\"\n"));
+        let (_, config) = curl_args(url, Some("a\"b\\")).unwrap();
+        assert_eq!(config.as_deref(), Some("cookie = \"session=a\\\"b\\\\\"\n"));
+        // no options smuggled into the config
+        assert!(curl_args(url, Some("abc\nurl = \"https://evil.com\"")).is_err());
+        assert!(curl_args(url, Some("a\rb")).is_err());
+        assert!(curl_args(url, Some("a\0b")).is_err());
+
+        // the session is not leaked to other hosts or over http
+        for url in &[
+            "http://example.com/adventofcode.com",
+            "http://adventofcode.com/2019/day/1/input",
+            "https://evil.com#@adventofcode.com/",
+            "https://evil.com?@adventofcode.com/",
+            "https://evil.com/@adventofcode.com/",
+        ] {
+            let (args, config) = curl_args(url, Some("abc")).unwrap();
+            assert_eq!(config, None);
+            assert_eq!(
+                args,
+                vec![
+                    "--silent",
+                    "--show-error",
+                    "--fail",
+                    "--location",
+                    "--proto-redir",
+                    "=https",
+                    url
+                ]
+            );
+        }
+    }
+}
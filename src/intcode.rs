@@ -0,0 +1,2512 @@
+//! Intcode virtual machine shared by the intcode days.
+//!
+//! With the `serde` feature the machine state ([`IntcodeISS`], [`Snapshot`]
+//! and [`StopReason`]) implements `Serialize` and `Deserialize`, e.g. to
+//! checkpoint long running programs with any serde format.
+
+use crate::json::Json;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex};
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+pub enum E {
+    InvalidSnapshot,
+    InvalidBinary,
+    EmptyProgram,
+    PatchOutOfRange {
+        addr: Addr,
+        len: usize,
+    },
+    CellOutOfRange {
+        addr: Addr,
+        value: Value,
+    },
+    // pc of the instruction whose result doesn't fit into a Value
+    ArithmeticOverflow {
+        pc: Addr,
+    },
+    // pc and first cell of the faulting instruction
+    InvalidOpcode {
+        pc: Addr,
+        opcode: Value,
+        word: Value,
+    },
+    InvalidMode {
+        pc: Addr,
+        word: Value,
+    },
+    // pc of the instruction and the address it refers to
+    InvalidAddress {
+        pc: Addr,
+        addr: Value,
+    },
+    // index of the cell in the text form, the token and where it starts,
+    // line and column are 1-based
+    InvalidCell {
+        index: usize,
+        token: String,
+        line: usize,
+        column: usize,
+    },
+    // the address is beyond the cells the memory may have, see
+    // IntcodeISS::with_memory_policy
+    AddressOutOfBounds {
+        pc: Addr,
+        addr: Value,
+        limit: Addr,
+    },
+}
+
+/// Execution error of a machine, the machine stays at the faulting
+/// instruction.
+pub type IntcodeError = crate::Error<E>;
+
+pub type Addr = usize;
+pub type Value = i64;
+const PAGE_SIZE: Addr = 1024;
+/// Limit of the addresses of a growing memory, addresses beyond are rejected
+/// instead of growing the memory to them unless the memory is sparse, see
+/// [`MemoryPolicy::Grow`].
+pub const MAX_ADDR: Addr = 1 << 24;
+
+// Sparse memory beyond the program, pages of PAGE_SIZE cells by their
+// number.
+type Pages = HashMap<Addr, Box<[Value]>>;
+
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopReason {
+    NeedInput,
+    ProgramHalt,
+    // the run took more steps than allowed, see IntcodeISS::with_step_limit
+    LimitExceeded,
+}
+
+/// Where the input of a machine comes from. `None` stops the machine with
+/// `StopReason::NeedInput`, it can be resumed when there is more input.
+/// Machines with [`InputPolicy::Default`] read the default value instead.
+///
+/// Besides slices, queues and channels any closure returning the next
+/// value can be plugged in, e.g. one reading stdin.
+pub trait InputSource {
+    fn next_input(&mut self) -> Option<Value>;
+}
+
+/// What a machine does when it reads from an empty [`InputSource`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputPolicy {
+    /// Stop with `StopReason::NeedInput`.
+    Suspend,
+    /// Read the value and go on, like the network computers of day 23 which
+    /// read -1 when no packet is waiting.
+    Default(Value),
+}
+
+/// How far the memory of a machine reaches, see
+/// [`IntcodeISS::with_memory_policy`]. Addresses beyond stop the machine
+/// with `E::AddressOutOfBounds`, [`IntcodeISS::poke`] fails with it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryPolicy {
+    /// Grow the memory up to the highest address used. Addresses must be
+    /// below [`MAX_ADDR`] unless the memory is sparse, sparse memory grows
+    /// by page and has no limit.
+    Grow,
+    /// Grow the memory up to the given number of cells.
+    Capped(Addr),
+    /// Keep the memory at the size it has, reads beyond the memory are 0.
+    Fixed,
+}
+
+/// Device mapped into the memory of a machine, see
+/// [`IntcodeISS::with_mmio`]. Reads and writes of the cells of its region
+/// go to the device instead of the memory.
+pub trait Mmio: Send {
+    /// The value of the cell at `offset` into the region.
+    fn read(&mut self, offset: Addr) -> Value;
+    /// `value` is written to the cell at `offset` into the region.
+    fn write(&mut self, offset: Addr, value: Value);
+}
+
+// A device and the cells it is mapped to.
+type Mapping = (Range<Addr>, Arc<Mutex<dyn Mmio>>);
+
+/// Where the output of a machine goes, closures taking each value can be
+/// plugged in as well.
+pub trait OutputSink {
+    fn put(&mut self, value: Value);
+}
+
+impl InputSource for std::slice::Iter<'_, Value> {
+    fn next_input(&mut self) -> Option<Value> {
+        self.next().copied()
+    }
+}
+
+impl InputSource for VecDeque<Value> {
+    fn next_input(&mut self) -> Option<Value> {
+        self.pop_front()
+    }
+}
+
+// Blocks until a value is sent, runs dry once all senders are gone.
+impl InputSource for mpsc::Receiver<Value> {
+    fn next_input(&mut self) -> Option<Value> {
+        self.recv().ok()
+    }
+}
+
+impl<F: FnMut() -> Option<Value>> InputSource for F {
+    fn next_input(&mut self) -> Option<Value> {
+        self()
+    }
+}
+
+/// Input parsed from a reader as the machine asks for it, numbers separated
+/// by commas or whitespace like the puzzle inputs. Long streams are fed to
+/// the machine without reading them up front. The input ends with the
+/// stream or with the first error, see [`ReadInput::take_error`].
+pub struct ReadInput<R> {
+    reader: std::io::BufReader<R>,
+    error: Option<Box<dyn std::error::Error>>,
+}
+
+impl<R: std::io::Read> ReadInput<R> {
+    pub fn new(reader: R) -> ReadInput<R> {
+        ReadInput {
+            reader: std::io::BufReader::new(reader),
+            error: None,
+        }
+    }
+
+    /// The error which ended the input, e.g. an invalid number.
+    pub fn take_error(&mut self) -> Option<Box<dyn std::error::Error>> {
+        self.error.take()
+    }
+
+    // The next number, `None` at the end of the stream.
+    fn read_value(&mut self) -> crate::Result<Option<Value>> {
+        use std::io::BufRead;
+        let mut token = Vec::new();
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let separator = |byte: &u8| *byte == b',' || byte.is_ascii_whitespace();
+            let skip = if token.is_empty() {
+                buf.iter().take_while(|byte| separator(byte)).count()
+            } else {
+                0
+            };
+            let len = buf[skip..]
+                .iter()
+                .take_while(|byte| !separator(byte))
+                .count();
+            token.extend_from_slice(&buf[skip..skip + len]);
+            let done = skip + len < buf.len();
+            self.reader.consume(skip + len);
+            if done {
+                break;
+            }
+        }
+        if token.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(token)?.parse()?))
+    }
+}
+
+impl<R: std::io::Read> InputSource for ReadInput<R> {
+    fn next_input(&mut self) -> Option<Value> {
+        if self.error.is_some() {
+            return None;
+        }
+        self.read_value().unwrap_or_else(|err| {
+            self.error = Some(err);
+            None
+        })
+    }
+}
+
+impl OutputSink for Vec<Value> {
+    fn put(&mut self, value: Value) {
+        self.push(value);
+    }
+}
+
+impl OutputSink for VecDeque<Value> {
+    fn put(&mut self, value: Value) {
+        self.push_back(value);
+    }
+}
+
+// Values sent after the receiver is gone are dropped.
+impl OutputSink for mpsc::Sender<Value> {
+    fn put(&mut self, value: Value) {
+        let _ = self.send(value);
+    }
+}
+
+impl<F: FnMut(Value)> OutputSink for F {
+    fn put(&mut self, value: Value) {
+        self(value)
+    }
+}
+
+/// A machine running on its own thread, see [`IntcodeISS::spawn`].
+pub type Spawned = std::thread::JoinHandle<(
+    Result<StopReason, IntcodeError>,
+    IntcodeISS,
+    mpsc::Receiver<Value>,
+)>;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntcodeISS {
+    mem: Vec<Value>,
+    pc: Addr,
+    #[cfg_attr(feature = "serde", serde(rename = "rb"))]
+    relative_base: Value,
+    step_limit: Option<u64>,
+    input_policy: InputPolicy,
+    memory_policy: MemoryPolicy,
+    // every run uses checked arithmetic, see with_checked_arithmetic
+    checked: bool,
+    // decode cache of `mem` by address, `None` for Backend::Interpreter,
+    // deserialized machines are interpreted
+    #[cfg_attr(feature = "serde", serde(skip))]
+    code: Option<Vec<Option<Encoded>>>,
+    // memory beyond `mem`, if it is sparse
+    pages: Option<Pages>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mmio: Vec<Mapping>,
+}
+
+/// A decoded instruction as it is executed, see [`Tracer`]. Operands read
+/// by the instruction are fetched according to their modes, the address
+/// written to comes first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    Add(Addr, Value, Value),
+    Mul(Addr, Value, Value),
+    Get(Addr),
+    Put(Value),
+    // the target is only checked when jumping
+    Jpt(Value, Value),
+    Jpf(Value, Value),
+    Lt(Addr, Value, Value),
+    Eq(Addr, Value, Value),
+    Rbo(Value),
+    Halt,
+}
+
+impl Instruction {
+    /// Name of the instruction, like in the [`crate::asm`] format.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add(..) => "add",
+            Instruction::Mul(..) => "mul",
+            Instruction::Get(..) => "get",
+            Instruction::Put(..) => "put",
+            Instruction::Jpt(..) => "jpt",
+            Instruction::Jpf(..) => "jpf",
+            Instruction::Lt(..) => "lt",
+            Instruction::Eq(..) => "eq",
+            Instruction::Rbo(..) => "rbo",
+            Instruction::Halt => "halt",
+        }
+    }
+
+    /// Number of cells of the instruction, including the first one.
+    pub fn size(&self) -> Addr {
+        match self {
+            Instruction::Add(..)
+            | Instruction::Mul(..)
+            | Instruction::Lt(..)
+            | Instruction::Eq(..) => 4,
+            Instruction::Jpt(..) | Instruction::Jpf(..) => 3,
+            Instruction::Get(..) | Instruction::Put(..) | Instruction::Rbo(..) => 2,
+            Instruction::Halt => 1,
+        }
+    }
+}
+
+/// How a machine executes its program, see [`IntcodeISS::with_backend`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Backend {
+    /// Decode every instruction from memory when it is executed.
+    Interpreter,
+    /// Interpreter with a cache of the decoded instructions: the word, modes
+    /// and raw operands are kept once decoded, the operands are still
+    /// fetched and the instruction dispatched on every step. Writes to
+    /// memory drop the cached instructions they touch, so programs modifying
+    /// themselves run as they are interpreted.
+    DecodeCache,
+}
+
+// An instruction as it is in memory, before its operands are fetched.
+#[derive(Clone, Copy, Debug)]
+struct Encoded {
+    word: Value,
+    raw: [Value; 3],
+    opcode: u8,
+    // modes of the raw operands, the destination last
+    modes: [u8; 3],
+}
+
+/// An intcode program, the initial memory of a machine.
+///
+/// Programs are checked when they are built and hold at least one cell.
+/// The text form is the comma separated list of the puzzle inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntcodeProgram {
+    cells: Vec<Value>,
+}
+
+impl IntcodeProgram {
+    pub fn new(cells: Vec<Value>) -> Result<IntcodeProgram, crate::Error<E>> {
+        if cells.is_empty() {
+            return Err(crate::Error::new(E::EmptyProgram));
+        }
+        Ok(IntcodeProgram { cells })
+    }
+
+    /// Read a program from `path`, either in the binary form or as text.
+    pub fn from_path(path: &str) -> crate::Result<IntcodeProgram> {
+        let bytes = std::fs::read(path)?;
+        if bytes.starts_with(BINARY_MAGIC) {
+            return Ok(IntcodeProgram::new(decode_binary(&bytes)?)?);
+        }
+        parse_program(&String::from_utf8_lossy(&bytes))
+    }
+
+    pub fn cells(&self) -> &[Value] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The cells converted to the narrower cell type of a day's own
+    /// machine.
+    pub fn cells_as<T: TryFrom<Value>>(&self) -> Result<Vec<T>, crate::Error<E>> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(addr, &value)| {
+                T::try_from(value).map_err(|_| crate::Error::new(E::CellOutOfRange { addr, value }))
+            })
+            .collect()
+    }
+
+    /// Cell width of the binary form in bytes.
+    pub fn cell_width(&self) -> u8 {
+        cell_width(&self.cells)
+    }
+
+    /// Replace the cell at `addr`, e.g. with the inputs some puzzles patch
+    /// into the program before running it.
+    pub fn patch(&mut self, addr: Addr, value: Value) -> Result<(), crate::Error<E>> {
+        let len = self.cells.len();
+        let cell = self
+            .cells
+            .get_mut(addr)
+            .ok_or_else(|| crate::Error::new(E::PatchOutOfRange { addr, len }))?;
+        *cell = value;
+        Ok(())
+    }
+
+    /// Copy of the program with the `(addr, value)` patches applied.
+    pub fn patched(&self, patches: &[(Addr, Value)]) -> Result<IntcodeProgram, crate::Error<E>> {
+        let mut prog = self.clone();
+        for &(addr, value) in patches {
+            prog.patch(addr, value)?;
+        }
+        Ok(prog)
+    }
+}
+
+impl TryFrom<&[Value]> for IntcodeProgram {
+    type Error = crate::Error<E>;
+
+    fn try_from(cells: &[Value]) -> Result<IntcodeProgram, crate::Error<E>> {
+        IntcodeProgram::new(cells.to_vec())
+    }
+}
+
+impl FromStr for IntcodeProgram {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> crate::Result<IntcodeProgram> {
+        parse_program(s)
+    }
+}
+
+impl fmt::Display for IntcodeProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, cell) in self.cells.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", cell)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse an intcode program, comma separated values like `1,0,0,3,99`.
+/// Goes through the cache of parsed inputs if it is enabled. Invalid values
+/// are reported with their index and position, see `E::InvalidCell`.
+///
+/// Hand written programs can be laid out freely, values are separated by
+/// commas or whitespace and `#` starts a comment up to the end of the line:
+///
+/// ```text
+/// 1101, 1, 2, 9   # [9] = 1 + 2
+/// 4, 9            # output [9]
+/// 99
+/// ```
+pub fn parse_program(input: &str) -> crate::Result<IntcodeProgram> {
+    crate::cache::parse_cached(input, |input| Ok(IntcodeProgram::new(parse_cells(input)?)?))
+}
+
+// Cells of the text form, none for blank input. Every comma must be
+// preceded and followed by a value.
+fn parse_cells(input: &str) -> Result<Vec<Value>, crate::Error<E>> {
+    let invalid = |index, token: &str, offset| {
+        let (line, column) = position(input, offset);
+        crate::Error::new(E::InvalidCell {
+            index,
+            token: token.to_string(),
+            line,
+            column,
+        })
+    };
+
+    let mut cells = Vec::new();
+    // start of the token being read
+    let mut start = None;
+    // offset behind a comma which still waits for its value
+    let mut comma = None;
+    let mut in_comment = false;
+    let end = std::iter::once((input.len(), '\n'));
+    for (offset, c) in input.char_indices().chain(end) {
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        if c != ',' && c != '#' && !c.is_whitespace() {
+            start.get_or_insert(offset);
+            continue;
+        }
+        if let Some(start) = start.take() {
+            let token = &input[start..offset];
+            let value = token
+                .parse()
+                .map_err(|_| invalid(cells.len(), token, start))?;
+            cells.push(value);
+            comma = None;
+        }
+        match c {
+            ',' if comma.is_some() || cells.is_empty() => {
+                return Err(invalid(cells.len(), "", comma.unwrap_or(offset)));
+            }
+            ',' => comma = Some(offset + 1),
+            '#' => in_comment = true,
+            _ => {}
+        }
+    }
+    match comma {
+        Some(offset) => Err(invalid(cells.len(), "", offset)),
+        None => Ok(cells),
+    }
+}
+
+// 1-based line and column of the byte at `offset` of `input`.
+fn position(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |nl| nl + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Binary form of programs and memory images, all fields little-endian:
+///
+/// ```text
+/// magic "ICB1" | cell width u8 (1, 2, 4 or 8) | cell count u64 | cells
+/// ```
+///
+/// Cells are signed and stored with the smallest width holding all of them.
+pub const BINARY_MAGIC: &[u8; 4] = b"ICB1";
+
+// Smallest supported cell width in bytes which can hold every cell.
+fn cell_width(cells: &[Value]) -> u8 {
+    let fits = |bits: u32| {
+        let max = (1 << (bits - 1)) - 1;
+        cells.iter().all(|&v| -max - 1 <= v && v <= max)
+    };
+    match () {
+        _ if fits(8) => 1,
+        _ if fits(16) => 2,
+        _ if fits(32) => 4,
+        _ => 8,
+    }
+}
+
+/// Encode `cells` in the binary form, see [`BINARY_MAGIC`].
+pub fn encode_binary(cells: &[Value]) -> Vec<u8> {
+    let width = cell_width(cells);
+    let mut out = Vec::with_capacity(13 + cells.len() * width as usize);
+    out.extend_from_slice(BINARY_MAGIC);
+    out.push(width);
+    out.extend_from_slice(&(cells.len() as u64).to_le_bytes());
+    for &cell in cells {
+        out.extend_from_slice(&cell.to_le_bytes()[..width as usize]);
+    }
+    out
+}
+
+/// Decode the binary form of a program or memory image.
+pub fn decode_binary(bytes: &[u8]) -> Result<Vec<Value>, crate::Error<E>> {
+    let invalid = || crate::Error::new(E::InvalidBinary);
+    if bytes.len() < 13 || &bytes[..4] != BINARY_MAGIC {
+        return Err(invalid());
+    }
+    let width = match bytes[4] {
+        w @ (1 | 2 | 4 | 8) => w as usize,
+        _ => return Err(invalid()),
+    };
+    let len = u64::from_le_bytes(<[u8; 8]>::try_from(&bytes[5..13]).map_err(|_| invalid())?);
+    let cells = &bytes[13..];
+    if usize::try_from(len).ok().and_then(|l| l.checked_mul(width)) != Some(cells.len()) {
+        return Err(invalid());
+    }
+    Ok(cells
+        .chunks_exact(width)
+        .map(|cell| {
+            // sign extend the cell to the full value width
+            let fill = if cell[width - 1] & 0x80 != 0 { 0xff } else { 0 };
+            let mut le = [fill; 8];
+            le[..width].copy_from_slice(cell);
+            Value::from_le_bytes(le)
+        })
+        .collect())
+}
+
+/// Write `cells` to `path` in the binary form.
+pub fn save_binary(path: &str, cells: &[Value]) -> crate::Result<()> {
+    std::fs::write(path, encode_binary(cells))?;
+    Ok(())
+}
+
+impl IntcodeISS {
+    pub fn new(prog: &IntcodeProgram) -> IntcodeISS {
+        IntcodeISS {
+            mem: prog.cells.clone(),
+            pc: 0,
+            relative_base: 0,
+            step_limit: None,
+            input_policy: InputPolicy::Suspend,
+            memory_policy: MemoryPolicy::Grow,
+            checked: false,
+            code: Some(Vec::new()),
+            pages: None,
+            mmio: Vec::new(),
+        }
+    }
+
+    /// Keep the memory beyond the program in pages which are allocated
+    /// when they are written to, instead of growing it up to the highest
+    /// address used. For programs using few far apart addresses, which can
+    /// then be anywhere in the range of a [`Value`].
+    pub fn with_sparse_memory(mut self) -> IntcodeISS {
+        self.pages.get_or_insert_with(Pages::new);
+        self
+    }
+
+    /// Stop every run after `limit` instructions with
+    /// `StopReason::LimitExceeded`, e.g. to keep a program which loops
+    /// forever from hanging a test. The machine resumes with the next
+    /// instruction on the next run, which gets the full budget again.
+    pub fn with_step_limit(mut self, limit: u64) -> IntcodeISS {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    /// Set what the machine does when it runs out of input, it suspends by
+    /// default. Programs polling for input loop forever with a default
+    /// value, they need a step limit to stop.
+    pub fn with_input_policy(mut self, policy: InputPolicy) -> IntcodeISS {
+        self.input_policy = policy;
+        self
+    }
+
+    /// Set how far the memory reaches, it grows by default. A cap keeps
+    /// programs addressing absurdly far from allocating gigabytes.
+    pub fn with_memory_policy(mut self, policy: MemoryPolicy) -> IntcodeISS {
+        self.memory_policy = policy;
+        self
+    }
+
+    /// Map `device` to the cells in `region`, the memory of the cells is
+    /// left alone. Forks and clones of the machine share the device, the
+    /// caller keeps a handle to look at it, e.g. a framebuffer:
+    ///
+    /// ```text
+    /// let screen = Arc::new(Mutex::new(Screen::default()));
+    /// let iss = IntcodeISS::new(&prog).with_mmio(1000..1000 + 40 * 25, screen.clone());
+    /// ```
+    pub fn with_mmio(mut self, region: Range<Addr>, device: Arc<Mutex<dyn Mmio>>) -> IntcodeISS {
+        self.mmio.push((region, device));
+        self
+    }
+
+    // Whether a device is mapped to any of the cells of `cells`.
+    fn maps(&self, cells: Range<Addr>) -> bool {
+        self.mmio
+            .iter()
+            .any(|(region, _)| region.start < cells.end && cells.start < region.end)
+    }
+
+    // The device mapped to `addr` and the offset into its region.
+    fn mapped(&self, addr: Addr) -> Option<(&Mutex<dyn Mmio>, Addr)> {
+        self.mmio
+            .iter()
+            .find(|(region, _)| region.contains(&addr))
+            .map(|(region, device)| (&**device, addr - region.start))
+    }
+
+    /// Use checked arithmetic in every run, not only in the `try_compute`
+    /// family: overflows stop `compute`, `run_until_output`, `outputs`,
+    /// `spawn` and `compute_traced` with `E::ArithmeticOverflow` as well.
+    pub fn with_checked_arithmetic(mut self) -> IntcodeISS {
+        self.checked = true;
+        self
+    }
+
+    /// Execute the program with `backend`, with the decode cache by default.
+    /// The plain interpreter saves the memory of the cache, e.g. for many
+    /// short lived machines.
+    pub fn with_backend(mut self, backend: Backend) -> IntcodeISS {
+        self.code = match backend {
+            Backend::Interpreter => None,
+            Backend::DecodeCache => Some(Vec::new()),
+        };
+        self
+    }
+
+    fn resize_mem(&mut self, addr: Addr) {
+        let new_size = (addr + PAGE_SIZE) / PAGE_SIZE * PAGE_SIZE;
+        self.mem.resize(new_size, 0);
+    }
+
+    /// The whole memory of the machine, e.g. to dump it with
+    /// [`save_binary`]. Of a sparse machine only the program part, the
+    /// pages beyond are in its [`Snapshot`].
+    pub fn memory(&self) -> &[Value] {
+        &self.mem
+    }
+
+    pub fn peek(&mut self, addr: Addr) -> Value {
+        if let Some((device, offset)) = self.mapped(addr) {
+            device
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .read(offset)
+        } else if let Some(cell) = self.mem.get(addr) {
+            *cell
+        } else if let Some(pages) = &self.pages {
+            pages
+                .get(&(addr / PAGE_SIZE))
+                .map_or(0, |page| page[addr % PAGE_SIZE])
+        } else if self.addr_limit().is_some_and(|limit| addr >= limit) {
+            // e.g. operands of an instruction at the end of a fixed memory
+            0
+        } else {
+            self.resize_mem(addr);
+            self.mem[addr]
+        }
+    }
+
+    /// Write `val` to `addr`, addresses beyond the memory policy are
+    /// rejected like the ones of the program.
+    pub fn poke(&mut self, addr: Addr, val: Value) -> Result<(), IntcodeError> {
+        match self.addr_limit() {
+            Some(limit) if addr >= limit => Err(crate::Error::new(E::AddressOutOfBounds {
+                pc: self.pc,
+                addr: Value::try_from(addr).unwrap_or(Value::MAX),
+                limit,
+            })),
+            _ => {
+                self.store(addr, val);
+                Ok(())
+            }
+        }
+    }
+
+    // Write `val` to `addr`, which is within the memory policy.
+    fn store(&mut self, addr: Addr, val: Value) {
+        if let Some(code) = &mut self.code {
+            // the instructions which can cover `addr`
+            let start = addr.saturating_sub(3).min(code.len());
+            let end = addr.saturating_add(1).min(code.len());
+            code[start..end].fill(None);
+        }
+        if let Some((device, offset)) = self.mapped(addr) {
+            device
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .write(offset, val);
+        } else if let Some(cell) = self.mem.get_mut(addr) {
+            *cell = val;
+        } else if let Some(pages) = &mut self.pages {
+            let page = pages
+                .entry(addr / PAGE_SIZE)
+                .or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice());
+            page[addr % PAGE_SIZE] = val;
+        } else {
+            self.resize_mem(addr);
+            self.mem[addr] = val;
+        }
+    }
+
+    fn invalid_address(&self, addr: Value) -> IntcodeError {
+        crate::Error::new(E::InvalidAddress { pc: self.pc, addr })
+    }
+
+    // Number of cells the memory may have, `None` if there is no limit.
+    fn addr_limit(&self) -> Option<Addr> {
+        match self.memory_policy {
+            MemoryPolicy::Grow if self.pages.is_some() => None,
+            MemoryPolicy::Grow => Some(MAX_ADDR),
+            MemoryPolicy::Capped(limit) => Some(limit),
+            MemoryPolicy::Fixed => Some(self.mem.len()),
+        }
+    }
+
+    // Address `val` used by the instruction at pc.
+    fn addr(&self, val: Value) -> Result<Addr, IntcodeError> {
+        let addr = Addr::try_from(val).map_err(|_| self.invalid_address(val))?;
+        match self.addr_limit() {
+            Some(limit) if addr >= limit => Err(crate::Error::new(E::AddressOutOfBounds {
+                pc: self.pc,
+                addr: val,
+                limit,
+            })),
+            _ => Ok(addr),
+        }
+    }
+
+    // Address `val` relative to the relative base.
+    fn relative_addr(&self, val: Value) -> Result<Addr, IntcodeError> {
+        match self.relative_base.checked_add(val) {
+            Some(addr) => self.addr(addr),
+            None => Err(self.invalid_address(val)),
+        }
+    }
+
+    fn addr_fetch(&mut self, word: Value, am: Value, val: Value) -> Result<Addr, IntcodeError> {
+        match am {
+            0 => self.addr(val),
+            1 => self.addr(val),
+            2 => self.relative_addr(val),
+            _ => Err(self.invalid_mode(word)),
+        }
+    }
+
+    fn fetch(&mut self, word: Value, am: Value, val: Value) -> Result<Value, IntcodeError> {
+        match am {
+            0 => Ok(self.peek(self.addr(val)?)),
+            1 => Ok(val),
+            2 => Ok(self.peek(self.relative_addr(val)?)),
+            _ => Err(self.invalid_mode(word)),
+        }
+    }
+
+    fn invalid_mode(&self, word: Value) -> IntcodeError {
+        crate::Error::new(E::InvalidMode { pc: self.pc, word })
+    }
+
+    // The instruction word at `addr` and the instruction with its operands.
+    fn decode(&mut self, addr: Addr) -> Result<(Value, Instruction), IntcodeError> {
+        // what a device gives can change without a write, it isn't cached
+        let cacheable = addr < self.mem.len() && !self.maps(addr..addr.saturating_add(4));
+        let cached = match &mut self.code {
+            Some(code) if cacheable => {
+                // only as far as instructions are executed
+                if code.len() <= addr {
+                    code.resize(addr + 1, None);
+                }
+                Some(code[addr])
+            }
+            _ => None,
+        };
+        let encoded = match cached {
+            Some(Some(encoded)) => encoded,
+            Some(None) => {
+                let encoded = self.encoded(addr)?;
+                if let Some(entry) = self.code.as_mut().and_then(|code| code.get_mut(addr)) {
+                    *entry = Some(encoded);
+                }
+                encoded
+            }
+            None => self.encoded(addr)?,
+        };
+        Ok((encoded.word, self.resolve(&encoded)?))
+    }
+
+    // Split the instruction at `addr` into its parts, the opcode is
+    // checked, the modes when the operands are fetched. Only the cells of
+    // the instruction are read, the ones after it may be a device.
+    fn encoded(&mut self, addr: Addr) -> Result<Encoded, IntcodeError> {
+        let word = self.peek(addr);
+        let opcode = word % 100;
+        let len = match opcode {
+            1 | 2 | 7 | 8 => 3,
+            5 | 6 => 2,
+            3 | 4 | 9 => 1,
+            _ => 0,
+        };
+        let mut raw = [0; 3];
+        for (idx, operand) in raw.iter_mut().enumerate().take(len) {
+            *operand = self.peek(addr + 1 + idx);
+        }
+        match opcode {
+            1..=9 | 99 => Ok(Encoded {
+                word,
+                raw,
+                opcode: opcode as u8,
+                modes: [100, 1000, 10000].map(|digit| ((word / digit) % 10) as u8),
+            }),
+            _ => {
+                let pc = self.pc;
+                Err(crate::Error::new(E::InvalidOpcode { pc, opcode, word }))
+            }
+        }
+    }
+
+    // Fetch the operands of `encoded`.
+    fn resolve(&mut self, encoded: &Encoded) -> Result<Instruction, IntcodeError> {
+        let Encoded {
+            word,
+            raw: [r1, r2, rd],
+            opcode,
+            modes,
+        } = *encoded;
+        let [m1, m2, md] = modes.map(Value::from);
+        Ok(match opcode {
+            1 => Instruction::Add(
+                self.addr_fetch(word, md, rd)?,
+                self.fetch(word, m1, r1)?,
+                self.fetch(word, m2, r2)?,
+            ),
+            2 => Instruction::Mul(
+                self.addr_fetch(word, md, rd)?,
+                self.fetch(word, m1, r1)?,
+                self.fetch(word, m2, r2)?,
+            ),
+            3 => Instruction::Get(self.addr_fetch(word, m1, r1)?),
+            4 => Instruction::Put(self.fetch(word, m1, r1)?),
+            5 => Instruction::Jpt(self.fetch(word, m1, r1)?, self.fetch(word, m2, r2)?),
+            6 => Instruction::Jpf(self.fetch(word, m1, r1)?, self.fetch(word, m2, r2)?),
+            7 => Instruction::Lt(
+                self.addr_fetch(word, md, rd)?,
+                self.fetch(word, m1, r1)?,
+                self.fetch(word, m2, r2)?,
+            ),
+            8 => Instruction::Eq(
+                self.addr_fetch(word, md, rd)?,
+                self.fetch(word, m1, r1)?,
+                self.fetch(word, m2, r2)?,
+            ),
+            9 => Instruction::Rbo(self.fetch(word, m1, r1)?),
+            _ => Instruction::Halt,
+        })
+    }
+
+    /// Run until the machine needs more than `input` or halts, returns the
+    /// values output on the way. A malformed program stops the machine at
+    /// the faulting instruction with an error.
+    pub fn compute(
+        &mut self,
+        input: std::slice::Iter<'_, Value>,
+    ) -> Result<(StopReason, Vec<Value>), IntcodeError> {
+        let mut output = Vec::new();
+        let reason = self.compute_into(input, &mut output)?;
+        Ok((reason, output))
+    }
+
+    /// Same as `compute`, but the output is written to `output` which is
+    /// cleared first. Lets callers resuming the machine over and over reuse
+    /// one buffer instead of allocating a new one per call.
+    pub fn compute_into(
+        &mut self,
+        mut input: std::slice::Iter<'_, Value>,
+        output: &mut Vec<Value>,
+    ) -> Result<StopReason, IntcodeError> {
+        output.clear();
+        self.compute_io(&mut input, output)
+    }
+
+    /// Run until the machine halts or `input` runs dry, the output goes to
+    /// `output`.
+    pub fn compute_io<I: InputSource, O: OutputSink>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<StopReason, IntcodeError> {
+        self.run_configured::<false, _, _, _>(input, output, &mut ())
+            .map(Exit::stop_reason)
+    }
+
+    /// Run until the machine outputs a value or stops. The machine resumes
+    /// after the output on the next run.
+    pub fn run_until_output<I: InputSource>(
+        &mut self,
+        input: &mut I,
+    ) -> Result<Exit, IntcodeError> {
+        self.run_configured::<true, _, _, _>(input, &mut |_| {}, &mut ())
+    }
+
+    /// Iterator over the output values of the machine, computed as they
+    /// are taken. It ends when the machine stops, see
+    /// [`Outputs::stop_reason`], or after an error.
+    pub fn outputs<I: InputSource>(&mut self, input: I) -> Outputs<'_, I> {
+        Outputs {
+            iss: self,
+            input,
+            stop_reason: None,
+            failed: false,
+        }
+    }
+
+    /// Same as `compute`, but with checked arithmetic: an Add, Mul or
+    /// relative base update overflowing a `Value` stops the machine at the
+    /// instruction with `E::ArithmeticOverflow`, in debug and release builds
+    /// alike.
+    pub fn try_compute(
+        &mut self,
+        input: std::slice::Iter<'_, Value>,
+    ) -> Result<(StopReason, Vec<Value>), IntcodeError> {
+        let mut output = Vec::new();
+        let reason = self.try_compute_into(input, &mut output)?;
+        Ok((reason, output))
+    }
+
+    /// Checked arithmetic version of `compute_into`, see `try_compute`.
+    pub fn try_compute_into(
+        &mut self,
+        mut input: std::slice::Iter<'_, Value>,
+        output: &mut Vec<Value>,
+    ) -> Result<StopReason, IntcodeError> {
+        output.clear();
+        self.try_compute_io(&mut input, output)
+    }
+
+    /// Run the machine on its own thread with input from `input` and output
+    /// to `output`, until it halts, all senders of `input` are gone or it
+    /// fails. Joining the thread gives back how the machine stopped, the
+    /// machine and `input` with the values it didn't consume.
+    pub fn spawn(
+        mut self,
+        mut input: mpsc::Receiver<Value>,
+        mut output: mpsc::Sender<Value>,
+    ) -> Spawned {
+        std::thread::spawn(move || {
+            let reason = self.compute_io(&mut input, &mut output);
+            (reason, self, input)
+        })
+    }
+
+    /// Checked arithmetic version of `compute_io`, see `try_compute`.
+    pub fn try_compute_io<I: InputSource, O: OutputSink>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<StopReason, IntcodeError> {
+        self.run::<true, false, _, _, _>(input, output, &mut ())
+            .map(Exit::stop_reason)
+    }
+
+    /// Same as `compute`, every instruction is passed to `tracer` before it
+    /// is executed.
+    pub fn compute_traced<T: Tracer>(
+        &mut self,
+        mut input: std::slice::Iter<'_, Value>,
+        tracer: &mut T,
+    ) -> Result<(StopReason, Vec<Value>), IntcodeError> {
+        let mut output = Vec::new();
+        let exit = self.run_configured::<false, _, _, _>(&mut input, &mut output, tracer)?;
+        Ok((exit.stop_reason(), output))
+    }
+
+    // Run with checked arithmetic if the machine is configured to.
+    fn run_configured<const YIELD: bool, I: InputSource, O: OutputSink, T: Tracer>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+        tracer: &mut T,
+    ) -> Result<Exit, IntcodeError> {
+        if self.checked {
+            self.run::<true, YIELD, _, _, _>(input, output, tracer)
+        } else {
+            self.run::<false, YIELD, _, _, _>(input, output, tracer)
+        }
+    }
+
+    // Yielding runs return on the first output instead of putting it to
+    // `output`.
+    fn run<const CHECKED: bool, const YIELD: bool, I: InputSource, O: OutputSink, T: Tracer>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+        tracer: &mut T,
+    ) -> Result<Exit, IntcodeError> {
+        enum IssOp {
+            Step(Addr),
+            Jump(Addr),
+            Halt,
+        }
+
+        let limit = self.step_limit.unwrap_or(u64::MAX);
+        let mut steps = 0;
+        Ok(loop {
+            if steps == limit {
+                break Exit::Stop(StopReason::LimitExceeded);
+            }
+            steps += 1;
+            let pc = self.pc;
+            let (word, insn) = self.decode(pc)?;
+            tracer.step(pc, word, &insn);
+            let overflow = || crate::Error::new(E::ArithmeticOverflow { pc });
+            let iss_op = match insn {
+                Instruction::Add(d, op1, op2) => {
+                    let sum = if CHECKED {
+                        op1.checked_add(op2).ok_or_else(overflow)?
+                    } else {
+                        op1 + op2
+                    };
+                    self.store(d, sum);
+                    IssOp::Step(4)
+                }
+                Instruction::Mul(d, op1, op2) => {
+                    let product = if CHECKED {
+                        op1.checked_mul(op2).ok_or_else(overflow)?
+                    } else {
+                        op1 * op2
+                    };
+                    self.store(d, product);
+                    IssOp::Step(4)
+                }
+                Instruction::Get(d) => {
+                    let default = match self.input_policy {
+                        InputPolicy::Suspend => None,
+                        InputPolicy::Default(value) => Some(value),
+                    };
+                    if let Some(i) = input.next_input().or(default) {
+                        self.store(d, i);
+                        IssOp::Step(2)
+                    } else {
+                        break Exit::Stop(StopReason::NeedInput);
+                    }
+                }
+                Instruction::Put(op1) if YIELD => {
+                    self.pc += 2;
+                    break Exit::Output(op1);
+                }
+                Instruction::Put(op1) => {
+                    output.put(op1);
+                    IssOp::Step(2)
+                }
+                Instruction::Jpt(op1, d) => {
+                    if op1 != 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Jpf(op1, d) => {
+                    if op1 == 0 {
+                        IssOp::Jump(self.addr(d)?)
+                    } else {
+                        IssOp::Step(3)
+                    }
+                }
+                Instruction::Lt(d, op1, op2) => {
+                    self.store(d, (op1 < op2) as Value);
+                    IssOp::Step(4)
+                }
+                Instruction::Eq(d, op1, op2) => {
+                    self.store(d, (op1 == op2) as Value);
+                    IssOp::Step(4)
+                }
+                Instruction::Rbo(op1) => {
+                    self.relative_base = if CHECKED {
+                        self.relative_base.checked_add(op1).ok_or_else(overflow)?
+                    } else {
+                        self.relative_base + op1
+                    };
+                    IssOp::Step(2)
+                }
+                Instruction::Halt => IssOp::Halt,
+            };
+
+            match iss_op {
+                IssOp::Step(len) => self.pc += len,
+                IssOp::Jump(addr) => self.pc = addr,
+                IssOp::Halt => break Exit::Stop(StopReason::ProgramHalt),
+            }
+        })
+    }
+}
+
+/// How a run returned, see [`IntcodeISS::run_until_output`].
+#[derive(PartialEq, Debug)]
+pub enum Exit {
+    Output(Value),
+    Stop(StopReason),
+}
+
+impl Exit {
+    fn stop_reason(self) -> StopReason {
+        match self {
+            Exit::Stop(reason) => reason,
+            Exit::Output(_) => unreachable!("only yielding runs return on output"),
+        }
+    }
+}
+
+/// Builder of a machine, the patches of its program and the options of
+/// the `with_*` methods of [`IntcodeISS`] in one chain:
+///
+/// ```text
+/// let iss = IntcodeBuilder::new(&prog).poke(1, 12).poke(2, 2).step_limit(1000).build()?;
+/// ```
+pub struct IntcodeBuilder<'a> {
+    prog: &'a IntcodeProgram,
+    patches: Vec<(Addr, Value)>,
+    step_limit: Option<u64>,
+    sparse: bool,
+    backend: Backend,
+    input_policy: InputPolicy,
+    memory_policy: MemoryPolicy,
+    checked: bool,
+    mmio: Vec<Mapping>,
+}
+
+impl<'a> IntcodeBuilder<'a> {
+    pub fn new(prog: &'a IntcodeProgram) -> IntcodeBuilder<'a> {
+        IntcodeBuilder {
+            prog,
+            patches: Vec::new(),
+            step_limit: None,
+            sparse: false,
+            backend: Backend::DecodeCache,
+            input_policy: InputPolicy::Suspend,
+            memory_policy: MemoryPolicy::Grow,
+            checked: false,
+            mmio: Vec::new(),
+        }
+    }
+
+    /// Patch the cell at `addr` of the program, see
+    /// [`IntcodeProgram::patched`].
+    pub fn poke(mut self, addr: Addr, value: Value) -> IntcodeBuilder<'a> {
+        self.patches.push((addr, value));
+        self
+    }
+
+    /// See [`IntcodeISS::with_step_limit`].
+    pub fn step_limit(mut self, limit: u64) -> IntcodeBuilder<'a> {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    /// See [`IntcodeISS::with_sparse_memory`].
+    pub fn sparse_memory(mut self) -> IntcodeBuilder<'a> {
+        self.sparse = true;
+        self
+    }
+
+    /// See [`IntcodeISS::with_backend`].
+    pub fn backend(mut self, backend: Backend) -> IntcodeBuilder<'a> {
+        self.backend = backend;
+        self
+    }
+
+    /// See [`IntcodeISS::with_input_policy`].
+    pub fn input_policy(mut self, policy: InputPolicy) -> IntcodeBuilder<'a> {
+        self.input_policy = policy;
+        self
+    }
+
+    /// See [`IntcodeISS::with_memory_policy`].
+    pub fn memory_policy(mut self, policy: MemoryPolicy) -> IntcodeBuilder<'a> {
+        self.memory_policy = policy;
+        self
+    }
+
+    /// See [`IntcodeISS::with_checked_arithmetic`].
+    pub fn checked_arithmetic(mut self) -> IntcodeBuilder<'a> {
+        self.checked = true;
+        self
+    }
+
+    /// See [`IntcodeISS::with_mmio`].
+    pub fn mmio(mut self, region: Range<Addr>, device: Arc<Mutex<dyn Mmio>>) -> IntcodeBuilder<'a> {
+        self.mmio.push((region, device));
+        self
+    }
+
+    /// The machine, fails if a patch is beyond the program.
+    pub fn build(self) -> Result<IntcodeISS, IntcodeError> {
+        let mut iss = IntcodeISS::new(&self.prog.patched(&self.patches)?)
+            .with_backend(self.backend)
+            .with_input_policy(self.input_policy)
+            .with_memory_policy(self.memory_policy);
+        if let Some(limit) = self.step_limit {
+            iss = iss.with_step_limit(limit);
+        }
+        if self.sparse {
+            iss = iss.with_sparse_memory();
+        }
+        if self.checked {
+            iss = iss.with_checked_arithmetic();
+        }
+        for (region, device) in self.mmio {
+            iss = iss.with_mmio(region, device);
+        }
+        Ok(iss)
+    }
+}
+
+/// Output values of a machine, see [`IntcodeISS::outputs`].
+pub struct Outputs<'a, I> {
+    iss: &'a mut IntcodeISS,
+    input: I,
+    stop_reason: Option<StopReason>,
+    failed: bool,
+}
+
+impl<I> Outputs<'_, I> {
+    /// Why the machine stopped, `None` while it may output more.
+    pub fn stop_reason(&self) -> Option<&StopReason> {
+        self.stop_reason.as_ref()
+    }
+}
+
+impl<I: InputSource> Iterator for Outputs<'_, I> {
+    type Item = Result<Value, IntcodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stop_reason.is_some() || self.failed {
+            return None;
+        }
+        match self.iss.run_until_output(&mut self.input) {
+            Ok(Exit::Output(value)) => Some(Ok(value)),
+            Ok(Exit::Stop(reason)) => {
+                self.stop_reason = Some(reason);
+                None
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Observer of the executed instructions, e.g. to log them or collect
+/// statistics, see [`IntcodeISS::compute_traced`]. It gets the pc, the
+/// first cell (opcode and modes) and the decoded instruction before the
+/// instruction is executed. The no-op tracer `()` is compiled out of the
+/// plain runs.
+pub trait Tracer {
+    fn step(&mut self, pc: Addr, word: Value, insn: &Instruction);
+}
+
+impl Tracer for () {
+    #[inline(always)]
+    fn step(&mut self, _pc: Addr, _word: Value, _insn: &Instruction) {}
+}
+
+impl<F: FnMut(Addr, &Instruction)> Tracer for F {
+    fn step(&mut self, pc: Addr, _word: Value, insn: &Instruction) {
+        self(pc, insn)
+    }
+}
+
+/// Fingerprint of the instructions a machine executed: their number and an
+/// FNV-1a hash over the pc and first cell of each one, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceHash {
+    pub steps: u64,
+    pub hash: u64,
+}
+
+impl TraceHash {
+    pub fn new() -> TraceHash {
+        TraceHash {
+            steps: 0,
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+}
+
+impl Default for TraceHash {
+    fn default() -> TraceHash {
+        TraceHash::new()
+    }
+}
+
+impl Tracer for TraceHash {
+    fn step(&mut self, pc: Addr, word: Value, _insn: &Instruction) {
+        self.steps += 1;
+        let (pc, insn) = ((pc as u64).to_le_bytes(), word.to_le_bytes());
+        self.hash = pc.iter().chain(&insn).fold(self.hash, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    }
+}
+
+/// Execution counts of a run, per instruction and per address, to find
+/// where a program spends its time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    /// Executions per mnemonic.
+    pub instructions: BTreeMap<&'static str, u64>,
+    // executions and size of the instruction at each address
+    addrs: Vec<(u64, Addr)>,
+}
+
+/// Straight run of instructions which were executed equally often, a basic
+/// block as far as the profile can tell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Address of the first instruction.
+    pub start: Addr,
+    /// Address after the last instruction.
+    pub end: Addr,
+    pub instructions: usize,
+    /// Executions of each instruction.
+    pub executions: u64,
+}
+
+impl Region {
+    /// Instructions executed in the region.
+    pub fn steps(&self) -> u64 {
+        self.executions * self.instructions as u64
+    }
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.instructions.values().sum()
+    }
+
+    /// Executions of the instruction at `addr`.
+    pub fn executions(&self, addr: Addr) -> u64 {
+        self.addrs.get(addr).map_or(0, |&(count, _)| count)
+    }
+
+    /// The `count` regions with the most steps, most first.
+    pub fn hot_regions(&self, count: usize) -> Vec<Region> {
+        let mut regions: Vec<Region> = Vec::new();
+        let mut addr = 0;
+        while addr < self.addrs.len() {
+            let (executions, size) = self.addrs[addr];
+            if executions == 0 {
+                addr += 1;
+                continue;
+            }
+            match regions.last_mut() {
+                Some(last) if last.end == addr && last.executions == executions => {
+                    last.end += size;
+                    last.instructions += 1;
+                }
+                _ => regions.push(Region {
+                    start: addr,
+                    end: addr + size,
+                    instructions: 1,
+                    executions,
+                }),
+            }
+            addr += size;
+        }
+        regions.sort_by_key(|region| (std::cmp::Reverse(region.steps()), region.start));
+        regions.truncate(count);
+        regions
+    }
+}
+
+impl Tracer for Profile {
+    fn step(&mut self, pc: Addr, _word: Value, insn: &Instruction) {
+        *self.instructions.entry(insn.mnemonic()).or_insert(0) += 1;
+        if pc >= self.addrs.len() {
+            self.addrs.resize(pc + 1, (0, 0));
+        }
+        self.addrs[pc] = (self.addrs[pc].0 + 1, insn.size());
+    }
+}
+
+/// Captured machine state (memory, pc and relative base), which can be
+/// restored into a machine later on or stored as text.
+///
+/// The text form is a header line `pc=<pc> rb=<relative base>` followed by
+/// the memory as comma separated values, trailing zero cells are omitted.
+/// Snapshots of sparse machines have a `pages=<n>` field in the header and
+/// the pages follow as lines `<address>:<cells>`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    mem: Vec<Value>,
+    pc: Addr,
+    // named like in the text and JSON forms
+    #[cfg_attr(feature = "serde", serde(rename = "rb"))]
+    relative_base: Value,
+    // first address and cells of the pages of a sparse machine, sorted
+    pages: Option<Vec<(Addr, Vec<Value>)>>,
+}
+
+impl IntcodeISS {
+    pub fn snapshot(&self) -> Snapshot {
+        let pages = self.pages.as_ref().map(|pages| {
+            let mut pages: Vec<_> = pages
+                .iter()
+                .filter_map(|(&nr, page)| {
+                    // without trailing zero cells like the memory
+                    let len = page.iter().rposition(|&v| v != 0)? + 1;
+                    Some((nr * PAGE_SIZE, page[..len].to_vec()))
+                })
+                .collect();
+            pages.sort_unstable();
+            pages
+        });
+        Snapshot {
+            mem: self.mem.clone(),
+            pc: self.pc,
+            relative_base: self.relative_base,
+            pages,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.mem.clone_from(&snapshot.mem);
+        if let Some(code) = &mut self.code {
+            code.clear();
+        }
+        self.pc = snapshot.pc;
+        self.relative_base = snapshot.relative_base;
+        // straight into the pages, neither devices nor the memory below
+        // the pages see a restore
+        self.pages = snapshot.pages.as_ref().map(|pages| {
+            let mut restored = Pages::new();
+            for (addr, cells) in pages {
+                for (offset, &val) in cells.iter().enumerate() {
+                    let addr = addr + offset;
+                    let page = restored
+                        .entry(addr / PAGE_SIZE)
+                        .or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice());
+                    page[addr % PAGE_SIZE] = val;
+                }
+            }
+            restored
+        });
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot) -> IntcodeISS {
+        let mut iss = IntcodeISS {
+            mem: Vec::new(),
+            pc: 0,
+            relative_base: 0,
+            step_limit: None,
+            input_policy: InputPolicy::Suspend,
+            memory_policy: MemoryPolicy::Grow,
+            checked: false,
+            code: Some(Vec::new()),
+            pages: None,
+            mmio: Vec::new(),
+        };
+        iss.restore(snapshot);
+        iss
+    }
+
+    /// A machine continuing from the current state independently of this
+    /// one, e.g. to look ahead. Cheaper than going through a [`Snapshot`],
+    /// the state is copied once.
+    pub fn fork(&self) -> IntcodeISS {
+        self.clone()
+    }
+}
+
+impl Snapshot {
+    // The memory without trailing zero cells, memory not covered reads as
+    // zero anyway.
+    fn used_mem(&self) -> &[Value] {
+        let len = self.mem.iter().rposition(|&v| v != 0).map_or(0, |p| p + 1);
+        &self.mem[..len]
+    }
+
+    /// The snapshot as JSON object `{"pc": 2, "rb": -3, "mem": [109, -3]}`,
+    /// trailing zero cells are omitted like in the text form. The pages of
+    /// a sparse machine are in `"pages": [[4096, [1, 2]]]`.
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("pc", Json::Int(self.pc as i64)),
+            ("rb", Json::Int(self.relative_base)),
+            ("mem", Json::from(self.used_mem().to_vec())),
+        ];
+        if let Some(pages) = &self.pages {
+            let pages = pages
+                .iter()
+                .map(|(addr, cells)| {
+                    Json::Array(vec![Json::Int(*addr as i64), Json::from(cells.clone())])
+                })
+                .collect();
+            fields.push(("pages", Json::Array(pages)));
+        }
+        Json::object(fields)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Snapshot, crate::Error<E>> {
+        let invalid = || crate::Error::new(E::InvalidSnapshot);
+        let field = |name| json.get(name).ok_or_else(invalid);
+        let relative_base = field("rb")?.as_i64().ok_or_else(invalid)?;
+        let cells = |json: &Json| {
+            json.as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|v| v.as_i64().ok_or_else(invalid))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let addr = |json: &Json| {
+            let addr = json.as_i64().ok_or_else(invalid)?;
+            Addr::try_from(addr).map_err(|_| invalid())
+        };
+        let pages = match json.get("pages") {
+            None => None,
+            Some(pages) => Some(
+                pages
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|page| match page.as_array() {
+                        Some([start, page]) => Ok((addr(start)?, cells(page)?)),
+                        _ => Err(invalid()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+        Ok(Snapshot {
+            mem: cells(field("mem")?)?,
+            pc: addr(field("pc")?)?,
+            relative_base,
+            pages,
+        })
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pc={} rb={}", self.pc, self.relative_base)?;
+        if let Some(pages) = &self.pages {
+            write!(f, " pages={}", pages.len())?;
+        }
+        let cells = |cells: &[Value]| {
+            let cells: Vec<_> = cells.iter().map(|v| v.to_string()).collect();
+            cells.join(",")
+        };
+        write!(f, "\n{}", cells(self.used_mem()))?;
+        for (addr, page) in self.pages.iter().flatten() {
+            write!(f, "\n{}:{}", addr, cells(page))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = crate::Error<E>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::Error::new(E::InvalidSnapshot);
+
+        let mut lines = s.lines();
+        let header = lines.next().ok_or_else(invalid)?;
+        let mut pc = None;
+        let mut relative_base = None;
+        let mut page_count = None;
+        for field in header.split_ascii_whitespace() {
+            match field.split_at(field.find('=').ok_or_else(invalid)?) {
+                ("pc", v) => pc = v[1..].parse::<Addr>().ok(),
+                ("rb", v) => relative_base = v[1..].parse::<Value>().ok(),
+                ("pages", v) => page_count = Some(v[1..].parse::<usize>().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let cells = |cells: &str| {
+            if cells.is_empty() {
+                return Ok(Vec::new());
+            }
+            cells
+                .split(',')
+                .map(|v| v.trim().parse::<Value>().map_err(|_| invalid()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mem = cells(lines.next().map_or("", |l| l.trim()))?;
+        let pages = match page_count {
+            None => None,
+            Some(count) => Some(
+                lines
+                    .by_ref()
+                    .take(count)
+                    .map(|line| {
+                        let (addr, page) = line.split_once(':').ok_or_else(invalid)?;
+                        let addr = addr.trim().parse::<Addr>().map_err(|_| invalid())?;
+                        Ok((addr, cells(page.trim())?))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+        if pages.as_ref().map_or(0, Vec::len) != page_count.unwrap_or(0) {
+            return Err(invalid());
+        }
+
+        Ok(Snapshot {
+            mem,
+            pc: pc.ok_or_else(invalid)?,
+            relative_base: relative_base.ok_or_else(invalid)?,
+            pages,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn program(cells: &[Value]) -> IntcodeProgram {
+        IntcodeProgram::try_from(cells).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        // read a value, output it doubled and loop
+        let prog = vec![3, 9, 1002, 9, 2, 9, 4, 9, 1105, 1, 0];
+        let mut iss = IntcodeISS::new(&program(&prog));
+        assert_eq!(
+            iss.compute([21].iter()).unwrap(),
+            (StopReason::NeedInput, vec![42])
+        );
+
+        let snapshot = iss.snapshot();
+        assert_eq!(
+            iss.compute([1].iter()).unwrap(),
+            (StopReason::NeedInput, vec![2])
+        );
+
+        iss.restore(&snapshot);
+        assert_eq!(
+            iss.compute([5].iter()).unwrap(),
+            (StopReason::NeedInput, vec![10])
+        );
+
+        let mut resumed = IntcodeISS::from_snapshot(&snapshot);
+        assert_eq!(
+            resumed.compute([7].iter()).unwrap(),
+            (StopReason::NeedInput, vec![14])
+        );
+
+        // forks don't disturb each other
+        let mut fork = iss.fork();
+        assert_eq!(
+            fork.compute([3].iter()).unwrap(),
+            (StopReason::NeedInput, vec![6])
+        );
+        assert_eq!(fork.peek(9), 6);
+        assert_eq!(iss.peek(9), 10);
+        iss.restore(&fork.snapshot());
+        assert_eq!(iss.snapshot(), fork.snapshot());
+    }
+
+    #[test]
+    fn test_snapshot_text() {
+        let prog = vec![109, -3, 3, 9, 4, 9, 99, 0, 0, 0];
+        let mut iss = IntcodeISS::new(&program(&prog));
+        iss.poke(2000, 0).unwrap(); // grows memory with zeros
+        assert_eq!(iss.compute([].iter()).unwrap().0, StopReason::NeedInput);
+
+        let snapshot = iss.snapshot();
+        let text = snapshot.to_string();
+        assert_eq!(text, "pc=2 rb=-3\n109,-3,3,9,4,9,99");
+        let parsed = text.parse::<Snapshot>().unwrap();
+
+        let mut resumed = IntcodeISS::from_snapshot(&parsed);
+        assert_eq!(
+            resumed.compute([8].iter()).unwrap(),
+            (StopReason::ProgramHalt, vec![8])
+        );
+
+        assert!("pc=2\n1,2".parse::<Snapshot>().is_err());
+
+        let json = snapshot.to_json();
+        assert_eq!(
+            json.to_string(),
+            "{\"pc\": 2, \"rb\": -3, \"mem\": [109, -3, 3, 9, 4, 9, 99]}"
+        );
+        let parsed = Snapshot::from_json(&crate::json::parse(&json.to_string()).unwrap()).unwrap();
+        assert_eq!(parsed, text.parse::<Snapshot>().unwrap());
+        assert!(Snapshot::from_json(
+            &crate::json::parse("{\"pc\": -1, \"rb\": 0, \"mem\": []}").unwrap()
+        )
+        .is_err());
+        assert!("pc=2 rb=0\n1,x".parse::<Snapshot>().is_err());
+        assert!("pc=2 rb=0 foo=1\n1".parse::<Snapshot>().is_err());
+        assert_eq!("pc=0 rb=0".parse::<Snapshot>().unwrap().mem, vec![]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        // there is no serde format among the dependencies, check that the
+        // machine state can go through any of them
+        fn state<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+        state::<IntcodeISS>();
+        state::<Snapshot>();
+        state::<StopReason>();
+    }
+
+    #[test]
+    fn test_binary() {
+        let prog = vec![1, -1, 127, -128];
+        let bytes = encode_binary(&prog);
+        assert_eq!(&bytes[..5], b"ICB1\x01");
+        assert_eq!(&bytes[5..13], &4u64.to_le_bytes());
+        assert_eq!(&bytes[13..], &[1, 0xff, 0x7f, 0x80]);
+        assert_eq!(decode_binary(&bytes).unwrap(), prog);
+
+        for (cells, width) in [
+            (vec![128], 2),
+            (vec![-32769], 4),
+            (vec![Value::MAX, Value::MIN], 8),
+            (vec![], 1),
+        ] {
+            let bytes = encode_binary(&cells);
+            assert_eq!(bytes[4], width);
+            assert_eq!(decode_binary(&bytes).unwrap(), cells);
+        }
+
+        assert!(decode_binary(b"ICB1").is_err());
+        assert!(decode_binary(&bytes[..bytes.len() - 1]).is_err());
+        let mut bad_width = bytes.clone();
+        bad_width[4] = 3;
+        assert!(decode_binary(&bad_width).is_err());
+    }
+
+    #[test]
+    fn test_from_path() {
+        let dir = std::env::temp_dir();
+        let (text, binary) = (dir.join("aoc19-prog.txt"), dir.join("aoc19-prog.icb"));
+        std::fs::write(&text, "1,0,0,3,99\n").unwrap();
+        let prog = IntcodeProgram::from_path(text.to_str().unwrap()).unwrap();
+        save_binary(binary.to_str().unwrap(), prog.cells()).unwrap();
+        assert_eq!(
+            IntcodeProgram::from_path(binary.to_str().unwrap()).unwrap(),
+            prog
+        );
+        std::fs::remove_file(text).unwrap();
+        std::fs::remove_file(binary).unwrap();
+    }
+
+    #[test]
+    fn test_program() {
+        let prog: IntcodeProgram = "1,0,0,3,99\n".parse().unwrap();
+        assert_eq!(prog.len(), 5);
+        assert_eq!(prog.cell_width(), 1);
+        assert_eq!(prog.to_string(), "1,0,0,3,99");
+        assert_eq!(prog.to_string().parse::<IntcodeProgram>().unwrap(), prog);
+        assert!("".parse::<IntcodeProgram>().is_err());
+        assert!(IntcodeProgram::new(vec![]).is_err());
+
+        let invalid = |input: &str| match parse_cells(input).unwrap_err().err {
+            E::InvalidCell {
+                index,
+                token,
+                line,
+                column,
+            } => (index, token, line, column),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(invalid("1,2,x,4"), (2, "x".to_string(), 1, 5));
+        assert_eq!(invalid("1,2,\n 3, 4y"), (3, "4y".to_string(), 2, 5));
+        assert_eq!(invalid("1,2,"), (2, "".to_string(), 1, 5));
+        assert_eq!(invalid("1,,2"), (1, "".to_string(), 1, 3));
+        assert_eq!(invalid(",1"), (0, "".to_string(), 1, 1));
+        assert_eq!(invalid("1 # one\n2x # two"), (1, "2x".to_string(), 2, 1));
+
+        // comments and free layout
+        let text = "# adds 1 and 2\n1101, 1, 2, 9   # [9] = 1 + 2\n\n  4 9\t99,\n0 #";
+        assert_eq!(parse_cells(text).unwrap(), vec![1101, 1, 2, 9, 4, 9, 99, 0]);
+        assert_eq!(parse_cells("# nothing\n  \n").unwrap(), vec![]);
+        assert!(parse_program("# nothing").is_err());
+
+        let patched = prog.patched(&[(1, 12), (2, -2)]).unwrap();
+        assert_eq!(patched.cells(), &[1, 12, -2, 3, 99]);
+        assert_eq!(prog.cells()[1], 0);
+        assert!(prog.patched(&[(5, 1)]).is_err());
+
+        assert_eq!(prog.cells_as::<u8>().unwrap(), vec![1, 0, 0, 3, 99]);
+        assert!(program(&[1, -1]).cells_as::<u32>().is_err());
+    }
+
+    #[test]
+    fn test_compute_into() {
+        // output the input and loop
+        let prog = vec![3, 7, 4, 7, 1105, 1, 0, 0];
+        let mut iss = IntcodeISS::new(&program(&prog));
+        let mut output = vec![99, 99];
+        assert_eq!(
+            iss.compute_into([1, 2].iter(), &mut output).unwrap(),
+            StopReason::NeedInput
+        );
+        assert_eq!(output, vec![1, 2]);
+        let capacity = output.capacity();
+        assert_eq!(
+            iss.compute_into([3].iter(), &mut output).unwrap(),
+            StopReason::NeedInput
+        );
+        assert_eq!(output, vec![3]);
+        assert_eq!(output.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_compute_io() {
+        // the day 7 feedback loop example, the amplifiers are connected by
+        // queues
+        let prog: IntcodeProgram = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,\
+                                    1001,28,-1,28,1005,28,6,99,0,0,5"
+            .parse()
+            .unwrap();
+        let mut amps = vec![IntcodeISS::new(&prog); 5];
+        let mut queues: Vec<VecDeque<Value>> = [9, 8, 7, 6, 5]
+            .iter()
+            .map(|&phase| VecDeque::from(vec![phase]))
+            .collect();
+        queues[0].push_back(0);
+        let mut last = None;
+        while last != Some(StopReason::ProgramHalt) {
+            for idx in 0..5 {
+                let mut input = std::mem::take(&mut queues[idx]);
+                let output = &mut queues[(idx + 1) % 5];
+                last = Some(amps[idx].compute_io(&mut input, output).unwrap());
+            }
+        }
+        assert_eq!(queues[0], VecDeque::from(vec![139629729]));
+
+        // closures and channels
+        let prog = program(&[3, 9, 1002, 9, 2, 9, 4, 9, 1105, 1, 0]);
+        let mut values = vec![1, 2, 3].into_iter();
+        let mut sum = 0;
+        let reason = IntcodeISS::new(&prog)
+            .compute_io(&mut || values.next(), &mut |v| sum += v)
+            .unwrap();
+        assert_eq!((reason, sum), (StopReason::NeedInput, 12));
+
+        let (input, mut rx) = mpsc::channel();
+        let (mut tx, output) = mpsc::channel();
+        let doubler =
+            std::thread::spawn(move || IntcodeISS::new(&prog).compute_io(&mut rx, &mut tx));
+        input.send(21).unwrap();
+        assert_eq!(output.recv().unwrap(), 42);
+        drop(input);
+        assert_eq!(doubler.join().unwrap().unwrap(), StopReason::NeedInput);
+    }
+
+    #[test]
+    fn test_outputs() {
+        // output the input doubled and loop
+        let prog = program(&[3, 9, 1002, 9, 2, 9, 4, 9, 1105, 1, 0]);
+        let mut iss = IntcodeISS::new(&prog);
+        let mut input = [1, 2].iter();
+        assert_eq!(iss.run_until_output(&mut input).unwrap(), Exit::Output(2));
+        assert_eq!(iss.run_until_output(&mut input).unwrap(), Exit::Output(4));
+        assert_eq!(
+            iss.run_until_output(&mut input).unwrap(),
+            Exit::Stop(StopReason::NeedInput)
+        );
+
+        let mut outputs = iss.outputs([3, 4, 5].iter());
+        assert_eq!(outputs.next().unwrap().unwrap(), 6);
+        assert_eq!(outputs.stop_reason(), None);
+        assert_eq!(
+            outputs.by_ref().collect::<Result<Vec<_>, _>>().unwrap(),
+            vec![8, 10]
+        );
+        assert_eq!(outputs.stop_reason(), Some(&StopReason::NeedInput));
+        assert!(outputs.next().is_none());
+
+        let prog = program(&[104, 1, 104, 2, 99]);
+        let mut iss = IntcodeISS::new(&prog);
+        assert_eq!(
+            iss.outputs([].iter())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            iss.run_until_output(&mut [].iter()).unwrap(),
+            Exit::Stop(StopReason::ProgramHalt)
+        );
+    }
+
+    #[test]
+    fn test_spawn() {
+        // add pairs of input values, the last one has no partner
+        let prog = program(&[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+        let (input, rx) = mpsc::channel();
+        let (tx, output) = mpsc::channel();
+        let adder = IntcodeISS::new(&prog).spawn(rx, tx);
+        for value in &[20, 22, 7] {
+            input.send(*value).unwrap();
+        }
+        assert_eq!(output.recv().unwrap(), 42);
+        let (reason, iss, rest) = adder.join().unwrap();
+        assert_eq!(reason.unwrap(), StopReason::ProgramHalt);
+        assert_eq!(iss.memory()[13], 42);
+        assert_eq!(rest.try_iter().collect::<Vec<_>>(), vec![7]);
+        // the sender of the output is gone with the thread
+        assert!(output.recv().is_err());
+    }
+
+    #[test]
+    fn test_compute_traced() {
+        let prog = program(&[3, 7, 4, 7, 1105, 1, 0, 0]);
+        let mut trace = TraceHash::new();
+        let (reason, output) = IntcodeISS::new(&prog)
+            .compute_traced([5].iter(), &mut trace)
+            .unwrap();
+        assert_eq!((reason, output), (StopReason::NeedInput, vec![5]));
+        // in, out, jump and the in waiting for input
+        assert_eq!(trace.steps, 4);
+
+        let mut again = TraceHash::new();
+        IntcodeISS::new(&prog)
+            .compute_traced([6].iter(), &mut again)
+            .unwrap();
+        assert_eq!(again, trace);
+        let mut other = TraceHash::new();
+        IntcodeISS::new(&prog)
+            .compute_traced([].iter(), &mut other)
+            .unwrap();
+        assert_ne!(other, trace);
+
+        // the decoded instructions, the jump target is fetched as well
+        let mut steps = Vec::new();
+        IntcodeISS::new(&prog)
+            .compute_traced([5].iter(), &mut |pc, insn: &Instruction| {
+                steps.push((pc, *insn))
+            })
+            .unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                (0, Instruction::Get(7)),
+                (2, Instruction::Put(5)),
+                (4, Instruction::Jpt(1, 0)),
+                (0, Instruction::Get(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let run = |cells: &[Value]| IntcodeISS::new(&program(cells)).compute([].iter());
+        assert!(matches!(
+            run(&[104, 1, 42, 0]).unwrap_err().err,
+            E::InvalidOpcode {
+                pc: 2,
+                opcode: 42,
+                word: 42
+            }
+        ));
+        assert!(matches!(
+            run(&[304, 1, 99]).unwrap_err().err,
+            E::InvalidMode { pc: 0, word: 304 }
+        ));
+        assert!(matches!(
+            run(&[4, -1, 99]).unwrap_err().err,
+            E::InvalidAddress { pc: 0, addr: -1 }
+        ));
+        assert!(matches!(
+            run(&[109, -5, 204, 1, 99]).unwrap_err().err,
+            E::InvalidAddress { pc: 2, addr: -4 }
+        ));
+        // jump targets are only checked when jumping
+        assert!(run(&[1105, 0, -1, 99]).is_ok());
+        assert!(matches!(
+            run(&[1105, 1, -1, 99]).unwrap_err().err,
+            E::InvalidAddress { pc: 0, addr: -1 }
+        ));
+
+        // the machine stays at the faulting instruction, output before it
+        // is kept
+        let mut iss = IntcodeISS::new(&program(&[104, 7, 42]));
+        let mut output = Vec::new();
+        assert!(iss.compute_into([].iter(), &mut output).is_err());
+        assert_eq!(output, vec![7]);
+        assert!(iss.compute([].iter()).is_err());
+        let mut outputs = iss.outputs([].iter());
+        assert!(outputs.next().unwrap().is_err());
+        assert!(outputs.next().is_none());
+    }
+
+    #[test]
+    fn test_memory_policy() {
+        let run = |cells: &[Value], policy| {
+            let mut iss = IntcodeISS::new(&program(cells)).with_memory_policy(policy);
+            let result = iss.compute([].iter());
+            (result, iss.memory().len())
+        };
+        // writes to 2000
+        let prog = [1101, 1, 2, 2000, 99];
+        let (result, len) = run(&prog, MemoryPolicy::Grow);
+        assert!(result.is_ok() && len > 2000);
+        let (result, len) = run(&prog, MemoryPolicy::Capped(4096));
+        assert!(result.is_ok() && len > 2000);
+        let (result, len) = run(&prog, MemoryPolicy::Capped(2000));
+        assert!(matches!(
+            result.unwrap_err().err,
+            E::AddressOutOfBounds {
+                pc: 0,
+                addr: 2000,
+                limit: 2000
+            }
+        ));
+        assert_eq!(len, 5);
+        let (result, _) = run(&prog, MemoryPolicy::Fixed);
+        assert!(matches!(
+            result.unwrap_err().err,
+            E::AddressOutOfBounds { limit: 5, .. }
+        ));
+
+        // absurd addresses are rejected before the memory grows to them
+        let (result, len) = run(&[4, Value::MAX, 99], MemoryPolicy::Grow);
+        assert!(matches!(
+            result.unwrap_err().err,
+            E::AddressOutOfBounds { pc: 0, .. }
+        ));
+        assert_eq!(len, 3);
+
+        // a growing memory stops at MAX_ADDR, a sparse one has no limit
+        let prog = [1101, 1, 2, MAX_ADDR as Value, 99];
+        let (result, _) = run(&prog, MemoryPolicy::Grow);
+        assert!(matches!(
+            result.unwrap_err().err,
+            E::AddressOutOfBounds {
+                limit: MAX_ADDR,
+                ..
+            }
+        ));
+        let mut iss = IntcodeISS::new(&program(&prog)).with_sparse_memory();
+        assert_eq!(iss.compute([].iter()).unwrap().0, StopReason::ProgramHalt);
+        assert_eq!(iss.peek(MAX_ADDR), 3);
+
+        // pokes are bounded by the policy as well
+        let mut iss = IntcodeISS::new(&program(&[99])).with_memory_policy(MemoryPolicy::Capped(8));
+        iss.poke(7, 1).unwrap();
+        assert!(matches!(
+            iss.poke(8, 1).unwrap_err().err,
+            E::AddressOutOfBounds {
+                addr: 8,
+                limit: 8,
+                ..
+            }
+        ));
+        assert!(IntcodeISS::new(&program(&[99])).poke(Addr::MAX, 1).is_err());
+        let mut iss = IntcodeISS::new(&program(&[99])).with_memory_policy(MemoryPolicy::Fixed);
+        assert!(iss.poke(1, 1).is_err());
+        assert_eq!(iss.memory(), &[99]);
+
+        // the missing operand of an instruction at the end reads as 0
+        let mut iss =
+            IntcodeISS::new(&program(&[1101, 7, 0])).with_memory_policy(MemoryPolicy::Fixed);
+        assert!(matches!(
+            iss.compute([].iter()).unwrap_err().err,
+            E::InvalidOpcode { pc: 4, .. }
+        ));
+        assert_eq!(iss.memory(), &[7, 7, 0]);
+    }
+
+    #[test]
+    fn test_profile() {
+        // count down from 3, the loop body runs three times
+        let prog = program(&[1101, 0, 3, 13, 1001, 13, -1, 13, 1005, 13, 4, 99, 0, 0]);
+        let mut profile = Profile::new();
+        IntcodeISS::new(&prog)
+            .compute_traced([].iter(), &mut profile)
+            .unwrap();
+        assert_eq!(profile.steps(), 8);
+        assert_eq!(profile.instructions["add"], 4);
+        assert_eq!(profile.instructions["jpt"], 3);
+        assert_eq!((profile.executions(4), profile.executions(5)), (3, 0));
+        assert_eq!(
+            profile.hot_regions(2),
+            vec![
+                Region {
+                    start: 4,
+                    end: 11,
+                    instructions: 2,
+                    executions: 3
+                },
+                Region {
+                    start: 0,
+                    end: 4,
+                    instructions: 1,
+                    executions: 1
+                },
+            ]
+        );
+        assert_eq!(profile.hot_regions(5).len(), 3);
+    }
+
+    #[test]
+    fn test_sparse_memory() {
+        // store the input far away, read it back and output it
+        let far = 1 << 40;
+        let prog = program(&[3, far, 4, far, 99]);
+        let mut iss = IntcodeISS::new(&prog).with_sparse_memory();
+        assert_eq!(
+            iss.compute([7].iter()).unwrap(),
+            (StopReason::ProgramHalt, vec![7])
+        );
+        assert_eq!(iss.memory().len(), 5);
+        assert_eq!(iss.peek(far as Addr), 7);
+        assert_eq!(iss.peek(far as Addr + 1), 0);
+        // too far for flat memory
+        assert!(IntcodeISS::new(&prog).compute([7].iter()).is_err());
+
+        let snapshot = iss.snapshot();
+        let text = snapshot.to_string();
+        assert_eq!(
+            text,
+            format!("pc=4 rb=0 pages=1\n3,{0},4,{0},99\n{0}:7", far)
+        );
+        let parsed = text.parse::<Snapshot>().unwrap();
+        assert_eq!(parsed, snapshot);
+        let json = crate::json::parse(&snapshot.to_json().to_string()).unwrap();
+        assert_eq!(Snapshot::from_json(&json).unwrap(), snapshot);
+        let mut resumed = IntcodeISS::from_snapshot(&parsed);
+        assert_eq!(resumed.peek(far as Addr), 7);
+        assert_eq!(resumed.snapshot(), snapshot);
+
+        assert!("pc=0 rb=0 pages=2\n1\n1024:1".parse::<Snapshot>().is_err());
+        assert!("pc=0 rb=0 pages=1\n1\n1024".parse::<Snapshot>().is_err());
+        assert_eq!(
+            "pc=0 rb=0 pages=0\n1".parse::<Snapshot>().unwrap().pages,
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_step_limit() {
+        // loops forever
+        let prog = program(&[1105, 1, 0]);
+        let mut iss = IntcodeISS::new(&prog).with_step_limit(10);
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::LimitExceeded, vec![])
+        );
+
+        // every run gets the full budget and resumes where the last one
+        // stopped
+        let prog = program(&[104, 1, 104, 2, 104, 3, 99]);
+        let mut iss = IntcodeISS::new(&prog).with_step_limit(2);
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::LimitExceeded, vec![1, 2])
+        );
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::ProgramHalt, vec![3])
+        );
+        let mut iss = IntcodeISS::new(&prog).with_step_limit(0);
+        assert_eq!(
+            iss.run_until_output(&mut [].iter()).unwrap(),
+            Exit::Stop(StopReason::LimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_read_input() {
+        // hands out one byte per read, numbers span several buffers
+        struct Trickle<'a>(&'a [u8]);
+        impl std::io::Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(1);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        // echoes its input
+        let prog = program(&[3, 7, 4, 7, 1105, 1, 0, 0]);
+        let mut input = ReadInput::new(Trickle(b" 12,-3\n\n456 ,7,\n"));
+        let mut output = Vec::new();
+        let reason = IntcodeISS::new(&prog)
+            .compute_io(&mut input, &mut output)
+            .unwrap();
+        assert_eq!(
+            (reason, output),
+            (StopReason::NeedInput, vec![12, -3, 456, 7])
+        );
+        assert!(input.take_error().is_none());
+
+        let mut input = ReadInput::new(&b"1 x 2"[..]);
+        let mut output = Vec::new();
+        let mut iss = IntcodeISS::new(&prog);
+        iss.compute_io(&mut input, &mut output).unwrap();
+        assert_eq!(output, vec![1]);
+        assert!(input.take_error().is_some());
+    }
+
+    #[test]
+    fn test_mmio() {
+        // reads give 10 times the offset, reads and writes are recorded
+        #[derive(Default)]
+        struct Device {
+            reads: Vec<Addr>,
+            writes: Vec<(Addr, Value)>,
+        }
+        impl Mmio for Device {
+            fn read(&mut self, offset: Addr) -> Value {
+                self.reads.push(offset);
+                offset as Value * 10
+            }
+            fn write(&mut self, offset: Addr, value: Value) {
+                self.writes.push((offset, value));
+            }
+        }
+
+        let prog = crate::asm::assemble(
+            "add 5, 0, [1001]
+             add [1002], 0, [x]
+             put [x]
+             halt
+             x: .data 0",
+        )
+        .unwrap();
+        let device = Arc::new(Mutex::new(Device::default()));
+        let mut iss = IntcodeISS::new(&prog).with_mmio(1000..1004, device.clone());
+        let mut fork = iss.fork();
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::ProgramHalt, vec![20])
+        );
+        assert_eq!(iss.memory().get(1001).copied().unwrap_or(0), 0);
+        assert_eq!(device.lock().unwrap().writes, vec![(1, 5)]);
+        // every cell is read once, only when an instruction uses it
+        assert_eq!(device.lock().unwrap().reads, vec![2]);
+
+        // forks share the device
+        fork.compute([].iter()).unwrap();
+        assert_eq!(device.lock().unwrap().writes, vec![(1, 5), (1, 5)]);
+
+        // the cells after the halt aren't read
+        let prog = program(&[99]);
+        let device = Arc::new(Mutex::new(Device::default()));
+        let mut iss = IntcodeISS::new(&prog).with_mmio(1..4, device.clone());
+        iss.compute([].iter()).unwrap();
+        assert!(device.lock().unwrap().reads.is_empty());
+
+        // a restore writes the memory, not the device
+        let mut sparse = IntcodeBuilder::new(&prog).sparse_memory().build().unwrap();
+        sparse.poke(1001, 7).unwrap();
+        let snapshot = sparse.snapshot();
+        let device = Arc::new(Mutex::new(Device::default()));
+        let mut iss = IntcodeISS::new(&prog)
+            .with_sparse_memory()
+            .with_mmio(1000..1004, device.clone());
+        iss.restore(&snapshot);
+        assert!(device.lock().unwrap().writes.is_empty());
+        assert_eq!(iss.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_mmio_code() {
+        // `put n; jmp 0` with n counting the reads of the operand
+        struct Code(Value);
+        impl Mmio for Code {
+            fn read(&mut self, offset: Addr) -> Value {
+                match offset {
+                    0 => 104,
+                    1 => {
+                        self.0 += 1;
+                        self.0
+                    }
+                    2 => 1105,
+                    3 => 1,
+                    _ => 0,
+                }
+            }
+            fn write(&mut self, _: Addr, _: Value) {}
+        }
+
+        // jumps into the device and back, the device code isn't cached
+        let prog = program(&[1105, 1, 4, 0, 0, 0, 0, 0, 0]);
+        let mut iss = IntcodeISS::new(&prog)
+            .with_mmio(4..9, Arc::new(Mutex::new(Code(0))))
+            .with_step_limit(6);
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::LimitExceeded, vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        // adds the cells the patched operands point to, loops forever
+        let prog = program(&[1, 0, 0, 0, 1105, 1, 4]);
+        let mut iss = IntcodeBuilder::new(&prog)
+            .poke(1, 5)
+            .poke(2, 6)
+            .step_limit(10)
+            .sparse_memory()
+            .build()
+            .unwrap();
+        assert_eq!(iss.compute([].iter()).unwrap().0, StopReason::LimitExceeded);
+        assert_eq!(iss.peek(0), 1 + 4);
+        assert!(IntcodeBuilder::new(&prog).poke(7, 1).build().is_err());
+    }
+
+    #[test]
+    fn test_input_policy() {
+        // echoes its input
+        let prog = program(&[3, 7, 4, 7, 1105, 1, 0, 0]);
+        let mut iss = IntcodeISS::new(&prog)
+            .with_input_policy(InputPolicy::Default(-1))
+            .with_step_limit(7);
+        assert_eq!(
+            iss.compute([5].iter()).unwrap(),
+            (StopReason::LimitExceeded, vec![5, -1])
+        );
+
+        let mut iss = iss.with_input_policy(InputPolicy::Suspend);
+        assert_eq!(
+            iss.compute([].iter()).unwrap(),
+            (StopReason::NeedInput, vec![-1])
+        );
+    }
+
+    #[test]
+    fn test_decode_cache() {
+        // the operand of the put is counted up in place
+        let prog = crate::asm::assemble(
+            "loop: put 0
+                   add [loop+1], 1, [loop+1]
+                   lt [loop+1], 3, [more]
+                   jpt [more], loop
+                   halt
+             more: .data 0",
+        )
+        .unwrap();
+        for prog in [prog, program(&[3, 0, 4, 0, 99]), program(&[1, 2, 0, 0, 99])] {
+            let input = [7];
+            let expected = IntcodeISS::new(&prog)
+                .with_backend(Backend::Interpreter)
+                .compute(input.iter())
+                .unwrap();
+            let mut iss = IntcodeISS::new(&prog).with_backend(Backend::DecodeCache);
+            assert_eq!(iss.compute(input.iter()).unwrap(), expected);
+        }
+        for case in crate::golden::corpus().unwrap() {
+            let expected = IntcodeISS::new(&case.program)
+                .with_backend(Backend::Interpreter)
+                .compute(case.input.iter());
+            let mut iss = IntcodeISS::new(&case.program).with_backend(Backend::DecodeCache);
+            let result = iss.compute(case.input.iter());
+            assert_eq!(result.ok(), expected.ok(), "{}", case.name);
+        }
+
+        // decoded instructions don't survive a restore
+        let mut iss = IntcodeISS::new(&program(&[104, 1, 99])).with_backend(Backend::DecodeCache);
+        let snapshot = IntcodeISS::new(&program(&[104, 2, 99])).snapshot();
+        assert_eq!(iss.compute([].iter()).unwrap().1, vec![1]);
+        iss.restore(&snapshot);
+        assert_eq!(iss.compute([].iter()).unwrap().1, vec![2]);
+        iss.restore(&IntcodeISS::new(&program(&[5, 0, 0])).snapshot());
+        assert!(iss.compute([].iter()).is_err());
+    }
+
+    fn overflow_pc(result: Result<(StopReason, Vec<Value>), crate::Error<E>>) -> Option<Addr> {
+        match result {
+            Err(crate::Error {
+                err: E::ArithmeticOverflow { pc },
+            }) => Some(pc),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let max = Value::MAX;
+        // in range
+        let prog = vec![1101, max - 1, 1, 9, 1102, 3, 4, 10, 99, 0, 0];
+        let mut iss = IntcodeISS::new(&program(&prog));
+        assert_eq!(
+            iss.try_compute([].iter()).unwrap(),
+            (StopReason::ProgramHalt, vec![])
+        );
+        assert_eq!(iss.peek(9), max);
+        assert_eq!(iss.peek(10), 12);
+
+        // the faulting instruction is reported and the machine stays there
+        let mut iss = IntcodeISS::new(&program(&[1101, 1, 1, 0, 1101, max, 1, 0, 99]));
+        assert_eq!(overflow_pc(iss.try_compute([].iter())), Some(4));
+        assert_eq!(overflow_pc(iss.try_compute([].iter())), Some(4));
+        let mut iss = IntcodeISS::new(&program(&[1102, max, 2, 0, 99]));
+        assert_eq!(overflow_pc(iss.try_compute([].iter())), Some(0));
+        let mut iss = IntcodeISS::new(&program(&[1102, Value::MIN, -1, 0, 99]));
+        assert_eq!(overflow_pc(iss.try_compute([].iter())), Some(0));
+        let mut iss = IntcodeISS::new(&program(&[109, max, 109, 1, 99]));
+        assert_eq!(overflow_pc(iss.try_compute([].iter())), Some(2));
+
+        // output before the fault is kept, as is the consumed input
+        let mut iss = IntcodeISS::new(&program(&[3, 0, 104, 7, 1101, max, max, 0, 99]));
+        let mut output = Vec::new();
+        assert!(iss.try_compute_into([1].iter(), &mut output).is_err());
+        assert_eq!(output, vec![7]);
+        assert_eq!(iss.peek(0), 1);
+
+        // as a mode of the machine
+        let prog = program(&[104, 7, 1101, max, 1, 0, 99]);
+        let mut iss = IntcodeISS::new(&prog).with_checked_arithmetic();
+        assert_eq!(
+            iss.run_until_output(&mut [].iter()).unwrap(),
+            Exit::Output(7)
+        );
+        assert_eq!(overflow_pc(iss.compute([].iter())), Some(2));
+        let mut outputs = IntcodeISS::new(&prog).with_checked_arithmetic();
+        let mut outputs = outputs.outputs([].iter());
+        assert_eq!(outputs.next().unwrap().unwrap(), 7);
+        assert!(outputs.next().unwrap().is_err());
+    }
+}
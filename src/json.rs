@@ -0,0 +1,458 @@
+//! Minimal JSON values, to exchange results and machine snapshots with
+//! external tools.
+//!
+//! Values are encoded compactly through `Display` and decoded with
+//! [`parse`]. Integers are kept apart from floats so intcode values survive
+//! a round trip without losing precision, and objects keep the order of
+//! their members.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum E {
+    // byte offset of the error in the input
+    InvalidJson(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Object with the given members.
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Json::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Number as float, integers included.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Int(i) => Some(i as f64),
+            Json::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(i: i32) -> Self {
+        Json::Int(i64::from(i))
+    }
+}
+
+impl From<i64> for Json {
+    fn from(i: i64) -> Self {
+        Json::Int(i)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(i: u32) -> Self {
+        Json::Int(i64::from(i))
+    }
+}
+
+impl From<f64> for Json {
+    fn from(f: f64) -> Self {
+        Json::Float(f)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Self {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// The answer of a part of a day as object
+/// `{"day": 12, "part": 1, "answer": 9139}`. Integer answers are numbers,
+/// anything else is a string, e.g. digits with leading zeros.
+pub fn answer(day: u32, part: usize, answer: &str) -> Json {
+    let value = match answer.parse::<i64>() {
+        Ok(i) if i.to_string() == answer => Json::from(i),
+        _ => Json::from(answer),
+    };
+    Json::object(vec![
+        ("day", Json::from(day)),
+        ("part", Json::Int(part as i64)),
+        ("answer", value),
+    ])
+}
+
+/// Quote `s` as a JSON string.
+pub fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Int(i) => write!(f, "{}", i),
+            // Debug keeps the fraction of whole numbers, e.g. 1.0
+            Json::Float(x) if x.is_finite() => write!(f, "{:?}", x),
+            // JSON has no representation of NaN and infinities
+            Json::Float(_) => write!(f, "null"),
+            Json::String(s) => write!(f, "{}", json_string(s)),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Parse the JSON document `s`.
+pub fn parse(s: &str) -> crate::Result<Json> {
+    let mut parser = Parser { s, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != s.len() {
+        return Err(parser.error());
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> Box<dyn std::error::Error> {
+        crate::Error::boxed(E::InvalidJson(self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> crate::Result<()> {
+        if !self.s[self.pos..].starts_with(token) {
+            return Err(self.error());
+        }
+        self.pos += token.len();
+        Ok(())
+    }
+
+    fn value(&mut self) -> crate::Result<Json> {
+        self.skip_whitespace();
+        match self.peek().ok_or_else(|| self.error())? {
+            b'n' => self.expect("null").map(|_| Json::Null),
+            b't' => self.expect("true").map(|_| Json::Bool(true)),
+            b'f' => self.expect("false").map(|_| Json::Bool(false)),
+            b'"' => self.string().map(Json::String),
+            b'[' => self.array(),
+            b'{' => self.object(),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn number(&mut self) -> crate::Result<Json> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        let token = &self.s[start..self.pos];
+        let float = token.contains(&['.', 'e', 'E'][..]);
+        match token.parse::<i64>() {
+            Ok(i) if !float => Ok(Json::Int(i)),
+            // integers out of range are kept as float
+            _ => token.parse::<f64>().map(Json::Float).map_err(|_| {
+                self.pos = start;
+                self.error()
+            }),
+        }
+    }
+
+    fn string(&mut self) -> crate::Result<String> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        loop {
+            let c = self.s[self.pos..]
+                .chars()
+                .next()
+                .ok_or_else(|| self.error())?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = self.peek().ok_or_else(|| self.error())?;
+                    self.pos += 1;
+                    match escape {
+                        b'"' => s.push('"'),
+                        b'\\' => s.push('\\'),
+                        b'/' => s.push('/'),
+                        b'b' => s.push('\u{8}'),
+                        b'f' => s.push('\u{c}'),
+                        b'n' => s.push('\n'),
+                        b'r' => s.push('\r'),
+                        b't' => s.push('\t'),
+                        b'u' => s.push(self.unicode_escape()?),
+                        _ => return Err(self.error()),
+                    }
+                }
+                c if (c as u32) < 0x20 => return Err(self.error()),
+                c => s.push(c),
+            }
+        }
+    }
+
+    // The character of a `\uXXXX` escape, after the `\u`. Characters outside
+    // the basic plane are escaped as surrogate pair.
+    fn unicode_escape(&mut self) -> crate::Result<char> {
+        let hex = |p: &mut Parser| -> crate::Result<u32> {
+            let digits = p.s.get(p.pos..p.pos + 4).ok_or_else(|| p.error())?;
+            let code = u32::from_str_radix(digits, 16).map_err(|_| p.error())?;
+            p.pos += 4;
+            Ok(code)
+        };
+        let mut code = hex(self)?;
+        if (0xd800..0xdc00).contains(&code) {
+            self.expect("\\u")?;
+            let low = hex(self)?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error());
+            }
+            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+        }
+        std::char::from_u32(code).ok_or_else(|| self.error())
+    }
+
+    // Items of a list enclosed in `open` and `close`, separated by commas.
+    fn list(
+        &mut self,
+        open: &str,
+        close: u8,
+        mut item: impl FnMut(&mut Self) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        self.expect(open)?;
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> crate::Result<Json> {
+        let mut items = Vec::new();
+        self.list("[", b']', |p| {
+            items.push(p.value()?);
+            Ok(())
+        })?;
+        Ok(Json::Array(items))
+    }
+
+    fn object(&mut self) -> crate::Result<Json> {
+        let mut members = Vec::new();
+        self.list("{", b'}', |p| {
+            p.skip_whitespace();
+            let key = p.string()?;
+            p.skip_whitespace();
+            p.expect(":")?;
+            members.push((key, p.value()?));
+            Ok(())
+        })?;
+        Ok(Json::Object(members))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\\n\x1B"), "\"a\\\"b\\\\\\n\\u001b\"");
+    }
+
+    #[test]
+    fn test_encode() {
+        let json = Json::object(vec![
+            ("day", Json::from(9)),
+            ("answers", Json::from(vec![2436480432_i64, 45710])),
+            ("name", Json::from("a\"b")),
+            ("ratio", Json::from(1.0)),
+            ("nan", Json::from(f64::NAN)),
+            ("empty", Json::Array(vec![])),
+            ("ok", Json::from(true)),
+            ("none", Json::Null),
+        ]);
+        assert_eq!(
+            json.to_string(),
+            "{\"day\": 9, \"answers\": [2436480432, 45710], \"name\": \"a\\\"b\", \
+             \"ratio\": 1.0, \"nan\": null, \"empty\": [], \"ok\": true, \"none\": null}"
+        );
+    }
+
+    #[test]
+    fn test_answer() {
+        assert_eq!(
+            answer(12, 2, "420788524631496").to_string(),
+            "{\"day\": 12, \"part\": 2, \"answer\": 420788524631496}"
+        );
+        assert_eq!(
+            answer(16, 1, "01234567").to_string(),
+            "{\"day\": 16, \"part\": 1, \"answer\": \"01234567\"}"
+        );
+        assert_eq!(
+            answer(8, 2, "#.\n").get("answer"),
+            Some(&Json::from("#.\n"))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let json =
+            parse(" {\"a\" : [1, -2.5e1, \"x\\u00e9\\ud83d\\ude00\\n\"], \"b\": {}, \"c\": null} ")
+                .unwrap();
+        assert_eq!(
+            json.get("a").and_then(Json::as_array),
+            Some(
+                &[
+                    Json::Int(1),
+                    Json::Float(-25.0),
+                    Json::String("x\u{e9}\u{1f600}\n".to_string())
+                ][..]
+            )
+        );
+        assert_eq!(json.get("b"), Some(&Json::Object(vec![])));
+        assert_eq!(json.get("c"), Some(&Json::Null));
+        assert_eq!(json.get("d"), None);
+
+        // round trip, large integers keep their precision
+        let big = Json::from(vec![i64::MAX, i64::MIN]);
+        assert_eq!(parse(&big.to_string()).unwrap(), big);
+        assert_eq!(
+            parse("18446744073709551616").unwrap(),
+            Json::Float(18446744073709551616.0)
+        );
+
+        for invalid in &[
+            "",
+            "[1,]",
+            "{\"a\" 1}",
+            "[1] x",
+            "\"abc",
+            "tru",
+            "-",
+            "\"\\ud800\"",
+        ] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
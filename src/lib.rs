@@ -1,6 +1,63 @@
 use std::error;
 use std::fmt;
 
+pub mod alloc_count;
+pub mod asm;
+pub mod bench;
+pub mod bus;
+pub mod cache;
+pub mod cast;
+pub mod cli;
+pub mod console;
+pub mod csv;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day16;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod decompile;
+pub mod dense;
+pub mod ffi;
+pub mod fixtures;
+pub mod gif;
+pub mod golden;
+pub mod graphml;
+pub mod grid;
+pub mod heatmap;
+pub mod image;
+pub mod input;
+pub mod intcode;
+pub mod json;
+pub mod math;
+pub mod optimize;
+pub mod palette;
+pub mod par;
+pub mod png;
+pub mod process;
+pub mod replay;
+pub mod report;
+pub mod search;
+pub mod solver;
+pub mod svg;
+pub mod term;
+pub mod viz;
+pub mod web;
+
+// Tests check allocation counts, see alloc_count.
+#[cfg(test)]
+#[global_allocator]
+static ALLOC: alloc_count::CountingAlloc = alloc_count::CountingAlloc;
+
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 #[derive(Debug)]
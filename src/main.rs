@@ -1,13 +1,264 @@
-fn main() {
+use aoc19::report::{self, CaseResult, ReportFormat};
+use std::time::Instant;
+
+// Answers to check with `verify`, the binary is run with `args` and its
+// `--quiet` output has to start with `answers`, one per line. Pictures drawn
+// as answer are not checked.
+const CASES: &[(&str, &[&str], &[&str])] = &[
+    ("day1", &["input/day1"], &["3267638", "4898585"]),
+    ("day2", &["input/day2"], &["4714701", "5121"]),
+    ("day3", &["input/day3"], &["273", "15622"]),
+    ("day4", &[], &["1169", "757"]),
+    ("day5", &["input/day5"], &["2845163", "9436229"]),
+    ("day6", &[], &["314247", "514"]),
+    ("day7", &[], &["359142", "4374895"]),
+    ("day8", &[], &["1920"]),
+    ("day9", &[], &["2436480432", "45710"]),
+    ("day10", &[], &["274"]),
+    ("day11", &[], &["2064"]),
+    ("day12", &[], &["9139", "420788524631496"]),
+    ("day13", &[], &["344", "17336"]),
+    ("day14", &[], &["273638", "4200533"]),
+    ("day16", &[], &["19239468", "96966221"]),
+];
+
+// Run the binary of a case, which is expected next to this one.
+fn check(bin: &str, args: &[&str], answers: &[&str]) -> aoc19::Result<CaseResult> {
+    let path = std::env::current_exe()?.with_file_name(bin);
+    let start = Instant::now();
+    let output = std::process::Command::new(&path)
+        .args(args)
+        .arg("--quiet")
+        .stdin(std::process::Stdio::null())
+        .output();
+    let duration = start.elapsed();
+
+    let failure = match output {
+        Err(err) => Some(format!("failed to run {}: {}", path.display(), err)),
+        Ok(output) if !output.status.success() => Some(format!(
+            "exited with {}\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )),
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let found: Vec<_> = stdout.lines().take(answers.len()).collect();
+            if found == answers {
+                None
+            } else {
+                Some(format!("expected {:?}\nfound {:?}", answers, found))
+            }
+        }
+    };
+    Ok(CaseResult {
+        name: bin.to_string(),
+        failure,
+        duration,
+    })
+}
+
+// Check the answers of all days, returns whether all of them passed.
+fn verify(args: &aoc19::cli::Args) -> aoc19::Result<bool> {
+    let format = args
+        .parse_value::<ReportFormat>("--report")?
+        .unwrap_or(ReportFormat::Text);
+    let days: Vec<_> = (1..).map_while(|idx| args.positional(idx)).collect();
+
+    let mut results = Vec::new();
+    for (bin, bin_args, answers) in CASES {
+        if days.is_empty() || days.iter().any(|day| day == bin) {
+            results.push(check(bin, bin_args, answers)?);
+        }
+    }
+
+    match args.value("--output") {
+        Some(path) => {
+            let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+            report::write(&mut out, format, "aoc19", &results)?;
+            std::io::Write::flush(&mut out)?;
+        }
+        None => report::write(&mut std::io::stdout(), format, "aoc19", &results)?,
+    }
+    Ok(results.iter().all(|r| r.passed()))
+}
+
+// Run the registered solvers of the given days (all if none is given) in
+// process and print their answers, as JSON objects with --format json.
+fn solve(args: &aoc19::cli::Args) -> aoc19::Result<()> {
+    let days: Vec<_> = (1..).map_while(|idx| args.positional(idx)).collect();
+    for solver in aoc19::solver::SOLVERS {
+        let day = format!("day{}", solver.day);
+        if !days.is_empty() && !days.contains(&day.as_str()) {
+            continue;
+        }
+        let answer = aoc19::input::read_with(&aoc19::solver::input_path(solver.day), solver.solve)?;
+        let answers = aoc19::cli::Answers::from_args(solver.day, args)?;
+        if answers.format() == aoc19::cli::Format::Json {
+            answers.part(solver.part as usize, "", &answer);
+        } else if answer.contains('\n') {
+            println!("{} part {}:\n{}", day, solver.part, answer.trim_end());
+        } else {
+            println!("{} part {}: {}", day, solver.part, answer);
+        }
+    }
+    Ok(())
+}
+
+// Check the intcode machine against the golden traces, returns whether all
+// of them match. With --record the traces of the current machine are
+// stored as the new golden ones instead.
+fn golden(args: &aoc19::cli::Args) -> aoc19::Result<bool> {
+    use aoc19::golden;
+
+    let current = golden::corpus()?
+        .iter()
+        .map(golden::record)
+        .collect::<aoc19::Result<Vec<_>>>()?;
+    if args.flag("--record") {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(golden::PATH)?);
+        golden::write(&mut out, &current)?;
+        std::io::Write::flush(&mut out)?;
+        println!("recorded {} traces in {}", current.len(), golden::PATH);
+        return Ok(true);
+    }
+
+    let recorded = aoc19::input::read_with(golden::PATH, golden::read)?;
+    let drift = golden::drift(&recorded, &current);
+    for line in &drift {
+        println!("{}", line);
+    }
+    println!(
+        "{} of {} traces match",
+        current.len() - drift.len(),
+        current.len()
+    );
+    Ok(drift.is_empty())
+}
+
+// Profile the intcode programs of the golden corpus whose names contain one
+// of the given filters (all if none is given): executions per instruction
+// and the hottest regions.
+fn profile(args: &aoc19::cli::Args) -> aoc19::Result<()> {
+    use aoc19::intcode::{IntcodeISS, Profile};
+
+    let filters: Vec<_> = (1..).map_while(|idx| args.positional(idx)).collect();
+    for case in aoc19::golden::corpus()? {
+        if !filters.is_empty() && !filters.iter().any(|f| case.name.contains(f)) {
+            continue;
+        }
+        let mut profile = Profile::new();
+        IntcodeISS::new(&case.program).compute_traced(case.input.iter(), &mut profile)?;
+        let steps = profile.steps();
+        println!("{}: {} steps", case.name, steps);
+        let percent = |count: u64| 100.0 * count as f64 / steps as f64;
+        for (mnemonic, &count) in &profile.instructions {
+            println!("  {:<4} {:>12} {:5.1}%", mnemonic, count, percent(count));
+        }
+        for region in profile.hot_regions(5) {
+            println!(
+                "  {:>5}..{:<5} {:>3} x {:>10} {:5.1}%",
+                region.start,
+                region.end,
+                region.instructions,
+                region.executions,
+                percent(region.steps())
+            );
+        }
+    }
+    Ok(())
+}
+
+// Play the intcode program at the given path on the console.
+fn console(args: &aoc19::cli::Args) -> aoc19::Result<()> {
+    let Some(path) = args.positional(1) else {
+        eprintln!("usage: aoc2019 console <program>");
+        std::process::exit(2);
+    };
+    let prog = aoc19::intcode::IntcodeProgram::from_path(path)?;
+    let mut iss = aoc19::intcode::IntcodeISS::new(&prog);
+    let reason = aoc19::console::run(&mut iss, std::io::stdin().lock(), std::io::stdout())?;
+    if reason != aoc19::intcode::StopReason::ProgramHalt {
+        eprintln!("stopped: {:?}", reason);
+    }
+    Ok(())
+}
+
+// Print the pseudocode of the intcode program at the given path.
+fn decompile(args: &aoc19::cli::Args) -> aoc19::Result<()> {
+    let Some(path) = args.positional(1) else {
+        eprintln!("usage: aoc2019 decompile <program>");
+        std::process::exit(2);
+    };
+    let prog = aoc19::intcode::IntcodeProgram::from_path(path)?;
+    print!("{}", aoc19::decompile::decompile(&prog));
+    Ok(())
+}
+
+// Print the changes the optimizer makes to the intcode program at the
+// given path.
+fn optimize(args: &aoc19::cli::Args) -> aoc19::Result<()> {
+    let Some(path) = args.positional(1) else {
+        eprintln!("usage: aoc2019 optimize <program>");
+        std::process::exit(2);
+    };
+    let prog = aoc19::intcode::IntcodeProgram::from_path(path)?;
+    let (_, changes) = aoc19::optimize::optimize(&prog)?;
+    for change in &changes {
+        println!("{}", change);
+    }
+    println!("{} changes", changes.len());
+    Ok(())
+}
+
+fn main() -> aoc19::Result<()> {
+    let args = aoc19::cli::Args::from_env(&["--report", "--output"])?;
+    if let Some("verify") | Some("selftest") = args.positional(0) {
+        if !verify(&args)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some("solve") = args.positional(0) {
+        return solve(&args);
+    }
+    if let Some("profile") = args.positional(0) {
+        return profile(&args);
+    }
+    if let Some("console") = args.positional(0) {
+        return console(&args);
+    }
+    if let Some("decompile") = args.positional(0) {
+        return decompile(&args);
+    }
+    if let Some("optimize") = args.positional(0) {
+        return optimize(&args);
+    }
+    if let Some("golden") = args.positional(0) {
+        if !golden(&args)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     println!("--- Happy Advent of Code 2019 ---");
-    println!("");
+    println!();
     println!("This project is organized as follows:");
     println!("  src/bin/dayN ............ solution of day N");
     println!("  input/dayN .............. input for day N");
     println!("  instruction/dayN ........ instructions for day N");
-    println!("");
+    println!();
     println!("Actions:");
     println!("  cargo build --bins ...... build all binaries at once");
     println!("  cargo run --bin dayN .... run binary for day N ");
     println!("  cargo test --bins ....... run all tests in all binaries");
+    println!("  cargo run -- verify [dayN..] [--report text|tap|junit] [--output file]");
+    println!("  ......................... check the answers of all binaries");
+    println!("  cargo run --release --bin microbench [filter..] . time hot library code");
+    println!("  cargo run -- solve [dayN..] [--format text|json] . run the solvers registered with #[aoc]");
+    println!("  cargo run -- golden [--record] . check the intcode machine against golden/intcode");
+    println!("  cargo run -- profile [name..] . hottest instructions of the golden intcode runs");
+    println!("  cargo run -- console <program> . play an ASCII intcode program");
+    println!("  cargo run -- decompile <program> . pseudocode of an intcode program");
+    println!("  cargo run -- optimize <program> . constant folding report of an intcode program");
+    Ok(())
 }
@@ -0,0 +1,57 @@
+//! Number theory helpers shared by the days.
+
+/// Greatest common divisor, `gcd(0, 0)` is 0.
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Least common multiple, computed as `a / gcd * b` in 128 bits so it can't
+/// overflow. `lcm(0, x)` is 0.
+pub fn lcm(a: u64, b: u64) -> u128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    u128::from(a / gcd(a, b)) * u128::from(b)
+}
+
+/// Least common multiple, `None` if it doesn't fit into a `u64`.
+pub fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    use std::convert::TryFrom;
+    u64::try_from(lcm(a, b)).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(18, 12), 6);
+        assert_eq!(gcd(7, 13), 1);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd(5, 0), 5);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(gcd(u64::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        assert_eq!(lcm(u64::MAX, u64::MAX), u128::from(u64::MAX));
+        assert_eq!(
+            lcm(u64::MAX, u64::MAX - 1),
+            u128::from(u64::MAX) * u128::from(u64::MAX - 1)
+        );
+
+        assert_eq!(checked_lcm(4, 6), Some(12));
+        assert_eq!(checked_lcm(1 << 32, 1 << 40), Some(1 << 40));
+        assert_eq!(checked_lcm(1 << 32, (1 << 32) + 1), None);
+    }
+}
@@ -0,0 +1,241 @@
+//! Optimizer folding constants and removing dead stores of intcode
+//! programs, before they are run.
+//!
+//! Jumps go to absolute addresses, so instructions are rewritten in place:
+//! reads of cells with a known value become immediates, arithmetic on
+//! immediates is folded to an `add v, 0` and stores overwritten before they
+//! are read are jumped over. Values are only known within straight-line
+//! code, up to the next jump or jump target. The code is found like in
+//! [`crate::decompile`], the program must not write to it.
+
+use crate::decompile::{constant, find_code, Insn, Operand};
+use crate::intcode::{Addr, IntcodeProgram, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug)]
+#[allow(dead_code)] // fields are reported through Debug
+pub enum E {
+    // the instruction at `pc` writes to the code
+    SelfModifying { pc: Addr },
+}
+
+/// A transformation applied to the instruction at `addr`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// The read of `cell` was replaced by its known `value`.
+    Propagated {
+        addr: Addr,
+        cell: Value,
+        value: Value,
+    },
+    /// The result is `value` no matter what.
+    Folded { addr: Addr, value: Value },
+    /// The result was overwritten before it was read.
+    DeadStore { addr: Addr },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Propagated { addr, cell, value } => {
+                write!(f, "{:>6}  [{}] is {}", addr, cell, value)
+            }
+            Change::Folded { addr, value } => write!(f, "{:>6}  folded to {}", addr, value),
+            Change::DeadStore { addr } => write!(f, "{:>6}  dead store removed", addr),
+        }
+    }
+}
+
+// Write `insn` to `cells` at `addr`.
+fn encode(cells: &mut [Value], addr: Addr, insn: &Insn) {
+    let mut modes = 0;
+    for (idx, operand) in insn.operands.iter().enumerate() {
+        let (mode, val) = match *operand {
+            Operand::Pos(val) => (0, val),
+            Operand::Imm(val) => (1, val),
+            Operand::Rel(val) => (2, val),
+        };
+        modes += mode * Value::pow(10, idx as u32);
+        cells[addr + 1 + idx] = val;
+    }
+    cells[addr] = modes * 100 + insn.opcode;
+}
+
+// Whether the instruction writes its last operand.
+fn stores(insn: &Insn) -> bool {
+    matches!(insn.opcode, 1 | 2 | 3 | 7 | 8)
+}
+
+/// Optimize `prog`, returns the program and the changes applied in the
+/// order of the code. Programs writing to their code are rejected.
+pub fn optimize(prog: &IntcodeProgram) -> crate::Result<(IntcodeProgram, Vec<Change>)> {
+    let mut cells = prog.cells().to_vec();
+    let mut code = find_code(&cells);
+    let code_cells: BTreeSet<Addr> = code
+        .iter()
+        .flat_map(|(&addr, insn)| addr..addr + insn.size())
+        .collect();
+    for (&pc, insn) in &code {
+        if let (true, Some(Operand::Pos(dest))) = (stores(insn), insn.operands.last()) {
+            if Addr::try_from(*dest).is_ok_and(|dest| code_cells.contains(&dest)) {
+                return Err(crate::Error::boxed(E::SelfModifying { pc }));
+            }
+        }
+    }
+
+    // straight-line code starts at jump targets, behind jumps and at the
+    // stored constants, which may be return addresses
+    let mut leaders: BTreeSet<Addr> = BTreeSet::new();
+    for (&addr, insn) in &code {
+        if let Some((target, _)) = insn.jump() {
+            leaders.extend(target.target());
+            leaders.insert(addr + insn.size());
+        }
+        leaders.extend(constant(insn).and_then(|value| Addr::try_from(value).ok()));
+    }
+
+    let mut changes = Vec::new();
+    let mut known: HashMap<Value, Value> = HashMap::new();
+    // the stores not read yet by their cell
+    let mut unread: HashMap<Value, Addr> = HashMap::new();
+    let addrs: Vec<Addr> = code.keys().copied().collect();
+    for addr in addrs {
+        if leaders.contains(&addr) {
+            known.clear();
+            unread.clear();
+        }
+        let insn = code.get_mut(&addr).unwrap();
+        let reads = insn.operands.len() - stores(insn) as usize;
+        for operand in &mut insn.operands[..reads] {
+            match *operand {
+                Operand::Pos(cell) => {
+                    unread.remove(&cell);
+                    if let Some(&value) = known.get(&cell) {
+                        *operand = Operand::Imm(value);
+                        changes.push(Change::Propagated { addr, cell, value });
+                    }
+                }
+                // may read any cell
+                Operand::Rel(_) => unread.clear(),
+                Operand::Imm(_) => {}
+            }
+        }
+
+        let value = match (insn.opcode, &insn.operands[..]) {
+            (7, [Operand::Imm(a), Operand::Imm(b), _]) => Some((a < b) as Value),
+            (8, [Operand::Imm(a), Operand::Imm(b), _]) => Some((a == b) as Value),
+            _ => constant(insn),
+        };
+        if let Some(value) = value {
+            let folded = Insn {
+                opcode: 1,
+                operands: vec![Operand::Imm(value), Operand::Imm(0), insn.operands[2]],
+            };
+            if (insn.opcode, &insn.operands[..2]) != (1, &folded.operands[..2]) {
+                changes.push(Change::Folded { addr, value });
+            }
+            *insn = folded;
+        }
+
+        match (stores(insn), insn.operands.last().copied()) {
+            (true, Some(Operand::Pos(cell))) => {
+                if let Some(dead) = unread.remove(&cell) {
+                    changes.retain(|change| match change {
+                        Change::Propagated { addr, .. } | Change::Folded { addr, .. } => {
+                            *addr != dead
+                        }
+                        Change::DeadStore { .. } => true,
+                    });
+                    changes.push(Change::DeadStore { addr: dead });
+                }
+                // input can't be dropped
+                if insn.opcode != 3 {
+                    unread.insert(cell, addr);
+                }
+                match value {
+                    Some(value) => known.insert(cell, value),
+                    None => known.remove(&cell),
+                };
+            }
+            // may write any cell
+            (true, _) => known.clear(),
+            _ => {}
+        }
+        encode(&mut cells, addr, insn);
+        if insn.jump().is_some() || insn.opcode == 99 {
+            known.clear();
+            unread.clear();
+        }
+    }
+
+    for change in &changes {
+        if let Change::DeadStore { addr } = *change {
+            // jump over the store, its last cell is left as it is
+            cells[addr..addr + 3].copy_from_slice(&[1106, 0, (addr + 4) as Value]);
+        }
+    }
+    changes.sort_by_key(|change| match *change {
+        Change::Propagated { addr, .. }
+        | Change::Folded { addr, .. }
+        | Change::DeadStore { addr } => addr,
+    });
+    Ok((IntcodeProgram::new(cells)?, changes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::IntcodeISS;
+
+    #[test]
+    fn test_optimize() {
+        let prog = crate::asm::assemble(
+            "      add 1, 1, [y]
+                   add 2, 3, [x]
+                   mul [x], 4, [y]
+                   get [x]
+                   lt [y], 30, [y]
+                   put [y]
+                   put [x]
+                   halt
+             x:    .data 0
+             y:    .data 0",
+        )
+        .unwrap();
+        let (optimized, changes) = optimize(&prog).unwrap();
+        let text: Vec<_> = changes
+            .iter()
+            .map(|c| c.to_string().trim().to_string())
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "0  dead store removed",
+                "4  folded to 5",
+                "8  [23] is 5",
+                "8  folded to 20",
+                "14  [24] is 20",
+                "14  folded to 1",
+                "18  [24] is 1",
+            ]
+        );
+        let run = |prog: &IntcodeProgram| IntcodeISS::new(prog).compute([7].iter()).unwrap();
+        assert_eq!(run(&optimized), run(&prog));
+        assert_eq!(&optimized.cells()[..3], &[1106, 0, 4]);
+
+        // day 2 style programs write to their code
+        let prog = IntcodeProgram::new(vec![1101, 1, 1, 1, 99]).unwrap();
+        assert!(optimize(&prog).is_err());
+
+        for case in crate::golden::corpus().unwrap() {
+            let Ok((optimized, _)) = optimize(&case.program) else {
+                continue;
+            };
+            let expected = IntcodeISS::new(&case.program).compute(case.input.iter());
+            let result = IntcodeISS::new(&optimized).compute(case.input.iter());
+            assert_eq!(result.ok(), expected.ok(), "{}", case.name);
+        }
+    }
+}
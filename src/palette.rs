@@ -0,0 +1,270 @@
+//! Color palettes of the visualizations.
+//!
+//! A palette is loaded from a theme file holding one section per day with
+//! the colors of its tiles/values:
+//!
+//! ```text
+//! # comment
+//! [day13]
+//! wall = #808080
+//! ```
+//!
+//! Colors missing in a theme are taken from the default theme. Besides the
+//! built-in themes (see `themes/`) any theme file can be given by its path.
+//!
+//! The characters the tiles are drawn with on the terminal are part of the
+//! palette as well, from a Unicode or a plain ASCII charset.
+
+use crate::image::Rgb;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidLine { line: usize },
+    InvalidColor { line: usize, color: String },
+    MissingColor { section: String, name: String },
+    MissingGlyph { section: String, name: String },
+    UnknownTheme(String),
+}
+
+const THEMES: &[(&str, &str)] = &[
+    ("default", include_str!("../themes/default.theme")),
+    (
+        "high-contrast",
+        include_str!("../themes/high-contrast.theme"),
+    ),
+    ("monochrome", include_str!("../themes/monochrome.theme")),
+];
+
+// Characters of the tiles as (section, name, unicode, ascii).
+const GLYPHS: &[(&str, &str, char, char)] = &[
+    ("day8", "black", ' ', ' '),
+    ("day8", "white", '\u{2588}', '#'),
+    ("day8", "transparent", '\u{b7}', '.'),
+    ("day13", "empty", ' ', ' '),
+    ("day13", "wall", '\u{2588}', '#'),
+    ("day13", "block", '\u{2592}', '='),
+    ("day13", "paddle", '\u{2594}', '-'),
+    ("day13", "ball", '\u{2022}', 'o'),
+];
+
+/// Characters available to draw the tiles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    /// Unicode if the locale of the terminal uses UTF-8, ASCII otherwise.
+    pub fn detect() -> Charset {
+        // the first of these which is set decides, as for setlocale
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+        Charset::from_locale(locale.as_deref())
+    }
+
+    fn from_locale(locale: Option<&str>) -> Charset {
+        let codeset = locale
+            .and_then(|locale| locale.split('.').nth(1))
+            .map(|codeset| codeset.split('@').next().unwrap_or("").to_ascii_lowercase());
+        match codeset.as_deref() {
+            Some("utf-8") | Some("utf8") => Charset::Unicode,
+            _ => Charset::Ascii,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colors: HashMap<(String, String), Rgb>,
+    charset: Charset,
+}
+
+impl Default for Palette {
+    /// The default theme.
+    fn default() -> Self {
+        Palette::parse(THEMES[0].1).expect("built-in default theme is valid")
+    }
+}
+
+fn parse_color(color: &str) -> Option<Rgb> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+impl Palette {
+    /// Names of the built-in themes.
+    pub fn themes() -> impl Iterator<Item = &'static str> {
+        THEMES.iter().map(|&(name, _)| name)
+    }
+
+    /// Parse a theme file, line numbers in errors are 1-based.
+    pub fn parse(text: &str) -> crate::Result<Palette> {
+        let mut colors = HashMap::new();
+        let mut section = String::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let (name, color) = match line.split_once('=') {
+                Some((name, color)) if !section.is_empty() => (name.trim(), color.trim()),
+                _ => return Err(crate::Error::boxed(E::InvalidLine { line: idx + 1 })),
+            };
+            let rgb = parse_color(color).ok_or_else(|| {
+                crate::Error::boxed(E::InvalidColor {
+                    line: idx + 1,
+                    color: color.to_string(),
+                })
+            })?;
+            colors.insert((section.clone(), name.to_string()), rgb);
+        }
+        Ok(Palette {
+            colors,
+            charset: Charset::Unicode,
+        })
+    }
+
+    /// The same palette drawing the tiles with characters of `charset`.
+    pub fn with_charset(mut self, charset: Charset) -> Palette {
+        self.charset = charset;
+        self
+    }
+
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    /// Load the built-in theme `name`, or else the theme file at path `name`.
+    /// Colors missing in the theme are taken from the default theme.
+    pub fn theme(name: &str) -> crate::Result<Palette> {
+        let theme = match THEMES.iter().find(|&&(theme, _)| theme == name) {
+            Some(&(_, text)) => Palette::parse(text)?,
+            None if std::path::Path::new(name).is_file() => {
+                Palette::parse(&std::fs::read_to_string(name)?)?
+            }
+            None => return Err(crate::Error::boxed(E::UnknownTheme(name.to_string()))),
+        };
+
+        let mut palette = Palette::default();
+        palette.colors.extend(theme.colors);
+        Ok(palette)
+    }
+
+    /// Color `name` of the day `section`.
+    pub fn color(&self, section: &str, name: &str) -> crate::Result<Rgb> {
+        self.colors
+            .get(&(section.to_string(), name.to_string()))
+            .copied()
+            .ok_or_else(|| {
+                crate::Error::boxed(E::MissingColor {
+                    section: section.to_string(),
+                    name: name.to_string(),
+                })
+                .into()
+            })
+    }
+
+    /// Character the tile `name` of the day `section` is drawn with.
+    pub fn glyph(&self, section: &str, name: &str) -> crate::Result<char> {
+        GLYPHS
+            .iter()
+            .find(|&&(s, n, _, _)| s == section && n == name)
+            .map(|&(_, _, unicode, ascii)| match self.charset {
+                Charset::Unicode => unicode,
+                Charset::Ascii => ascii,
+            })
+            .ok_or_else(|| {
+                crate::Error::boxed(E::MissingGlyph {
+                    section: section.to_string(),
+                    name: name.to_string(),
+                })
+                .into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let palette = Palette::parse("# colors\n[a]\nx = #ff8000\n\n[b]\n  y=#0A0b0C  \n").unwrap();
+        assert_eq!(palette.color("a", "x").unwrap(), [0xff, 0x80, 0x00]);
+        assert_eq!(palette.color("b", "y").unwrap(), [0x0a, 0x0b, 0x0c]);
+        assert!(palette.color("a", "y").is_err());
+
+        assert!(Palette::parse("x = #000000").is_err());
+        assert!(Palette::parse("[a]\nx #000000").is_err());
+        assert!(Palette::parse("[a]\nx = #00000").is_err());
+        assert!(Palette::parse("[a]\nx = 000000").is_err());
+        assert!(Palette::parse("[a]\nx = #00000g").is_err());
+    }
+
+    #[test]
+    fn test_themes() {
+        let default = Palette::default();
+        for name in Palette::themes() {
+            let theme = Palette::theme(name).unwrap();
+            // every built-in theme defines all colors of the default theme
+            assert_eq!(
+                Palette::parse(THEMES.iter().find(|t| t.0 == name).unwrap().1)
+                    .unwrap()
+                    .colors
+                    .len(),
+                default.colors.len()
+            );
+            assert_eq!(theme.colors.len(), default.colors.len());
+        }
+        assert_eq!(
+            Palette::theme("monochrome")
+                .unwrap()
+                .color("day13", "block")
+                .unwrap(),
+            [0xa0, 0xa0, 0xa0]
+        );
+        assert!(Palette::theme("no-such-theme").is_err());
+    }
+
+    #[test]
+    fn test_glyphs() {
+        let palette = Palette::default();
+        assert_eq!(palette.charset(), Charset::Unicode);
+        assert_eq!(palette.glyph("day13", "wall").unwrap(), '\u{2588}');
+        let palette = palette.with_charset(Charset::Ascii);
+        assert_eq!(palette.glyph("day13", "wall").unwrap(), '#');
+        assert!(palette.glyph("day13", "missile").is_err());
+
+        // every color of the default theme has a glyph, all ASCII ones are
+        // printable
+        for (section, name) in palette.colors.keys() {
+            let c = palette.glyph(section, name).unwrap();
+            assert!(c.is_ascii_graphic() || c == ' ');
+        }
+
+        assert_eq!(Charset::from_locale(Some("en_US.UTF-8")), Charset::Unicode);
+        assert_eq!(
+            Charset::from_locale(Some("de_DE.utf8@euro")),
+            Charset::Unicode
+        );
+        assert_eq!(Charset::from_locale(Some("C")), Charset::Ascii);
+        assert_eq!(
+            Charset::from_locale(Some("en_US.ISO-8859-1")),
+            Charset::Ascii
+        );
+        assert_eq!(Charset::from_locale(None), Charset::Ascii);
+    }
+}
@@ -0,0 +1,56 @@
+//! Parallel map over chunks of a slice on scoped std threads.
+
+/// Number of jobs to use by default, one per available CPU.
+pub fn jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Split `items` into at most `jobs` contiguous chunks of about the same
+/// size and apply `f` to every chunk on its own thread. The results are
+/// returned in the order of the chunks, there are none for empty `items`.
+///
+/// A single chunk is mapped on the calling thread.
+pub fn map_chunks<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&[T]) -> R + Sync,
+{
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let chunk_size = items.len().div_ceil(jobs.max(1));
+    if chunk_size == items.len() {
+        return vec![f(items)];
+    }
+
+    let f = &f;
+    std::thread::scope(|s| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || f(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Worker thread panicked!"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_map_chunks() {
+        let items: Vec<u32> = (1..=10).collect();
+        let sum = |chunk: &[u32]| chunk.iter().sum::<u32>();
+        assert_eq!(map_chunks(&items, 1, sum), vec![55]);
+        assert_eq!(map_chunks(&items, 0, sum), vec![55]);
+        assert_eq!(map_chunks(&items, 2, sum), vec![15, 40]);
+        assert_eq!(map_chunks(&items, 3, sum), vec![10, 26, 19]);
+        // no more chunks than items
+        assert_eq!(map_chunks(&items, 64, sum).len(), 10);
+        assert!(map_chunks(&[] as &[u32], 4, sum).is_empty());
+    }
+}
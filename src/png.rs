@@ -0,0 +1,149 @@
+//! Minimal PNG encoder.
+//!
+//! Images are written as 8 bit RGB without interlacing. The zlib stream only
+//! uses stored (uncompressed) deflate blocks, so files are larger than
+//! necessary but still readable by any viewer.
+
+use crate::grid::Grid2D;
+use crate::image::{scaled_rows, Rgb};
+use std::io::Write;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Largest payload of a stored deflate block.
+const MAX_STORED: usize = 65535;
+
+// CRC-32 (ISO-HDLC) as used by the PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// Adler-32 checksum of the zlib stream.
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+// Wrap `data` into a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED).max(1);
+    let mut out = Vec::with_capacity(2 + data.len() + blocks * 5 + 4);
+    // deflate with a 32K window, no preset dictionary, fastest compression
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(MAX_STORED).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn write_chunk(out: &mut dyn Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let mut crc_data = kind.to_vec();
+    crc_data.extend_from_slice(data);
+    out.write_all(&crc32(&crc_data).to_be_bytes())
+}
+
+/// Write `grid` as PNG image.
+pub fn write_png<T: Clone>(
+    out: &mut dyn Write,
+    grid: &Grid2D<T>,
+    scale: usize,
+    color: impl Fn(&T) -> Rgb,
+) -> std::io::Result<()> {
+    let (width, height) = (grid.width() * scale, grid.height() * scale);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, truecolor, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    // every row starts with its filter type, rows aren't filtered
+    let mut raw = Vec::with_capacity(height * (1 + width * 3));
+    for row in scaled_rows(grid, scale, color) {
+        raw.push(0);
+        raw.extend_from_slice(&row);
+    }
+
+    out.write_all(&SIGNATURE)?;
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_zlib_stored() {
+        assert_eq!(
+            zlib_stored(b"ab"),
+            vec![0x78, 0x01, 1, 2, 0, 0xfd, 0xff, b'a', b'b', 0x01, 0x26, 0, 0xc4]
+        );
+        // data is split into blocks of at most 65535 bytes
+        let data = vec![7; MAX_STORED + 1];
+        let out = zlib_stored(&data);
+        assert_eq!(out.len(), 2 + 5 + MAX_STORED + 5 + 1 + 4);
+        assert_eq!(&out[2..7], &[0, 0xff, 0xff, 0, 0]);
+        assert_eq!(
+            &out[7 + MAX_STORED..12 + MAX_STORED],
+            &[1, 1, 0, 0xfe, 0xff]
+        );
+        assert_eq!(
+            zlib_stored(&[]),
+            vec![0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut grid = Grid2D::new(0u8);
+        grid.set(1, 0, 1);
+        let mut out = Vec::new();
+        write_png(&mut out, &grid, 1, |&v| [v, 2 * v, 3 * v]).unwrap();
+
+        assert_eq!(&out[0..8], &SIGNATURE);
+        assert_eq!(&out[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&out[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(&out[33..41], &[0, 0, 0, 18, b'I', b'D', b'A', b'T']);
+        // filter byte followed by the two pixels, inside the stored block
+        assert_eq!(&out[48..55], &[0, 0, 0, 0, 1, 2, 3]);
+        assert_eq!(
+            &out[out.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Connects the ASCII input and output of an intcode machine to an external
+//! process, so intcode programs can be driven by other tools (or drive them).
+//!
+//! ASCII output of the machine is written to the stdin of the process and
+//! every line the process prints is fed back to the machine as input,
+//! including the line break. Output values outside of the ASCII range are
+//! not forwarded but collected as the result of the run. The output of the
+//! process is read on its own thread, so a process echoing what it is sent
+//! can't block on a full pipe while the machine output is still written.
+
+use crate::intcode::{IntcodeISS, StopReason, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+#[derive(Debug)]
+pub enum E {
+    // the machine needs input but the process closed its output
+    ProcessClosed,
+    // line of the process containing non ASCII characters
+    NonAscii(String),
+    // the machine ran into its step limit
+    LimitExceeded,
+}
+
+/// Spawned process with piped stdin and stdout.
+pub struct ProcessIo {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    // lines of the stdout of the process, until it is closed or fails
+    lines: Receiver<std::io::Result<String>>,
+}
+
+impl ProcessIo {
+    /// Spawn `cmd`, its stderr is inherited.
+    pub fn spawn(cmd: &mut Command) -> crate::Result<ProcessIo> {
+        let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take();
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let line = match stdout.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => Ok(line),
+                Err(e) => Err(e),
+            };
+            let failed = line.is_err();
+            if sender.send(line).is_err() || failed {
+                break;
+            }
+        });
+        Ok(ProcessIo {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    /// Write the ASCII values of `output` to the process, returns the values
+    /// which aren't ASCII.
+    pub fn send(&mut self, output: &[Value]) -> crate::Result<Vec<Value>> {
+        let (ascii, other): (Vec<_>, Vec<_>) = output.iter().partition(|&&v| (0..128).contains(&v));
+        if let Some(stdin) = self.stdin.as_mut() {
+            let bytes: Vec<_> = ascii.iter().map(|&v| v as u8).collect();
+            stdin.write_all(&bytes)?;
+            stdin.flush()?;
+        }
+        Ok(other)
+    }
+
+    /// Read the next line of the process as input values, `None` once the
+    /// process closed its output.
+    pub fn receive(&mut self) -> crate::Result<Option<Vec<Value>>> {
+        let line = match self.lines.recv() {
+            Ok(line) => line?,
+            // the reader is gone once the output is closed
+            Err(_) => return Ok(None),
+        };
+        if !line.is_ascii() {
+            return Err(crate::Error::boxed(E::NonAscii(line)));
+        }
+        Ok(Some(line.bytes().map(Value::from).collect()))
+    }
+
+    /// Run `iss` until it halts, talking to the process. Returns the output
+    /// values which aren't ASCII. Arithmetic is checked, see
+    /// `IntcodeISS::try_compute`.
+    pub fn run(&mut self, iss: &mut IntcodeISS) -> crate::Result<Vec<Value>> {
+        let mut values = Vec::new();
+        let mut input = Vec::new();
+        loop {
+            let (reason, output) = iss.try_compute(input.iter())?;
+            values.extend(self.send(&output)?);
+            match reason {
+                StopReason::ProgramHalt => return Ok(values),
+                StopReason::LimitExceeded => {
+                    return Err(crate::Error::boxed(E::LimitExceeded));
+                }
+                StopReason::NeedInput => {
+                    input = self
+                        .receive()?
+                        .ok_or_else(|| crate::Error::boxed(E::ProcessClosed))?;
+                }
+            }
+        }
+    }
+
+    /// Close the stdin of the process and wait for it to exit.
+    pub fn finish(mut self) -> crate::Result<std::process::ExitStatus> {
+        drop(self.stdin.take());
+        drop(self.lines);
+        Ok(self.child.wait()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        // print "ab\n", read three values and output their sum
+        let prog = "104,97,104,98,104,10,3,100,3,101,3,102,1,100,101,103,1,103,102,103,4,103,99"
+            .parse()
+            .unwrap();
+        let mut iss = IntcodeISS::new(&prog);
+        let mut cat = ProcessIo::spawn(&mut Command::new("cat")).unwrap();
+        assert_eq!(cat.run(&mut iss).unwrap(), vec![97 + 98 + 10]);
+        assert!(cat.finish().unwrap().success());
+
+        // the process exits without answering
+        let mut iss = IntcodeISS::new(&prog);
+        let mut truecmd = ProcessIo::spawn(&mut Command::new("true")).unwrap();
+        assert!(truecmd.run(&mut iss).is_err());
+        truecmd.finish().unwrap();
+
+        // prints far more than fits into a pipe, which cat echoes
+        let mut prog = [104, 97].repeat(200_000);
+        prog.push(99);
+        let mut iss = IntcodeISS::new(&crate::intcode::IntcodeProgram::new(prog).unwrap());
+        let mut cat = ProcessIo::spawn(&mut Command::new("cat")).unwrap();
+        assert_eq!(cat.run(&mut iss).unwrap(), vec![]);
+        assert!(cat.finish().unwrap().success());
+    }
+}
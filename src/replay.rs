@@ -0,0 +1,151 @@
+//! Recorded games of day 13.
+//!
+//! Replay files contain one line per frame: the joystick input fed to the
+//! game before the frame ('-' if none) followed by the frame's output as
+//! space separated 'x,y,tile' triples. The special position (-1, 0) carries
+//! the score.
+//!
+//! ```text
+//! day13-replay v1
+//! - 0,0,1 1,0,4
+//! -1 -1,0,42
+//! ```
+
+use crate::grid::Grid2D;
+use crate::intcode::Value;
+use std::convert::TryFrom;
+use std::io::Write;
+
+#[derive(Debug)]
+pub enum E {
+    InvalidReplay(usize),
+}
+
+pub const HEADER: &str = "day13-replay v1";
+
+/// Characters the tiles are drawn with, indexed by tile id.
+pub const TILE_CHARS: [char; 5] = [' ', '\u{2588}', '\u{2592}', '\u{2594}', '\u{2022}'];
+
+// Largest screen of a replay, larger positions are rejected instead of
+// allocating a screen for them.
+const MAX_SIZE: usize = 256;
+
+pub struct Frame {
+    pub input: Option<Value>,
+    pub output: Vec<Value>,
+}
+
+pub fn write_header(out: &mut dyn Write) -> crate::Result<()> {
+    writeln!(out, "{}", HEADER)?;
+    Ok(())
+}
+
+pub fn write_frame(
+    out: &mut dyn Write,
+    input: Option<Value>,
+    output: &[Value],
+) -> crate::Result<()> {
+    match input {
+        Some(input) => write!(out, "{}", input)?,
+        None => write!(out, "-")?,
+    }
+    for chunk in output.chunks(3) {
+        let triple: Vec<_> = chunk.iter().map(|v| v.to_string()).collect();
+        write!(out, " {}", triple.join(","))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Parse a replay file, line numbers in errors are 1-based.
+pub fn read(replay: &str) -> crate::Result<Vec<Frame>> {
+    let mut lines = replay.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header == HEADER => {}
+        _ => return Err(crate::Error::boxed(E::InvalidReplay(1))),
+    }
+
+    let mut frames = Vec::new();
+    for (nr, line) in lines {
+        let invalid = || crate::Error::boxed(E::InvalidReplay(nr + 1));
+        let mut tokens = line.split_ascii_whitespace();
+        let input = match tokens.next().ok_or_else(invalid)? {
+            "-" => None,
+            input => Some(input.parse::<Value>().map_err(|_| invalid())?),
+        };
+        let mut output = Vec::new();
+        for triple in tokens {
+            for v in triple.split(',') {
+                output.push(v.parse::<Value>().map_err(|_| invalid())?);
+            }
+        }
+        if !output.len().is_multiple_of(3) {
+            return Err(invalid());
+        }
+        frames.push(Frame { input, output });
+    }
+    Ok(frames)
+}
+
+/// Draw the screen after every frame as text, with the score in the first
+/// line. Unknown tiles are drawn as '?'.
+pub fn text_frames(frames: &[Frame]) -> crate::Result<Vec<String>> {
+    let mut screen = Grid2D::new(' ');
+    let mut score = 0;
+    let mut texts = Vec::with_capacity(frames.len());
+    for (nr, frame) in frames.iter().enumerate() {
+        for chunk in frame.output.chunks_exact(3) {
+            let (x, y, t) = (chunk[0], chunk[1], chunk[2]);
+            if x == -1 && y == 0 {
+                score = t;
+                continue;
+            }
+            match (usize::try_from(x), usize::try_from(y)) {
+                (Ok(x), Ok(y)) if x < MAX_SIZE && y < MAX_SIZE => {
+                    let c = usize::try_from(t).ok().and_then(|t| TILE_CHARS.get(t));
+                    screen.set(x, y, *c.unwrap_or(&'?'));
+                }
+                // the header is line 1
+                _ => return Err(crate::Error::boxed(E::InvalidReplay(nr + 2))),
+            }
+        }
+        texts.push(format!("Score: {}\n{}", score, screen.render(|&c| c)));
+    }
+    Ok(texts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut out = Vec::new();
+        write_header(&mut out).unwrap();
+        write_frame(&mut out, None, &[0, 0, 1, 1, 0, 4]).unwrap();
+        write_frame(&mut out, Some(-1), &[-1, 0, 42, 0, 1, 7]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "day13-replay v1\n- 0,0,1 1,0,4\n-1 -1,0,42 0,1,7\n");
+
+        let frames = read(&text).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].input, None);
+        assert_eq!(frames[0].output, vec![0, 0, 1, 1, 0, 4]);
+        assert_eq!(frames[1].input, Some(-1));
+        assert_eq!(frames[1].output, vec![-1, 0, 42, 0, 1, 7]);
+
+        assert_eq!(
+            text_frames(&frames).unwrap(),
+            vec![
+                "Score: 0\n\u{2588}\u{2022}\n",
+                "Score: 42\n\u{2588}\u{2022}\n? \n"
+            ]
+        );
+
+        assert!(read("- 0,0,1").is_err());
+        assert!(read("day13-replay v1\n- 0,0").is_err());
+        assert!(read("day13-replay v1\nx 0,0,1").is_err());
+        let far = read("day13-replay v1\n- 1000,0,1").unwrap();
+        assert!(text_frames(&far).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Results of a self-check run, written for people or as TAP / JUnit XML for
+//! external report viewers.
+//!
+//! The runner collects one `CaseResult` per check and writes the whole run
+//! once it is done, so every format sees the same data.
+
+use crate::svg::escape;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum E {
+    UnknownFormat(String),
+}
+
+/// Output format of a report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Tap,
+    Junit,
+}
+
+impl FromStr for ReportFormat {
+    type Err = crate::Error<E>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "tap" => Ok(ReportFormat::Tap),
+            "junit" => Ok(ReportFormat::Junit),
+            _ => Err(crate::Error::new(E::UnknownFormat(s.to_string()))),
+        }
+    }
+}
+
+/// Outcome of a single check, `failure` is `None` if it passed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub name: String,
+    pub failure: Option<String>,
+    pub duration: Duration,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Write `results` as `format`, `suite` names the run in JUnit XML.
+pub fn write(
+    out: &mut dyn Write,
+    format: ReportFormat,
+    suite: &str,
+    results: &[CaseResult],
+) -> crate::Result<()> {
+    match format {
+        ReportFormat::Text => write_text(out, results),
+        ReportFormat::Tap => write_tap(out, results),
+        ReportFormat::Junit => write_junit(out, suite, results),
+    }
+}
+
+fn write_text(out: &mut dyn Write, results: &[CaseResult]) -> crate::Result<()> {
+    for result in results {
+        let status = if result.passed() { "ok" } else { "FAILED" };
+        writeln!(
+            out,
+            "{:<8} {:<6} ({:.2}s)",
+            result.name,
+            status,
+            result.duration.as_secs_f64()
+        )?;
+        if let Some(failure) = &result.failure {
+            for line in failure.lines() {
+                writeln!(out, "    {}", line)?;
+            }
+        }
+    }
+    let passed = results.iter().filter(|r| r.passed()).count();
+    writeln!(out, "{} of {} passed", passed, results.len())?;
+    Ok(())
+}
+
+// TAP version 13, failures are attached as YAML block.
+fn write_tap(out: &mut dyn Write, results: &[CaseResult]) -> crate::Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", results.len())?;
+    for (idx, result) in results.iter().enumerate() {
+        let status = if result.passed() { "ok" } else { "not ok" };
+        writeln!(out, "{} {} - {}", status, idx + 1, result.name)?;
+        if let Some(failure) = &result.failure {
+            writeln!(out, "  ---")?;
+            writeln!(out, "  message: |")?;
+            for line in failure.lines() {
+                writeln!(out, "    {}", line)?;
+            }
+            writeln!(out, "  ...")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_junit(out: &mut dyn Write, suite: &str, results: &[CaseResult]) -> crate::Result<()> {
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    let time: Duration = results.iter().map(|r| r.duration).sum();
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+        escape(suite),
+        results.len(),
+        failures,
+        time.as_secs_f64()
+    )?;
+    for result in results {
+        let open = format!(
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            escape(suite),
+            escape(&result.name),
+            result.duration.as_secs_f64()
+        );
+        match &result.failure {
+            None => writeln!(out, "{}/>", open)?,
+            Some(failure) => {
+                writeln!(out, "{}>", open)?;
+                writeln!(
+                    out,
+                    "    <failure message=\"{}\"/>",
+                    escape(failure.lines().next().unwrap_or(""))
+                )?;
+                writeln!(out, "    <system-out>{}</system-out>", escape(failure))?;
+                writeln!(out, "  </testcase>")?;
+            }
+        }
+    }
+    writeln!(out, "</testsuite>")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn results() -> Vec<CaseResult> {
+        vec![
+            CaseResult {
+                name: "day1".to_string(),
+                failure: None,
+                duration: Duration::from_millis(250),
+            },
+            CaseResult {
+                name: "day2".to_string(),
+                failure: Some("expected 5121\nfound <none>".to_string()),
+                duration: Duration::from_millis(500),
+            },
+        ]
+    }
+
+    fn render(format: ReportFormat) -> String {
+        let mut out = Vec::new();
+        write(&mut out, format, "aoc19", &results()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_tap() {
+        assert_eq!(
+            render(ReportFormat::Tap),
+            "TAP version 13\n\
+             1..2\n\
+             ok 1 - day1\n\
+             not ok 2 - day2\n  \
+               ---\n  \
+               message: |\n    \
+                 expected 5121\n    \
+                 found <none>\n  \
+               ...\n"
+        );
+    }
+
+    #[test]
+    fn test_junit() {
+        assert_eq!(
+            render(ReportFormat::Junit),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="aoc19" tests="2" failures="1" time="0.750">
+  <testcase classname="aoc19" name="day1" time="0.250"/>
+  <testcase classname="aoc19" name="day2" time="0.500">
+    <failure message="expected 5121"/>
+    <system-out>expected 5121
+found &lt;none&gt;</system-out>
+  </testcase>
+</testsuite>
+"#
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            "junit".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Junit
+        );
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
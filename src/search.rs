@@ -0,0 +1,105 @@
+use crate::grid::Grid2D;
+use std::collections::VecDeque;
+
+/// Breadth-first distances from `start` to the cells of `grid` reachable
+/// through horizontally/vertically adjacent cells for which `passable`
+/// holds. Unreachable cells are `None`, the map has the size of `grid`.
+pub fn distances<T: Clone>(
+    grid: &Grid2D<T>,
+    start: (usize, usize),
+    passable: impl Fn(&T) -> bool,
+) -> Grid2D<Option<u64>> {
+    let mut dist = Grid2D::new(None);
+    if grid.is_empty() {
+        return dist;
+    }
+    dist.set(grid.width() - 1, grid.height() - 1, None);
+    if !grid.get(start.0, start.1).is_some_and(&passable) {
+        return dist;
+    }
+
+    dist.set(start.0, start.1, Some(0));
+    let mut queue = VecDeque::from(vec![(start, 0)]);
+    while let Some(((x, y), d)) = queue.pop_front() {
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for &(nx, ny) in &neighbours {
+            let open = grid.get(nx, ny).is_some_and(&passable);
+            if open && dist.get(nx, ny) == Some(&None) {
+                dist.set(nx, ny, Some(d + 1));
+                queue.push_back(((nx, ny), d + 1));
+            }
+        }
+    }
+    dist
+}
+
+/// Find the largest value in `lower..upper` for which the monotonic
+/// predicate `pred` holds, that is `pred` is true up to some value and false
+/// after it. `pred(lower)` is assumed to hold, `pred(upper)` must not hold.
+/// Errors returned by `pred` abort the search.
+pub fn last_true(
+    mut lower: i64,
+    mut upper: i64,
+    mut pred: impl FnMut(i64) -> crate::Result<bool>,
+) -> crate::Result<i64> {
+    while upper - lower > 1 {
+        let mid = lower + (upper - lower) / 2;
+        if pred(mid)? {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    Ok(lower)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_last_true() {
+        assert_eq!(last_true(0, 100, |x| Ok(x * x <= 50)).unwrap(), 7);
+        assert_eq!(last_true(3, 4, |_| Ok(false)).unwrap(), 3);
+        assert_eq!(last_true(0, 1 << 40, |x| Ok(x < 12345)).unwrap(), 12344);
+
+        let mut calls = 0;
+        last_true(0, 1024, |_| {
+            calls += 1;
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(calls, 10);
+
+        assert!(last_true(0, 10, |_| Err("failed".into())).is_err());
+    }
+
+    #[test]
+    fn test_distances() {
+        // #..#
+        // .#..
+        // ....
+        let mut grid = Grid2D::new(true);
+        grid.set(0, 0, false);
+        grid.set(1, 1, false);
+        grid.set(3, 2, true);
+        grid.set(3, 0, false);
+
+        let dist = distances(&grid, (0, 1), |&open| open);
+        let text = dist.render(|d| match d {
+            Some(d) => std::char::from_digit(*d as u32, 10).unwrap(),
+            None => '#',
+        });
+        assert_eq!(text, "#65#\n0#45\n1234\n");
+
+        assert!(distances(&grid, (0, 0), |&open| open)
+            .iter()
+            .all(|(_, d)| d.is_none()));
+        assert!(distances(&Grid2D::new(true), (0, 0), |&open| open).is_empty());
+    }
+}
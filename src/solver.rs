@@ -0,0 +1,132 @@
+//! Table of the solvers of all days.
+//!
+//! Solvers are registered with `#[aoc(day = N, part = P)]` on the `part1` /
+//! `part2` functions of the day modules and listed in `SOLVERS`, which picks
+//! up the day and part from the attributes. A function with the attribute
+//! which isn't listed doesn't compile.
+
+use crate::grid::Grid2D;
+
+pub use aoc19_macros::aoc;
+
+/// Solver of one part of a day, `solve` returns the answer as text.
+pub struct Solver {
+    pub day: u32,
+    pub part: u32,
+    pub name: &'static str,
+    pub solve: fn(&str) -> crate::Result<String>,
+}
+
+/// All registered solvers, sorted by day and part.
+pub const SOLVERS: &[Solver] = aoc19_macros::solvers![
+    day1::part1,
+    day1::part2,
+    day2::part1,
+    day2::part2,
+    day3::part1,
+    day3::part2,
+    day4::part1,
+    day4::part2,
+    day5::part1,
+    day5::part2,
+    day6::part1,
+    day6::part2,
+    day7::part1,
+    day7::part2,
+    day8::part1,
+    day8::part2,
+    day9::part1,
+    day9::part2,
+    day10::part1,
+    day11::part1,
+    day11::part2,
+    day12::part1,
+    day12::part2,
+    day13::part1,
+    day13::part2,
+    day14::part1,
+    day14::part2,
+    day16::part1,
+    day16::part2,
+];
+
+/// Whether `SOLVERS` holds a solver of `day` and `part`, checked at compile
+/// time for every `#[aoc]`.
+pub const fn registered(day: u32, part: u32) -> bool {
+    let mut idx = 0;
+    while idx < SOLVERS.len() {
+        if SOLVERS[idx].day == day && SOLVERS[idx].part == part {
+            return true;
+        }
+        idx += 1;
+    }
+    false
+}
+
+/// Answer of a solver printed as text.
+pub trait Answer {
+    fn answer(&self) -> String;
+}
+
+macro_rules! display_answer {
+    ($($t:ty),*) => {
+        $(impl Answer for $t {
+            fn answer(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+display_answer!(i32, i64, u32, u64, usize, String);
+
+impl<T: Answer> Answer for Option<T> {
+    fn answer(&self) -> String {
+        self.as_ref().map_or_else(|| "none".to_string(), T::answer)
+    }
+}
+
+/// Pictures are drawn with lit (1) pixels as blocks.
+impl<T: Copy + Into<i64>> Answer for Grid2D<T> {
+    fn answer(&self) -> String {
+        self.render(|&pixel| if pixel.into() == 1 { '\u{2588}' } else { ' ' })
+    }
+}
+
+/// Solvers of `day`.
+pub fn day(day: u32) -> impl Iterator<Item = &'static Solver> {
+    SOLVERS.iter().filter(move |solver| solver.day == day)
+}
+
+/// Path of the puzzle input of `day`.
+pub fn input_path(day: u32) -> String {
+    format!("input/day{}", day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        assert!(SOLVERS
+            .windows(2)
+            .all(|w| (w[0].day, w[0].part) < (w[1].day, w[1].part)));
+        let day1: Vec<_> = day(1).map(|solver| solver.name).collect();
+        assert_eq!(day1, vec!["day1::part1", "day1::part2"]);
+
+        let solver = day(4).nth(1).unwrap();
+        assert_eq!((solver.day, solver.part), (4, 2));
+        assert_eq!((solver.solve)("236491-713787").unwrap(), "757");
+        assert!(registered(10, 1) && !registered(10, 2));
+    }
+
+    #[test]
+    fn test_answer() {
+        assert_eq!(Some(5121).answer(), "5121");
+        assert_eq!(None::<i64>.answer(), "none");
+        let mut grid = Grid2D::new(0u32);
+        grid.set(1, 0, 1);
+        assert_eq!(grid.answer(), " \u{2588}\n");
+    }
+}
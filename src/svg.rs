@@ -0,0 +1,171 @@
+//! Minimal SVG writer for vector plots of the puzzles.
+//!
+//! Shapes are appended to an [`Svg`] document in drawing order and written
+//! out as a standalone SVG file. Coordinates are in user units with the
+//! origin at the top left, y growing downwards.
+
+use crate::image::Rgb;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Format `rgb` as SVG color `#rrggbb`.
+pub fn color(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Escape the XML special characters in `s`.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// SVG document of `width` x `height` units.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    width: f64,
+    height: f64,
+    background: Option<Rgb>,
+    body: String,
+}
+
+impl Svg {
+    pub fn new(width: f64, height: f64) -> Svg {
+        Svg {
+            width,
+            height,
+            background: None,
+            body: String::new(),
+        }
+    }
+
+    /// Fill the whole document with `rgb` before any shape is drawn.
+    pub fn with_background(mut self, rgb: Rgb) -> Svg {
+        self.background = Some(rgb);
+        self
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: Rgb) -> &mut Svg {
+        let _ = writeln!(
+            self.body,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            x,
+            y,
+            width,
+            height,
+            color(fill)
+        );
+        self
+    }
+
+    pub fn circle(&mut self, cx: f64, cy: f64, r: f64, fill: Rgb) -> &mut Svg {
+        let _ = writeln!(
+            self.body,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+            cx,
+            cy,
+            r,
+            color(fill)
+        );
+        self
+    }
+
+    /// Open polyline through `points`, nothing is drawn for less than two
+    /// points.
+    pub fn path(&mut self, points: &[(f64, f64)], stroke: Rgb, width: f64) -> &mut Svg {
+        if points.len() < 2 {
+            return self;
+        }
+        let mut d = String::new();
+        for (idx, (x, y)) in points.iter().enumerate() {
+            let _ = write!(d, "{}{} {}", if idx == 0 { "M" } else { " L" }, x, y);
+        }
+        let _ = writeln!(
+            self.body,
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            d,
+            color(stroke),
+            width
+        );
+        self
+    }
+
+    /// Text with its baseline starting at `(x, y)`.
+    pub fn text(&mut self, x: f64, y: f64, size: f64, text: &str, fill: Rgb) -> &mut Svg {
+        let _ = writeln!(
+            self.body,
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"monospace\" fill=\"{}\">{}</text>",
+            x,
+            y,
+            size,
+            color(fill),
+            escape(text)
+        );
+        self
+    }
+
+    pub fn write(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = self.width,
+            h = self.height
+        )?;
+        if let Some(rgb) = self.background {
+            writeln!(
+                out,
+                "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+                color(rgb)
+            )?;
+        }
+        out.write_all(self.body.as_bytes())?;
+        writeln!(out, "</svg>")
+    }
+
+    pub fn save(&self, path: &str) -> crate::Result<()> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_svg() {
+        let mut svg = Svg::new(10.0, 5.5).with_background([0, 0, 0]);
+        svg.rect(0.0, 0.0, 2.0, 1.0, [255, 0, 0])
+            .circle(1.5, 2.0, 0.5, [0, 255, 0])
+            .path(&[(0.0, 0.0), (1.0, -2.5)], [0, 0, 255], 0.25)
+            .path(&[(3.0, 3.0)], [0, 0, 255], 1.0)
+            .text(1.0, 4.0, 2.0, "a<b & c", [0x12, 0x34, 0x56]);
+        let mut out = Vec::new();
+        svg.write(&mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10\" height=\"5.5\" viewBox=\"0 0 10 5.5\">",
+                "<rect width=\"100%\" height=\"100%\" fill=\"#000000\"/>",
+                "<rect x=\"0\" y=\"0\" width=\"2\" height=\"1\" fill=\"#ff0000\"/>",
+                "<circle cx=\"1.5\" cy=\"2\" r=\"0.5\" fill=\"#00ff00\"/>",
+                "<path d=\"M0 0 L1 -2.5\" fill=\"none\" stroke=\"#0000ff\" stroke-width=\"0.25\"/>",
+                "<text x=\"1\" y=\"4\" font-size=\"2\" font-family=\"monospace\" fill=\"#123456\">a&lt;b &amp; c</text>",
+                "</svg>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,794 @@
+//! Terminal rendering for the visualizations.
+//!
+//! Frames are drawn into an off-screen cell buffer. Presenting a frame only
+//! redraws the cells which changed since the previous frame by addressing
+//! the cursor directly, instead of clearing and printing the whole screen.
+//! Cells can be given a color, which is drawn with 24 bit color escape
+//! sequences if color output is enabled.
+//!
+//! Text widgets like the score or key hints are composited into bars above
+//! and below the cells, so nothing needs to be printed besides the frames.
+//!
+//! A headless renderer doesn't draw to the terminal at all, every presented
+//! frame is written as numbered text file into a directory instead.
+//!
+//! The renderer keeps the timing of the presented frames, which can be
+//! shown in the bottom bar to tune the frame rate of a visualization.
+//!
+//! Cells larger than the terminal can be shown through a [`Viewport`],
+//! which is panned and zoomed with keys read from a [`RawTerminal`].
+
+use crate::cast::CastWriter;
+use crate::grid::Grid2D;
+use crate::heatmap::Heatmap;
+use crate::image::{FrameDir, Rgb};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum E {
+    TerminalSetupFailed,
+}
+
+const CLEAR_SCREEN: &str = "\x1B[2J";
+const HIDE_CURSOR: &str = "\x1B[?25l";
+const SHOW_CURSOR: &str = "\x1B[?25h";
+const ENTER_ALTERNATE_SCREEN: &str = "\x1B[?1049h";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1B[?1049l";
+const RESET_COLOR: &str = "\x1B[0m";
+
+// Character of a cell with its optional foreground color.
+type Cell = (char, Option<Rgb>);
+
+/// Place of a widget, the bars are left/right aligned to the cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Slot {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Double-buffered terminal renderer with bars of text widgets above and
+/// below the cells.
+///
+/// The cursor is hidden while frames are presented to the terminal and shown
+/// again when the renderer is dropped.
+pub struct Renderer {
+    back: Grid2D<Cell>,
+    front: Grid2D<Cell>,
+    widgets: [String; 4],
+    shown_top: Option<String>,
+    shown_bottom: Option<(usize, String)>,
+    alternate_screen: bool,
+    color: bool,
+    capture: Option<FrameDir>,
+    presented: bool,
+    stats: FrameStats,
+    overlay: bool,
+    viewport: Option<Viewport>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer {
+            back: Grid2D::new((' ', None)),
+            front: Grid2D::new((' ', None)),
+            widgets: Default::default(),
+            shown_top: None,
+            shown_bottom: None,
+            alternate_screen: false,
+            color: false,
+            capture: None,
+            presented: false,
+            stats: FrameStats::default(),
+            overlay: false,
+            viewport: None,
+        }
+    }
+
+    /// Draw on the alternate screen, which restores the previous terminal
+    /// content once the renderer is dropped.
+    pub fn with_alternate_screen(mut self) -> Renderer {
+        self.alternate_screen = true;
+        self
+    }
+
+    /// Draw colored cells in their color, otherwise only the characters are
+    /// drawn.
+    pub fn with_color(mut self, color: bool) -> Renderer {
+        self.color = color;
+        self
+    }
+
+    /// Write the presented frames as text files into `frames` instead of
+    /// drawing them on the terminal.
+    pub fn headless(mut self, frames: FrameDir) -> Renderer {
+        self.capture = Some(frames);
+        self
+    }
+
+    /// Show the frame number and timing of the previous frame at the right
+    /// of the bottom bar.
+    pub fn with_overlay(mut self, overlay: bool) -> Renderer {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Only show the cells in `viewport`.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Renderer {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    pub fn viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// Pan/zoom the viewport with `key` (see [`Viewport::apply`]), returns
+    /// whether the key was used.
+    pub fn pan_zoom(&mut self, key: Key) -> bool {
+        let cells = (self.back.width(), self.back.height());
+        match self.viewport.as_mut() {
+            Some(viewport) => viewport.apply(key, cells),
+            None => false,
+        }
+    }
+
+    /// Timing of the frames presented so far.
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Account `idle` as time spent waiting (e.g. by a [`FrameClock`])
+    /// instead of computing the next frame.
+    pub fn add_idle(&mut self, idle: Duration) {
+        self.stats.idle += idle;
+    }
+
+    /// Set the text of the widget at `slot`, an empty text removes it.
+    pub fn set_widget(&mut self, slot: Slot, text: &str) {
+        let widget = &mut self.widgets[slot as usize];
+        widget.clear();
+        widget.push_str(text);
+    }
+
+    /// Set the status at the top left.
+    pub fn set_status(&mut self, status: &str) {
+        self.set_widget(Slot::TopLeft, status);
+    }
+
+    /// Set the cell at `(x, y)` of the next frame.
+    pub fn set(&mut self, x: usize, y: usize, c: char) {
+        self.back.set(x, y, (c, None));
+    }
+
+    /// Set the cell at `(x, y)` of the next frame drawn in `color`.
+    pub fn set_colored(&mut self, x: usize, y: usize, c: char, color: Rgb) {
+        self.back.set(x, y, (c, Some(color)));
+    }
+
+    /// Draw `grid` into the next frame starting at the top left cell.
+    pub fn draw_grid<T: Clone>(&mut self, grid: &Grid2D<T>, to_char: impl Fn(&T) -> char) {
+        self.back.grow_to(grid.width(), grid.height());
+        for ((x, y), v) in grid.iter() {
+            self.back.set(x, y, (to_char(v), None));
+        }
+    }
+
+    /// Draw `grid` into the next frame starting at the top left cell, with
+    /// `to_cell` mapping every cell to a character and its color.
+    pub fn draw_grid_colored<T: Clone>(
+        &mut self,
+        grid: &Grid2D<T>,
+        to_cell: impl Fn(&T) -> (char, Rgb),
+    ) {
+        self.back.grow_to(grid.width(), grid.height());
+        for ((x, y), v) in grid.iter() {
+            let (c, color) = to_cell(v);
+            self.back.set(x, y, (c, Some(color)));
+        }
+    }
+
+    /// Draw a heatmap of `values` into the next frame starting at the top
+    /// left cell, see [`Heatmap`]. Cells without a value stay blank.
+    pub fn draw_heatmap(&mut self, values: &Grid2D<Option<u64>>) {
+        let heatmap = Heatmap::new(values);
+        for ((x, y), v) in values.iter() {
+            let cell = match *v {
+                Some(v) => (heatmap.shade(v), Some(heatmap.color(v))),
+                None => (' ', None),
+            };
+            self.back.set(x, y, cell);
+        }
+    }
+
+    // Compose a bar from its left and right aligned text.
+    fn bar(&self, left: &str, right: &str) -> String {
+        if right.is_empty() {
+            return left.to_string();
+        }
+        let used = left.chars().count() + right.chars().count();
+        let gap = self.view_size().0.saturating_sub(used).max(1);
+        format!("{}{}{}", left, " ".repeat(gap), right)
+    }
+
+    fn top_bar(&self) -> String {
+        let widget = |slot: Slot| &self.widgets[slot as usize];
+        self.bar(widget(Slot::TopLeft), widget(Slot::TopRight))
+    }
+
+    fn bottom_bar(&self) -> String {
+        let widget = |slot: Slot| &self.widgets[slot as usize];
+        let mut right = widget(Slot::BottomRight).clone();
+        if self.overlay {
+            if !right.is_empty() {
+                right.push_str("  ");
+            }
+            right.push_str(&self.stats.overlay());
+        }
+        self.bar(widget(Slot::BottomLeft), &right)
+    }
+
+    // Size of the shown cells as `(columns, rows)`.
+    fn view_size(&self) -> (usize, usize) {
+        match self.viewport {
+            Some(viewport) => (viewport.width, viewport.height),
+            None => (self.back.width(), self.back.height()),
+        }
+    }
+
+    // The shown cells, all of them without a viewport.
+    fn view(&self) -> Cow<'_, Grid2D<Cell>> {
+        match self.viewport {
+            Some(viewport) => Cow::Owned(viewport.sample(&self.back)),
+            None => Cow::Borrowed(&self.back),
+        }
+    }
+
+    /// Terminal size needed to show the frames as `(columns, rows)`, with
+    /// the bars around and the cursor parked below the cells.
+    pub fn size(&self) -> (usize, usize) {
+        let (top, bottom) = (self.top_bar(), self.bottom_bar());
+        let (width, height) = self.view_size();
+        let width = width.max(top.chars().count()).max(bottom.chars().count());
+        let bottom_rows = if bottom.is_empty() { 0 } else { 1 };
+        (width, height + 2 + bottom_rows)
+    }
+
+    /// The next frame as plain text, the top bar followed by the rows of
+    /// cells and the bottom bar (if any) without trailing whitespace.
+    pub fn text(&self) -> String {
+        frame_text(&self.top_bar(), &self.view(), &self.bottom_bar())
+    }
+
+    /// Escape sequences updating the terminal from the previous frame to the
+    /// next one. The first frame clears the screen.
+    pub fn diff(&mut self) -> String {
+        let mut frame = String::new();
+        if self.shown_top.is_none() {
+            frame.push_str(CLEAR_SCREEN);
+        }
+
+        let top = self.top_bar();
+        if self.shown_top.as_ref() != Some(&top) {
+            frame.push_str(&format!("\x1B[1;1H{}\x1B[K", top));
+            self.shown_top = Some(top);
+        }
+
+        // the bottom bar moves down when the cells grow, clear it where it
+        // was shown before drawing cells over it
+        let bottom = self.bottom_bar();
+        let bottom_row = self.view_size().1 + 2;
+        if let Some((row, _)) = self.shown_bottom {
+            if row != bottom_row || bottom.is_empty() {
+                frame.push_str(&format!("\x1B[{};1H\x1B[K", row));
+                self.shown_bottom = None;
+            }
+        }
+
+        let view = self.view().into_owned();
+        for ((x, y), &cell) in view.iter() {
+            if self.front.get(x, y) == Some(&cell) {
+                continue;
+            }
+            self.front.set(x, y, cell);
+            // terminal rows/columns are 1-based, row 1 is the status line
+            frame.push_str(&format!("\x1B[{};{}H", y + 2, x + 1));
+            match cell {
+                (c, Some([r, g, b])) if self.color => {
+                    frame.push_str(&format!("\x1B[38;2;{};{};{}m{}{}", r, g, b, c, RESET_COLOR))
+                }
+                (c, _) => frame.push(c),
+            }
+        }
+        let mut park_row = bottom_row;
+        if !bottom.is_empty() {
+            if self.shown_bottom.as_ref() != Some(&(bottom_row, bottom.clone())) {
+                frame.push_str(&format!("\x1B[{};1H{}\x1B[K", bottom_row, bottom));
+                self.shown_bottom = Some((bottom_row, bottom));
+            }
+            park_row += 1;
+        }
+        // park the cursor below the cells and bars
+        frame.push_str(&format!("\x1B[{};1H", park_row));
+        frame
+    }
+
+    /// Draw the next frame on the terminal, written at once.
+    pub fn present(&mut self) -> std::io::Result<()> {
+        let start = Instant::now();
+        let diff = self.diff();
+        self.write_frame(&diff)?;
+        self.stats.record(start, Instant::now());
+        Ok(())
+    }
+
+    /// Draw the next frame on the terminal and also record it to `cast`.
+    pub fn present_recorded<W: Write>(&mut self, cast: &mut CastWriter<W>) -> std::io::Result<()> {
+        let start = Instant::now();
+        let diff = self.diff();
+        cast.frame(self.size(), &diff)?;
+        self.write_frame(&diff)?;
+        self.stats.record(start, Instant::now());
+        Ok(())
+    }
+
+    fn write_frame(&mut self, diff: &str) -> std::io::Result<()> {
+        if self.capture.is_some() {
+            let text = self.text();
+            if let Some(frames) = self.capture.as_mut() {
+                return frames.save_text(&text);
+            }
+        }
+
+        let mut frame = String::new();
+        if !self.presented {
+            if self.alternate_screen {
+                frame.push_str(ENTER_ALTERNATE_SCREEN);
+            }
+            frame.push_str(HIDE_CURSOR);
+            self.presented = true;
+        }
+        frame.push_str(diff);
+
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+fn frame_text(top: &str, cells: &Grid2D<Cell>, bottom: &str) -> String {
+    let mut text = format!("{}\n", top.trim_end());
+    for line in cells.render(|&(c, _)| c).lines() {
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    if !bottom.is_empty() {
+        text.push_str(bottom.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        if !self.presented {
+            return;
+        }
+        print!("{}", SHOW_CURSOR);
+        if self.alternate_screen {
+            print!("{}", LEAVE_ALTERNATE_SCREEN);
+        }
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Part of the cells shown on the terminal: `width` x `height` characters
+/// starting at the cell `(x, y)`, every character covering `scale` x `scale`
+/// cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub scale: usize,
+}
+
+impl Viewport {
+    const MAX_SCALE: usize = 16;
+
+    pub fn new(width: usize, height: usize) -> Viewport {
+        Viewport {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            scale: 1,
+        }
+    }
+
+    /// Viewport filling the terminal besides `rows` rows for the bars, 80x24
+    /// if the terminal size is unknown.
+    pub fn fit_terminal(rows: usize) -> Viewport {
+        let (width, height) = terminal_size().unwrap_or((80, 24));
+        Viewport::new(width, height.saturating_sub(rows).max(1))
+    }
+
+    /// Pan with the arrow keys or h/j/k/l by a quarter of the view, zoom in
+    /// and out with +/- and reset with 0. Panning stops at the edges of the
+    /// `cells` given as `(columns, rows)`. Returns whether the key was used.
+    pub fn apply(&mut self, key: Key, cells: (usize, usize)) -> bool {
+        let step = |size: usize| (size * self.scale / 4).max(1);
+        let (step_x, step_y) = (step(self.width), step(self.height));
+        let max_x = cells.0.saturating_sub(self.width * self.scale);
+        let max_y = cells.1.saturating_sub(self.height * self.scale);
+        match key {
+            Key::Left | Key::Char(b'h') => self.x = self.x.saturating_sub(step_x),
+            Key::Right | Key::Char(b'l') => self.x = (self.x + step_x).min(max_x),
+            Key::Up | Key::Char(b'k') => self.y = self.y.saturating_sub(step_y),
+            Key::Down | Key::Char(b'j') => self.y = (self.y + step_y).min(max_y),
+            Key::Char(b'+') => self.scale = (self.scale / 2).max(1),
+            Key::Char(b'-') => self.scale = (self.scale * 2).min(Viewport::MAX_SCALE),
+            Key::Char(b'0') => *self = Viewport::new(self.width, self.height),
+            _ => return false,
+        }
+        true
+    }
+
+    // The cells in view, every character shows the first non-blank cell of
+    // the cells it covers.
+    fn sample(&self, cells: &Grid2D<Cell>) -> Grid2D<Cell> {
+        let mut view = Grid2D::new((' ', None));
+        for vy in 0..self.height {
+            for vx in 0..self.width {
+                let (x, y) = (self.x + vx * self.scale, self.y + vy * self.scale);
+                let mut covered = (y..y + self.scale)
+                    .flat_map(|y| (x..x + self.scale).map(move |x| (x, y)))
+                    .filter_map(|(x, y)| cells.get(x, y));
+                let first = covered.next().copied();
+                let cell = std::iter::once(first)
+                    .flatten()
+                    .chain(covered.copied())
+                    .find(|&(c, _)| c != ' ')
+                    .or(first)
+                    .unwrap_or((' ', None));
+                view.set(vx, vy, cell);
+            }
+        }
+        view
+    }
+}
+
+/// Key read from a terminal in raw mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Char(u8),
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Block until the next key is pressed, arrow keys are decoded from their
+/// escape sequences.
+pub fn read_key(stdin: &mut impl Read) -> std::io::Result<Key> {
+    let mut next = || -> std::io::Result<u8> {
+        let mut byte = [0];
+        stdin.read_exact(&mut byte)?;
+        Ok(byte[0])
+    };
+
+    loop {
+        match next()? {
+            0x1B => {
+                if next()? != b'[' {
+                    continue;
+                }
+                match next()? {
+                    b'A' => return Ok(Key::Up),
+                    b'B' => return Ok(Key::Down),
+                    b'C' => return Ok(Key::Right),
+                    b'D' => return Ok(Key::Left),
+                    _ => {}
+                }
+            }
+            byte => return Ok(Key::Char(byte)),
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> crate::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(crate::Error::boxed(E::TerminalSetupFailed));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Size of the terminal as `(columns, rows)`, if stdin is a terminal.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    let size = stty(&["size"]).ok()?;
+    let (rows, columns) = size.split_once(' ')?;
+    Some((columns.parse().ok()?, rows.parse().ok()?))
+}
+
+/// Terminal in raw mode (unbuffered input without echo), the previous
+/// terminal settings are restored when dropped.
+pub struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    pub fn enable() -> crate::Result<RawTerminal> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(RawTerminal { saved })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\r\n");
+        let _ = std::io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Timing of the frames presented by a [`Renderer`].
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    /// Number of frames presented.
+    pub frames: usize,
+    /// Time between the previous and the last frame, without idle time.
+    pub compute: Duration,
+    /// Time spent drawing the last frame.
+    pub render: Duration,
+    /// Frames per second, smoothed over the recent frames.
+    pub fps: f64,
+    idle: Duration,
+    last: Option<(Instant, Instant)>,
+}
+
+impl FrameStats {
+    // Weight of the latest frame in the smoothed frame rate.
+    const FPS_SMOOTHING: f64 = 0.1;
+
+    // Account a frame drawn from `start` to `end`.
+    fn record(&mut self, start: Instant, end: Instant) {
+        if let Some((last_start, last_end)) = self.last {
+            self.compute = start.duration_since(last_end).saturating_sub(self.idle);
+            let fps = 1.0 / start.duration_since(last_start).as_secs_f64().max(1e-9);
+            self.fps = if self.frames == 1 {
+                fps
+            } else {
+                self.fps + FrameStats::FPS_SMOOTHING * (fps - self.fps)
+            };
+        }
+        self.render = end.duration_since(start);
+        self.idle = Duration::default();
+        self.last = Some((start, end));
+        self.frames += 1;
+    }
+
+    /// Summary shown by the overlay, e.g.
+    /// `frame 12  9.9 fps  compute 1.2 ms  render 0.3 ms`.
+    pub fn overlay(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        format!(
+            "frame {}  {:.1} fps  compute {:.1} ms  render {:.1} ms",
+            self.frames,
+            self.fps,
+            ms(self.compute),
+            ms(self.render)
+        )
+    }
+}
+
+/// Paces a render loop to a fixed number of frames per second, only sleeping
+/// for the part of the frame period not already spent computing/rendering.
+pub struct FrameClock {
+    period: Duration,
+    next: Instant,
+}
+
+impl FrameClock {
+    pub fn new(fps: f64) -> FrameClock {
+        FrameClock {
+            period: Duration::from_secs_f64(1.0 / fps),
+            next: Instant::now(),
+        }
+    }
+
+    /// Sleep until the next frame is due, returns the time slept.
+    pub fn tick(&mut self) -> Duration {
+        self.next += self.period;
+        let now = Instant::now();
+        if self.next > now {
+            std::thread::sleep(self.next - now);
+            self.next - now
+        } else {
+            // running behind, don't try to catch up
+            self.next = now;
+            Duration::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut renderer = Renderer::new();
+        renderer.set_status("a");
+        renderer.set(0, 0, '#');
+        renderer.set(1, 1, 'o');
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[2J\x1B[1;1Ha\x1B[K\x1B[2;1H#\x1B[2;2H \x1B[3;1H \x1B[3;2Ho\x1B[4;1H"
+        );
+
+        // nothing changed
+        assert_eq!(renderer.diff(), "\x1B[4;1H");
+
+        renderer.set(1, 1, ' ');
+        renderer.set(2, 0, 'o');
+        renderer.set_status("b");
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[1;1Hb\x1B[K\x1B[2;3Ho\x1B[3;2H \x1B[4;1H"
+        );
+    }
+
+    #[test]
+    fn test_widgets() {
+        let mut renderer = Renderer::new();
+        renderer.set(5, 0, '#');
+        renderer.set_widget(Slot::TopLeft, "a");
+        renderer.set_widget(Slot::TopRight, "b");
+        renderer.set_widget(Slot::BottomLeft, "keys");
+        assert_eq!(renderer.text(), "a    b\n     #\nkeys\n");
+        assert_eq!(renderer.size(), (6, 4));
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[2J\x1B[1;1Ha    b\x1B[K\x1B[2;1H \x1B[2;2H \x1B[2;3H \x1B[2;4H \x1B[2;5H \x1B[2;6H#\x1B[3;1Hkeys\x1B[K\x1B[4;1H"
+        );
+        assert_eq!(renderer.diff(), "\x1B[4;1H");
+
+        // the cells grow over the bottom bar, which moves down. The row is
+        // cleared, so only the non-blank cells are drawn.
+        renderer.set(0, 1, 'o');
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[3;1H\x1B[K\x1B[3;1Ho\x1B[4;1Hkeys\x1B[K\x1B[5;1H"
+        );
+
+        renderer.set_widget(Slot::BottomLeft, "");
+        assert_eq!(renderer.diff(), "\x1B[4;1H\x1B[K\x1B[4;1H");
+        assert_eq!(renderer.size(), (6, 4));
+    }
+
+    #[test]
+    fn test_headless() {
+        let dir = std::env::temp_dir().join(format!("aoc19-headless-{}", std::process::id()));
+        let frames = FrameDir::create(dir.to_str().unwrap()).unwrap();
+        let mut renderer = Renderer::new().headless(frames);
+        renderer.set_status("a");
+        renderer.set(0, 0, '#');
+        renderer.set(2, 1, 'o');
+        assert_eq!(renderer.text(), "a\n#\n  o\n");
+        renderer.present().unwrap();
+        renderer.set(2, 1, ' ');
+        renderer.present().unwrap();
+
+        let read = |name| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("frame_00000.txt"), "a\n#\n  o\n");
+        assert_eq!(read("frame_00001.txt"), "a\n#\n\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        let mut stats = FrameStats::default();
+        stats.record(start, start + ms(1));
+        assert_eq!(
+            stats.overlay(),
+            "frame 1  0.0 fps  compute 0.0 ms  render 1.0 ms"
+        );
+
+        stats.idle += ms(40);
+        stats.record(start + ms(100), start + ms(102));
+        assert_eq!(stats.compute, ms(59));
+        assert_eq!(
+            stats.overlay(),
+            "frame 2  10.0 fps  compute 59.0 ms  render 2.0 ms"
+        );
+
+        stats.record(start + ms(150), start + ms(152));
+        // 20 fps are smoothed in
+        assert_eq!(
+            stats.overlay(),
+            "frame 3  11.0 fps  compute 48.0 ms  render 2.0 ms"
+        );
+
+        let mut renderer = Renderer::new().with_overlay(true);
+        renderer.set(0, 0, '#');
+        renderer.set_widget(Slot::BottomRight, "x");
+        assert_eq!(
+            renderer.bottom_bar(),
+            " x  frame 0  0.0 fps  compute 0.0 ms  render 0.0 ms"
+        );
+    }
+
+    #[test]
+    fn test_viewport() {
+        let mut renderer = Renderer::new().with_viewport(Viewport::new(2, 1));
+        renderer.set(0, 0, '.');
+        renderer.set(5, 0, '#');
+        renderer.set(7, 2, 'o');
+        assert_eq!(renderer.text(), "\n.\n");
+        assert_eq!(renderer.size(), (2, 3));
+
+        assert!(renderer.pan_zoom(Key::Right));
+        for _ in 0..10 {
+            assert!(renderer.pan_zoom(Key::Char(b'l')));
+        }
+        // stops at the right edge
+        assert_eq!(renderer.viewport().unwrap().x, 6);
+        assert!(renderer.pan_zoom(Key::Left));
+        assert!(renderer.pan_zoom(Key::Left));
+        assert_eq!(renderer.text(), "\n #\n");
+        // non-blank cells win when zoomed out
+        assert!(renderer.pan_zoom(Key::Char(b'-')));
+        assert_eq!(renderer.text(), "\n#\n");
+        assert!(renderer.pan_zoom(Key::Char(b'0')));
+        assert!(renderer.pan_zoom(Key::Down));
+        assert_eq!(renderer.viewport().unwrap().y, 1);
+        assert!(!renderer.pan_zoom(Key::Char(b'q')));
+
+        let mut keys: &[u8] = b"\x1B[Dx\x1B[A";
+        assert_eq!(read_key(&mut keys).unwrap(), Key::Left);
+        assert_eq!(read_key(&mut keys).unwrap(), Key::Char(b'x'));
+        assert_eq!(read_key(&mut keys).unwrap(), Key::Up);
+        assert!(read_key(&mut keys).is_err());
+    }
+
+    #[test]
+    fn test_diff_color() {
+        let mut renderer = Renderer::new();
+        renderer.set_colored(0, 0, '#', [1, 2, 3]);
+        assert_eq!(renderer.diff(), "\x1B[2J\x1B[1;1H\x1B[K\x1B[2;1H#\x1B[3;1H");
+
+        let mut renderer = Renderer::new().with_color(true);
+        renderer.set_colored(0, 0, '#', [1, 2, 3]);
+        renderer.set(1, 0, 'o');
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[2J\x1B[1;1H\x1B[K\x1B[2;1H\x1B[38;2;1;2;3m#\x1B[0m\x1B[2;2Ho\x1B[3;1H"
+        );
+
+        // only the color changed
+        renderer.set_colored(0, 0, '#', [4, 5, 6]);
+        assert_eq!(
+            renderer.diff(),
+            "\x1B[2;1H\x1B[38;2;4;5;6m#\x1B[0m\x1B[3;1H"
+        );
+    }
+}
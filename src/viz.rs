@@ -0,0 +1,185 @@
+//! Event stream of the visualizations.
+//!
+//! Solvers emit what they draw into a [`VizSink`] instead of talking to a
+//! renderer directly: the cells which changed, text annotations and when a
+//! frame is complete. Which sink they are given decides whether the frames
+//! are animated on the terminal, captured as text or dropped, so the solver
+//! code is the same for all of them.
+
+use crate::grid::Grid2D;
+use crate::image::{FrameDir, Rgb};
+use crate::term::{FrameClock, Renderer, Slot};
+
+/// Receiver of the frames drawn by a solver.
+pub trait VizSink {
+    /// Set the cell at `(x, y)` of the next frame, drawn in `color` if given.
+    fn cell_changed(&mut self, x: usize, y: usize, c: char, color: Option<Rgb>);
+
+    /// Set the text shown at `slot`, an empty text removes it.
+    fn annotate(&mut self, slot: Slot, text: &str);
+
+    /// The next frame is complete.
+    fn frame_done(&mut self) -> crate::Result<()>;
+}
+
+impl dyn VizSink + '_ {
+    /// Draw `grid` into the next frame starting at the top left cell, with
+    /// `to_cell` mapping every cell to a character and its color.
+    pub fn draw_grid<T: Clone>(
+        &mut self,
+        grid: &Grid2D<T>,
+        to_cell: impl Fn(&T) -> (char, Option<Rgb>),
+    ) {
+        for ((x, y), v) in grid.iter() {
+            let (c, color) = to_cell(v);
+            self.cell_changed(x, y, c, color);
+        }
+    }
+}
+
+impl VizSink for Renderer {
+    fn cell_changed(&mut self, x: usize, y: usize, c: char, color: Option<Rgb>) {
+        match color {
+            Some(color) => self.set_colored(x, y, c, color),
+            None => self.set(x, y, c),
+        }
+    }
+
+    fn annotate(&mut self, slot: Slot, text: &str) {
+        self.set_widget(slot, text);
+    }
+
+    fn frame_done(&mut self) -> crate::Result<()> {
+        self.present()?;
+        Ok(())
+    }
+}
+
+/// Animates the frames on the terminal, paced by an optional clock.
+pub struct TerminalSink {
+    renderer: Renderer,
+    clock: Option<FrameClock>,
+}
+
+impl TerminalSink {
+    pub fn new(renderer: Renderer, clock: Option<FrameClock>) -> TerminalSink {
+        TerminalSink { renderer, clock }
+    }
+}
+
+impl VizSink for TerminalSink {
+    fn cell_changed(&mut self, x: usize, y: usize, c: char, color: Option<Rgb>) {
+        self.renderer.cell_changed(x, y, c, color);
+    }
+
+    fn annotate(&mut self, slot: Slot, text: &str) {
+        self.renderer.annotate(slot, text);
+    }
+
+    fn frame_done(&mut self) -> crate::Result<()> {
+        self.renderer.frame_done()?;
+        if let Some(clock) = self.clock.as_mut() {
+            let idle = clock.tick();
+            self.renderer.add_idle(idle);
+        }
+        Ok(())
+    }
+}
+
+/// Captures the frames as plain text (see [`Renderer::text`]), kept in
+/// memory or written as numbered text files into a directory.
+pub struct CaptureSink {
+    renderer: Renderer,
+    dir: Option<FrameDir>,
+    frames: Vec<String>,
+}
+
+impl Default for CaptureSink {
+    fn default() -> Self {
+        CaptureSink::new()
+    }
+}
+
+impl CaptureSink {
+    /// Keep the frames in memory.
+    pub fn new() -> CaptureSink {
+        CaptureSink {
+            renderer: Renderer::new(),
+            dir: None,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Write the frames into `dir` instead of keeping them.
+    pub fn to_dir(dir: FrameDir) -> CaptureSink {
+        CaptureSink {
+            dir: Some(dir),
+            ..CaptureSink::new()
+        }
+    }
+
+    /// Frames kept in memory.
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+}
+
+impl VizSink for CaptureSink {
+    fn cell_changed(&mut self, x: usize, y: usize, c: char, color: Option<Rgb>) {
+        self.renderer.cell_changed(x, y, c, color);
+    }
+
+    fn annotate(&mut self, slot: Slot, text: &str) {
+        self.renderer.annotate(slot, text);
+    }
+
+    fn frame_done(&mut self) -> crate::Result<()> {
+        let text = self.renderer.text();
+        match self.dir.as_mut() {
+            Some(dir) => dir.save_text(&text)?,
+            None => self.frames.push(text),
+        }
+        Ok(())
+    }
+}
+
+/// Drops all frames.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullSink;
+
+impl VizSink for NullSink {
+    fn cell_changed(&mut self, _x: usize, _y: usize, _c: char, _color: Option<Rgb>) {}
+
+    fn annotate(&mut self, _slot: Slot, _text: &str) {}
+
+    fn frame_done(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        let mut grid = Grid2D::new(false);
+        grid.set(1, 0, true);
+
+        let mut capture = CaptureSink::new();
+        let sink: &mut dyn VizSink = &mut capture;
+        sink.draw_grid(&grid, |&on| {
+            if on {
+                ('#', Some([1, 2, 3]))
+            } else {
+                ('.', None)
+            }
+        });
+        sink.annotate(Slot::TopLeft, "a");
+        sink.frame_done().unwrap();
+        sink.cell_changed(0, 1, 'o', None);
+        sink.annotate(Slot::TopLeft, "");
+        sink.frame_done().unwrap();
+        assert_eq!(capture.frames(), &["a\n.#\n", "\n.#\no\n"]);
+    }
+}
@@ -0,0 +1,280 @@
+//! Browser view of the visualizations.
+//!
+//! A tiny HTTP server serves a static page (see `web/index.html`) at `/` and
+//! streams the frames of a [`WebSink`] as server-sent events at `/events`,
+//! one JSON object per frame:
+//!
+//! ```text
+//! {"frame": 0, "widgets": ["top left", "", "", ""], "rows": ["#."],
+//!  "colors": [["#ff0000", null]]}
+//! ```
+//!
+//! Browsers connecting later first receive the latest frame. Every request
+//! is served on its own thread and every browser has its own writer thread,
+//! browsers which are too slow to keep up skip frames instead of holding up
+//! the visualization.
+
+use crate::grid::Grid2D;
+use crate::image::Rgb;
+use crate::json::json_string;
+use crate::term::{FrameClock, Slot};
+use crate::viz::VizSink;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+const INDEX_HTML: &str = include_str!("../web/index.html");
+
+// Time a browser gets to send its request or to take a write.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Events queued for a browser before it skips frames.
+const EVENT_BACKLOG: usize = 16;
+
+#[derive(Default)]
+struct Clients {
+    // event queues of the browsers' writer threads
+    queues: Vec<SyncSender<Arc<str>>>,
+    last: Option<Arc<str>>,
+}
+
+/// HTTP server accepting browsers in a background thread.
+pub struct WebServer {
+    addr: std::net::SocketAddr,
+    clients: Arc<(Mutex<Clients>, Condvar)>,
+}
+
+impl WebServer {
+    /// Listen on `addr`, e.g. `127.0.0.1:8019` (port 0 picks a free port).
+    pub fn bind(addr: impl ToSocketAddrs) -> crate::Result<WebServer> {
+        let listener = TcpListener::bind(addr)?;
+        let server = WebServer {
+            addr: listener.local_addr()?,
+            clients: Arc::new((Mutex::new(Clients::default()), Condvar::new())),
+        };
+
+        let clients = Arc::clone(&server.clients);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let clients = Arc::clone(&clients);
+                // a broken or stalled request only affects its own connection
+                std::thread::spawn(move || {
+                    let _ = serve(stream, &clients);
+                });
+            }
+        });
+        Ok(server)
+    }
+
+    pub fn addr(&self) -> std::net::SocketAddr {
+        self.addr
+    }
+
+    /// Block until a browser is connected to the event stream.
+    pub fn wait_for_client(&self) {
+        let (clients, connected) = &*self.clients;
+        let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+        while clients.queues.is_empty() {
+            clients = connected.wait(clients).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    // Queue `event` for all browsers, dropping the ones which disconnected.
+    // Browsers with a full queue miss this event.
+    fn broadcast(&self, event: String) {
+        let event: Arc<str> = event.into();
+        let mut clients = self.clients.0.lock().unwrap_or_else(|e| e.into_inner());
+        clients.queues.retain(|queue| {
+            !matches!(
+                queue.try_send(Arc::clone(&event)),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+        clients.last = Some(event);
+    }
+}
+
+// Answer one request: the page, the event stream or not found.
+fn serve(mut stream: TcpStream, clients: &(Mutex<Clients>, Condvar)) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line.trim_end() != "" {
+        line.clear();
+    }
+
+    match request.split_whitespace().nth(1) {
+        Some("/") => write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            INDEX_HTML.len(),
+            INDEX_HTML
+        ),
+        Some("/events") => {
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+            )?;
+            let (queue, events) = mpsc::sync_channel::<Arc<str>>(EVENT_BACKLOG);
+            {
+                let (clients, connected) = clients;
+                let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(last) = &clients.last {
+                    let _ = queue.try_send(Arc::clone(last));
+                }
+                clients.queues.push(queue);
+                connected.notify_all();
+            }
+            // the queue is dropped from the clients by the next broadcast
+            // once the browser is gone
+            for event in events {
+                stream.write_all(event.as_bytes())?;
+            }
+            Ok(())
+        }
+        _ => stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    }
+}
+
+/// Streams the frames to the browsers connected to a [`WebServer`], paced
+/// by an optional clock.
+pub struct WebSink {
+    server: WebServer,
+    clock: Option<FrameClock>,
+    cells: Grid2D<(char, Option<Rgb>)>,
+    widgets: [String; 4],
+    frames: usize,
+}
+
+impl WebSink {
+    pub fn new(server: WebServer, clock: Option<FrameClock>) -> WebSink {
+        WebSink {
+            server,
+            clock,
+            cells: Grid2D::new((' ', None)),
+            widgets: Default::default(),
+            frames: 0,
+        }
+    }
+
+    // The next frame as JSON object.
+    fn frame_json(&self) -> String {
+        let rows: Vec<_> = self
+            .cells
+            .rows()
+            .map(|row| json_string(&row.iter().map(|&(c, _)| c).collect::<String>()))
+            .collect();
+        let colors: Vec<_> = self
+            .cells
+            .rows()
+            .map(|row| {
+                let colors: Vec<_> = row
+                    .iter()
+                    .map(|&(_, color)| match color {
+                        Some(rgb) => json_string(&crate::svg::color(rgb)),
+                        None => "null".to_string(),
+                    })
+                    .collect();
+                format!("[{}]", colors.join(", "))
+            })
+            .collect();
+        let widgets: Vec<_> = self.widgets.iter().map(|w| json_string(w)).collect();
+        format!(
+            "{{\"frame\": {}, \"widgets\": [{}], \"rows\": [{}], \"colors\": [{}]}}",
+            self.frames,
+            widgets.join(", "),
+            rows.join(", "),
+            colors.join(", ")
+        )
+    }
+}
+
+impl VizSink for WebSink {
+    fn cell_changed(&mut self, x: usize, y: usize, c: char, color: Option<Rgb>) {
+        self.cells.set(x, y, (c, color));
+    }
+
+    fn annotate(&mut self, slot: Slot, text: &str) {
+        self.widgets[slot as usize] = text.to_string();
+    }
+
+    fn frame_done(&mut self) -> crate::Result<()> {
+        self.server
+            .broadcast(format!("data: {}\n\n", self.frame_json()));
+        self.frames += 1;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.tick();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+    use std::time::Duration;
+
+    fn get(server: &WebServer, path: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        stream
+    }
+
+    #[test]
+    fn test_server() {
+        let server = WebServer::bind("127.0.0.1:0").unwrap();
+
+        let mut page = String::new();
+        get(&server, "/").read_to_string(&mut page).unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(page.ends_with(INDEX_HTML));
+
+        let mut missing = String::new();
+        get(&server, "/x").read_to_string(&mut missing).unwrap();
+        assert!(missing.starts_with("HTTP/1.1 404"));
+
+        let events = get(&server, "/events");
+        server.wait_for_client();
+        let mut sink = WebSink::new(server, None);
+        sink.cell_changed(1, 0, '#', Some([255, 0, 0]));
+        sink.annotate(Slot::TopLeft, "a\"b");
+        sink.frame_done().unwrap();
+
+        let mut lines = BufReader::new(events).lines().map(|l| l.unwrap());
+        assert_eq!(lines.next().unwrap(), "HTTP/1.1 200 OK");
+        let data = lines.find(|l| l.starts_with("data: ")).unwrap();
+        assert_eq!(
+            data,
+            "data: {\"frame\": 0, \"widgets\": [\"a\\\"b\", \"\", \"\", \"\"], \"rows\": [\" #\"], \"colors\": [[null, \"#ff0000\"]]}"
+        );
+    }
+
+    #[test]
+    fn test_stalled_client() {
+        let server = WebServer::bind("127.0.0.1:0").unwrap();
+        // connects but never sends its request
+        let _idle = TcpStream::connect(server.addr()).unwrap();
+
+        let mut page = String::new();
+        get(&server, "/").read_to_string(&mut page).unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+
+        // a browser which doesn't read its events doesn't hold up the frames
+        let _events = get(&server, "/events");
+        server.wait_for_client();
+        let mut sink = WebSink::new(server, None);
+        sink.cell_changed(0, 0, '#', None);
+        for _ in 0..10 * EVENT_BACKLOG {
+            sink.frame_done().unwrap();
+        }
+    }
+}